@@ -1,3 +1,5 @@
 pub mod block;
 pub mod bouncer;
+pub mod genesis;
+pub mod simulation;
 pub mod transaction_executor;
@@ -1,5 +1,6 @@
 use std::num::NonZeroU128;
 
+use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp};
 use starknet_api::core::ContractAddress;
 use starknet_api::hash::StarkFelt;
@@ -16,7 +17,7 @@ use crate::versioned_constants::VersionedConstants;
 #[path = "block_test.rs"]
 pub mod block_test;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct BlockInfo {
     pub block_number: BlockNumber,
     pub block_timestamp: BlockTimestamp,
@@ -27,7 +28,7 @@ pub struct BlockInfo {
     pub use_kzg_da: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GasPrices {
     pub eth_l1_gas_price: NonZeroU128,       // In wei.
     pub strk_l1_gas_price: NonZeroU128,      // In fri.
@@ -80,7 +81,14 @@ pub fn pre_process_block(
         return Err(StateError::OldBlockHashNotProvided);
     }
 
-    Ok(BlockContext { block_info, chain_info, versioned_constants })
+    Ok(BlockContext {
+        block_info,
+        chain_info,
+        versioned_constants,
+        concurrency_mode: false,
+        tracer: None,
+        contract_executors: None,
+    })
 }
 
 pub struct BlockNumberHashPair {
@@ -0,0 +1,253 @@
+use std::sync::Arc;
+
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use starknet_api::core::{
+    calculate_contract_address, ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector,
+};
+use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::transaction::{Calldata, ContractAddressSalt};
+use thiserror::Error;
+
+use crate::context::{BlockContext, TransactionContext};
+use crate::execution::contract_class::ContractClass;
+use crate::execution::entry_point::{
+    CallEntryPoint, CallType, ConstructorContext, EntryPointExecutionContext,
+};
+use crate::execution::errors::EntryPointExecutionError;
+use crate::execution::execution_utils::execute_deployment;
+use crate::state::cached_state::{CachedState, GlobalContractCache};
+use crate::state::errors::StateError;
+use crate::state::state_api::StateResult;
+use crate::state::state_snapshot::SnapshotStateReader;
+use crate::transaction::errors::TransactionExecutionError;
+use crate::transaction::objects::{DeprecatedTransactionInfo, FeeType, TransactionInfo};
+
+#[cfg(test)]
+#[path = "genesis_test.rs"]
+pub mod test;
+
+#[derive(Debug, Error)]
+pub enum GenesisError {
+    #[error("Failed to execute a genesis call to {contract_address:?}: {error}")]
+    CallFailed { contract_address: ContractAddress, error: EntryPointExecutionError },
+    #[error("Failed to deploy a genesis contract at {contract_address:?}: {error}")]
+    DeploymentFailed { contract_address: ContractAddress, error: EntryPointExecutionError },
+    #[error(transparent)]
+    StateError(#[from] StateError),
+    #[error(transparent)]
+    TransactionExecutionError(#[from] TransactionExecutionError),
+}
+
+pub type GenesisResult<T> = Result<T, GenesisError>;
+
+/// A contract to deploy at genesis; its constructor, if any, is executed with the given calldata.
+#[derive(Clone, Debug)]
+pub struct GenesisContract {
+    pub address: ContractAddress,
+    pub class_hash: ClassHash,
+    pub constructor_calldata: Calldata,
+}
+
+/// A call to execute at genesis, once all contracts are deployed; e.g., to initialize a contract
+/// (such as a StarkGate token) that is not initialized by its constructor.
+#[derive(Clone, Debug)]
+pub struct GenesisCall {
+    pub caller_address: ContractAddress,
+    pub contract_address: ContractAddress,
+    pub entry_point_selector: EntryPointSelector,
+    pub calldata: Calldata,
+}
+
+/// A fee token, deployed at the address of the chain info. Its metadata (name, symbol, decimals,
+/// and owner) is set as its class defines: either by its constructor or by a genesis call.
+#[derive(Clone, Debug)]
+pub struct GenesisFeeToken {
+    pub class_hash: ClassHash,
+    pub constructor_calldata: Calldata,
+    /// The entry point the accounts are funded through, called by the minter with
+    /// `(recipient, amount: Uint256)`; e.g., `permissionedMint` of a StarkGate token.
+    pub mint_selector: EntryPointSelector,
+    pub minter_address: ContractAddress,
+}
+
+/// An account to deploy at genesis, funded in both fee tokens. Its public key, if any, is passed
+/// in the constructor calldata, as the account class defines.
+#[derive(Clone, Debug)]
+pub struct GenesisAccount {
+    pub class_hash: ClassHash,
+    pub constructor_calldata: Calldata,
+    pub balance: u128,
+    pub salt: ContractAddressSalt,
+}
+
+impl GenesisAccount {
+    /// The address the account would have been deployed at by a `DeployAccount` transaction.
+    pub fn address(&self) -> StateResult<ContractAddress> {
+        Ok(calculate_contract_address(
+            self.salt,
+            self.class_hash,
+            &self.constructor_calldata,
+            ContractAddress::default(),
+        )?)
+    }
+}
+
+/// Builds the initial state of a fresh chain: the fee token contracts, funded accounts, and
+/// optionally a universal deployer and other contracts, all on top of the given declared classes.
+/// Contracts are deployed by executing their constructors; then, the genesis calls are executed
+/// in order, and finally, the accounts are funded.
+#[derive(Debug)]
+pub struct GenesisBuilder {
+    fee_tokens: Vec<(FeeType, GenesisFeeToken)>,
+    accounts: Vec<GenesisAccount>,
+    contracts: Vec<GenesisContract>,
+    calls: Vec<GenesisCall>,
+    state_reader: SnapshotStateReader,
+}
+
+impl GenesisBuilder {
+    pub fn new(eth_fee_token: GenesisFeeToken, strk_fee_token: GenesisFeeToken) -> Self {
+        Self {
+            fee_tokens: vec![(FeeType::Eth, eth_fee_token), (FeeType::Strk, strk_fee_token)],
+            accounts: Vec::new(),
+            contracts: Vec::new(),
+            calls: Vec::new(),
+            state_reader: SnapshotStateReader::default(),
+        }
+    }
+
+    /// Declares the given class; the compiled class hash is only relevant for Cairo 1 classes.
+    pub fn declare_class(
+        mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+        compiled_class_hash: Option<CompiledClassHash>,
+    ) -> Self {
+        self.state_reader.classes.insert(class_hash, contract_class);
+        if let Some(compiled_class_hash) = compiled_class_hash {
+            self.state_reader.compiled_class_hashes.insert(class_hash, compiled_class_hash);
+        }
+        self
+    }
+
+    pub fn with_account(mut self, account: GenesisAccount) -> Self {
+        self.accounts.push(account);
+        self
+    }
+
+    /// The universal deployer has no constructor arguments.
+    pub fn with_universal_deployer(self, address: ContractAddress, class_hash: ClassHash) -> Self {
+        self.with_contract(GenesisContract {
+            address,
+            class_hash,
+            constructor_calldata: Calldata::default(),
+        })
+    }
+
+    pub fn with_contract(mut self, contract: GenesisContract) -> Self {
+        self.contracts.push(contract);
+        self
+    }
+
+    pub fn with_call(mut self, call: GenesisCall) -> Self {
+        self.calls.push(call);
+        self
+    }
+
+    /// Executes the deployments and calls in the given block context; e.g., the fee tokens are
+    /// deployed at the addresses of its chain info.
+    pub fn build(
+        self,
+        block_context: &BlockContext,
+        global_contract_cache: GlobalContractCache,
+    ) -> GenesisResult<CachedState<SnapshotStateReader>> {
+        let mut state = CachedState::new(self.state_reader, global_contract_cache);
+        // Genesis executions are not part of any transaction.
+        let tx_context = TransactionContext {
+            block_context: block_context.clone(),
+            tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
+        };
+        let limit_steps_by_resources = false;
+        let mut context =
+            EntryPointExecutionContext::new_invoke(Arc::new(tx_context), limit_steps_by_resources)?;
+        let mut resources = ExecutionResources::default();
+        let initial_gas = block_context.versioned_constants().tx_initial_gas();
+        let chain_info = block_context.chain_info();
+
+        let mut deployments = Vec::new();
+        for (fee_type, fee_token) in &self.fee_tokens {
+            deployments.push(GenesisContract {
+                address: chain_info.fee_token_address(fee_type),
+                class_hash: fee_token.class_hash,
+                constructor_calldata: fee_token.constructor_calldata.clone(),
+            });
+        }
+        for account in &self.accounts {
+            deployments.push(GenesisContract {
+                address: account.address()?,
+                class_hash: account.class_hash,
+                constructor_calldata: account.constructor_calldata.clone(),
+            });
+        }
+        deployments.extend(self.contracts);
+        for deployment in deployments {
+            let ctor_context = ConstructorContext {
+                class_hash: deployment.class_hash,
+                code_address: None,
+                storage_address: deployment.address,
+                caller_address: ContractAddress::default(),
+            };
+            execute_deployment(
+                &mut state,
+                &mut resources,
+                &mut context,
+                ctor_context,
+                deployment.constructor_calldata,
+                initial_gas,
+            )
+            .map_err(|error| match error {
+                EntryPointExecutionError::StateError(error) => GenesisError::StateError(error),
+                error => {
+                    GenesisError::DeploymentFailed { contract_address: deployment.address, error }
+                }
+            })?;
+        }
+
+        let mut calls = self.calls;
+        for (fee_type, fee_token) in &self.fee_tokens {
+            for account in &self.accounts {
+                // The amount is a `Uint256`, given as (low, high).
+                calls.push(GenesisCall {
+                    caller_address: fee_token.minter_address,
+                    contract_address: chain_info.fee_token_address(fee_type),
+                    entry_point_selector: fee_token.mint_selector,
+                    calldata: Calldata(Arc::new(vec![
+                        *account.address()?.0.key(),
+                        stark_felt!(account.balance),
+                        StarkFelt::ZERO,
+                    ])),
+                });
+            }
+        }
+        for call in calls {
+            let entry_point = CallEntryPoint {
+                class_hash: None,
+                code_address: None,
+                entry_point_type: EntryPointType::External,
+                entry_point_selector: call.entry_point_selector,
+                calldata: call.calldata,
+                storage_address: call.contract_address,
+                caller_address: call.caller_address,
+                call_type: CallType::Call,
+                initial_gas,
+            };
+            entry_point.execute(&mut state, &mut resources, &mut context).map_err(|error| {
+                GenesisError::CallFailed { contract_address: call.contract_address, error }
+            })?;
+        }
+
+        Ok(state)
+    }
+}
@@ -0,0 +1,206 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{Calldata, ContractAddressSalt, Fee};
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::{
+    get_fee_token_var_address, get_storage_var_address, selector_from_name,
+};
+use crate::blockifier::genesis::{
+    GenesisAccount, GenesisBuilder, GenesisCall, GenesisContract, GenesisError, GenesisFeeToken,
+};
+use crate::context::BlockContext;
+use crate::execution::errors::EntryPointExecutionError;
+use crate::invoke_tx_args;
+use crate::state::cached_state::{GlobalContractCache, GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST};
+use crate::state::errors::StateError;
+use crate::state::state_api::StateReader;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::{create_calldata, CairoVersion, BALANCE, MAX_FEE};
+use crate::transaction::objects::FeeType;
+use crate::transaction::test_utils::account_invoke_tx;
+use crate::transaction::transactions::ExecutableTransaction;
+
+const MINTER_ADDRESS: &str = "0x2222";
+
+fn fee_token() -> GenesisFeeToken {
+    GenesisFeeToken {
+        class_hash: FeatureContract::ERC20.get_class_hash(),
+        // The (StarkGate) ERC20 has no constructor; it is initialized by a genesis call.
+        constructor_calldata: Calldata::default(),
+        mint_selector: selector_from_name("permissionedMint"),
+        minter_address: contract_address!(MINTER_ADDRESS),
+    }
+}
+
+/// Initializes the given fee token with its name, symbol, decimals and minter.
+fn initialize_call(fee_token_address: ContractAddress, symbol: &str) -> GenesisCall {
+    GenesisCall {
+        caller_address: ContractAddress::default(),
+        contract_address: fee_token_address,
+        entry_point_selector: selector_from_name("initialize"),
+        calldata: calldata![
+            stark_felt!(4_u8),
+            stark_felt!(symbol),
+            stark_felt!(symbol),
+            stark_felt!(18_u8),
+            stark_felt!(MINTER_ADDRESS)
+        ],
+    }
+}
+
+fn genesis_builder(block_context: &BlockContext) -> GenesisBuilder {
+    let chain_info = block_context.chain_info();
+    let erc20 = FeatureContract::ERC20;
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    GenesisBuilder::new(fee_token(), fee_token())
+        .declare_class(erc20.get_class_hash(), erc20.get_class(), None)
+        .declare_class(account.get_class_hash(), account.get_class(), None)
+        .with_call(initialize_call(chain_info.fee_token_address(&FeeType::Eth), "0x455448"))
+        .with_call(initialize_call(chain_info.fee_token_address(&FeeType::Strk), "0x5354524b"))
+}
+
+#[test]
+fn test_genesis_state() {
+    let block_context = BlockContext::create_for_testing();
+    let chain_info = block_context.chain_info();
+    let erc20 = FeatureContract::ERC20;
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let genesis_account = GenesisAccount {
+        class_hash: account.get_class_hash(),
+        constructor_calldata: Calldata::default(),
+        balance: BALANCE,
+        salt: ContractAddressSalt::default(),
+    };
+    let account_address = genesis_account.address().unwrap();
+    let universal_deployer_address = contract_address!("0x1111");
+    let test_contract_address = contract_address!("0x3333");
+
+    let mut state = genesis_builder(&block_context)
+        .declare_class(test_contract.get_class_hash(), test_contract.get_class(), None)
+        .with_account(genesis_account)
+        .with_universal_deployer(universal_deployer_address, erc20.get_class_hash())
+        .with_contract(GenesisContract {
+            address: test_contract_address,
+            class_hash: test_contract.get_class_hash(),
+            constructor_calldata: calldata![stark_felt!(1_u8), stark_felt!(2_u8)],
+        })
+        .build(&block_context, GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST))
+        .unwrap();
+
+    assert_eq!(state.get_class_hash_at(account_address).unwrap(), account.get_class_hash());
+    assert_eq!(
+        state.get_class_hash_at(universal_deployer_address).unwrap(),
+        erc20.get_class_hash()
+    );
+    // Constructors are executed.
+    assert_eq!(
+        state
+            .get_storage_at(test_contract_address, get_storage_var_address("my_storage_var", &[]))
+            .unwrap(),
+        stark_felt!(3_u8)
+    );
+    for (fee_type, symbol) in [(FeeType::Eth, "0x455448"), (FeeType::Strk, "0x5354524b")] {
+        let fee_token_address = chain_info.fee_token_address(&fee_type);
+        assert_eq!(state.get_class_hash_at(fee_token_address).unwrap(), erc20.get_class_hash());
+        assert_eq!(
+            state.get_fee_token_balance(account_address, fee_token_address).unwrap(),
+            (stark_felt!(BALANCE), StarkFelt::ZERO)
+        );
+        for (var_name, value) in [
+            ("ERC20_total_supply", stark_felt!(BALANCE)),
+            ("ERC20_symbol", stark_felt!(symbol)),
+            ("ERC20_decimals", stark_felt!(18_u8)),
+            ("permitted_minter", stark_felt!(MINTER_ADDRESS)),
+        ] {
+            assert_eq!(
+                state
+                    .get_storage_at(fee_token_address, get_storage_var_address(var_name, &[]))
+                    .unwrap(),
+                value
+            );
+        }
+    }
+
+    // The genesis account can pay for transactions.
+    let tx = account_invoke_tx(invoke_tx_args! {
+        sender_address: account_address,
+        calldata: create_calldata(
+            chain_info.fee_token_address(&FeeType::Eth),
+            "balanceOf",
+            &[*account_address.0.key()],
+        ),
+        max_fee: Fee(MAX_FEE),
+    });
+    let tx_execution_info = tx.execute(&mut state, &block_context, true, true).unwrap();
+    assert_eq!(tx_execution_info.revert_error, None);
+    assert_eq!(state.get_nonce_at(account_address).unwrap(), Nonce(stark_felt!(1_u8)));
+    assert_eq!(
+        state
+            .get_storage_at(
+                chain_info.fee_token_address(&FeeType::Eth),
+                get_fee_token_var_address(account_address)
+            )
+            .unwrap(),
+        stark_felt!(BALANCE - tx_execution_info.actual_fee.0)
+    );
+}
+
+#[test]
+fn test_genesis_errors() {
+    let block_context = BlockContext::create_for_testing();
+    let build = |builder: GenesisBuilder| {
+        builder.build(&block_context, GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST))
+    };
+
+    let undeclared_class_hash = class_hash!("0x1");
+    let result = build(
+        genesis_builder(&block_context)
+            .with_universal_deployer(contract_address!("0x1111"), undeclared_class_hash),
+    );
+    assert_matches!(
+        result,
+        Err(GenesisError::StateError(StateError::UndeclaredClassHash(class_hash)))
+        if class_hash == undeclared_class_hash
+    );
+
+    // Fee token addresses are taken.
+    let fee_token_address = block_context.chain_info().fee_token_address(&FeeType::Eth);
+    let result = build(
+        genesis_builder(&block_context)
+            .with_universal_deployer(fee_token_address, FeatureContract::ERC20.get_class_hash()),
+    );
+    assert_matches!(
+        result,
+        Err(GenesisError::StateError(StateError::UnavailableContractAddress(address)))
+        if address == fee_token_address
+    );
+
+    // Accounts cannot be funded by an uninitialized fee token, which has no minter.
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let result = build(
+        GenesisBuilder::new(fee_token(), fee_token())
+            .declare_class(
+                FeatureContract::ERC20.get_class_hash(),
+                FeatureContract::ERC20.get_class(),
+                None,
+            )
+            .declare_class(account.get_class_hash(), account.get_class(), None)
+            .with_account(GenesisAccount {
+                class_hash: account.get_class_hash(),
+                constructor_calldata: Calldata::default(),
+                balance: BALANCE,
+                salt: ContractAddressSalt::default(),
+            }),
+    );
+    assert_matches!(
+        result,
+        Err(GenesisError::CallFailed {
+            contract_address,
+            error: EntryPointExecutionError::VirtualMachineExecutionErrorWithTrace { .. },
+        }) if contract_address == fee_token_address
+    );
+}
@@ -0,0 +1,124 @@
+use crate::blockifier::transaction_executor::{TransactionExecutor, TransactionExecutorResult};
+use crate::context::BlockContext;
+use crate::fee::estimate::FeeEstimate;
+use crate::state::cached_state::{CachedState, CommitmentStateDiff, TransactionalState};
+use crate::state::state_api::StateReader;
+use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::objects::{
+    HasRelatedFeeType, TransactionExecutionInfo, TransactionExecutionResult,
+};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::{
+    DeclareTransaction, DeployAccountTransaction, ExecutableTransaction, InvokeTransaction,
+};
+
+#[cfg(test)]
+#[path = "simulation_test.rs"]
+pub mod simulation_test;
+
+/// Controls the execution flow of simulated transactions.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SimulationFlags {
+    /// Skips the `__validate__` (or equivalent) entry point of account transactions.
+    pub skip_validate: bool,
+    /// Skips the fee transfer (and the balance checks) of account transactions.
+    pub skip_fee_charge: bool,
+    /// Executes account transactions as queries; i.e., with the query bit set in their version.
+    pub only_query: bool,
+}
+
+/// The outcome of a simulated transaction.
+#[derive(Debug)]
+pub struct SimulatedTransaction {
+    pub execution_info: TransactionExecutionInfo,
+    /// The changes made by this transaction alone.
+    pub state_diff: CommitmentStateDiff,
+    pub fee_estimate: FeeEstimate,
+}
+
+impl<S: StateReader> TransactionExecutor<S> {
+    /// Executes the given transactions one after the other on top of the executor's state, and
+    /// reverts them all; i.e., the state is left untouched.
+    /// Stops at the first failure (which is the last returned result), since the transactions
+    /// following it would be simulated on top of an unexpected state.
+    pub fn simulate(
+        &mut self,
+        txs: Vec<Transaction>,
+        simulation_flags: SimulationFlags,
+    ) -> Vec<TransactionExecutorResult<SimulatedTransaction>> {
+        simulate_txs(&mut self.state, &self.block_context, txs, simulation_flags)
+            .into_iter()
+            .map(|simulated_tx| simulated_tx.map_err(Into::into))
+            .collect()
+    }
+}
+
+/// Same as `TransactionExecutor::simulate`, on top of the given state.
+pub fn simulate_txs<S: StateReader>(
+    state: &mut CachedState<S>,
+    block_context: &BlockContext,
+    txs: Vec<Transaction>,
+    simulation_flags: SimulationFlags,
+) -> Vec<TransactionExecutionResult<SimulatedTransaction>> {
+    let mut simulation_state = CachedState::create_transactional(state);
+    let mut simulated_txs = Vec::new();
+    for tx in txs {
+        let simulated_tx = simulate_tx(&mut simulation_state, block_context, tx, simulation_flags);
+        let is_failed = simulated_tx.is_err();
+        simulated_txs.push(simulated_tx);
+        if is_failed {
+            break;
+        }
+    }
+    simulation_state.abort();
+
+    simulated_txs
+}
+
+fn simulate_tx<S: StateReader>(
+    state: &mut TransactionalState<'_, S>,
+    block_context: &BlockContext,
+    tx: Transaction,
+    simulation_flags: SimulationFlags,
+) -> TransactionExecutionResult<SimulatedTransaction> {
+    let tx = if simulation_flags.only_query { to_query_tx(tx)? } else { tx };
+    let fee_type = match &tx {
+        Transaction::AccountTransaction(account_tx) => account_tx.fee_type(),
+        Transaction::L1HandlerTransaction(l1_handler_tx) => l1_handler_tx.fee_type(),
+    };
+
+    let mut tx_state = CachedState::create_transactional(state);
+    let charge_fee = !simulation_flags.skip_fee_charge;
+    let validate = !simulation_flags.skip_validate;
+    let execution_info = tx.execute_raw(&mut tx_state, block_context, charge_fee, validate)?;
+    let state_diff = tx_state.to_state_diff();
+    // Subsequent transactions are simulated on top of this one.
+    tx_state.commit();
+
+    let fee_estimate =
+        FeeEstimate::calculate(block_context, &execution_info.actual_resources, &fee_type)?;
+
+    Ok(SimulatedTransaction { execution_info, state_diff, fee_estimate })
+}
+
+/// Sets the query bit of account transactions; L1 handler transactions have no query version.
+fn to_query_tx(tx: Transaction) -> TransactionExecutionResult<Transaction> {
+    let account_tx = match tx {
+        Transaction::AccountTransaction(account_tx) => account_tx,
+        Transaction::L1HandlerTransaction(_) => return Ok(tx),
+    };
+
+    let query_account_tx = match account_tx {
+        AccountTransaction::Declare(tx) => AccountTransaction::Declare(
+            DeclareTransaction::new_for_query(tx.tx, tx.tx_hash, tx.class_info)?,
+        ),
+        AccountTransaction::DeployAccount(tx) => {
+            AccountTransaction::DeployAccount(DeployAccountTransaction { only_query: true, ..tx })
+        }
+        AccountTransaction::Invoke(tx) => {
+            AccountTransaction::Invoke(InvokeTransaction { only_query: true, ..tx })
+        }
+    };
+
+    Ok(Transaction::AccountTransaction(query_account_tx))
+}
@@ -0,0 +1,131 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::core::{Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::Fee;
+use starknet_api::{patricia_key, stark_felt};
+
+use crate::blockifier::simulation::SimulationFlags;
+use crate::blockifier::transaction_executor::{TransactionExecutor, TransactionExecutorError};
+use crate::bouncer::BouncerConfig;
+use crate::context::BlockContext;
+use crate::invoke_tx_args;
+use crate::state::state_api::StateReader;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{create_calldata, CairoVersion, NonceManager, BALANCE, MAX_FEE};
+use crate::transaction::errors::{TransactionExecutionError, TransactionPreValidationError};
+use crate::transaction::test_utils::{account_invoke_tx, block_context};
+use crate::transaction::transaction_execution::Transaction;
+
+fn invoke_txs(
+    test_contract: FeatureContract,
+    account_contract: FeatureContract,
+    nonces: &[Nonce],
+) -> Vec<Transaction> {
+    nonces
+        .iter()
+        .map(|&nonce| {
+            // Each transaction writes the nonce its account has after it.
+            let calldata = create_calldata(
+                test_contract.get_instance_address(0),
+                "test_storage_read_write",
+                &[stark_felt!(15_u8), nonce.try_increment().unwrap().0],
+            );
+            Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+                max_fee: Fee(MAX_FEE),
+                sender_address: account_contract.get_instance_address(0),
+                calldata,
+                nonce,
+            }))
+        })
+        .collect()
+}
+
+#[rstest]
+fn test_simulate(
+    block_context: BlockContext,
+    #[values(true, false)] skip_validate: bool,
+    #[values(true, false)] skip_fee_charge: bool,
+    #[values(true, false)] only_query: bool,
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let account_contract = FeatureContract::AccountWithoutValidations(cairo_version);
+    let state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+
+    // The second transaction is simulated on top of the first one.
+    let mut nonce_manager = NonceManager::default();
+    let sender_address = account_contract.get_instance_address(0);
+    let nonces = [nonce_manager.next(sender_address), nonce_manager.next(sender_address)];
+    let txs = invoke_txs(test_contract, account_contract, &nonces);
+    let simulation_flags = SimulationFlags { skip_validate, skip_fee_charge, only_query };
+    let simulated_txs = tx_executor.simulate(txs, simulation_flags);
+
+    assert_eq!(simulated_txs.len(), nonces.len());
+    let storage_key = StorageKey(patricia_key!(15_u8));
+    let test_contract_address = test_contract.get_instance_address(0);
+    let expected_nonces_after = [Nonce(stark_felt!(1_u8)), Nonce(stark_felt!(2_u8))];
+    for (simulated_tx, expected_nonce_after) in simulated_txs.into_iter().zip(expected_nonces_after)
+    {
+        let simulated_tx = simulated_tx.unwrap();
+        let execution_info = &simulated_tx.execution_info;
+        assert!(!execution_info.is_reverted());
+        assert_eq!(execution_info.validate_call_info.is_none(), skip_validate);
+        assert_eq!(execution_info.fee_transfer_call_info.is_none(), skip_fee_charge);
+        if !skip_fee_charge {
+            assert_eq!(simulated_tx.fee_estimate.overall_fee, execution_info.actual_fee);
+        }
+
+        // Each state diff contains the changes of its transaction alone.
+        let state_diff = &simulated_tx.state_diff;
+        assert_eq!(
+            state_diff.storage_updates[&test_contract_address][&storage_key],
+            expected_nonce_after.0
+        );
+        assert_eq!(state_diff.address_to_nonce[&sender_address], expected_nonce_after);
+    }
+
+    // The simulation is reverted.
+    let state = &tx_executor.state;
+    assert_eq!(state.get_nonce_at(sender_address).unwrap(), Nonce::default());
+    assert_eq!(
+        state.get_storage_at(test_contract_address, storage_key).unwrap(),
+        StarkFelt::default()
+    );
+}
+
+#[rstest]
+fn test_simulate_stops_at_failure(block_context: BlockContext) {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+
+    // The second transaction reuses the nonce of the first one.
+    let nonces = [Nonce(stark_felt!(0_u8)), Nonce(stark_felt!(0_u8)), Nonce(stark_felt!(1_u8))];
+    let txs = invoke_txs(test_contract, account_contract, &nonces);
+    let simulated_txs = tx_executor.simulate(txs, SimulationFlags::default());
+
+    assert_eq!(simulated_txs.len(), 2);
+    assert!(simulated_txs[0].is_ok());
+    assert_matches!(
+        simulated_txs[1],
+        Err(TransactionExecutorError::TransactionExecutionError(
+            TransactionExecutionError::TransactionPreValidationError(
+                TransactionPreValidationError::InvalidNonce { .. }
+            )
+        ))
+    );
+}
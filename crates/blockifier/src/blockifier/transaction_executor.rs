@@ -1,26 +1,39 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
 use std::sync::Arc;
+use std::thread;
 use std::vec::IntoIter;
 
 use cairo_vm::vm::runners::builtin_runner::HASH_BUILTIN_NAME;
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
-use starknet_api::core::ClassHash;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{TransactionHash, TransactionSignature};
 use thiserror::Error;
 
 use crate::blockifier::bouncer::BouncerInfo;
+use crate::bouncer::{Bouncer, BouncerConfig, BouncerWeights};
+use crate::commitment::block_hash::TransactionHashingData;
+use crate::concurrency::speculative_execution::{execute_speculatively, SpeculativeExecution};
+use crate::concurrency::versioned_state::VersionedState;
+use crate::concurrency::TxIndex;
 use crate::context::BlockContext;
 use crate::execution::call_info::{CallInfo, MessageL1CostInfo};
+use crate::execution::contract_class::ContractClass;
 use crate::fee::actual_cost::ActualCost;
 use crate::fee::gas_usage::{get_messages_gas_usage, get_onchain_data_segment_length};
 use crate::state::cached_state::{
-    CachedState, CommitmentStateDiff, StagedTransactionalState, StateChangesKeys, StorageEntry,
-    TransactionalState,
+    CachedState, CommitmentStateDiff, StagedTransactionalState, StorageEntry, TransactionalState,
 };
 use crate::state::errors::StateError;
-use crate::state::state_api::{State, StateReader};
+use crate::state::state_api::{PrefetchedState, State, StateReader, StateResult};
 use crate::transaction::account_transaction::AccountTransaction;
 use crate::transaction::errors::TransactionExecutionError;
-use crate::transaction::objects::TransactionExecutionInfo;
+use crate::transaction::objects::{
+    TransactionExecutionInfo, TransactionExecutionResult, TransactionInfoCreator,
+};
 use crate::transaction::transaction_execution::Transaction;
 use crate::transaction::transactions::{ExecutableTransaction, ValidatableTransaction};
 
@@ -30,6 +43,13 @@ pub mod transaction_executor_test;
 
 #[derive(Debug, Error)]
 pub enum TransactionExecutorError {
+    #[error("Transaction cannot be added to the current block, block capacity reached.")]
+    BlockFull,
+    #[error(
+        "Transaction weights exceed the maximum block capacity; max capacity: {max_capacity:?}, \
+         transaction weights: {tx_weights:?}."
+    )]
+    TransactionTooLarge { max_capacity: BouncerWeights, tx_weights: BouncerWeights },
     #[error(transparent)]
     StateError(#[from] StateError),
     #[error(transparent)]
@@ -42,12 +62,9 @@ pub type VisitedSegmentsMapping = Vec<(ClassHash, Vec<usize>)>;
 // TODO(Gilad): make this hold TransactionContext instead of BlockContext.
 pub struct TransactionExecutor<S: StateReader> {
     pub block_context: BlockContext,
-
-    // Maintained for counting purposes.
-    pub executed_class_hashes: HashSet<ClassHash>,
-    pub visited_storage_entries: HashSet<StorageEntry>,
-    // This member should be consistent with the state's modified keys.
-    state_changes_keys: StateChangesKeys,
+    // Tracks the block capacity, along with the information needed for counting the resources of
+    // each transaction (w.r.t. the block).
+    pub bouncer: Bouncer,
 
     // State-related fields.
     pub state: CachedState<S>,
@@ -56,20 +73,34 @@ pub struct TransactionExecutor<S: StateReader> {
     // Is `Some` only after transaction has finished executing, and before commit/revert have been
     // called. `None` while a transaction is being executed and in between transactions.
     pub staged_for_commit_state: Option<StagedTransactionalState>,
+    // The weights of the staged transaction, counted upon commit.
+    staged_tx_weights: Option<BouncerWeights>,
+    // The hashing data of the staged transaction, recorded upon commit.
+    staged_tx_hashing_data: Option<TransactionHashingData>,
+    // The data the block hash commits to, of each committed transaction, in order.
+    pub block_txs_hashing_data: Vec<TransactionHashingData>,
+    // The number of speculatively executed transactions that were re-executed upon commit.
+    n_reexecuted_txs: usize,
 }
 
 impl<S: StateReader> TransactionExecutor<S> {
-    pub fn new(state: CachedState<S>, block_context: BlockContext) -> Self {
+    pub fn new(
+        state: CachedState<S>,
+        block_context: BlockContext,
+        bouncer_config: BouncerConfig,
+    ) -> Self {
         log::debug!("Initializing Transaction Executor...");
         let tx_executor = Self {
             block_context,
-            executed_class_hashes: HashSet::<ClassHash>::new(),
-            visited_storage_entries: HashSet::<StorageEntry>::new(),
             // Note: the state might not be empty even at this point; it is the creator's
             // responsibility to tune the bouncer according to pre and post block process.
-            state_changes_keys: StateChangesKeys::default(),
+            bouncer: Bouncer::new(bouncer_config),
             state,
             staged_for_commit_state: None,
+            staged_tx_weights: None,
+            staged_tx_hashing_data: None,
+            block_txs_hashing_data: Vec::new(),
+            n_reexecuted_txs: 0,
         };
         log::debug!("Initialized Transaction Executor.");
 
@@ -84,12 +115,14 @@ impl<S: StateReader> TransactionExecutor<S> {
         tx: Transaction,
         charge_fee: bool,
     ) -> TransactionExecutorResult<(TransactionExecutionInfo, BouncerInfo)> {
-        let l1_handler_payload_size: Option<usize> =
-            if let Transaction::L1HandlerTransaction(l1_handler_tx) = &tx {
-                Some(l1_handler_tx.payload_size())
-            } else {
-                None
-            };
+        let l1_handler_payload_size = get_l1_handler_payload_size(&tx);
+        let (tx_hash, tx_signature) = get_tx_hash_and_signature(&tx);
+        if let Transaction::AccountTransaction(account_tx) = &tx {
+            // Prefetching only warms the state; values it fails to load are read on demand.
+            if let Err(error) = account_tx.prefetch_state(&self.state, &self.block_context) {
+                log::warn!("Failed to prefetch the state of transaction {}: {error}", tx_hash.0);
+            }
+        }
         let mut transactional_state = CachedState::create_transactional(&mut self.state);
         let validate = true;
 
@@ -97,62 +130,15 @@ impl<S: StateReader> TransactionExecutor<S> {
             tx.execute_raw(&mut transactional_state, &self.block_context, charge_fee, validate);
         match tx_execution_result {
             Ok(tx_execution_info) => {
-                // Prepare bouncer info; the countings here should be linear in the transactional
-                // state changes and execution info rather than the cumulative state attributes.
-
-                // TODO(Elin, 01/06/2024): consider moving Bouncer logic to a function.
-                let tx_execution_summary = tx_execution_info.summarize();
-
-                // Count message to L1 resources.
-                let call_infos: IntoIter<&CallInfo> =
-                    [&tx_execution_info.validate_call_info, &tx_execution_info.execute_call_info]
-                        .iter()
-                        .filter_map(|&call_info| call_info.as_ref())
-                        .collect::<Vec<&CallInfo>>()
-                        .into_iter();
-
-                let message_cost_info =
-                    MessageL1CostInfo::calculate(call_infos, l1_handler_payload_size)?;
-
-                let starknet_gas_usage =
-                    get_messages_gas_usage(&message_cost_info, l1_handler_payload_size);
-
-                // Count additional OS resources.
-                let mut additional_os_resources = get_casm_hash_calculation_resources(
-                    &mut transactional_state,
-                    &self.executed_class_hashes,
-                    &tx_execution_summary.executed_class_hashes,
+                let (staged_state, bouncer_info) = stage_transactional_state(
+                    transactional_state,
+                    &tx_execution_info,
+                    l1_handler_payload_size,
+                    &self.bouncer,
                 )?;
-                additional_os_resources += &get_particia_update_resources(
-                    &self.visited_storage_entries,
-                    &tx_execution_summary.visited_storage_entries,
-                )?;
-
-                // Count residual state diff size (w.r.t. the OS output encoding).
-                let tx_state_changes_keys =
-                    transactional_state.get_actual_state_changes()?.into_keys();
-                let tx_unique_state_changes_keys =
-                    tx_state_changes_keys.difference(&self.state_changes_keys);
-                // Note: block-constant felts are not counted here. so the bouncer needs to
-                // tune the size limit accordingly. E.g., the felt that encodes the number of
-                // modified contracts in a block.
-                let state_diff_size =
-                    get_onchain_data_segment_length(&tx_unique_state_changes_keys.count());
-
-                // Finalize counting logic.
-                let bouncer_info = BouncerInfo::calculate(
-                    &tx_execution_info.bouncer_resources,
-                    starknet_gas_usage,
-                    additional_os_resources,
-                    message_cost_info.message_segment_length,
-                    state_diff_size,
-                    tx_execution_summary.n_events,
-                )?;
-                self.staged_for_commit_state = Some(transactional_state.stage(
-                    tx_execution_summary.executed_class_hashes,
-                    tx_execution_summary.visited_storage_entries,
-                    tx_unique_state_changes_keys,
-                ));
+                self.stage_tx_hashing_data(tx_hash, tx_signature, &tx_execution_info)?;
+                self.staged_for_commit_state = Some(staged_state);
+                self.staged_tx_weights = Some(BouncerWeights::from(&bouncer_info));
 
                 Ok((tx_execution_info, bouncer_info))
             }
@@ -163,6 +149,42 @@ impl<S: StateReader> TransactionExecutor<S> {
         }
     }
 
+    /// Executes the given transactions one after the other, committing each successful one.
+    /// Stops once the block is full; i.e., the output may be shorter than the input, in which case
+    /// the remaining transactions should be executed in the next block.
+    pub fn execute_txs(
+        &mut self,
+        txs: Vec<Transaction>,
+        charge_fee: bool,
+    ) -> Vec<TransactionExecutorResult<(TransactionExecutionInfo, BouncerInfo)>> {
+        let mut tx_execution_results = Vec::new();
+        for tx in txs {
+            let tx_execution_result = self.execute(tx, charge_fee);
+            if !self.handle_tx_execution_result(&tx_execution_result) {
+                break;
+            }
+            tx_execution_results.push(tx_execution_result);
+        }
+
+        tx_execution_results
+    }
+
+    /// Commits a successfully executed transaction. Returns whether the execution of the batch
+    /// should proceed; i.e., whether the block is not full.
+    fn handle_tx_execution_result(
+        &mut self,
+        tx_execution_result: &TransactionExecutorResult<(TransactionExecutionInfo, BouncerInfo)>,
+    ) -> bool {
+        match tx_execution_result {
+            Ok(_) => {
+                self.commit();
+                true
+            }
+            Err(TransactionExecutorError::BlockFull) => false,
+            Err(_) => true,
+        }
+    }
+
     pub fn validate(
         &mut self,
         account_tx: &AccountTransaction,
@@ -238,21 +260,340 @@ impl<S: StateReader> TransactionExecutor<S> {
             finalized_transactional_state.global_class_hash_to_class,
         );
         self.state.update_visited_pcs_cache(&finalized_transactional_state.visited_pcs);
+        self.state.update_state_accesses(finalized_transactional_state.state_accesses);
 
-        self.executed_class_hashes.extend(&finalized_transactional_state.tx_executed_class_hashes);
-        self.visited_storage_entries
+        self.bouncer
+            .executed_class_hashes
+            .extend(&finalized_transactional_state.tx_executed_class_hashes);
+        self.bouncer
+            .visited_storage_entries
             .extend(&finalized_transactional_state.tx_visited_storage_entries);
 
         // Note: cancelling writes (0 -> 1 -> 0) will not be removed,
         // but it's fine since fee was charged for them.
-        self.state_changes_keys.extend(&finalized_transactional_state.tx_unique_state_changes_keys);
+        self.bouncer
+            .state_changes_keys
+            .extend(&finalized_transactional_state.tx_unique_state_changes_keys);
+
+        if let Some(tx_weights) = self.staged_tx_weights.take() {
+            self.bouncer
+                .update(tx_weights)
+                .expect("A staged transaction must fit in the block; verified upon execution.");
+        }
+        self.block_txs_hashing_data.extend(self.staged_tx_hashing_data.take());
 
         self.staged_for_commit_state = None
     }
 
     pub fn abort(&mut self) {
-        self.staged_for_commit_state = None
+        self.staged_for_commit_state = None;
+        self.staged_tx_weights = None;
+        self.staged_tx_hashing_data = None;
+    }
+
+    fn stage_tx_hashing_data(
+        &mut self,
+        tx_hash: TransactionHash,
+        tx_signature: Option<TransactionSignature>,
+        tx_execution_info: &TransactionExecutionInfo,
+    ) -> TransactionExecutorResult<()> {
+        let tx_hashing_data = TransactionHashingData::new(
+            tx_hash,
+            tx_signature,
+            tx_execution_info,
+            self.block_context.versioned_constants(),
+        )
+        .map_err(TransactionExecutionError::from)?;
+        self.staged_tx_hashing_data = Some(tx_hashing_data);
+
+        Ok(())
+    }
+}
+
+impl<S: StateReader + Send> TransactionExecutor<S> {
+    /// Executes the given transactions using `n_workers` threads; the output (as well as the
+    /// resulting state) is identical to that of `execute_txs`, including stopping once the block
+    /// is full.
+    ///
+    /// Transactions are executed speculatively and concurrently, on top of a versioned state, and
+    /// are committed in order as their executions complete: a transaction whose observed values
+    /// match the executor's state is committed as is; otherwise, it is re-executed on top of that
+    /// state. The fee transfer of each transaction is done upon its commit.
+    ///
+    /// If a tracer is attached to the block context, the transactions are executed sequentially,
+    /// so that the tracer observes each of them once, in order.
+    pub fn execute_txs_concurrently(
+        &mut self,
+        txs: Vec<Transaction>,
+        charge_fee: bool,
+        n_workers: usize,
+    ) -> Vec<TransactionExecutorResult<(TransactionExecutionInfo, BouncerInfo)>> {
+        if self.block_context.tracer.is_some() {
+            return self.execute_txs(txs, charge_fee);
+        }
+
+        let global_contract_cache = self.state.global_contract_cache();
+        let mut speculative_block_context = self.block_context.clone();
+        speculative_block_context.concurrency_mode = true;
+        let versioned_state = VersionedState::new(ExecutorState(self));
+        let next_tx_index = AtomicUsize::new(0);
+        let is_block_full = AtomicBool::new(false);
+        let (execution_sender, execution_receiver) = mpsc::channel();
+
+        // Transactions are picked in order, so that the writes of the preceding transactions are
+        // likely to be visible.
+        let run_worker = |execution_sender: Sender<(TxIndex, SpeculativeExecution)>| {
+            while !is_block_full.load(Ordering::SeqCst) {
+                let tx_index = next_tx_index.fetch_add(1, Ordering::SeqCst);
+                let Some(tx) = txs.get(tx_index) else { break };
+                let speculative_execution = execute_speculatively(
+                    tx.clone(),
+                    tx_index,
+                    &versioned_state,
+                    global_contract_cache.clone(),
+                    &speculative_block_context,
+                    charge_fee,
+                );
+                if execution_sender.send((tx_index, speculative_execution)).is_err() {
+                    break;
+                }
+            }
+        };
+        let run_worker = &run_worker;
+
+        thread::scope(|scope| {
+            for _ in 0..n_workers.max(1) {
+                let execution_sender = execution_sender.clone();
+                scope.spawn(move || run_worker(execution_sender));
+            }
+            drop(execution_sender);
+
+            // Executions completed ahead of their turn to be committed.
+            let mut pending_executions = HashMap::new();
+            let mut tx_execution_results = Vec::with_capacity(txs.len());
+            for (tx_index, tx) in txs.iter().enumerate() {
+                let speculative_execution = loop {
+                    if let Some(speculative_execution) = pending_executions.remove(&tx_index) {
+                        break speculative_execution;
+                    }
+                    let (executed_tx_index, speculative_execution) =
+                        execution_receiver.recv().expect("Execution worker panicked.");
+                    pending_executions.insert(executed_tx_index, speculative_execution);
+                };
+
+                let mut executor_state = versioned_state.state();
+                let executor = &mut executor_state.0;
+                let tx_execution_result =
+                    executor.commit_speculative_execution(tx, speculative_execution, charge_fee);
+                if !executor.handle_tx_execution_result(&tx_execution_result) {
+                    is_block_full.store(true, Ordering::SeqCst);
+                    break;
+                }
+                tx_execution_results.push(tx_execution_result);
+            }
+
+            tx_execution_results
+        })
+    }
+
+    /// Stages a speculatively executed transaction on top of the executor's state; the execution
+    /// is used if the values it observed are up to date, and is redone otherwise.
+    fn commit_speculative_execution(
+        &mut self,
+        tx: &Transaction,
+        speculative_execution: SpeculativeExecution,
+        charge_fee: bool,
+    ) -> TransactionExecutorResult<(TransactionExecutionInfo, BouncerInfo)> {
+        // Only the accesses of the staged execution are recorded, not the validation reads.
+        let read_set = &speculative_execution.read_set;
+        if self.state.read_untracked(|state| read_set.validate(state)) {
+            self.stage_speculative_execution(tx, speculative_execution.result, charge_fee)
+        } else {
+            self.n_reexecuted_txs += 1;
+            self.execute(tx.clone(), charge_fee)
+        }
+    }
+
+    /// Stages the writes of a valid speculative execution on top of the executor's state, followed
+    /// by the fee transfer, as if the transaction was executed on it.
+    fn stage_speculative_execution(
+        &mut self,
+        tx: &Transaction,
+        speculative_execution_result: TransactionExecutionResult<(
+            TransactionExecutionInfo,
+            StagedTransactionalState,
+        )>,
+        charge_fee: bool,
+    ) -> TransactionExecutorResult<(TransactionExecutionInfo, BouncerInfo)> {
+        let (mut tx_execution_info, speculative_state) = speculative_execution_result?;
+        let mut transactional_state = CachedState::create_transactional(&mut self.state);
+        transactional_state.restore_cache(speculative_state.cache);
+        transactional_state.update_contract_class_caches(
+            speculative_state.class_hash_to_class,
+            speculative_state.global_class_hash_to_class,
+        );
+        transactional_state.update_visited_pcs_cache(&speculative_state.visited_pcs);
+
+        if let Transaction::AccountTransaction(account_tx) = tx {
+            let tx_context = Arc::new(self.block_context.to_tx_context(account_tx));
+            let fee_transfer_result = account_tx.handle_fee(
+                &mut transactional_state,
+                tx_context,
+                tx_execution_info.actual_fee,
+                charge_fee,
+            );
+            tx_execution_info.fee_transfer_call_info = match fee_transfer_result {
+                Ok(fee_transfer_call_info) => fee_transfer_call_info,
+                Err(error) => {
+                    transactional_state.abort();
+                    return Err(TransactionExecutorError::TransactionExecutionError(error));
+                }
+            };
+            tx_execution_info.state_changes = transactional_state.get_actual_state_changes()?;
+            tx_execution_info.state_accesses.extend(&transactional_state.get_state_accesses());
+        }
+        transactional_state.update_state_accesses(speculative_state.state_accesses);
+
+        let (staged_state, bouncer_info) = stage_transactional_state(
+            transactional_state,
+            &tx_execution_info,
+            get_l1_handler_payload_size(tx),
+            &self.bouncer,
+        )?;
+        let (tx_hash, tx_signature) = get_tx_hash_and_signature(tx);
+        self.stage_tx_hashing_data(tx_hash, tx_signature, &tx_execution_info)?;
+        self.staged_for_commit_state = Some(staged_state);
+        self.staged_tx_weights = Some(BouncerWeights::from(&bouncer_info));
+
+        Ok((tx_execution_info, bouncer_info))
+    }
+}
+
+/// Exposes the executor's state to speculative executions, while the executor commits
+/// transactions on top of it. The reads are not recorded in the accesses of the executor's state,
+/// as the accesses of the staged speculative executions are merged into it instead.
+struct ExecutorState<'a, S: StateReader>(&'a mut TransactionExecutor<S>);
+
+impl<S: StateReader> StateReader for ExecutorState<'_, S> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.0.state.read_untracked(|state| state.get_storage_at(contract_address, key))
     }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.0.state.read_untracked(|state| state.get_nonce_at(contract_address))
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.0.state.read_untracked(|state| state.get_class_hash_at(contract_address))
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.0.state.read_untracked(|state| state.get_compiled_contract_class(class_hash))
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.0.state.read_untracked(|state| state.get_compiled_class_hash(class_hash))
+    }
+
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        self.0.state.read_untracked(|state| {
+            state.prefetch(storage_entries, contract_addresses, class_hashes)
+        })
+    }
+}
+
+fn get_l1_handler_payload_size(tx: &Transaction) -> Option<usize> {
+    if let Transaction::L1HandlerTransaction(l1_handler_tx) = tx {
+        Some(l1_handler_tx.payload_size())
+    } else {
+        None
+    }
+}
+
+/// Returns the hash and the signature of the given transaction; `L1Handler` transactions are not
+/// signed.
+fn get_tx_hash_and_signature(tx: &Transaction) -> (TransactionHash, Option<TransactionSignature>) {
+    let tx_info = tx.create_tx_info();
+    let tx_signature = match tx {
+        Transaction::AccountTransaction(_) => Some(tx_info.signature()),
+        Transaction::L1HandlerTransaction(_) => None,
+    };
+
+    (tx_info.transaction_hash(), tx_signature)
+}
+
+/// Computes the bouncer information of an executed transaction and detaches its transactional
+/// state, awaiting commit. Fails if the transaction does not fit in the block.
+/// Note: the countings here should be linear in the transactional state changes and execution
+/// info rather than the cumulative state attributes.
+fn stage_transactional_state<S: StateReader>(
+    mut transactional_state: TransactionalState<'_, S>,
+    tx_execution_info: &TransactionExecutionInfo,
+    l1_handler_payload_size: Option<usize>,
+    bouncer: &Bouncer,
+) -> TransactionExecutorResult<(StagedTransactionalState, BouncerInfo)> {
+    let tx_execution_summary = tx_execution_info.summarize();
+
+    // Count message to L1 resources.
+    let call_infos: IntoIter<&CallInfo> =
+        [&tx_execution_info.validate_call_info, &tx_execution_info.execute_call_info]
+            .iter()
+            .filter_map(|&call_info| call_info.as_ref())
+            .collect::<Vec<&CallInfo>>()
+            .into_iter();
+
+    let message_cost_info = MessageL1CostInfo::calculate(call_infos, l1_handler_payload_size)?;
+
+    let starknet_gas_usage = get_messages_gas_usage(&message_cost_info, l1_handler_payload_size);
+
+    // Count additional OS resources.
+    let mut additional_os_resources = get_casm_hash_calculation_resources(
+        &mut transactional_state,
+        &bouncer.executed_class_hashes,
+        &tx_execution_summary.executed_class_hashes,
+    )?;
+    additional_os_resources += &get_particia_update_resources(
+        &bouncer.visited_storage_entries,
+        &tx_execution_summary.visited_storage_entries,
+    )?;
+
+    // Count residual state diff size (w.r.t. the OS output encoding).
+    let tx_state_changes_keys = tx_execution_info.state_changes.clone().into_keys();
+    let tx_unique_state_changes_keys =
+        tx_state_changes_keys.difference(&bouncer.state_changes_keys);
+    // Note: block-constant felts are not counted here. so the bouncer needs to
+    // tune the size limit accordingly. E.g., the felt that encodes the number of
+    // modified contracts in a block.
+    let state_diff_size = get_onchain_data_segment_length(&tx_unique_state_changes_keys.count());
+
+    // Finalize counting logic.
+    let bouncer_info = BouncerInfo::calculate(
+        &tx_execution_info.bouncer_resources,
+        starknet_gas_usage,
+        additional_os_resources,
+        message_cost_info.message_segment_length,
+        state_diff_size,
+        tx_execution_summary.n_events,
+    )?;
+    // Make sure the transaction fits in the block.
+    bouncer.get_capacity_after_update(BouncerWeights::from(&bouncer_info))?;
+
+    let staged_state = transactional_state.stage(
+        tx_execution_summary.executed_class_hashes,
+        tx_execution_summary.visited_storage_entries,
+        tx_unique_state_changes_keys,
+    );
+
+    Ok((staged_state, bouncer_info))
 }
 
 /// Returns the estimated VM resources for Casm hash calculation (done by the OS), of the newly
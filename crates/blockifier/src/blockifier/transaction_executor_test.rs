@@ -1,20 +1,24 @@
 use pretty_assertions::assert_eq;
 use rstest::rstest;
-use starknet_api::hash::StarkFelt;
-use starknet_api::stark_felt;
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
 use starknet_api::transaction::{Fee, TransactionVersion};
+use starknet_api::{contract_address, patricia_key, stark_felt};
 
 use crate::blockifier::bouncer::BouncerInfo;
-use crate::blockifier::transaction_executor::TransactionExecutor;
+use crate::blockifier::transaction_executor::{ExecutorState, TransactionExecutor};
+use crate::bouncer::{BouncerConfig, BouncerWeights};
 use crate::context::BlockContext;
-use crate::state::cached_state::CachedState;
+use crate::state::cached_state::{CachedState, StateAccesses};
 use crate::state::state_api::StateReader;
 use crate::test_utils::contracts::FeatureContract;
 use crate::test_utils::declare::declare_tx;
 use crate::test_utils::deploy_account::deploy_account_tx;
 use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::prefetch_recording_reader::PrefetchRecordingReader;
 use crate::test_utils::{
-    create_calldata, CairoVersion, NonceManager, BALANCE, DEFAULT_STRK_L1_GAS_PRICE,
+    create_calldata, CairoVersion, NonceManager, BALANCE, DEFAULT_STRK_L1_GAS_PRICE, MAX_FEE,
 };
 use crate::transaction::account_transaction::AccountTransaction;
 use crate::transaction::test_utils::{
@@ -31,7 +35,7 @@ fn tx_executor_test_body<S: StateReader>(
     charge_fee: bool,
     expected_bouncer_info: BouncerInfo,
 ) {
-    let mut tx_executor = TransactionExecutor::new(state, block_context);
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
     // TODO(Arni, 30/03/2024): Consider adding a test for the transaction execution info. If A test
     // should not be added, rename the test to `test_bouncer_info`.
     // TODO(Arni, 30/03/2024): Test all fields of bouncer info.
@@ -199,6 +203,37 @@ fn test_invoke(
     tx_executor_test_body(state, block_context, tx, charge_fee, expected_bouncer_info);
 }
 
+#[rstest]
+fn test_invoke_with_failing_prefetch(block_context: BlockContext) {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let state_reader = PrefetchRecordingReader::new(test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    ))
+    .with_failing_prefetch();
+    let mut tx_executor = TransactionExecutor::new(
+        CachedState::from(state_reader),
+        block_context,
+        BouncerConfig::max(),
+    );
+
+    // A failed prefetch does not fail the transaction; its values are read during execution.
+    let tx = Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+        sender_address: account_contract.get_instance_address(0),
+        calldata: create_calldata(
+            test_contract.get_instance_address(0),
+            "return_result",
+            &[stark_felt!(2_u8)],
+        ),
+        max_fee: Fee(MAX_FEE),
+    }));
+    let (tx_execution_info, _) = tx_executor.execute(tx, true).unwrap();
+    assert_eq!(tx_execution_info.revert_error, None);
+    assert!(!tx_executor.state.state.batches.borrow().is_empty());
+}
+
 #[rstest]
 fn test_l1_handler(block_context: BlockContext, #[values(true, false)] charge_fee: bool) {
     let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
@@ -216,3 +251,208 @@ fn test_l1_handler(block_context: BlockContext, #[values(true, false)] charge_fe
     };
     tx_executor_test_body(state, block_context, tx, charge_fee, expected_bouncer_info);
 }
+
+#[rstest]
+fn test_execute_txs_concurrently(
+    block_context: BlockContext,
+    #[values(true, false)] charge_fee: bool,
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let account_contract = FeatureContract::AccountWithoutValidations(cairo_version);
+    let n_accounts = 3;
+    let create_state = || {
+        test_state(
+            &block_context.chain_info,
+            BALANCE,
+            &[(test_contract, 1), (account_contract, n_accounts)],
+        )
+    };
+
+    // Transactions sent by the same account depend on each other (via the nonce), and all
+    // transactions write to the same storage cell.
+    let mut nonce_manager = NonceManager::default();
+    let txs: Vec<Transaction> = (0..3 * n_accounts)
+        .map(|i| {
+            let sender_address = account_contract.get_instance_address(i % n_accounts);
+            let calldata = create_calldata(
+                test_contract.get_instance_address(0),
+                "test_storage_read_write",
+                &[stark_felt!(15_u8), stark_felt!(u32::from(i))],
+            );
+            Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+                sender_address,
+                calldata,
+                nonce: nonce_manager.next(sender_address),
+            }))
+        })
+        .collect();
+
+    let mut sequential_executor =
+        TransactionExecutor::new(create_state(), block_context.clone(), BouncerConfig::max());
+    let sequential_results = sequential_executor.execute_txs(txs.clone(), charge_fee);
+    let mut concurrent_executor =
+        TransactionExecutor::new(create_state(), block_context.clone(), BouncerConfig::max());
+    let n_workers = 4;
+    let concurrent_results =
+        concurrent_executor.execute_txs_concurrently(txs, charge_fee, n_workers);
+
+    assert_eq!(sequential_results.len(), concurrent_results.len());
+    for (sequential_result, concurrent_result) in
+        sequential_results.into_iter().zip(concurrent_results)
+    {
+        assert_eq!(sequential_result.unwrap(), concurrent_result.unwrap());
+    }
+    let (sequential_state_diff, _) = sequential_executor.finalize(false).unwrap();
+    let (concurrent_state_diff, _) = concurrent_executor.finalize(false).unwrap();
+    assert_eq!(sequential_state_diff, concurrent_state_diff);
+    assert_eq!(
+        sequential_executor.block_txs_hashing_data,
+        concurrent_executor.block_txs_hashing_data
+    );
+
+    // Speculative and validation reads are not recorded in the accesses of the block.
+    let without_read_counts = |accesses: StateAccesses| StateAccesses {
+        n_cache_hits: 0,
+        n_state_reader_hits: 0,
+        ..accesses
+    };
+    assert_eq!(
+        without_read_counts(sequential_executor.state.get_state_accesses()),
+        without_read_counts(concurrent_executor.state.get_state_accesses())
+    );
+}
+
+#[rstest]
+fn test_executor_state_reads_are_untracked(block_context: BlockContext) {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let mut tx_executor =
+        TransactionExecutor::new(CachedState::default(), block_context, BouncerConfig::max());
+
+    // Reads on behalf of speculative executions only count the values they load.
+    let executor_state = ExecutorState(&mut tx_executor);
+    executor_state.get_storage_at(contract_address, key).unwrap();
+    executor_state.get_storage_at(contract_address, key).unwrap();
+    executor_state.get_nonce_at(contract_address).unwrap();
+    assert_eq!(
+        tx_executor.state.get_state_accesses(),
+        StateAccesses { n_state_reader_hits: 2, ..Default::default() }
+    );
+}
+
+#[rstest]
+fn test_execute_independent_txs_concurrently_with_fee(
+    block_context: BlockContext,
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let account_contract = FeatureContract::AccountWithoutValidations(cairo_version);
+    let n_accounts = 8;
+    let create_state = || {
+        test_state(
+            &block_context.chain_info,
+            BALANCE,
+            &[(test_contract, 1), (account_contract, n_accounts)],
+        )
+    };
+
+    // Each account writes to its own storage cell; the transactions only share the sequencer, to
+    // which they all transfer their fee.
+    let txs: Vec<Transaction> = (0..n_accounts)
+        .map(|i| {
+            let calldata = create_calldata(
+                test_contract.get_instance_address(0),
+                "test_storage_read_write",
+                &[stark_felt!(u32::from(i) + 1), stark_felt!(15_u8)],
+            );
+            Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+                sender_address: account_contract.get_instance_address(i),
+                calldata,
+                max_fee: Fee(MAX_FEE),
+            }))
+        })
+        .collect();
+
+    let charge_fee = true;
+    let mut sequential_executor =
+        TransactionExecutor::new(create_state(), block_context.clone(), BouncerConfig::max());
+    let sequential_results = sequential_executor.execute_txs(txs.clone(), charge_fee);
+    let mut concurrent_executor =
+        TransactionExecutor::new(create_state(), block_context.clone(), BouncerConfig::max());
+    let n_workers = 4;
+    let concurrent_results =
+        concurrent_executor.execute_txs_concurrently(txs, charge_fee, n_workers);
+
+    assert_eq!(concurrent_results.len(), usize::from(n_accounts));
+    for (sequential_result, concurrent_result) in
+        sequential_results.into_iter().zip(concurrent_results)
+    {
+        let concurrent_execution_info = concurrent_result.unwrap();
+        assert!(concurrent_execution_info.0.fee_transfer_call_info.is_some());
+        assert_eq!(sequential_result.unwrap(), concurrent_execution_info);
+    }
+    // The fee transfers do not invalidate the speculative executions.
+    assert_eq!(concurrent_executor.n_reexecuted_txs, 0);
+    let (sequential_state_diff, _) = sequential_executor.finalize(false).unwrap();
+    let (concurrent_state_diff, _) = concurrent_executor.finalize(false).unwrap();
+    assert_eq!(sequential_state_diff, concurrent_state_diff);
+}
+
+#[rstest]
+fn test_execute_txs_stops_at_block_capacity(
+    block_context: BlockContext,
+    #[values(true, false)] concurrent: bool,
+) {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+    let sender_address = account_contract.get_instance_address(0);
+
+    // Each transaction emits a single event, and the block can hold only two events.
+    let mut nonce_manager = NonceManager::default();
+    let txs: Vec<Transaction> = (0..3)
+        .map(|_| {
+            let calldata = create_calldata(
+                test_contract.get_instance_address(0),
+                "test_emit_events",
+                &[
+                    stark_felt!(1_u32), // events_number.
+                    stark_felt!(0_u32), // keys length.
+                    stark_felt!(0_u32), // data length.
+                ],
+            );
+            Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+                sender_address,
+                calldata,
+                nonce: nonce_manager.next(sender_address),
+            }))
+        })
+        .collect();
+    let bouncer_config = BouncerConfig {
+        block_max_capacity: BouncerWeights { n_events: 2, ..BouncerWeights::max() },
+    };
+    let mut tx_executor = TransactionExecutor::new(state, block_context, bouncer_config);
+
+    let charge_fee = true;
+    let tx_execution_results = if concurrent {
+        let n_workers = 2;
+        tx_executor.execute_txs_concurrently(txs, charge_fee, n_workers)
+    } else {
+        tx_executor.execute_txs(txs, charge_fee)
+    };
+    assert_eq!(tx_execution_results.len(), 2);
+    assert!(tx_execution_results.iter().all(|tx_execution_result| tx_execution_result.is_ok()));
+    assert_eq!(tx_executor.bouncer.capacity().n_events, 0);
+
+    // Only the transactions committed to the block are recorded for its hash.
+    let block_txs_hashing_data = &tx_executor.block_txs_hashing_data;
+    assert_eq!(block_txs_hashing_data.len(), 2);
+    assert!(block_txs_hashing_data.iter().all(|tx_hashing_data| {
+        tx_hashing_data.transaction_signature.is_some() && tx_hashing_data.events.len() == 1
+    }));
+}
@@ -1,9 +1,16 @@
 use std::collections::HashSet;
+use std::path::Path;
+use std::{fs, io};
 
+use cairo_vm::serde::deserialize_program::BuiltinName;
 use serde::Deserialize;
 use starknet_api::core::ClassHash;
+use thiserror::Error;
 
-use crate::blockifier::transaction_executor::TransactionExecutorResult;
+use crate::blockifier::bouncer::BouncerInfo;
+use crate::blockifier::transaction_executor::{
+    TransactionExecutorError, TransactionExecutorResult,
+};
 use crate::state::cached_state::{StateChangesKeys, StorageEntry, TransactionalState};
 use crate::state::state_api::StateReader;
 use crate::transaction::objects::TransactionExecutionInfo;
@@ -26,15 +33,44 @@ macro_rules! impl_checked_sub {
     };
 }
 
+#[derive(Debug, Error)]
+pub enum BouncerConfigError {
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+    #[error("JSON file cannot be serialized into BouncerConfig: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+/// The limits on the resources a block may consume.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub struct BouncerConfig {
+    pub block_max_capacity: BouncerWeights,
+}
+
+impl BouncerConfig {
+    /// A configuration with no effective limits.
+    pub fn max() -> Self {
+        Self { block_max_capacity: BouncerWeights::max() }
+    }
+}
+
+impl TryFrom<&Path> for BouncerConfig {
+    type Error = BouncerConfigError;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_reader(fs::File::open(path)?)?)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, derive_more::Sub, Deserialize, PartialEq)]
 /// Represents the execution resources counted throughout block creation.
 pub struct BouncerWeights {
-    builtin_count: BuiltinCount,
-    gas: usize,
-    message_segment_length: usize,
-    n_events: usize,
-    n_steps: usize,
-    state_diff_size: usize,
+    pub builtin_count: BuiltinCount,
+    pub gas: usize,
+    pub message_segment_length: usize,
+    pub n_events: usize,
+    pub n_steps: usize,
+    pub state_diff_size: usize,
 }
 
 impl BouncerWeights {
@@ -46,21 +82,71 @@ impl BouncerWeights {
         n_steps,
         state_diff_size
     );
+
+    pub fn max() -> Self {
+        Self {
+            builtin_count: BuiltinCount::max(),
+            gas: usize::MAX,
+            message_segment_length: usize::MAX,
+            n_events: usize::MAX,
+            n_steps: usize::MAX,
+            state_diff_size: usize::MAX,
+        }
+    }
+}
+
+impl From<&BouncerInfo> for BouncerWeights {
+    fn from(bouncer_info: &BouncerInfo) -> Self {
+        let builtin_instance_counter = &bouncer_info.execution_resources.builtin_instance_counter;
+        let get_builtin_count = |builtin_name: BuiltinName| {
+            builtin_instance_counter.get(builtin_name.name()).copied().unwrap_or_default()
+        };
+
+        Self {
+            builtin_count: BuiltinCount {
+                bitwise: get_builtin_count(BuiltinName::bitwise),
+                ecdsa: get_builtin_count(BuiltinName::ecdsa),
+                ec_op: get_builtin_count(BuiltinName::ec_op),
+                keccak: get_builtin_count(BuiltinName::keccak),
+                pedersen: get_builtin_count(BuiltinName::pedersen),
+                poseidon: get_builtin_count(BuiltinName::poseidon),
+                range_check: get_builtin_count(BuiltinName::range_check),
+            },
+            gas: bouncer_info.gas_weight,
+            message_segment_length: bouncer_info.message_segment_length,
+            n_events: bouncer_info.n_events,
+            // Memory holes are already counted as steps by the bouncer info.
+            n_steps: bouncer_info.execution_resources.n_steps,
+            state_diff_size: bouncer_info.state_diff_size,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, derive_more::Sub, Deserialize, PartialEq)]
 pub struct BuiltinCount {
-    bitwise: usize,
-    ecdsa: usize,
-    ec_op: usize,
-    keccak: usize,
-    pedersen: usize,
-    poseidon: usize,
-    range_check: usize,
+    pub bitwise: usize,
+    pub ecdsa: usize,
+    pub ec_op: usize,
+    pub keccak: usize,
+    pub pedersen: usize,
+    pub poseidon: usize,
+    pub range_check: usize,
 }
 
 impl BuiltinCount {
     impl_checked_sub!(bitwise, ecdsa, ec_op, keccak, pedersen, poseidon, range_check);
+
+    pub fn max() -> Self {
+        Self {
+            bitwise: usize::MAX,
+            ecdsa: usize::MAX,
+            ec_op: usize::MAX,
+            keccak: usize::MAX,
+            pedersen: usize::MAX,
+            poseidon: usize::MAX,
+            range_check: usize::MAX,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -68,18 +154,45 @@ pub struct Bouncer {
     pub executed_class_hashes: HashSet<ClassHash>,
     pub visited_storage_entries: HashSet<StorageEntry>,
     pub state_changes_keys: StateChangesKeys,
+    pub bouncer_config: BouncerConfig,
     // The capacity is calculated based of the values of the other Bouncer fields.
     capacity: BouncerWeights,
 }
 
 impl Bouncer {
-    pub fn new(capacity: BouncerWeights) -> Self {
+    pub fn new(bouncer_config: BouncerConfig) -> Self {
         Bouncer {
             executed_class_hashes: HashSet::new(),
             state_changes_keys: StateChangesKeys::default(),
             visited_storage_entries: HashSet::new(),
-            capacity,
+            bouncer_config,
+            capacity: bouncer_config.block_max_capacity,
+        }
+    }
+
+    /// Returns the capacity left in the block.
+    pub fn capacity(&self) -> BouncerWeights {
+        self.capacity
+    }
+
+    /// Returns the capacity that would be left in the block after adding a transaction with the
+    /// given weights, or an error if the transaction does not fit in it.
+    pub fn get_capacity_after_update(
+        &self,
+        tx_weights: BouncerWeights,
+    ) -> TransactionExecutorResult<BouncerWeights> {
+        let max_capacity = self.bouncer_config.block_max_capacity;
+        if max_capacity.checked_sub(tx_weights).is_none() {
+            return Err(TransactionExecutorError::TransactionTooLarge { max_capacity, tx_weights });
         }
+
+        self.capacity.checked_sub(tx_weights).ok_or(TransactionExecutorError::BlockFull)
+    }
+
+    /// Adds a transaction with the given weights to the block; the transaction must fit in it.
+    pub fn update(&mut self, tx_weights: BouncerWeights) -> TransactionExecutorResult<()> {
+        self.capacity = self.get_capacity_after_update(tx_weights)?;
+        Ok(())
     }
 
     pub fn create_transactional(self) -> TransactionalBouncer {
@@ -104,11 +217,17 @@ pub struct TransactionalBouncer {
 
 impl TransactionalBouncer {
     pub fn new(parent: Bouncer) -> TransactionalBouncer {
-        let capacity = parent.capacity;
-        TransactionalBouncer { bouncer: parent, transactional: Bouncer::new(capacity) }
+        let mut transactional = Bouncer::new(parent.bouncer_config);
+        transactional.capacity = parent.capacity;
+        TransactionalBouncer { bouncer: parent, transactional }
     }
 
-    // TODO update function (in the next PR)
+    /// Updates the capacity of the transactional bouncer, w.r.t. its parent's capacity.
+    /// Fails if the transaction does not fit in the block.
+    pub fn update(&mut self, tx_weights: BouncerWeights) -> TransactionExecutorResult<()> {
+        self.transactional.capacity = self.bouncer.get_capacity_after_update(tx_weights)?;
+        Ok(())
+    }
 
     pub fn update_auxiliary_info<S: StateReader>(
         &mut self,
@@ -1,6 +1,9 @@
 use std::ops::Sub;
 
-use crate::bouncer::{Bouncer, BouncerWeights, BuiltinCount};
+use assert_matches::assert_matches;
+
+use crate::blockifier::transaction_executor::TransactionExecutorError;
+use crate::bouncer::{Bouncer, BouncerConfig, BouncerWeights, BuiltinCount};
 
 #[test]
 fn test_block_weights_sub_checked() {
@@ -99,7 +102,7 @@ fn test_transactional_bouncer() {
         state_diff_size: 2,
     };
 
-    let bouncer = Bouncer::new(initial_bouncer_weights);
+    let bouncer = Bouncer::new(BouncerConfig { block_max_capacity: initial_bouncer_weights });
     let mut transactional_bouncer = bouncer.create_transactional();
     transactional_bouncer.transactional.capacity = weights_to_commit;
 
@@ -111,3 +114,75 @@ fn test_transactional_bouncer() {
     let final_weights = transactional_bouncer.commit();
     assert!(final_weights.capacity == weights_to_commit);
 }
+
+#[test]
+fn test_bouncer_update() {
+    let block_max_capacity = BouncerWeights { n_events: 10, n_steps: 10, ..BouncerWeights::max() };
+    let mut bouncer = Bouncer::new(BouncerConfig { block_max_capacity });
+
+    // A transaction that fits in the block.
+    let tx_weights = BouncerWeights { n_events: 7, ..Default::default() };
+    bouncer.update(tx_weights).unwrap();
+    let expected_capacity = block_max_capacity.sub(tx_weights);
+    assert_eq!(bouncer.capacity(), expected_capacity);
+
+    // A transaction that does not fit in the remaining capacity.
+    let result = bouncer.update(BouncerWeights { n_events: 4, ..Default::default() });
+    assert_matches!(result, Err(TransactionExecutorError::BlockFull));
+    assert_eq!(bouncer.capacity(), expected_capacity);
+
+    // A transaction that does not fit in any block.
+    let tx_weights = BouncerWeights { n_steps: 11, ..Default::default() };
+    let result = bouncer.update(tx_weights);
+    assert_matches!(
+        result,
+        Err(TransactionExecutorError::TransactionTooLarge { max_capacity, tx_weights: weights })
+        if max_capacity == block_max_capacity && weights == tx_weights
+    );
+    assert_eq!(bouncer.capacity(), expected_capacity);
+}
+
+#[test]
+fn test_bouncer_config_deserialization() {
+    let bouncer_config_json = r#"{
+        "block_max_capacity": {
+            "builtin_count": {
+                "bitwise": 1,
+                "ecdsa": 2,
+                "ec_op": 3,
+                "keccak": 4,
+                "pedersen": 5,
+                "poseidon": 6,
+                "range_check": 7
+            },
+            "gas": 8,
+            "message_segment_length": 9,
+            "n_events": 10,
+            "n_steps": 11,
+            "state_diff_size": 12
+        }
+    }"#;
+    let bouncer_config: BouncerConfig = serde_json::from_str(bouncer_config_json).unwrap();
+
+    assert_eq!(
+        bouncer_config,
+        BouncerConfig {
+            block_max_capacity: BouncerWeights {
+                builtin_count: BuiltinCount {
+                    bitwise: 1,
+                    ecdsa: 2,
+                    ec_op: 3,
+                    keccak: 4,
+                    pedersen: 5,
+                    poseidon: 6,
+                    range_check: 7,
+                },
+                gas: 8,
+                message_segment_length: 9,
+                n_events: 10,
+                n_steps: 11,
+                state_diff_size: 12,
+            },
+        }
+    );
+}
@@ -0,0 +1,5 @@
+pub mod block_hash;
+pub mod errors;
+pub mod node_store;
+pub mod patricia_trie;
+pub mod state_commitment;
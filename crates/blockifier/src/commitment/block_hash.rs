@@ -0,0 +1,396 @@
+use starknet_api::block::BlockHash;
+use starknet_api::core::{ClassHash, EthAddress, GlobalRoot};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{Event, Fee, MessageToL1, TransactionHash, TransactionSignature};
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+use crate::abi::abi_utils::starknet_keccak;
+use crate::blockifier::block::BlockInfo;
+use crate::commitment::errors::{CommitmentError, CommitmentResult};
+use crate::commitment::node_store::InMemoryNodeStore;
+use crate::commitment::patricia_trie::{PatriciaTrie, TrieHashFunction};
+use crate::commitment::state_commitment::short_string;
+use crate::execution::execution_utils::felt_to_stark_felt;
+use crate::fee::fee_utils::calculate_tx_gas_vector;
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::transaction::objects::{GasVector, TransactionExecutionInfo, TransactionFeeResult};
+use crate::versioned_constants::VersionedConstants;
+
+#[cfg(test)]
+#[path = "block_hash_test.rs"]
+pub mod test;
+
+const BLOCK_HASH_VERSION: &[u8] = b"STARKNET_BLOCK_HASH0";
+const STATE_DIFF_HASH_VERSION: &[u8] = b"STARKNET_STATE_DIFF0";
+/// The first Starknet version whose block hashes are calculated as here; those of earlier versions
+/// are (legacy) Pedersen hashes, which are not supported.
+const MIN_STARKNET_VERSION: [u64; 3] = [0, 13, 2];
+/// The height of the transaction, event and receipt tries, whose keys are the leaf indices.
+const COMMITMENT_TRIE_HEIGHT: u8 = 64;
+
+/// The data of an executed transaction that the hash of its block commits to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionHashingData {
+    pub transaction_hash: TransactionHash,
+    /// [None] for `L1Handler`.
+    pub transaction_signature: Option<TransactionSignature>,
+    pub events: Vec<Event>,
+    pub messages_sent: Vec<MessageToL1>,
+    pub actual_fee: Fee,
+    /// [None] if the transaction execution was successful.
+    pub revert_error: Option<String>,
+    pub gas_consumed: GasVector,
+}
+
+impl TransactionHashingData {
+    pub fn new(
+        transaction_hash: TransactionHash,
+        transaction_signature: Option<TransactionSignature>,
+        tx_execution_info: &TransactionExecutionInfo,
+        versioned_constants: &VersionedConstants,
+    ) -> TransactionFeeResult<Self> {
+        let mut events = Vec::new();
+        let mut messages_sent = Vec::new();
+        // Events and messages are ordered within each execution phase (validation, execution and
+        // fee transfer); the emitter of each is the contract in whose context it was emitted.
+        for call_info in tx_execution_info.non_optional_call_infos() {
+            let mut phase_events = Vec::new();
+            let mut phase_messages = Vec::new();
+            for inner_call in call_info.iter() {
+                let from_address = inner_call.call.storage_address;
+                phase_events.extend(inner_call.execution.events.iter().map(|ordered_event| {
+                    let event = Event { from_address, content: ordered_event.event.clone() };
+                    (ordered_event.order, event)
+                }));
+                phase_messages.extend(inner_call.execution.l2_to_l1_messages.iter().map(
+                    |ordered_message| {
+                        let message = MessageToL1 {
+                            from_address,
+                            to_address: ordered_message.message.to_address,
+                            payload: ordered_message.message.payload.clone(),
+                        };
+                        (ordered_message.order, message)
+                    },
+                ));
+            }
+            phase_events.sort_by_key(|(order, _)| *order);
+            phase_messages.sort_by_key(|(order, _)| *order);
+            events.extend(phase_events.into_iter().map(|(_, event)| event));
+            messages_sent.extend(phase_messages.into_iter().map(|(_, message)| message));
+        }
+
+        Ok(Self {
+            transaction_hash,
+            transaction_signature,
+            events,
+            messages_sent,
+            actual_fee: tx_execution_info.actual_fee,
+            revert_error: tx_execution_info.revert_error.clone(),
+            gas_consumed: calculate_tx_gas_vector(
+                &tx_execution_info.actual_resources,
+                versioned_constants,
+            )?,
+        })
+    }
+}
+
+/// The commitments of a block to its contents.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BlockCommitments {
+    pub transaction_commitment: StarkHash,
+    pub event_commitment: StarkHash,
+    pub receipt_commitment: StarkHash,
+    pub state_diff_commitment: StarkHash,
+    /// The transaction, event and state diff counts, along with the data availability mode.
+    pub concatenated_counts: StarkFelt,
+}
+
+/// The fields of a block header that its hash commits to, other than the block commitments.
+#[derive(Clone, Debug)]
+pub struct BlockHeaderWithoutHash {
+    pub block_info: BlockInfo,
+    pub state_root: GlobalRoot,
+    pub parent_hash: BlockHash,
+    pub starknet_version: String,
+}
+
+/// Calculates the commitments of a block to the given transactions, in order, and to its state
+/// diff; the latter lacks the classes declared by Cairo 0 declare transactions, given separately.
+pub fn calculate_block_commitments(
+    txs_hashing_data: &[TransactionHashingData],
+    state_diff: &CommitmentStateDiff,
+    deprecated_declared_classes: &[ClassHash],
+    use_kzg_da: bool,
+) -> CommitmentResult<BlockCommitments> {
+    let transaction_commitment =
+        calculate_commitment_root(txs_hashing_data.iter().map(calculate_transaction_leaf))?;
+
+    let event_leaves: Vec<FieldElement> = txs_hashing_data
+        .iter()
+        .flat_map(|tx_hashing_data| {
+            tx_hashing_data
+                .events
+                .iter()
+                .map(|event| calculate_event_leaf(event, tx_hashing_data.transaction_hash))
+        })
+        .collect();
+    let n_events = event_leaves.len();
+    let event_commitment = calculate_commitment_root(event_leaves)?;
+
+    let receipt_commitment =
+        calculate_commitment_root(txs_hashing_data.iter().map(calculate_receipt_leaf))?;
+
+    let state_diff_length = state_diff.address_to_class_hash.len()
+        + state_diff.class_hash_to_compiled_class_hash.len()
+        + deprecated_declared_classes.len()
+        + state_diff.address_to_nonce.len()
+        + state_diff
+            .storage_updates
+            .values()
+            .map(|storage_updates| storage_updates.len())
+            .sum::<usize>();
+
+    Ok(BlockCommitments {
+        transaction_commitment,
+        event_commitment,
+        receipt_commitment,
+        state_diff_commitment: calculate_state_diff_hash(state_diff, deprecated_declared_classes),
+        concatenated_counts: concat_counts(
+            txs_hashing_data.len(),
+            n_events,
+            state_diff_length,
+            use_kzg_da,
+        ),
+    })
+}
+
+/// Calculates the hash of a block with the given header and commitments; the Starknet version of
+/// the header must be at least 0.13.2.
+pub fn calculate_block_hash(
+    header: &BlockHeaderWithoutHash,
+    block_commitments: &BlockCommitments,
+) -> CommitmentResult<BlockHash> {
+    let block_info = &header.block_info;
+    let gas_prices = &block_info.gas_prices;
+    let block_hash = poseidon_hash_many(&[
+        short_string(BLOCK_HASH_VERSION),
+        FieldElement::from(block_info.block_number.0),
+        header.state_root.0.into(),
+        (*block_info.sequencer_address.0.key()).into(),
+        FieldElement::from(block_info.block_timestamp.0),
+        block_commitments.concatenated_counts.into(),
+        block_commitments.state_diff_commitment.into(),
+        block_commitments.transaction_commitment.into(),
+        block_commitments.event_commitment.into(),
+        block_commitments.receipt_commitment.into(),
+        FieldElement::from(u128::from(gas_prices.eth_l1_gas_price)),
+        FieldElement::from(u128::from(gas_prices.strk_l1_gas_price)),
+        FieldElement::from(u128::from(gas_prices.eth_l1_data_gas_price)),
+        FieldElement::from(u128::from(gas_prices.strk_l1_data_gas_price)),
+        starknet_version_as_felt(&header.starknet_version)?,
+        FieldElement::ZERO,
+        header.parent_hash.0.into(),
+    ]);
+
+    Ok(BlockHash(block_hash.into()))
+}
+
+/// Verifies that the given block hash is that of a block with the given header and commitments.
+pub fn verify_block_hash(
+    block_hash: BlockHash,
+    header: &BlockHeaderWithoutHash,
+    block_commitments: &BlockCommitments,
+) -> CommitmentResult<()> {
+    let actual = calculate_block_hash(header, block_commitments)?;
+    if actual != block_hash {
+        return Err(CommitmentError::BlockHashMismatch { expected: block_hash, actual });
+    }
+
+    Ok(())
+}
+
+/// Returns the root of the trie of the given leaves, keyed by their indices.
+fn calculate_commitment_root(
+    leaves: impl IntoIterator<Item = FieldElement>,
+) -> CommitmentResult<StarkHash> {
+    let store = InMemoryNodeStore::default();
+    let mut trie =
+        PatriciaTrie::new(&store, TrieHashFunction::Poseidon).with_height(COMMITMENT_TRIE_HEIGHT);
+    trie.update(
+        StarkHash::ZERO,
+        leaves.into_iter().enumerate().map(|(index, leaf)| {
+            (StarkFelt::from(u64::try_from(index).expect("Index overflow.")), leaf.into())
+        }),
+    )
+}
+
+/// Poseidon(transaction_hash, signature...); the signature of an `L1Handler` is `[0]`.
+fn calculate_transaction_leaf(tx_hashing_data: &TransactionHashingData) -> FieldElement {
+    let signature = match &tx_hashing_data.transaction_signature {
+        Some(signature) => signature.0.iter().map(|felt| FieldElement::from(*felt)).collect(),
+        None => vec![FieldElement::ZERO],
+    };
+    let mut elements = vec![tx_hashing_data.transaction_hash.0.into()];
+    elements.extend(signature);
+
+    poseidon_hash_many(&elements)
+}
+
+/// Poseidon(from_address, transaction_hash, n_keys, keys..., n_data, data...).
+fn calculate_event_leaf(event: &Event, transaction_hash: TransactionHash) -> FieldElement {
+    let mut elements = vec![(*event.from_address.0.key()).into(), transaction_hash.0.into()];
+    chain_size_and_elements(&mut elements, event.content.keys.iter().map(|key| key.0));
+    chain_size_and_elements(&mut elements, event.content.data.0.iter().copied());
+
+    poseidon_hash_many(&elements)
+}
+
+/// Poseidon(
+///     transaction_hash, actual_fee, messages_sent_hash, revert_reason_hash, l2_gas (always 0),
+///     l1_gas, l1_data_gas
+/// ).
+fn calculate_receipt_leaf(tx_hashing_data: &TransactionHashingData) -> FieldElement {
+    let revert_reason_hash = match &tx_hashing_data.revert_error {
+        Some(revert_error) => starknet_keccak(revert_error.as_bytes()),
+        None => 0_u8.into(),
+    };
+
+    poseidon_hash_many(&[
+        tx_hashing_data.transaction_hash.0.into(),
+        FieldElement::from(tx_hashing_data.actual_fee.0),
+        calculate_messages_sent_hash(&tx_hashing_data.messages_sent),
+        felt_to_stark_felt(&revert_reason_hash).into(),
+        FieldElement::ZERO,
+        FieldElement::from(tx_hashing_data.gas_consumed.l1_gas),
+        FieldElement::from(tx_hashing_data.gas_consumed.l1_data_gas),
+    ])
+}
+
+/// Poseidon(n_messages, (from_address, to_address, payload_size, payload...)...).
+fn calculate_messages_sent_hash(messages_sent: &[MessageToL1]) -> FieldElement {
+    let mut elements = vec![FieldElement::from(messages_sent.len())];
+    for message in messages_sent {
+        elements.push((*message.from_address.0.key()).into());
+        elements.push(eth_address_as_felt(message.to_address));
+        chain_size_and_elements(&mut elements, message.payload.0.iter().copied());
+    }
+
+    poseidon_hash_many(&elements)
+}
+
+/// Poseidon(
+///     "STARKNET_STATE_DIFF0", n_updated_contracts, (address, class_hash)...,
+///     n_declared_classes, (class_hash, compiled_class_hash)..., n_deprecated_declared_classes,
+///     class_hash..., 1, 0, n_contracts_with_storage_updates,
+///     (address, n_storage_updates, (key, value)...)..., n_nonces, (address, nonce)...
+/// ); each list sorted by its keys. The updated contracts are the deployed and the replaced ones.
+fn calculate_state_diff_hash(
+    state_diff: &CommitmentStateDiff,
+    deprecated_declared_classes: &[ClassHash],
+) -> StarkHash {
+    let mut elements = vec![short_string(STATE_DIFF_HASH_VERSION)];
+
+    let mut updated_contracts: Vec<_> = state_diff.address_to_class_hash.iter().collect();
+    updated_contracts.sort_unstable();
+    elements.push(FieldElement::from(updated_contracts.len()));
+    for (address, class_hash) in updated_contracts {
+        elements.extend([FieldElement::from(*address.0.key()), class_hash.0.into()]);
+    }
+
+    let mut declared_classes: Vec<_> =
+        state_diff.class_hash_to_compiled_class_hash.iter().collect();
+    declared_classes.sort_unstable();
+    elements.push(FieldElement::from(declared_classes.len()));
+    for (class_hash, compiled_class_hash) in declared_classes {
+        elements.extend([FieldElement::from(class_hash.0), compiled_class_hash.0.into()]);
+    }
+
+    let mut deprecated_declared_classes = deprecated_declared_classes.to_vec();
+    deprecated_declared_classes.sort_unstable();
+    chain_size_and_elements(
+        &mut elements,
+        deprecated_declared_classes.iter().map(|class_hash| class_hash.0),
+    );
+
+    // Placeholders.
+    elements.extend([FieldElement::ONE, FieldElement::ZERO]);
+
+    let mut storage_updates: Vec<_> = state_diff
+        .storage_updates
+        .iter()
+        .filter(|(_, contract_storage_updates)| !contract_storage_updates.is_empty())
+        .collect();
+    storage_updates.sort_unstable_by_key(|(address, _)| **address);
+    elements.push(FieldElement::from(storage_updates.len()));
+    for (address, contract_storage_updates) in storage_updates {
+        let mut contract_storage_updates: Vec<_> = contract_storage_updates.iter().collect();
+        contract_storage_updates.sort_unstable();
+        elements.push((*address.0.key()).into());
+        elements.push(FieldElement::from(contract_storage_updates.len()));
+        for (key, value) in contract_storage_updates {
+            elements.extend([FieldElement::from(*key.0.key()), (*value).into()]);
+        }
+    }
+
+    let mut nonces: Vec<_> = state_diff.address_to_nonce.iter().collect();
+    nonces.sort_unstable();
+    elements.push(FieldElement::from(nonces.len()));
+    for (address, nonce) in nonces {
+        elements.extend([FieldElement::from(*address.0.key()), nonce.0.into()]);
+    }
+
+    poseidon_hash_many(&elements).into()
+}
+
+/// Packs the given counts into a single felt: [
+///     transaction_count (64 bits) | event_count (64 bits) | state_diff_length (64 bits) |
+///     data availability mode: 0 for calldata, 1 for blob (1 bit) | 0 (63 bits)
+/// ].
+fn concat_counts(
+    transaction_count: usize,
+    event_count: usize,
+    state_diff_length: usize,
+    use_kzg_da: bool,
+) -> StarkFelt {
+    let to_64_bits =
+        |count: usize| u64::try_from(count).expect("Counts fit in 64 bits.").to_be_bytes();
+    let data_availability_byte: u8 = if use_kzg_da { 0b1000_0000 } else { 0 };
+
+    let mut bytes = [0_u8; 32];
+    bytes[0..8].copy_from_slice(&to_64_bits(transaction_count));
+    bytes[8..16].copy_from_slice(&to_64_bits(event_count));
+    bytes[16..24].copy_from_slice(&to_64_bits(state_diff_length));
+    bytes[24] = data_availability_byte;
+    StarkFelt::new(bytes).expect("Concatenated counts fit in a felt.")
+}
+
+fn chain_size_and_elements(
+    elements: &mut Vec<FieldElement>,
+    new_elements: impl ExactSizeIterator<Item = StarkFelt>,
+) {
+    elements.push(FieldElement::from(new_elements.len()));
+    elements.extend(new_elements.map(FieldElement::from));
+}
+
+fn eth_address_as_felt(address: EthAddress) -> FieldElement {
+    FieldElement::from_byte_slice_be(address.0.as_bytes())
+        .expect("Ethereum addresses fit in a field element.")
+}
+
+fn starknet_version_as_felt(starknet_version: &str) -> CommitmentResult<FieldElement> {
+    // A short string of dot-separated numbers; e.g., `0.13.2`.
+    let invalid_version = || CommitmentError::InvalidStarknetVersion(starknet_version.to_string());
+    if !starknet_version.is_ascii() || starknet_version.len() > 31 {
+        return Err(invalid_version());
+    }
+    let version_components = starknet_version
+        .split('.')
+        .map(str::parse)
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|_| invalid_version())?;
+    if version_components.as_slice() < MIN_STARKNET_VERSION.as_slice() {
+        return Err(CommitmentError::UnsupportedStarknetVersion(starknet_version.to_string()));
+    }
+
+    Ok(short_string(starknet_version.as_bytes()))
+}
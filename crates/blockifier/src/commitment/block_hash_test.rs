@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::num::NonZeroU128;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp};
+use starknet_api::core::{
+    ClassHash, CompiledClassHash, ContractAddress, EthAddress, GlobalRoot, Nonce,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::stark_felt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{
+    Event, EventContent, EventData, EventKey, Fee, L2ToL1Payload, MessageToL1, TransactionHash,
+    TransactionSignature,
+};
+
+use crate::abi::constants;
+use crate::blockifier::block::{BlockInfo, GasPrices};
+use crate::commitment::block_hash::{
+    calculate_block_commitments, calculate_block_hash, calculate_commitment_root,
+    calculate_event_leaf, calculate_messages_sent_hash, calculate_receipt_leaf,
+    calculate_state_diff_hash, calculate_transaction_leaf, concat_counts, verify_block_hash,
+    BlockHeaderWithoutHash, TransactionHashingData,
+};
+use crate::commitment::errors::CommitmentError;
+use crate::execution::call_info::{
+    CallExecution, CallInfo, MessageToL1 as CallMessageToL1, OrderedEvent, OrderedL2ToL1Message,
+};
+use crate::execution::entry_point::CallEntryPoint;
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::transaction::objects::{GasVector, ResourcesMapping, TransactionExecutionInfo};
+use crate::versioned_constants::VersionedConstants;
+
+fn felt(value: u64) -> StarkFelt {
+    StarkFelt::from(value)
+}
+
+fn address(value: u64) -> ContractAddress {
+    ContractAddress::from(u128::from(value))
+}
+
+fn generate_message_to_l1(seed: u64) -> MessageToL1 {
+    MessageToL1 {
+        from_address: address(seed),
+        to_address: EthAddress::try_from(felt(seed + 1)).unwrap(),
+        payload: L2ToL1Payload(vec![felt(seed + 2), felt(seed + 3)]),
+    }
+}
+
+fn generate_event(seed: u64) -> Event {
+    Event {
+        from_address: address(seed + 8),
+        content: EventContent {
+            keys: vec![EventKey(felt(seed)), EventKey(felt(seed + 1))],
+            data: EventData(vec![felt(seed + 2), felt(seed + 3), felt(seed + 4)]),
+        },
+    }
+}
+
+/// A reverted transaction that sent messages and emitted no events.
+fn get_tx_hashing_data(
+    transaction_hash: TransactionHash,
+    transaction_signature: Option<TransactionSignature>,
+) -> TransactionHashingData {
+    TransactionHashingData {
+        transaction_hash,
+        transaction_signature,
+        events: vec![],
+        messages_sent: vec![generate_message_to_l1(34), generate_message_to_l1(56)],
+        actual_fee: Fee(99804),
+        revert_error: Some("aborted".to_string()),
+        gas_consumed: GasVector { l1_gas: 16580, l1_data_gas: 32 },
+    }
+}
+
+fn get_state_diff() -> (CommitmentStateDiff, Vec<ClassHash>) {
+    let state_diff = CommitmentStateDiff {
+        // Deployed contracts, followed by a replaced class.
+        address_to_class_hash: indexmap! {
+            address(0) => ClassHash(felt(1)),
+            address(2) => ClassHash(felt(3)),
+            address(19) => ClassHash(felt(20)),
+        },
+        storage_updates: indexmap! {
+            address(4) => indexmap! {
+                StorageKey::from(5_u64) => felt(6),
+                StorageKey::from(7_u64) => felt(8),
+            },
+            address(9) => indexmap! { StorageKey::from(10_u64) => felt(11) },
+        },
+        class_hash_to_compiled_class_hash: indexmap! {
+            ClassHash(felt(12)) => CompiledClassHash(felt(13)),
+            ClassHash(felt(14)) => CompiledClassHash(felt(15)),
+        },
+        address_to_nonce: indexmap! { address(17) => Nonce(felt(18)) },
+    };
+
+    (state_diff, vec![ClassHash(felt(16))])
+}
+
+fn call_info(
+    storage_address: ContractAddress,
+    execution: CallExecution,
+    inner_calls: Vec<CallInfo>,
+) -> CallInfo {
+    CallInfo {
+        call: CallEntryPoint { storage_address, ..Default::default() },
+        execution,
+        inner_calls,
+        ..Default::default()
+    }
+}
+
+fn ordered_event(order: usize, event: &Event) -> OrderedEvent {
+    OrderedEvent { order, event: event.content.clone() }
+}
+
+fn ordered_message(order: usize, message: &MessageToL1) -> OrderedL2ToL1Message {
+    OrderedL2ToL1Message {
+        order,
+        message: CallMessageToL1 {
+            to_address: message.to_address,
+            payload: message.payload.clone(),
+        },
+    }
+}
+
+#[test]
+fn test_transaction_hashing_data() {
+    let events = [generate_event(0), generate_event(1), generate_event(2)];
+    let messages = [generate_message_to_l1(34), generate_message_to_l1(56)];
+    // Events and messages are ordered within each call tree, rather than by the tree traversal.
+    let validate_call_info = call_info(
+        events[1].from_address,
+        CallExecution { events: vec![ordered_event(1, &events[1])], ..Default::default() },
+        vec![call_info(
+            events[0].from_address,
+            CallExecution { events: vec![ordered_event(0, &events[0])], ..Default::default() },
+            vec![],
+        )],
+    );
+    let execute_call_info = call_info(
+        messages[1].from_address,
+        CallExecution {
+            l2_to_l1_messages: vec![ordered_message(1, &messages[1])],
+            ..Default::default()
+        },
+        vec![call_info(
+            messages[0].from_address,
+            CallExecution {
+                l2_to_l1_messages: vec![ordered_message(0, &messages[0])],
+                ..Default::default()
+            },
+            vec![],
+        )],
+    );
+    // The order of the fee transfer events restarts.
+    let fee_transfer_call_info = call_info(
+        events[2].from_address,
+        CallExecution { events: vec![ordered_event(0, &events[2])], ..Default::default() },
+        vec![],
+    );
+    let tx_execution_info = TransactionExecutionInfo {
+        validate_call_info: Some(validate_call_info),
+        execute_call_info: Some(execute_call_info),
+        fee_transfer_call_info: Some(fee_transfer_call_info),
+        actual_fee: Fee(99804),
+        actual_resources: ResourcesMapping(HashMap::from([
+            (constants::L1_GAS_USAGE.to_string(), 16580),
+            (constants::BLOB_GAS_USAGE.to_string(), 32),
+        ])),
+        revert_error: Some("aborted".to_string()),
+        ..Default::default()
+    };
+
+    let transaction_hash = TransactionHash(felt(1234));
+    let tx_hashing_data = TransactionHashingData::new(
+        transaction_hash,
+        None,
+        &tx_execution_info,
+        VersionedConstants::latest_constants(),
+    )
+    .unwrap();
+    assert_eq!(
+        tx_hashing_data,
+        TransactionHashingData {
+            events: events.to_vec(),
+            ..get_tx_hashing_data(transaction_hash, None)
+        }
+    );
+}
+
+#[test]
+fn test_transaction_commitment_regression() {
+    let tx_hashing_data = get_tx_hashing_data(
+        TransactionHash(felt(1)),
+        Some(TransactionSignature(vec![felt(2), felt(3)])),
+    );
+    assert_eq!(
+        StarkFelt::from(calculate_transaction_leaf(&tx_hashing_data)),
+        stark_felt!("0x2f0d8840bcf3bc629598d8a6cc80cb7c0d9e52d93dab244bbf9cd0dca0ad082")
+    );
+    assert_eq!(
+        calculate_commitment_root([
+            calculate_transaction_leaf(&tx_hashing_data),
+            calculate_transaction_leaf(&tx_hashing_data),
+        ])
+        .unwrap(),
+        stark_felt!("0x0282b635972328bd1cfa86496fe920d20bd9440cd78ee8dc90ae2b383d664dcf")
+    );
+
+    // The signature of an `L1Handler` is taken to be `[0]`.
+    let l1_handler_hashing_data = get_tx_hashing_data(TransactionHash(felt(1)), None);
+    assert_eq!(
+        StarkFelt::from(calculate_transaction_leaf(&l1_handler_hashing_data)),
+        stark_felt!("0x00a93bf5e58b9378d093aa86ddc2f61a3295a1d1e665bd0ef3384dd07b30e033")
+    );
+}
+
+#[test]
+fn test_event_commitment_regression() {
+    let transaction_hash = TransactionHash(stark_felt!("0x1234"));
+    let event_leaves: Vec<_> =
+        (0..3).map(|seed| calculate_event_leaf(&generate_event(seed), transaction_hash)).collect();
+    assert_eq!(
+        StarkFelt::from(event_leaves[2]),
+        stark_felt!("0x367807f532742a4dcbe2d8a47b974b22dd7496faa75edc64a3a5fdb6709057")
+    );
+    assert_eq!(
+        calculate_commitment_root(event_leaves).unwrap(),
+        stark_felt!("0x069bb140ddbbeb01d81c7201ecfb933031306e45dab9c77ff9f9ba3cd4c2b9c3")
+    );
+}
+
+#[test]
+fn test_receipt_commitment_regression() {
+    let messages_sent = [generate_message_to_l1(0), generate_message_to_l1(1)];
+    assert_eq!(
+        StarkFelt::from(calculate_messages_sent_hash(&messages_sent)),
+        stark_felt!("0x00c89474a9007dc060aed76caf8b30b927cfea1ebce2d134b943b8d7121004e4")
+    );
+
+    let tx_hashing_data = get_tx_hashing_data(TransactionHash(felt(1234)), None);
+    let receipt_leaf = calculate_receipt_leaf(&tx_hashing_data);
+    assert_eq!(
+        StarkFelt::from(receipt_leaf),
+        stark_felt!("0x6276abf21e7c68b2eecfdc8a845b11b44401901f5f040efe10c60d625049646")
+    );
+    assert_eq!(
+        calculate_commitment_root([receipt_leaf]).unwrap(),
+        stark_felt!("0x31963cb891ebb825e83514deb748c89b6967b5368cbc48a9b56193a1464ca87")
+    );
+}
+
+#[test]
+fn test_state_diff_hash_regression() {
+    let (state_diff, deprecated_declared_classes) = get_state_diff();
+    assert_eq!(
+        calculate_state_diff_hash(&state_diff, &deprecated_declared_classes),
+        stark_felt!("0x0281f5966e49ad7dad9323826d53d1d27c0c4e6ebe5525e2e2fbca549bfa0a67")
+    );
+}
+
+#[test]
+fn test_concat_counts() {
+    assert_eq!(
+        concat_counts(4, 3, 2, true),
+        stark_felt!("0x0000000000000004000000000000000300000000000000028000000000000000")
+    );
+    assert_eq!(
+        concat_counts(4, 3, 2, false),
+        stark_felt!("0x0000000000000004000000000000000300000000000000020000000000000000")
+    );
+}
+
+#[test]
+fn test_block_hash_regression() {
+    let header = BlockHeaderWithoutHash {
+        block_info: BlockInfo {
+            block_number: BlockNumber(1),
+            block_timestamp: BlockTimestamp(4),
+            sequencer_address: address(3),
+            gas_prices: GasPrices {
+                eth_l1_gas_price: NonZeroU128::new(7).unwrap(),
+                strk_l1_gas_price: NonZeroU128::new(6).unwrap(),
+                eth_l1_data_gas_price: NonZeroU128::new(9).unwrap(),
+                strk_l1_data_gas_price: NonZeroU128::new(10).unwrap(),
+            },
+            use_kzg_da: true,
+        },
+        state_root: GlobalRoot(felt(2)),
+        parent_hash: BlockHash(felt(11)),
+        starknet_version: "10".to_string(),
+    };
+    let txs_hashing_data = [get_tx_hashing_data(
+        TransactionHash(felt(1)),
+        Some(TransactionSignature(vec![felt(2), felt(3)])),
+    )];
+    let (state_diff, deprecated_declared_classes) = get_state_diff();
+    let block_commitments = calculate_block_commitments(
+        &txs_hashing_data,
+        &state_diff,
+        &deprecated_declared_classes,
+        header.block_info.use_kzg_da,
+    )
+    .unwrap();
+    // A single transaction with no events, and a state diff of length 10.
+    assert_eq!(block_commitments.event_commitment, StarkHash::ZERO);
+    assert_eq!(block_commitments.concatenated_counts, concat_counts(1, 0, 10, true));
+
+    let block_hash = calculate_block_hash(&header, &block_commitments).unwrap();
+    let expected_block_hash = BlockHash(stark_felt!(
+        "0x061e4998d51a248f1d0288d7e17f6287757b0e5e6c5e1e58ddf740616e312134"
+    ));
+    assert_eq!(block_hash, expected_block_hash);
+    verify_block_hash(block_hash, &header, &block_commitments).unwrap();
+
+    // Any change to the committed contents changes the hash.
+    let parent_hash = BlockHash(felt(12));
+    let other_header = BlockHeaderWithoutHash { parent_hash, ..header.clone() };
+    assert_matches!(
+        verify_block_hash(block_hash, &other_header, &block_commitments),
+        Err(CommitmentError::BlockHashMismatch { expected, actual })
+        if expected == block_hash && actual != block_hash
+    );
+
+    for starknet_version in ["0.13.2-\u{3b1}", "0.13.2-rc.1"] {
+        let invalid_header = BlockHeaderWithoutHash {
+            starknet_version: starknet_version.to_string(),
+            ..header.clone()
+        };
+        assert_matches!(
+            calculate_block_hash(&invalid_header, &block_commitments),
+            Err(CommitmentError::InvalidStarknetVersion(_))
+        );
+    }
+
+    // Legacy block hashes, of versions below 0.13.2, are not supported.
+    for starknet_version in ["0.13.1.1", "0.13"] {
+        let legacy_header = BlockHeaderWithoutHash {
+            starknet_version: starknet_version.to_string(),
+            ..header.clone()
+        };
+        assert_matches!(
+            calculate_block_hash(&legacy_header, &block_commitments),
+            Err(CommitmentError::UnsupportedStarknetVersion(version)) if version == starknet_version
+        );
+    }
+}
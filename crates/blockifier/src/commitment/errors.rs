@@ -0,0 +1,29 @@
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::GlobalRoot;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CommitmentError {
+    #[error("Block hash mismatch; expected: {}, computed: {}.", expected.0, actual.0)]
+    BlockHashMismatch { expected: BlockHash, actual: BlockHash },
+    #[error("Invalid Starknet version {0}; expected dot-separated numbers.")]
+    InvalidStarknetVersion(String),
+    #[error("Key {0} exceeds the height of the trie.")]
+    KeyOutOfRange(StarkFelt),
+    #[error("Node {0} is missing from the node store.")]
+    MissingNode(StarkHash),
+    #[error("Failed to access the node store: {0}.")]
+    NodeStoreError(String),
+    #[error(
+        "State root mismatch in block {}; expected: {}, computed: {}.",
+        block_number.0, expected.0, actual.0
+    )]
+    StateRootMismatch { block_number: BlockNumber, expected: GlobalRoot, actual: GlobalRoot },
+    #[error("Unsupported Starknet version {0}; block hashes are calculated from version 0.13.2.")]
+    UnsupportedStarknetVersion(String),
+    #[error("Node {0} is not of the expected kind.")]
+    UnexpectedNode(StarkHash),
+}
+
+pub type CommitmentResult<T> = Result<T, CommitmentError>;
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::hash::{StarkFelt, StarkHash};
+
+use crate::commitment::errors::CommitmentResult;
+use crate::commitment::state_commitment::ContractState;
+
+/// A node of a Patricia-Merkle trie of the Starknet state, keyed by its hash. Contract states are
+/// stored as the preimages of the leaves of the contracts trie.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Node {
+    Binary {
+        left: StarkHash,
+        right: StarkHash,
+    },
+    /// A path of `length` bits, from the top, leading to `child`.
+    Edge {
+        child: StarkHash,
+        path: StarkFelt,
+        length: u8,
+    },
+    ContractState(ContractState),
+}
+
+/// Holds the nodes of the tries of the Starknet state.
+pub trait NodeStore {
+    /// Returns the node of the given hash, if stored.
+    fn get_node(&self, hash: &StarkHash) -> CommitmentResult<Option<Node>>;
+
+    /// Stores the given nodes; nodes are immutable, so that the tries of previous roots stay
+    /// intact.
+    fn set_nodes(&mut self, nodes: HashMap<StarkHash, Node>) -> CommitmentResult<()>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryNodeStore {
+    pub nodes: HashMap<StarkHash, Node>,
+}
+
+impl NodeStore for InMemoryNodeStore {
+    fn get_node(&self, hash: &StarkHash) -> CommitmentResult<Option<Node>> {
+        Ok(self.nodes.get(hash).cloned())
+    }
+
+    fn set_nodes(&mut self, nodes: HashMap<StarkHash, Node>) -> CommitmentResult<()> {
+        self.nodes.extend(nodes);
+        Ok(())
+    }
+}
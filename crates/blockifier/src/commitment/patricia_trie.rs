@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_crypto::{poseidon_hash, FieldElement};
+
+use crate::commitment::errors::{CommitmentError, CommitmentResult};
+use crate::commitment::node_store::{Node, NodeStore};
+use crate::execution::execution_utils::{felt_to_stark_felt, stark_felt_to_felt};
+
+#[cfg(test)]
+#[path = "patricia_trie_test.rs"]
+pub mod test;
+
+/// The height of the tries of the Starknet state; i.e., the bit length of their keys.
+pub const TRIE_HEIGHT: u8 = 251;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrieHashFunction {
+    Pedersen,
+    Poseidon,
+}
+
+impl TrieHashFunction {
+    pub fn hash(&self, left: &StarkFelt, right: &StarkFelt) -> StarkHash {
+        match self {
+            Self::Pedersen => pedersen_hash(left, right),
+            Self::Poseidon => {
+                poseidon_hash(FieldElement::from(*left), FieldElement::from(*right)).into()
+            }
+        }
+    }
+
+    /// The hash of an edge node is `H(child, path) + length`.
+    pub fn hash_edge(&self, child: &StarkHash, path: &StarkFelt, length: u8) -> StarkHash {
+        (FieldElement::from(self.hash(child, path)) + FieldElement::from(length)).into()
+    }
+}
+
+/// A subtree, as seen from its parent. Edges are kept unhashed until their parent is known, since
+/// a parent with a single non-empty subtree extends its edge.
+#[derive(Clone, Debug)]
+enum Subtree {
+    Empty,
+    /// A stored or a new node, or a leaf.
+    Node(StarkHash),
+    /// A path of `length` bits, from the top, leading to a binary node or a leaf.
+    Edge {
+        path: BigUint,
+        length: u8,
+        child: StarkHash,
+    },
+}
+
+impl Subtree {
+    fn from_root(root: StarkHash) -> Self {
+        if root == StarkHash::ZERO {
+            Self::Empty
+        } else {
+            Self::Node(root)
+        }
+    }
+}
+
+/// Updates binary Patricia-Merkle tries, of height 251 unless set otherwise, whose nodes are read
+/// from a node store. The nodes of updated tries are accumulated, rather than written to the store.
+pub struct PatriciaTrie<'a, S: NodeStore + ?Sized> {
+    store: &'a S,
+    hash_function: TrieHashFunction,
+    height: u8,
+    new_nodes: HashMap<StarkHash, Node>,
+}
+
+impl<'a, S: NodeStore + ?Sized> PatriciaTrie<'a, S> {
+    pub fn new(store: &'a S, hash_function: TrieHashFunction) -> Self {
+        Self { store, hash_function, height: TRIE_HEIGHT, new_nodes: HashMap::new() }
+    }
+
+    pub fn with_height(mut self, height: u8) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Returns the nodes created by the updates so far.
+    pub fn into_new_nodes(self) -> HashMap<StarkHash, Node> {
+        self.new_nodes
+    }
+
+    /// Returns the leaf of the given key in the trie of the given root, if any.
+    pub fn get_leaf(&self, root: StarkHash, key: StarkFelt) -> CommitmentResult<Option<StarkHash>> {
+        let key = self.to_trie_key(key)?;
+        let mut subtree = Subtree::from_root(root);
+        for height in (0..self.height).rev() {
+            if let Subtree::Empty = subtree {
+                return Ok(None);
+            }
+            let (left, right) = self.children(subtree)?;
+            subtree = if key.bit(height.into()) { right } else { left };
+        }
+
+        match subtree {
+            Subtree::Empty => Ok(None),
+            Subtree::Node(leaf) => Ok(Some(leaf)),
+            Subtree::Edge { .. } => unreachable!("Edges do not extend below the leaves."),
+        }
+    }
+
+    /// Sets the given leaves in the trie of the given root, and returns the root of the updated
+    /// trie; a zero leaf removes its key from the trie. An empty trie has a zero root.
+    pub fn update(
+        &mut self,
+        root: StarkHash,
+        leaves: impl IntoIterator<Item = (StarkFelt, StarkHash)>,
+    ) -> CommitmentResult<StarkHash> {
+        let mut leaves = leaves
+            .into_iter()
+            .map(|(key, leaf)| Ok((self.to_trie_key(key)?, leaf)))
+            .collect::<CommitmentResult<Vec<_>>>()?;
+        // Stable, so that the last of repeated keys wins.
+        leaves.sort_by(|(key, _), (other_key, _)| key.cmp(other_key));
+
+        let subtree = self.update_subtree(Subtree::from_root(root), self.height, &leaves)?;
+        Ok(self.materialize(subtree))
+    }
+
+    /// Updates the given subtree, of the given height, with the given sorted leaves of its keys.
+    fn update_subtree(
+        &mut self,
+        subtree: Subtree,
+        height: u8,
+        leaves: &[(BigUint, StarkHash)],
+    ) -> CommitmentResult<Subtree> {
+        let Some((_, last_leaf)) = leaves.last() else {
+            return Ok(subtree);
+        };
+        if height == 0 {
+            return Ok(Subtree::from_root(*last_leaf));
+        }
+
+        let child_height = height - 1;
+        let (left, right) = self.children(subtree)?;
+        let n_left_leaves = leaves.partition_point(|(key, _)| !key.bit(child_height.into()));
+        let (left_leaves, right_leaves) = leaves.split_at(n_left_leaves);
+        let left = self.update_subtree(left, child_height, left_leaves)?;
+        let right = self.update_subtree(right, child_height, right_leaves)?;
+
+        match (left, right) {
+            (Subtree::Empty, Subtree::Empty) => Ok(Subtree::Empty),
+            (left, Subtree::Empty) => self.extend_edge(left, child_height, false),
+            (Subtree::Empty, right) => self.extend_edge(right, child_height, true),
+            (left, right) => {
+                let left = self.materialize(left);
+                let right = self.materialize(right);
+                let hash = self.hash_function.hash(&left, &right);
+                self.new_nodes.insert(hash, Node::Binary { left, right });
+                Ok(Subtree::Node(hash))
+            }
+        }
+    }
+
+    /// Returns the subtrees of the given (non-leaf) subtree.
+    fn children(&self, subtree: Subtree) -> CommitmentResult<(Subtree, Subtree)> {
+        match subtree {
+            Subtree::Empty => Ok((Subtree::Empty, Subtree::Empty)),
+            Subtree::Node(hash) => match self.get_node(hash)? {
+                Node::Binary { left, right } => Ok((Subtree::Node(left), Subtree::Node(right))),
+                Node::Edge { child, path, length } => {
+                    self.children(Subtree::Edge { path: to_biguint(path), length, child })
+                }
+                Node::ContractState(_) => Err(CommitmentError::UnexpectedNode(hash)),
+            },
+            Subtree::Edge { mut path, length, child } => {
+                let child_length = length - 1;
+                let is_right = path.bit(child_length.into());
+                let subtree = if child_length == 0 {
+                    Subtree::Node(child)
+                } else {
+                    path.set_bit(child_length.into(), false);
+                    Subtree::Edge { path, length: child_length, child }
+                };
+                Ok(if is_right { (Subtree::Empty, subtree) } else { (subtree, Subtree::Empty) })
+            }
+        }
+    }
+
+    /// Returns the subtree of a parent whose only non-empty child is the given one, of the given
+    /// height.
+    fn extend_edge(
+        &self,
+        subtree: Subtree,
+        height: u8,
+        is_right: bool,
+    ) -> CommitmentResult<Subtree> {
+        let (mut path, length, child) = match subtree {
+            Subtree::Edge { path, length, child } => (path, length, child),
+            Subtree::Node(leaf) if height == 0 => (BigUint::zero(), 0, leaf),
+            Subtree::Node(hash) => match self.get_node(hash)? {
+                // Edges are merged, to keep the trie canonical.
+                Node::Edge { child, path, length } => (to_biguint(path), length, child),
+                _ => (BigUint::zero(), 0, hash),
+            },
+            Subtree::Empty => unreachable!("Empty subtrees are not extended."),
+        };
+        if is_right {
+            path.set_bit(length.into(), true);
+        }
+
+        Ok(Subtree::Edge { path, length: length + 1, child })
+    }
+
+    /// Returns the hash of the given subtree, and records its node if it is a new edge.
+    fn materialize(&mut self, subtree: Subtree) -> StarkHash {
+        match subtree {
+            Subtree::Empty => StarkHash::ZERO,
+            Subtree::Node(hash) => hash,
+            Subtree::Edge { path, length, child } => {
+                let path = felt_to_stark_felt(&path.into());
+                let hash = self.hash_function.hash_edge(&child, &path, length);
+                self.new_nodes.insert(hash, Node::Edge { child, path, length });
+                hash
+            }
+        }
+    }
+
+    fn get_node(&self, hash: StarkHash) -> CommitmentResult<Node> {
+        if let Some(node) = self.new_nodes.get(&hash) {
+            return Ok(node.clone());
+        }
+
+        self.store.get_node(&hash)?.ok_or(CommitmentError::MissingNode(hash))
+    }
+
+    fn to_trie_key(&self, key: StarkFelt) -> CommitmentResult<BigUint> {
+        let trie_key = to_biguint(key);
+        if trie_key.bits() > self.height.into() {
+            return Err(CommitmentError::KeyOutOfRange(key));
+        }
+
+        Ok(trie_key)
+    }
+}
+
+fn to_biguint(felt: StarkFelt) -> BigUint {
+    stark_felt_to_felt(felt).to_biguint()
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use assert_matches::assert_matches;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::stark_felt;
+use starknet_crypto::FieldElement;
+
+use crate::commitment::errors::CommitmentError;
+use crate::commitment::node_store::{InMemoryNodeStore, NodeStore};
+use crate::commitment::patricia_trie::{PatriciaTrie, TrieHashFunction, TRIE_HEIGHT};
+use crate::execution::execution_utils::{felt_to_stark_felt, stark_felt_to_felt};
+
+const MAX_KEY: &str = "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+
+/// Computes the root of the trie holding the given leaves from scratch, by its definition: the
+/// leaves of a subtree sharing a key prefix hang from an edge of that prefix.
+fn reference_root(
+    hash_function: TrieHashFunction,
+    leaves: &BTreeMap<BigUint, StarkHash>,
+) -> StarkHash {
+    let leaves: Vec<(BigUint, StarkHash)> = leaves
+        .iter()
+        .filter(|(_, leaf)| **leaf != StarkHash::ZERO)
+        .map(|(key, leaf)| (key.clone(), *leaf))
+        .collect();
+    if leaves.is_empty() {
+        return StarkHash::ZERO;
+    }
+
+    reference_subtree_hash(hash_function, TRIE_HEIGHT, &leaves)
+}
+
+fn reference_subtree_hash(
+    hash_function: TrieHashFunction,
+    height: u8,
+    leaves: &[(BigUint, StarkHash)],
+) -> StarkHash {
+    let (first_key, first_leaf) = &leaves[0];
+    let common_prefix_length = (0..height)
+        .rev()
+        .take_while(|bit| {
+            leaves.iter().all(|(key, _)| key.bit((*bit).into()) == first_key.bit((*bit).into()))
+        })
+        .count();
+    let common_prefix_length = u8::try_from(common_prefix_length).unwrap();
+
+    let node_height = height - common_prefix_length;
+    let node = if node_height == 0 {
+        *first_leaf
+    } else {
+        let (left, right): (Vec<_>, Vec<_>) =
+            leaves.iter().cloned().partition(|(key, _)| !key.bit((node_height - 1).into()));
+        hash_function.hash(
+            &reference_subtree_hash(hash_function, node_height - 1, &left),
+            &reference_subtree_hash(hash_function, node_height - 1, &right),
+        )
+    };
+    if common_prefix_length == 0 {
+        return node;
+    }
+
+    let path = (first_key >> node_height) % (BigUint::from(1_u8) << common_prefix_length);
+    hash_function.hash_edge(&node, &felt_to_stark_felt(&path.into()), common_prefix_length)
+}
+
+fn to_biguint(felt: StarkFelt) -> BigUint {
+    stark_felt_to_felt(felt).to_biguint()
+}
+
+#[test]
+fn test_single_leaf() {
+    let store = InMemoryNodeStore::default();
+    let (key, leaf) = (stark_felt!("0x5"), stark_felt!("0x1234"));
+    let mut trie = PatriciaTrie::new(&store, TrieHashFunction::Pedersen);
+    let root = trie.update(StarkHash::ZERO, [(key, leaf)]).unwrap();
+
+    // A single edge, of the full key, leads to the leaf.
+    let expected_root: StarkHash =
+        (FieldElement::from(pedersen_hash(&leaf, &key)) + FieldElement::from(TRIE_HEIGHT)).into();
+    assert_eq!(root, expected_root);
+
+    let mut store = InMemoryNodeStore::default();
+    store.set_nodes(trie.into_new_nodes()).unwrap();
+    let trie = PatriciaTrie::new(&store, TrieHashFunction::Pedersen);
+    assert_eq!(trie.get_leaf(root, key).unwrap(), Some(leaf));
+    assert_eq!(trie.get_leaf(root, stark_felt!("0x4")).unwrap(), None);
+}
+
+#[test]
+fn test_sibling_leaves() {
+    let store = InMemoryNodeStore::default();
+    let (left_leaf, right_leaf) = (stark_felt!("0x1234"), stark_felt!("0x5678"));
+    let mut trie = PatriciaTrie::new(&store, TrieHashFunction::Pedersen);
+    let root = trie
+        .update(
+            StarkHash::ZERO,
+            [(stark_felt!("0x0"), left_leaf), (stark_felt!("0x1"), right_leaf)],
+        )
+        .unwrap();
+
+    // An edge of zeros leads to the binary node of the leaves, right above them.
+    let binary_node = pedersen_hash(&left_leaf, &right_leaf);
+    let expected_root: StarkHash =
+        (FieldElement::from(pedersen_hash(&binary_node, &StarkFelt::ZERO))
+            + FieldElement::from(TRIE_HEIGHT - 1))
+        .into();
+    assert_eq!(root, expected_root);
+}
+
+/// Known answers from the node hash tests of cairo-lang's Patricia tree (`nodes_test.py`), as
+/// roots of the smallest tries made of those nodes.
+#[test]
+fn test_node_hashes_known_answers() {
+    let store = InMemoryNodeStore::default();
+
+    // A binary node of two leaves.
+    let mut trie = PatriciaTrie::new(&store, TrieHashFunction::Pedersen).with_height(1);
+    let root = trie
+        .update(
+            StarkHash::ZERO,
+            [
+                (stark_felt!("0x0"), stark_felt!("0x1234")),
+                (stark_felt!("0x1"), stark_felt!("0xabcd")),
+            ],
+        )
+        .unwrap();
+    assert_eq!(
+        root,
+        stark_felt!("0x615bb8d47888d2987ad0c63fc06e9e771930986a4dd8adc55617febfcf3639e")
+    );
+
+    // An edge of the path 0b101010 to a leaf.
+    let mut trie = PatriciaTrie::new(&store, TrieHashFunction::Pedersen).with_height(6);
+    let root =
+        trie.update(StarkHash::ZERO, [(stark_felt!("0x2a"), stark_felt!("0x1234abcd"))]).unwrap();
+    assert_eq!(
+        root,
+        stark_felt!("0x1d937094c09b5f8e26a662d21911871e3cbc6858d55cc49af9848ea6fed4e9")
+    );
+}
+
+#[rstest]
+fn test_incremental_updates(
+    #[values(TrieHashFunction::Pedersen, TrieHashFunction::Poseidon)]
+    hash_function: TrieHashFunction,
+) {
+    let batches = [
+        vec![("0x0", "0x1"), ("0x1", "0x2"), ("0x10", "0x3"), (MAX_KEY, "0x4")],
+        // Splits existing edges, at the top and at the bottom of the trie.
+        vec![
+            ("0x400000000000000000000000000000000000000000000000000000000000000", "0x5"),
+            ("0x3", "0x6"),
+        ],
+        // Overrides leaves.
+        vec![("0x1", "0x7"), (MAX_KEY, "0x8")],
+        // Removes leaves, merging edges.
+        vec![
+            ("0x0", "0x0"),
+            ("0x10", "0x0"),
+            ("0x400000000000000000000000000000000000000000000000000000000000000", "0x0"),
+        ],
+        vec![("0x1", "0x0"), ("0x3", "0x0"), (MAX_KEY, "0x0")],
+    ];
+
+    let mut store = InMemoryNodeStore::default();
+    let mut root = StarkHash::ZERO;
+    let mut leaves = BTreeMap::new();
+    for batch in batches {
+        let batch: Vec<(StarkFelt, StarkHash)> = batch
+            .into_iter()
+            .map(|(key, leaf)| {
+                (StarkFelt::try_from(key).unwrap(), StarkFelt::try_from(leaf).unwrap())
+            })
+            .collect();
+        let mut trie = PatriciaTrie::new(&store, hash_function);
+        root = trie.update(root, batch.clone()).unwrap();
+        store.set_nodes(trie.into_new_nodes()).unwrap();
+        leaves.extend(batch.iter().map(|(key, leaf)| (to_biguint(*key), *leaf)));
+
+        assert_eq!(root, reference_root(hash_function, &leaves));
+        let trie = PatriciaTrie::new(&store, hash_function);
+        for (key, leaf) in batch {
+            let expected_leaf = if leaf == StarkHash::ZERO { None } else { Some(leaf) };
+            assert_eq!(trie.get_leaf(root, key).unwrap(), expected_leaf);
+        }
+    }
+
+    // All the leaves were removed.
+    assert_eq!(root, StarkHash::ZERO);
+}
+
+#[test]
+fn test_key_out_of_range() {
+    let store = InMemoryNodeStore::default();
+    let key = stark_felt!("0x800000000000000000000000000000000000000000000000000000000000000");
+    let mut trie = PatriciaTrie::new(&store, TrieHashFunction::Pedersen);
+    assert_matches!(
+        trie.update(StarkHash::ZERO, [(key, stark_felt!("0x1"))]),
+        Err(CommitmentError::KeyOutOfRange(out_of_range_key)) if out_of_range_key == key
+    );
+}
+
+#[test]
+fn test_custom_height() {
+    let store = InMemoryNodeStore::default();
+    let (key, leaf) = (stark_felt!("0xffffffffffffffff"), stark_felt!("0x1234"));
+    let mut trie = PatriciaTrie::new(&store, TrieHashFunction::Poseidon).with_height(64);
+    let root = trie.update(StarkHash::ZERO, [(key, leaf)]).unwrap();
+
+    let expected_root: StarkHash =
+        (FieldElement::from(TrieHashFunction::Poseidon.hash(&leaf, &key))
+            + FieldElement::from(64_u8))
+        .into();
+    assert_eq!(root, expected_root);
+
+    let out_of_range_key = stark_felt!("0x10000000000000000");
+    assert_matches!(
+        trie.update(root, [(out_of_range_key, leaf)]),
+        Err(CommitmentError::KeyOutOfRange(key)) if key == out_of_range_key
+    );
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce};
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_crypto::{poseidon_hash, poseidon_hash_many, FieldElement};
+
+use crate::commitment::errors::{CommitmentError, CommitmentResult};
+use crate::commitment::node_store::{Node, NodeStore};
+use crate::commitment::patricia_trie::{PatriciaTrie, TrieHashFunction};
+use crate::state::cached_state::CommitmentStateDiff;
+
+#[cfg(test)]
+#[path = "state_commitment_test.rs"]
+pub mod test;
+
+const CONTRACT_CLASS_LEAF_VERSION: &[u8] = b"CONTRACT_CLASS_LEAF_V0";
+const GLOBAL_STATE_VERSION: &[u8] = b"STARKNET_STATE_V0";
+
+/// The state of a contract, as committed to in the contracts trie.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ContractState {
+    pub class_hash: ClassHash,
+    pub storage_root: StarkHash,
+    pub nonce: Nonce,
+}
+
+impl ContractState {
+    /// Returns the leaf of the contract in the contracts trie.
+    pub fn hash(&self) -> StarkHash {
+        let hash = pedersen_hash(&self.class_hash.0, &self.storage_root);
+        let hash = pedersen_hash(&hash, &self.nonce.0);
+        // The version of the contract state.
+        pedersen_hash(&hash, &StarkFelt::ZERO)
+    }
+}
+
+/// Returns the leaf of a declared class in the classes trie.
+pub fn compute_class_leaf(compiled_class_hash: CompiledClassHash) -> StarkHash {
+    poseidon_hash(short_string(CONTRACT_CLASS_LEAF_VERSION), compiled_class_hash.0.into()).into()
+}
+
+/// The roots of the tries of the Starknet state; zero for empty tries.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateRoots {
+    pub contracts_trie_root: StarkHash,
+    pub classes_trie_root: StarkHash,
+}
+
+impl StateRoots {
+    /// Returns the root committed to in block headers; while no Cairo 1 class is declared, it is
+    /// the root of the contracts trie.
+    pub fn global_root(&self) -> GlobalRoot {
+        if self.classes_trie_root == StarkHash::ZERO {
+            return GlobalRoot(self.contracts_trie_root);
+        }
+
+        GlobalRoot(
+            poseidon_hash_many(&[
+                short_string(GLOBAL_STATE_VERSION),
+                self.contracts_trie_root.into(),
+                self.classes_trie_root.into(),
+            ])
+            .into(),
+        )
+    }
+}
+
+/// The state committed to after applying a state diff.
+#[derive(Clone, Debug)]
+pub struct StateCommitment {
+    pub roots: StateRoots,
+    /// The nodes of the new tries that are missing from the node store; to be written to it.
+    pub new_nodes: HashMap<StarkHash, Node>,
+}
+
+/// Applies the given state diff to the tries of the given roots, whose nodes are read from the
+/// given store; the store itself is not modified.
+pub fn commit_state_diff<S: NodeStore + ?Sized>(
+    store: &S,
+    previous_roots: &StateRoots,
+    state_diff: &CommitmentStateDiff,
+) -> CommitmentResult<StateCommitment> {
+    let mut new_nodes = HashMap::new();
+
+    let modified_contracts: HashSet<ContractAddress> = state_diff
+        .address_to_class_hash
+        .keys()
+        .chain(state_diff.address_to_nonce.keys())
+        .chain(state_diff.storage_updates.keys())
+        .copied()
+        .collect();
+    let mut contract_leaves = Vec::with_capacity(modified_contracts.len());
+    for contract_address in modified_contracts {
+        let mut contract_state =
+            get_contract_state(store, previous_roots.contracts_trie_root, contract_address)?;
+        if let Some(class_hash) = state_diff.address_to_class_hash.get(&contract_address) {
+            contract_state.class_hash = *class_hash;
+        }
+        if let Some(nonce) = state_diff.address_to_nonce.get(&contract_address) {
+            contract_state.nonce = *nonce;
+        }
+        if let Some(storage_updates) = state_diff.storage_updates.get(&contract_address) {
+            let mut storage_trie = PatriciaTrie::new(store, TrieHashFunction::Pedersen);
+            contract_state.storage_root = storage_trie.update(
+                contract_state.storage_root,
+                storage_updates.iter().map(|(key, value)| (*key.0.key(), *value)),
+            )?;
+            new_nodes.extend(storage_trie.into_new_nodes());
+        }
+
+        let contract_leaf = contract_state.hash();
+        new_nodes.insert(contract_leaf, Node::ContractState(contract_state));
+        contract_leaves.push((*contract_address.0.key(), contract_leaf));
+    }
+    let mut contracts_trie = PatriciaTrie::new(store, TrieHashFunction::Pedersen);
+    let contracts_trie_root =
+        contracts_trie.update(previous_roots.contracts_trie_root, contract_leaves)?;
+    new_nodes.extend(contracts_trie.into_new_nodes());
+
+    let mut classes_trie = PatriciaTrie::new(store, TrieHashFunction::Poseidon);
+    let classes_trie_root = classes_trie.update(
+        previous_roots.classes_trie_root,
+        state_diff.class_hash_to_compiled_class_hash.iter().map(
+            |(class_hash, compiled_class_hash)| {
+                (class_hash.0, compute_class_leaf(*compiled_class_hash))
+            },
+        ),
+    )?;
+    new_nodes.extend(classes_trie.into_new_nodes());
+
+    Ok(StateCommitment { roots: StateRoots { contracts_trie_root, classes_trie_root }, new_nodes })
+}
+
+/// Returns the state of the given contract in the contracts trie of the given root; undeployed
+/// contracts have a default state.
+pub fn get_contract_state<S: NodeStore + ?Sized>(
+    store: &S,
+    contracts_trie_root: StarkHash,
+    contract_address: ContractAddress,
+) -> CommitmentResult<ContractState> {
+    let contracts_trie = PatriciaTrie::new(store, TrieHashFunction::Pedersen);
+    let Some(contract_leaf) =
+        contracts_trie.get_leaf(contracts_trie_root, *contract_address.0.key())?
+    else {
+        return Ok(ContractState::default());
+    };
+
+    match store.get_node(&contract_leaf)? {
+        Some(Node::ContractState(contract_state)) => Ok(contract_state),
+        Some(_) => Err(CommitmentError::UnexpectedNode(contract_leaf)),
+        None => Err(CommitmentError::MissingNode(contract_leaf)),
+    }
+}
+
+pub(crate) fn short_string(string: &[u8]) -> FieldElement {
+    FieldElement::from_byte_slice_be(string).expect("Short strings fit in a field element.")
+}
@@ -0,0 +1,157 @@
+use indexmap::{indexmap, IndexMap};
+use pretty_assertions::assert_eq;
+use starknet_api::core::{
+    ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce, PatriciaKey,
+};
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+use starknet_crypto::{poseidon_hash, poseidon_hash_many, FieldElement};
+
+use crate::commitment::node_store::{InMemoryNodeStore, NodeStore};
+use crate::commitment::patricia_trie::TRIE_HEIGHT;
+use crate::commitment::state_commitment::{
+    commit_state_diff, compute_class_leaf, get_contract_state, ContractState, StateRoots,
+};
+use crate::state::cached_state::CommitmentStateDiff;
+
+/// Returns the root of a trie holding a single leaf.
+fn single_leaf_root(key: StarkFelt, leaf: StarkHash) -> StarkHash {
+    (FieldElement::from(pedersen_hash(&leaf, &key)) + FieldElement::from(TRIE_HEIGHT)).into()
+}
+
+fn commit(
+    store: &mut InMemoryNodeStore,
+    roots: &StateRoots,
+    state_diff: &CommitmentStateDiff,
+) -> StateRoots {
+    let state_commitment = commit_state_diff(&*store, roots, state_diff).unwrap();
+    store.set_nodes(state_commitment.new_nodes).unwrap();
+    state_commitment.roots
+}
+
+#[test]
+fn test_commit_state_diff() {
+    let contract_address = contract_address!("0x100");
+    let (key, value) = (StorageKey(patricia_key!("0x10")), stark_felt!("0x5"));
+    let class_hash = class_hash!("0x20");
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x30"));
+    let state_diff = CommitmentStateDiff {
+        address_to_class_hash: indexmap!(contract_address => class_hash),
+        address_to_nonce: indexmap!(contract_address => Nonce(stark_felt!("0x1"))),
+        storage_updates: indexmap!(contract_address => indexmap!(key => value)),
+        class_hash_to_compiled_class_hash: indexmap!(class_hash => compiled_class_hash),
+    };
+    let mut store = InMemoryNodeStore::default();
+    let roots = commit(&mut store, &StateRoots::default(), &state_diff);
+
+    let contract_state = ContractState {
+        class_hash,
+        storage_root: single_leaf_root(*key.0.key(), value),
+        nonce: Nonce(stark_felt!("0x1")),
+    };
+    let class_leaf: StarkHash = poseidon_hash(
+        FieldElement::from_byte_slice_be(b"CONTRACT_CLASS_LEAF_V0").unwrap(),
+        compiled_class_hash.0.into(),
+    )
+    .into();
+    assert_eq!(compute_class_leaf(compiled_class_hash), class_leaf);
+    let classes_trie_root: StarkHash = (poseidon_hash(class_leaf.into(), class_hash.0.into())
+        + FieldElement::from(TRIE_HEIGHT))
+    .into();
+    let expected_roots = StateRoots {
+        contracts_trie_root: single_leaf_root(*contract_address.0.key(), contract_state.hash()),
+        classes_trie_root,
+    };
+    assert_eq!(roots, expected_roots);
+    assert_eq!(
+        get_contract_state(&store, roots.contracts_trie_root, contract_address).unwrap(),
+        contract_state
+    );
+
+    // With a classes trie, the global root commits to both tries.
+    let expected_global_root = poseidon_hash_many(&[
+        FieldElement::from_byte_slice_be(b"STARKNET_STATE_V0").unwrap(),
+        expected_roots.contracts_trie_root.into(),
+        expected_roots.classes_trie_root.into(),
+    ]);
+    assert_eq!(roots.global_root(), GlobalRoot(expected_global_root.into()));
+    let roots_without_classes = StateRoots { classes_trie_root: StarkHash::ZERO, ..roots };
+    assert_eq!(roots_without_classes.global_root(), GlobalRoot(roots.contracts_trie_root));
+}
+
+/// A known answer from Pathfinder's contract state hash test.
+#[test]
+fn test_contract_state_hash_known_answer() {
+    let contract_state = ContractState {
+        class_hash: class_hash!(
+            "0x2ff4903e17f87b298ded00c44bfeb22874c5f73be2ced8f1d9d9556fb509779"
+        ),
+        storage_root: stark_felt!(
+            "0x4fb440e8ca9b74fc12a22ebffe0bc0658206337897226117b985434c239c028"
+        ),
+        nonce: Nonce::default(),
+    };
+    assert_eq!(
+        contract_state.hash(),
+        stark_felt!("0x7161b591c893836263a64f2a7e0d829c92f6956148a60ce5e99a3f55c7973f3")
+    );
+}
+
+#[test]
+fn test_incremental_commitment() {
+    let (contract_address0, contract_address1) =
+        (contract_address!("0x100"), contract_address!("0x101"));
+    let (key0, key1) = (StorageKey(patricia_key!("0x10")), StorageKey(patricia_key!("0x11")));
+    let first_state_diff = CommitmentStateDiff {
+        address_to_class_hash: indexmap!(
+            contract_address0 => class_hash!("0x20"),
+            contract_address1 => class_hash!("0x21"),
+        ),
+        address_to_nonce: indexmap!(contract_address0 => Nonce(stark_felt!("0x1"))),
+        storage_updates: indexmap!(
+            contract_address0 => indexmap!(key0 => stark_felt!("0x1"), key1 => stark_felt!("0x2")),
+        ),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+    // Changes only part of the state of the first contract; the rest is read from the tries.
+    let second_state_diff = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::new(),
+        storage_updates: indexmap!(
+            contract_address0 => indexmap!(key0 => stark_felt!("0x3"), key1 => StarkFelt::ZERO),
+        ),
+        class_hash_to_compiled_class_hash: indexmap!(
+            class_hash!("0x20") => CompiledClassHash(stark_felt!("0x30"))
+        ),
+    };
+    let mut store = InMemoryNodeStore::default();
+    let roots = commit(&mut store, &StateRoots::default(), &first_state_diff);
+    let roots = commit(&mut store, &roots, &second_state_diff);
+
+    let merged_state_diff = CommitmentStateDiff {
+        storage_updates: indexmap!(contract_address0 => indexmap!(key0 => stark_felt!("0x3"))),
+        class_hash_to_compiled_class_hash: second_state_diff.class_hash_to_compiled_class_hash,
+        ..first_state_diff
+    };
+    let expected_roots =
+        commit(&mut InMemoryNodeStore::default(), &StateRoots::default(), &merged_state_diff);
+    assert_eq!(roots, expected_roots);
+    assert_eq!(
+        get_contract_state(&store, roots.contracts_trie_root, contract_address0).unwrap(),
+        ContractState {
+            class_hash: class_hash!("0x20"),
+            storage_root: single_leaf_root(*key0.0.key(), stark_felt!("0x3")),
+            nonce: Nonce(stark_felt!("0x1")),
+        }
+    );
+
+    // An empty state diff keeps the roots.
+    let empty_state_diff = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::new(),
+        storage_updates: IndexMap::new(),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+    assert_eq!(commit(&mut store, &roots, &empty_state_diff), roots);
+}
@@ -0,0 +1,5 @@
+pub mod speculative_execution;
+pub mod versioned_state;
+
+/// The index of a transaction within the executed batch (block).
+pub type TxIndex = usize;
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use crate::concurrency::versioned_state::{ReadSet, VersionedState};
+use crate::concurrency::TxIndex;
+use crate::context::BlockContext;
+use crate::state::cached_state::{
+    CachedState, GlobalContractCache, StagedTransactionalState, StateChangesKeys,
+};
+use crate::state::state_api::StateReader;
+use crate::transaction::objects::{TransactionExecutionInfo, TransactionExecutionResult};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::ExecutableTransaction;
+
+/// The outcome of executing a transaction on top of a versioned state.
+pub struct SpeculativeExecution {
+    /// The values observed by the execution; it is valid only if they match the state on top of
+    /// which the transaction is eventually committed.
+    pub read_set: ReadSet,
+    pub result: TransactionExecutionResult<(TransactionExecutionInfo, StagedTransactionalState)>,
+}
+
+/// Executes the transaction at the given index on top of the versioned state, and publishes its
+/// writes to the following transactions.
+pub fn execute_speculatively<S: StateReader>(
+    tx: Transaction,
+    tx_index: TxIndex,
+    versioned_state: &VersionedState<S>,
+    global_contract_cache: GlobalContractCache,
+    block_context: &BlockContext,
+    charge_fee: bool,
+) -> SpeculativeExecution {
+    let mut state = CachedState::new(versioned_state.proxy(tx_index), global_contract_cache);
+    if let Transaction::AccountTransaction(account_tx) = &tx {
+        // A failed prefetch fails the validation of the execution, which is then redone upon
+        // commit.
+        let _ = account_tx.prefetch_state(&state, block_context);
+    }
+    let mut transactional_state = CachedState::create_transactional(&mut state);
+    let validate = true;
+
+    let result = tx
+        .execute_raw(&mut transactional_state, block_context, charge_fee, validate)
+        .map(|tx_execution_info| {
+            // The counting information is recomputed upon commit, w.r.t. the executor's state.
+            let staged_state = transactional_state.stage(
+                HashSet::default(),
+                HashSet::default(),
+                StateChangesKeys::default(),
+            );
+            versioned_state.apply_writes(
+                tx_index,
+                &tx_execution_info.state_changes,
+                &staged_state.class_hash_to_class,
+            );
+
+            (tx_execution_info, staged_state)
+        });
+
+    SpeculativeExecution { read_set: state.state.into_read_set(), result }
+}
@@ -0,0 +1,343 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::concurrency::TxIndex;
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::{ContractClassMapping, StateChanges, StorageEntry};
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "versioned_state_test.rs"]
+pub mod test;
+
+/// Holds, for each key, the values written to it, indexed by the writing transaction.
+#[derive(Debug)]
+struct VersionedStorage<K, V> {
+    writes: HashMap<K, BTreeMap<TxIndex, V>>,
+}
+
+impl<K, V> Default for VersionedStorage<K, V> {
+    fn default() -> Self {
+        Self { writes: HashMap::default() }
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> VersionedStorage<K, V> {
+    /// Returns the value written by the last transaction preceding `tx_index`, if there is one.
+    fn read(&self, tx_index: TxIndex, key: &K) -> Option<V> {
+        let key_writes = self.writes.get(key)?;
+        key_writes.range(..tx_index).next_back().map(|(_, value)| value.clone())
+    }
+
+    fn write(&mut self, tx_index: TxIndex, key: K, value: V) {
+        self.writes.entry(key).or_default().insert(tx_index, value);
+    }
+}
+
+#[derive(Debug, Default)]
+struct VersionedWrites {
+    storage: VersionedStorage<StorageEntry, StarkFelt>,
+    nonces: VersionedStorage<ContractAddress, Nonce>,
+    class_hashes: VersionedStorage<ContractAddress, ClassHash>,
+    compiled_class_hashes: VersionedStorage<ClassHash, CompiledClassHash>,
+    compiled_classes: VersionedStorage<ClassHash, ContractClass>,
+}
+
+/// A multi-version view of the state, used for speculative (concurrent) execution of a batch of
+/// transactions.
+/// A read made on behalf of a transaction observes the last write done by a preceding transaction
+/// that has already been executed; otherwise, it falls back to the underlying state.
+#[derive(Debug)]
+pub struct VersionedState<S: StateReader> {
+    state: Mutex<S>,
+    writes: Mutex<VersionedWrites>,
+}
+
+impl<S: StateReader> VersionedState<S> {
+    pub fn new(state: S) -> Self {
+        Self { state: Mutex::new(state), writes: Mutex::new(VersionedWrites::default()) }
+    }
+
+    /// Returns a reader of the state as seen by the transaction at the given index.
+    pub fn proxy(&self, tx_index: TxIndex) -> VersionedStateProxy<'_, S> {
+        VersionedStateProxy { tx_index, versioned_state: self, read_set: RefCell::default() }
+    }
+
+    /// Publishes the writes of the transaction at the given index, making them visible to the
+    /// transactions following it.
+    pub fn apply_writes(
+        &self,
+        tx_index: TxIndex,
+        state_changes: &StateChanges,
+        class_hash_to_class: &ContractClassMapping,
+    ) {
+        let mut writes = self.writes();
+        for (&storage_entry, &value) in &state_changes.storage_updates {
+            writes.storage.write(tx_index, storage_entry, value);
+        }
+        for (&contract_address, &nonce) in &state_changes.nonce_updates {
+            writes.nonces.write(tx_index, contract_address, nonce);
+        }
+        for (&contract_address, &class_hash) in &state_changes.class_hash_updates {
+            writes.class_hashes.write(tx_index, contract_address, class_hash);
+        }
+        for (&class_hash, &compiled_class_hash) in &state_changes.compiled_class_hash_updates {
+            writes.compiled_class_hashes.write(tx_index, class_hash, compiled_class_hash);
+        }
+        for (&class_hash, contract_class) in class_hash_to_class {
+            writes.compiled_classes.write(tx_index, class_hash, contract_class.clone());
+        }
+    }
+
+    fn get_storage_at(
+        &self,
+        tx_index: TxIndex,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        if let Some(value) = self.writes().storage.read(tx_index, &(contract_address, key)) {
+            return Ok(value);
+        }
+        self.state().get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(
+        &self,
+        tx_index: TxIndex,
+        contract_address: ContractAddress,
+    ) -> StateResult<Nonce> {
+        if let Some(nonce) = self.writes().nonces.read(tx_index, &contract_address) {
+            return Ok(nonce);
+        }
+        self.state().get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(
+        &self,
+        tx_index: TxIndex,
+        contract_address: ContractAddress,
+    ) -> StateResult<ClassHash> {
+        if let Some(class_hash) = self.writes().class_hashes.read(tx_index, &contract_address) {
+            return Ok(class_hash);
+        }
+        self.state().get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &self,
+        tx_index: TxIndex,
+        class_hash: ClassHash,
+    ) -> StateResult<ContractClass> {
+        if let Some(contract_class) = self.writes().compiled_classes.read(tx_index, &class_hash) {
+            return Ok(contract_class);
+        }
+        self.state().get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(
+        &self,
+        tx_index: TxIndex,
+        class_hash: ClassHash,
+    ) -> StateResult<CompiledClassHash> {
+        if let Some(compiled_class_hash) =
+            self.writes().compiled_class_hashes.read(tx_index, &class_hash)
+        {
+            return Ok(compiled_class_hash);
+        }
+        self.state().get_compiled_class_hash(class_hash)
+    }
+
+    /// Prefetches the given keys from the underlying state, unless a transaction preceding
+    /// `tx_index` wrote them.
+    fn prefetch(
+        &self,
+        tx_index: TxIndex,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<()> {
+        let (storage_entries, contract_addresses, class_hashes) = {
+            let writes = self.writes();
+            let storage_entries: Vec<StorageEntry> = storage_entries
+                .iter()
+                .copied()
+                .filter(|storage_entry| writes.storage.read(tx_index, storage_entry).is_none())
+                .collect();
+            let contract_addresses: Vec<ContractAddress> = contract_addresses
+                .iter()
+                .copied()
+                .filter(|contract_address| {
+                    writes.nonces.read(tx_index, contract_address).is_none()
+                        || writes.class_hashes.read(tx_index, contract_address).is_none()
+                })
+                .collect();
+            let class_hashes: Vec<ClassHash> = class_hashes
+                .iter()
+                .copied()
+                .filter(|class_hash| writes.compiled_classes.read(tx_index, class_hash).is_none())
+                .collect();
+            (storage_entries, contract_addresses, class_hashes)
+        };
+
+        self.state().prefetch(&storage_entries, &contract_addresses, &class_hashes)?;
+        Ok(())
+    }
+
+    /// Locks the underlying state; reads that fall back to it wait until the guard is dropped.
+    pub fn state(&self) -> MutexGuard<'_, S> {
+        self.state.lock().expect("Versioned state's underlying state is poisoned.")
+    }
+
+    fn writes(&self) -> MutexGuard<'_, VersionedWrites> {
+        self.writes.lock().expect("Versioned state's writes are poisoned.")
+    }
+}
+
+/// The values observed by a transaction during its execution.
+#[derive(Debug, Default)]
+pub struct ReadSet {
+    storage: HashMap<StorageEntry, StarkFelt>,
+    nonces: HashMap<ContractAddress, Nonce>,
+    class_hashes: HashMap<ContractAddress, ClassHash>,
+    compiled_class_hashes: HashMap<ClassHash, CompiledClassHash>,
+    // Maps a requested class to whether it was declared.
+    declared_classes: HashMap<ClassHash, bool>,
+    // Set if a read failed for a reason other than the requested class not being declared;
+    // such a read set is never valid.
+    has_failed_reads: bool,
+}
+
+impl ReadSet {
+    /// Returns whether the recorded values match the given state; i.e., whether the (deterministic)
+    /// execution that observed them would have behaved exactly the same on top of `state`.
+    pub fn validate(&self, state: &impl StateReader) -> bool {
+        !self.has_failed_reads
+            && self.storage.iter().all(|(&(contract_address, key), &value)| {
+                matches!(state.get_storage_at(contract_address, key), Ok(actual) if actual == value)
+            })
+            && self.nonces.iter().all(|(&contract_address, &nonce)| {
+                matches!(state.get_nonce_at(contract_address), Ok(actual) if actual == nonce)
+            })
+            && self.class_hashes.iter().all(|(&contract_address, &class_hash)| {
+                matches!(
+                    state.get_class_hash_at(contract_address), Ok(actual) if actual == class_hash
+                )
+            })
+            && self.compiled_class_hashes.iter().all(|(&class_hash, &compiled_class_hash)| {
+                matches!(
+                    state.get_compiled_class_hash(class_hash),
+                    Ok(actual) if actual == compiled_class_hash
+                )
+            })
+            && self.declared_classes.iter().all(|(&class_hash, &is_declared)| {
+                match state.get_compiled_contract_class(class_hash) {
+                    Ok(_) => is_declared,
+                    Err(StateError::UndeclaredClassHash(_)) => !is_declared,
+                    Err(_) => false,
+                }
+            })
+    }
+}
+
+/// A reader of a versioned state, on behalf of a specific transaction.
+/// Records the values it returns, so that the execution can be validated later.
+pub struct VersionedStateProxy<'a, S: StateReader> {
+    tx_index: TxIndex,
+    versioned_state: &'a VersionedState<S>,
+    read_set: RefCell<ReadSet>,
+}
+
+impl<S: StateReader> VersionedStateProxy<'_, S> {
+    pub fn into_read_set(self) -> ReadSet {
+        self.read_set.into_inner()
+    }
+
+    fn record_read<T>(
+        &self,
+        read_result: StateResult<T>,
+        record: impl FnOnce(&mut ReadSet, &T),
+    ) -> StateResult<T> {
+        let mut read_set = self.read_set.borrow_mut();
+        match &read_result {
+            Ok(value) => record(&mut read_set, value),
+            Err(_) => read_set.has_failed_reads = true,
+        }
+
+        read_result
+    }
+}
+
+impl<S: StateReader> StateReader for VersionedStateProxy<'_, S> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        let value = self.versioned_state.get_storage_at(self.tx_index, contract_address, key);
+        self.record_read(value, |read_set, &value| {
+            read_set.storage.insert((contract_address, key), value);
+        })
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        let nonce = self.versioned_state.get_nonce_at(self.tx_index, contract_address);
+        self.record_read(nonce, |read_set, &nonce| {
+            read_set.nonces.insert(contract_address, nonce);
+        })
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        let class_hash = self.versioned_state.get_class_hash_at(self.tx_index, contract_address);
+        self.record_read(class_hash, |read_set, &class_hash| {
+            read_set.class_hashes.insert(contract_address, class_hash);
+        })
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        let contract_class =
+            self.versioned_state.get_compiled_contract_class(self.tx_index, class_hash);
+        // An undeclared class is a legitimate observation, which must be validated as well.
+        if let Err(StateError::UndeclaredClassHash(_)) = contract_class {
+            self.read_set.borrow_mut().declared_classes.insert(class_hash, false);
+            return contract_class;
+        }
+        self.record_read(contract_class, |read_set, _| {
+            read_set.declared_classes.insert(class_hash, true);
+        })
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        let compiled_class_hash =
+            self.versioned_state.get_compiled_class_hash(self.tx_index, class_hash);
+        self.record_read(compiled_class_hash, |read_set, &compiled_class_hash| {
+            read_set.compiled_class_hashes.insert(class_hash, compiled_class_hash);
+        })
+    }
+
+    /// Warms the underlying state, but returns none of the values; that way, only the values the
+    /// transaction actually reads are recorded, and hence validated.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        if let Err(error) = self.versioned_state.prefetch(
+            self.tx_index,
+            storage_entries,
+            contract_addresses,
+            class_hashes,
+        ) {
+            self.read_set.borrow_mut().has_failed_reads = true;
+            return Err(error);
+        }
+        Ok(PrefetchedState::default())
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, HashSet};
+
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::concurrency::versioned_state::VersionedState;
+use crate::state::cached_state::StateChanges;
+use crate::state::state_api::{PrefetchedState, StateReader};
+use crate::test_utils::dict_state_reader::DictStateReader;
+use crate::test_utils::prefetch_recording_reader::{PrefetchBatch, PrefetchRecordingReader};
+
+#[test]
+fn test_versioned_state_reads() {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let initial_value = stark_felt!("0x1");
+    let versioned_state = VersionedState::new(DictStateReader {
+        storage_view: HashMap::from([((contract_address, key), initial_value)]),
+        ..Default::default()
+    });
+
+    let write = |tx_index, value| {
+        let state_changes = StateChanges {
+            storage_updates: HashMap::from([((contract_address, key), value)]),
+            ..Default::default()
+        };
+        versioned_state.apply_writes(tx_index, &state_changes, &HashMap::default());
+    };
+    write(1, stark_felt!("0x2"));
+    write(3, stark_felt!("0x3"));
+
+    // A transaction observes the last write of the transactions preceding it, and never its own
+    // writes or the writes of following transactions.
+    for (tx_index, expected_value) in [
+        (0, initial_value),
+        (1, initial_value),
+        (2, stark_felt!("0x2")),
+        (3, stark_felt!("0x2")),
+        (4, stark_felt!("0x3")),
+    ] {
+        let proxy = versioned_state.proxy(tx_index);
+        assert_eq!(proxy.get_storage_at(contract_address, key).unwrap(), expected_value);
+    }
+}
+
+#[test]
+fn test_versioned_state_prefetch() {
+    let contract_address = contract_address!("0x100");
+    let (written_key, key) = (StorageKey(patricia_key!("0x10")), StorageKey(patricia_key!("0x11")));
+    let versioned_state =
+        VersionedState::new(PrefetchRecordingReader::new(DictStateReader::default()));
+    versioned_state.apply_writes(
+        0,
+        &StateChanges {
+            storage_updates: HashMap::from([((contract_address, written_key), stark_felt!("0x1"))]),
+            ..Default::default()
+        },
+        &HashMap::default(),
+    );
+
+    // The underlying state is warmed with the keys that were not written by preceding
+    // transactions, and no values are returned, so that none are recorded without being read.
+    let proxy = versioned_state.proxy(1);
+    let storage_entries = [(contract_address, written_key), (contract_address, key)];
+    assert_eq!(
+        proxy.prefetch(&storage_entries, &[contract_address], &[]).unwrap(),
+        PrefetchedState::default()
+    );
+    assert_eq!(
+        versioned_state.state().batches.take(),
+        vec![PrefetchBatch {
+            storage_entries: HashSet::from([(contract_address, key)]),
+            contract_addresses: HashSet::from([contract_address]),
+            ..Default::default()
+        }]
+    );
+    assert!(proxy.into_read_set().validate(&DictStateReader::default()));
+}
+
+#[test]
+fn test_read_set_validation() {
+    let contract_address = contract_address!("0x100");
+    let undeclared_class_hash = class_hash!("0x200");
+    let versioned_state = VersionedState::new(DictStateReader::default());
+    versioned_state.apply_writes(
+        0,
+        &StateChanges {
+            nonce_updates: HashMap::from([(contract_address, Nonce(stark_felt!("0x1")))]),
+            ..Default::default()
+        },
+        &HashMap::default(),
+    );
+
+    let proxy = versioned_state.proxy(1);
+    assert_eq!(proxy.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!("0x1")));
+    assert!(proxy.get_compiled_contract_class(undeclared_class_hash).is_err());
+    let read_set = proxy.into_read_set();
+
+    // The nonce written by the first transaction is not part of the given state.
+    let mut state = DictStateReader::default();
+    assert!(!read_set.validate(&state));
+
+    state.address_to_nonce.insert(contract_address, Nonce(stark_felt!("0x1")));
+    assert!(read_set.validate(&state));
+}
@@ -1,6 +1,9 @@
+use serde::{Deserialize, Serialize};
 use starknet_api::core::{ChainId, ContractAddress};
 
 use crate::blockifier::block::BlockInfo;
+use crate::execution::contract_executor::SharedContractExecutorRegistry;
+use crate::execution::tracer::SharedExecutionTracer;
 use crate::transaction::objects::{FeeType, TransactionInfo, TransactionInfoCreator};
 use crate::versioned_constants::VersionedConstants;
 
@@ -16,6 +19,12 @@ pub struct BlockContext {
     pub(crate) block_info: BlockInfo,
     pub(crate) chain_info: ChainInfo,
     pub(crate) versioned_constants: VersionedConstants,
+    // Set for speculative (concurrent) executions, in which the fee transfer is deferred to the
+    // commit of the transaction.
+    pub(crate) concurrency_mode: bool,
+    // Attached to the execution context of every entry point executed in the block.
+    pub(crate) tracer: Option<SharedExecutionTracer>,
+    pub(crate) contract_executors: Option<SharedContractExecutorRegistry>,
 }
 
 impl BlockContext {
@@ -31,9 +40,27 @@ impl BlockContext {
             block_info: block_info.clone(),
             chain_info: chain_info.clone(),
             versioned_constants: versioned_constants.clone(),
+            concurrency_mode: false,
+            tracer: None,
+            contract_executors: None,
         }
     }
 
+    /// Attaches the tracer to all executions of the block's transactions.
+    pub fn with_tracer(mut self, tracer: SharedExecutionTracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Executes the calls of the block's transactions by the executors of the registry.
+    pub fn with_contract_executors(
+        mut self,
+        contract_executors: SharedContractExecutorRegistry,
+    ) -> Self {
+        self.contract_executors = Some(contract_executors);
+        self
+    }
+
     pub fn block_info(&self) -> &BlockInfo {
         &self.block_info
     }
@@ -59,7 +86,7 @@ impl BlockContext {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ChainInfo {
     pub chain_id: ChainId,
     pub fee_token_addresses: FeeTokenAddresses,
@@ -83,7 +110,7 @@ impl Default for ChainInfo {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct FeeTokenAddresses {
     pub strk_fee_token_address: ContractAddress,
     pub eth_fee_token_address: ContractAddress,
@@ -1,7 +1,9 @@
+pub mod call;
 pub mod call_info;
 pub mod common_hints;
 pub mod contract_address;
 pub mod contract_class;
+pub mod contract_executor;
 pub mod deprecated_entry_point_execution;
 pub mod deprecated_syscalls;
 pub mod entry_point;
@@ -10,3 +12,5 @@ pub mod errors;
 pub mod execution_utils;
 pub mod hint_code;
 pub mod syscalls;
+pub mod trace;
+pub mod tracer;
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_api::transaction::Calldata;
+
+use crate::context::{BlockContext, TransactionContext};
+use crate::execution::call_info::{CallInfo, Retdata};
+use crate::execution::entry_point::{
+    CallEntryPoint, CallType, EntryPointExecutionContext, EntryPointExecutionResult,
+};
+use crate::execution::errors::EntryPointExecutionError;
+use crate::state::cached_state::{CachedState, GlobalContractCache};
+use crate::state::state_api::StateReader;
+use crate::transaction::objects::{DeprecatedTransactionInfo, TransactionInfo};
+
+#[cfg(test)]
+#[path = "call_test.rs"]
+pub mod test;
+
+const CALL_CONTRACT_CACHE_SIZE: usize = 1;
+
+/// The outcome of a read-only contract call.
+#[derive(Debug)]
+pub struct CallContractResult {
+    pub retdata: Retdata,
+    pub call_info: CallInfo,
+}
+
+/// Calls the given external entry point of a deployed contract, outside of any transaction (as
+/// `starknet_call` does); the caller address and the transaction info seen by the contract are
+/// empty.
+/// Writes made during the call are discarded; i.e., the given state is never mutated.
+pub fn call_contract<S: StateReader>(
+    state: &S,
+    block_context: &BlockContext,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPointSelector,
+    calldata: Calldata,
+    initial_gas: u64,
+) -> EntryPointExecutionResult<CallContractResult> {
+    let call = CallEntryPoint {
+        class_hash: None,
+        code_address: Some(contract_address),
+        entry_point_type: EntryPointType::External,
+        entry_point_selector,
+        calldata,
+        storage_address: contract_address,
+        caller_address: ContractAddress::default(),
+        call_type: CallType::Call,
+        initial_gas,
+    };
+    let tx_context = TransactionContext {
+        block_context: block_context.clone(),
+        tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
+    };
+    // Steps are limited by the block's bounds only, as there are no resource bounds to derive
+    // them from.
+    let limit_steps_by_resources = false;
+    let mut context =
+        EntryPointExecutionContext::new_invoke(Arc::new(tx_context), limit_steps_by_resources)
+            .map_err(|error| EntryPointExecutionError::InternalError(error.to_string()))?;
+
+    // The writes are made on a cache on top of the given state, which is dropped afterwards; the
+    // classes read by the call are cached by it as well, so no cache is shared beyond the call.
+    let mut call_state =
+        CachedState::new(state, GlobalContractCache::new(CALL_CONTRACT_CACHE_SIZE));
+    let call_info =
+        call.execute(&mut call_state, &mut ExecutionResources::default(), &mut context)?;
+
+    Ok(CallContractResult { retdata: call_info.execution.retdata.clone(), call_info })
+}
@@ -0,0 +1,77 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::Calldata;
+use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::context::BlockContext;
+use crate::execution::call::call_contract;
+use crate::execution::call_info::Retdata;
+use crate::execution::errors::{EntryPointExecutionError, PreExecutionError};
+use crate::retdata;
+use crate::state::state_api::StateReader;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{CairoVersion, BALANCE};
+
+#[rstest]
+fn test_call_contract(
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let block_context = BlockContext::create_for_testing();
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let state = test_state(&block_context.chain_info, BALANCE, &[(test_contract, 1)]);
+    let contract_address = test_contract.get_instance_address(0);
+    let initial_gas = block_context.versioned_constants.gas_cost("initial_gas_cost");
+
+    let (key, value) = (stark_felt!(1234_u16), stark_felt!(18_u8));
+    let result = call_contract(
+        &state,
+        &block_context,
+        contract_address,
+        selector_from_name("test_storage_read_write"),
+        calldata![key, value],
+        initial_gas,
+    )
+    .unwrap();
+    assert_eq!(result.retdata, retdata![value]);
+    assert_eq!(result.call_info.execution.retdata, result.retdata);
+    // Cairo 0 storage writes also read the value they overwrite.
+    let expected_storage_read_values = match cairo_version {
+        CairoVersion::Cairo0 => vec![stark_felt!(0_u8), value],
+        CairoVersion::Cairo1 => vec![value],
+    };
+    assert_eq!(result.call_info.storage_read_values, expected_storage_read_values);
+
+    // The write made by the call is discarded.
+    assert_eq!(
+        state.get_storage_at(contract_address, StorageKey(patricia_key!(key))).unwrap(),
+        StarkFelt::default()
+    );
+}
+
+#[test]
+fn test_call_undeployed_contract() {
+    let block_context = BlockContext::create_for_testing();
+    let state = test_state(&block_context.chain_info, BALANCE, &[]);
+    let contract_address = contract_address!("0x1234");
+
+    let result = call_contract(
+        &state,
+        &block_context,
+        contract_address,
+        selector_from_name("test_storage_read_write"),
+        calldata![],
+        block_context.versioned_constants.gas_cost("initial_gas_cost"),
+    );
+    assert_matches!(
+        result,
+        Err(EntryPointExecutionError::PreExecutionError(
+            PreExecutionError::UninitializedStorageAddress(address)
+        )) if address == contract_address
+    );
+}
@@ -17,7 +17,7 @@ use cairo_vm::vm::runners::builtin_runner::{HASH_BUILTIN_NAME, POSEIDON_BUILTIN_
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
 use itertools::Itertools;
 use serde::de::Error as DeserializationError;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use starknet_api::core::EntryPointSelector;
 use starknet_api::deprecated_contract_class::{
     ContractClass as DeprecatedContractClass, EntryPoint, EntryPointOffset, EntryPointType,
@@ -117,7 +117,7 @@ impl ContractClassV0 {
             + self.n_builtins()
             + self.bytecode_length()
             + 1; // Hinted class hash.
-        // The hashed data size is approximately the number of hashes (invoked in hash chains).
+                 // The hashed data size is approximately the number of hashes (invoked in hash chains).
         let n_steps = constants::N_STEPS_PER_PEDERSEN * hashed_data_size;
 
         ExecutionResources {
@@ -339,7 +339,7 @@ pub struct ContractClassV1Inner {
     bytecode_segment_lengths: NestedIntList,
 }
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct EntryPointV1 {
     pub selector: EntryPointSelector,
     pub offset: EntryPointOffset,
@@ -356,17 +356,28 @@ impl TryFrom<CasmContractClass> for ContractClassV1 {
     type Error = ProgramError;
 
     fn try_from(class: CasmContractClass) -> Result<Self, Self::Error> {
-        let data: Vec<MaybeRelocatable> = class
-            .bytecode
-            .into_iter()
-            .map(|x| MaybeRelocatable::from(Felt252::from(x.value)))
-            .collect();
+        let bytecode: Vec<Felt252> =
+            class.bytecode.into_iter().map(|x| Felt252::from(x.value)).collect();
 
-        let mut hints: HashMap<usize, Vec<HintParams>> = HashMap::new();
+        let mut entry_points_by_type = HashMap::new();
+        entry_points_by_type.insert(
+            EntryPointType::Constructor,
+            convert_entry_points_v1(class.entry_points_by_type.constructor)?,
+        );
+        entry_points_by_type.insert(
+            EntryPointType::External,
+            convert_entry_points_v1(class.entry_points_by_type.external)?,
+        );
+        entry_points_by_type.insert(
+            EntryPointType::L1Handler,
+            convert_entry_points_v1(class.entry_points_by_type.l1_handler)?,
+        );
+
+        let mut hint_params_by_pc: HashMap<usize, Vec<HintParams>> = HashMap::new();
         for (i, hint_list) in class.hints.iter() {
             let hint_params: Result<Vec<HintParams>, ProgramError> =
                 hint_list.iter().map(hint_to_hint_params).collect();
-            hints.insert(*i, hint_params?);
+            hint_params_by_pc.insert(*i, hint_params?);
         }
 
         // Collect a sting to hint map so that the hint processor can fetch the correct [Hint]
@@ -378,6 +389,30 @@ impl TryFrom<CasmContractClass> for ContractClassV1 {
             }
         }
 
+        Self::from_parts(
+            bytecode,
+            hint_params_by_pc,
+            string_to_hint,
+            entry_points_by_type,
+            class.bytecode_segment_lengths,
+        )
+    }
+}
+
+impl ContractClassV1 {
+    /// Builds the class from its bytecode, the VM hint parameters of each PC, the hints by their
+    /// code and its entry points; i.e., the converted parts of a CASM class that are required for
+    /// execution.
+    pub fn from_parts(
+        bytecode: Vec<Felt252>,
+        hint_params_by_pc: HashMap<usize, Vec<HintParams>>,
+        hints: HashMap<String, Hint>,
+        entry_points_by_type: HashMap<EntryPointType, Vec<EntryPointV1>>,
+        bytecode_segment_lengths: Option<NestedIntList>,
+    ) -> Result<Self, ProgramError> {
+        let data: Vec<MaybeRelocatable> =
+            bytecode.into_iter().map(MaybeRelocatable::from).collect();
+
         let builtins = vec![]; // The builtins are initialize later.
         let main = Some(0);
         let reference_manager = ReferenceManager { references: Vec::new() };
@@ -389,35 +424,20 @@ impl TryFrom<CasmContractClass> for ContractClassV1 {
             builtins,
             data,
             main,
-            hints,
+            hint_params_by_pc,
             reference_manager,
             identifiers,
             error_message_attributes,
             instruction_locations,
         )?;
 
-        let mut entry_points_by_type = HashMap::new();
-        entry_points_by_type.insert(
-            EntryPointType::Constructor,
-            convert_entry_points_v1(class.entry_points_by_type.constructor)?,
-        );
-        entry_points_by_type.insert(
-            EntryPointType::External,
-            convert_entry_points_v1(class.entry_points_by_type.external)?,
-        );
-        entry_points_by_type.insert(
-            EntryPointType::L1Handler,
-            convert_entry_points_v1(class.entry_points_by_type.l1_handler)?,
-        );
-
-        let bytecode_segment_lengths = class
-            .bytecode_segment_lengths
-            .unwrap_or_else(|| NestedIntList::Leaf(program.data_len()));
+        let bytecode_segment_lengths =
+            bytecode_segment_lengths.unwrap_or_else(|| NestedIntList::Leaf(program.data_len()));
 
         Ok(Self(Arc::new(ContractClassV1Inner {
             program,
             entry_points_by_type,
-            hints: string_to_hint,
+            hints,
             bytecode_segment_lengths,
         })))
     }
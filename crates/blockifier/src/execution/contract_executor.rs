@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use starknet_api::core::ClassHash;
+
+use crate::execution::call_info::CallInfo;
+use crate::execution::contract_class::ContractClass;
+use crate::execution::entry_point::{
+    CallEntryPoint, EntryPointExecutionContext, EntryPointExecutionResult,
+};
+use crate::execution::execution_utils::execute_entry_point_call;
+use crate::state::state_api::State;
+
+#[cfg(test)]
+#[path = "contract_executor_test.rs"]
+pub mod test;
+
+/// A registry shared between the caller, who attaches it to the block context (or to a single
+/// execution context), and all (possibly nested) calls of the execution.
+pub type SharedContractExecutorRegistry = Arc<ContractExecutorRegistry>;
+
+/// An engine that executes calls to contract entry points; e.g., the Cairo VM, an instrumented
+/// VM, or a mock for tests.
+///
+/// The call is resolved before it is passed to the executor: its class hash is set, and the
+/// contract class is that of the class hash. The recursion depth is checked by the caller.
+pub trait ContractExecutor: Debug + Send + Sync {
+    fn execute(
+        &self,
+        call: CallEntryPoint,
+        contract_class: ContractClass,
+        state: &mut dyn State,
+        resources: &mut ExecutionResources,
+        context: &mut EntryPointExecutionContext,
+    ) -> EntryPointExecutionResult<CallInfo>;
+}
+
+/// Runs Cairo 0 and Cairo 1 contracts on the Cairo VM; the default executor.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CairoVmExecutor;
+
+impl ContractExecutor for CairoVmExecutor {
+    fn execute(
+        &self,
+        call: CallEntryPoint,
+        contract_class: ContractClass,
+        state: &mut dyn State,
+        resources: &mut ExecutionResources,
+        context: &mut EntryPointExecutionContext,
+    ) -> EntryPointExecutionResult<CallInfo> {
+        execute_entry_point_call(call, contract_class, state, resources, context)
+    }
+}
+
+/// Selects the executor of each call by the class of the called contract: an executor registered
+/// for the class hash, if any, and otherwise the executor of the Cairo version of the class.
+#[derive(Clone, Debug)]
+pub struct ContractExecutorRegistry {
+    cairo0_executor: Arc<dyn ContractExecutor>,
+    cairo1_executor: Arc<dyn ContractExecutor>,
+    class_executors: HashMap<ClassHash, Arc<dyn ContractExecutor>>,
+}
+
+impl Default for ContractExecutorRegistry {
+    fn default() -> Self {
+        Self {
+            cairo0_executor: Arc::new(CairoVmExecutor),
+            cairo1_executor: Arc::new(CairoVmExecutor),
+            class_executors: HashMap::new(),
+        }
+    }
+}
+
+impl ContractExecutorRegistry {
+    pub fn with_cairo0_executor(mut self, executor: Arc<dyn ContractExecutor>) -> Self {
+        self.cairo0_executor = executor;
+        self
+    }
+
+    pub fn with_cairo1_executor(mut self, executor: Arc<dyn ContractExecutor>) -> Self {
+        self.cairo1_executor = executor;
+        self
+    }
+
+    pub fn with_class_executor(
+        mut self,
+        class_hash: ClassHash,
+        executor: Arc<dyn ContractExecutor>,
+    ) -> Self {
+        self.class_executors.insert(class_hash, executor);
+        self
+    }
+
+    pub fn executor(
+        &self,
+        class_hash: ClassHash,
+        contract_class: &ContractClass,
+    ) -> &dyn ContractExecutor {
+        if let Some(executor) = self.class_executors.get(&class_hash) {
+            return executor.as_ref();
+        }
+
+        match contract_class {
+            ContractClass::V0(_) => self.cairo0_executor.as_ref(),
+            ContractClass::V1(_) => self.cairo1_executor.as_ref(),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use pretty_assertions::assert_eq;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::blockifier::transaction_executor::TransactionExecutor;
+use crate::bouncer::BouncerConfig;
+use crate::context::{BlockContext, TransactionContext};
+use crate::execution::call_info::{CallExecution, CallInfo, Retdata};
+use crate::execution::contract_class::ContractClass;
+use crate::execution::contract_executor::{
+    CairoVmExecutor, ContractExecutor, ContractExecutorRegistry,
+};
+use crate::execution::entry_point::{
+    CallEntryPoint, EntryPointExecutionContext, EntryPointExecutionResult,
+};
+use crate::state::state_api::State;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{create_calldata, trivial_external_entry_point_new, CairoVersion, BALANCE};
+use crate::transaction::objects::{DeprecatedTransactionInfo, TransactionInfo};
+use crate::transaction::test_utils::account_invoke_tx;
+use crate::transaction::transaction_execution::Transaction;
+use crate::{invoke_tx_args, retdata};
+
+/// Returns the same retdata for every call.
+#[derive(Debug)]
+struct MockExecutor(Retdata);
+
+impl ContractExecutor for MockExecutor {
+    fn execute(
+        &self,
+        call: CallEntryPoint,
+        _contract_class: ContractClass,
+        _state: &mut dyn State,
+        _resources: &mut ExecutionResources,
+        _context: &mut EntryPointExecutionContext,
+    ) -> EntryPointExecutionResult<CallInfo> {
+        Ok(CallInfo {
+            call,
+            execution: CallExecution::from_retdata(self.0.clone()),
+            ..Default::default()
+        })
+    }
+}
+
+/// Counts the calls it executes on the Cairo VM.
+#[derive(Debug, Default)]
+struct CountingExecutor(AtomicUsize);
+
+impl ContractExecutor for CountingExecutor {
+    fn execute(
+        &self,
+        call: CallEntryPoint,
+        contract_class: ContractClass,
+        state: &mut dyn State,
+        resources: &mut ExecutionResources,
+        context: &mut EntryPointExecutionContext,
+    ) -> EntryPointExecutionResult<CallInfo> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        CairoVmExecutor.execute(call, contract_class, state, resources, context)
+    }
+}
+
+fn execute_with_executors(
+    call: CallEntryPoint,
+    state: &mut dyn State,
+    contract_executors: ContractExecutorRegistry,
+) -> EntryPointExecutionResult<CallInfo> {
+    let tx_context = TransactionContext {
+        block_context: BlockContext::create_for_testing(),
+        tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
+    };
+    let mut context = EntryPointExecutionContext::new_invoke(Arc::new(tx_context), true)
+        .unwrap()
+        .with_contract_executors(Arc::new(contract_executors));
+    call.execute(state, &mut ExecutionResources::default(), &mut context)
+}
+
+#[test]
+fn test_contract_executor_registry() {
+    let cairo0_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let cairo1_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let chain_info = &BlockContext::create_for_testing().chain_info;
+    let mut state = test_state(chain_info, BALANCE, &[(cairo0_contract, 1), (cairo1_contract, 1)]);
+
+    // The Cairo 0 contract calls the Cairo 1 contract, whose class is executed by a mock.
+    let counting_executor = Arc::new(CountingExecutor::default());
+    let mock_retdata = retdata![stark_felt!("0x1234")];
+    let contract_executors = ContractExecutorRegistry::default()
+        .with_cairo0_executor(counting_executor.clone())
+        .with_class_executor(
+            cairo1_contract.get_class_hash(),
+            Arc::new(MockExecutor(mock_retdata.clone())),
+        );
+    let call = CallEntryPoint {
+        entry_point_selector: selector_from_name("test_call_contract"),
+        calldata: create_calldata(
+            cairo1_contract.get_instance_address(0),
+            "return_result",
+            &[stark_felt!(2_u8)],
+        ),
+        ..trivial_external_entry_point_new(cairo0_contract)
+    };
+    let call_info = execute_with_executors(call, &mut state, contract_executors).unwrap();
+
+    assert_eq!(call_info.execution.retdata, mock_retdata);
+    assert_eq!(call_info.inner_calls[0].call.class_hash, Some(cairo1_contract.get_class_hash()));
+    assert_eq!(counting_executor.0.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_block_contract_executors() {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let counting_executor = Arc::new(CountingExecutor::default());
+    let mock_retdata = retdata![stark_felt!("0x1234")];
+    let contract_executors = ContractExecutorRegistry::default()
+        .with_cairo1_executor(counting_executor.clone())
+        .with_class_executor(
+            test_contract.get_class_hash(),
+            Arc::new(MockExecutor(mock_retdata.clone())),
+        );
+    let block_context = BlockContext::create_for_account_testing()
+        .with_contract_executors(Arc::new(contract_executors));
+    let state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+
+    let tx = Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+        sender_address: account_contract.get_instance_address(0),
+        calldata: create_calldata(
+            test_contract.get_instance_address(0),
+            "return_result",
+            &[stark_felt!(2_u8)],
+        ),
+    }));
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+    let (tx_execution_info, _bouncer_info) = tx_executor.execute(tx, false).unwrap();
+
+    // The account's `__validate__` and `__execute__` run on the Cairo 1 executor, and the called
+    // contract on its class executor.
+    let execute_call_info = tx_execution_info.execute_call_info.unwrap();
+    assert_eq!(execute_call_info.inner_calls[0].execution.retdata, mock_retdata);
+    assert_eq!(counting_executor.0.load(Ordering::SeqCst), 2);
+}
@@ -46,6 +46,7 @@ use crate::execution::execution_utils::{
 };
 use crate::execution::hint_code;
 use crate::execution::syscalls::hint_processor::EmitEventError;
+use crate::execution::tracer::{SyscallData, SyscallTrace, SyscallTraceResponse};
 use crate::state::errors::StateError;
 use crate::state::state_api::State;
 
@@ -225,38 +226,54 @@ impl<'a> DeprecatedSyscallHintProcessor<'a> {
         self.increment_syscall_count(&selector);
 
         match selector {
-            DeprecatedSyscallSelector::CallContract => self.execute_syscall(vm, call_contract),
-            DeprecatedSyscallSelector::DelegateCall => self.execute_syscall(vm, delegate_call),
+            DeprecatedSyscallSelector::CallContract => {
+                self.execute_syscall(vm, selector, call_contract)
+            }
+            DeprecatedSyscallSelector::DelegateCall => {
+                self.execute_syscall(vm, selector, delegate_call)
+            }
             DeprecatedSyscallSelector::DelegateL1Handler => {
-                self.execute_syscall(vm, delegate_l1_handler)
+                self.execute_syscall(vm, selector, delegate_l1_handler)
+            }
+            DeprecatedSyscallSelector::Deploy => self.execute_syscall(vm, selector, deploy),
+            DeprecatedSyscallSelector::EmitEvent => self.execute_syscall(vm, selector, emit_event),
+            DeprecatedSyscallSelector::GetBlockNumber => {
+                self.execute_syscall(vm, selector, get_block_number)
             }
-            DeprecatedSyscallSelector::Deploy => self.execute_syscall(vm, deploy),
-            DeprecatedSyscallSelector::EmitEvent => self.execute_syscall(vm, emit_event),
-            DeprecatedSyscallSelector::GetBlockNumber => self.execute_syscall(vm, get_block_number),
             DeprecatedSyscallSelector::GetBlockTimestamp => {
-                self.execute_syscall(vm, get_block_timestamp)
+                self.execute_syscall(vm, selector, get_block_timestamp)
             }
             DeprecatedSyscallSelector::GetCallerAddress => {
-                self.execute_syscall(vm, get_caller_address)
+                self.execute_syscall(vm, selector, get_caller_address)
             }
             DeprecatedSyscallSelector::GetContractAddress => {
-                self.execute_syscall(vm, get_contract_address)
+                self.execute_syscall(vm, selector, get_contract_address)
             }
             DeprecatedSyscallSelector::GetSequencerAddress => {
-                self.execute_syscall(vm, get_sequencer_address)
+                self.execute_syscall(vm, selector, get_sequencer_address)
+            }
+            DeprecatedSyscallSelector::GetTxInfo => self.execute_syscall(vm, selector, get_tx_info),
+            DeprecatedSyscallSelector::GetTxSignature => {
+                self.execute_syscall(vm, selector, get_tx_signature)
+            }
+            DeprecatedSyscallSelector::LibraryCall => {
+                self.execute_syscall(vm, selector, library_call)
             }
-            DeprecatedSyscallSelector::GetTxInfo => self.execute_syscall(vm, get_tx_info),
-            DeprecatedSyscallSelector::GetTxSignature => self.execute_syscall(vm, get_tx_signature),
-            DeprecatedSyscallSelector::LibraryCall => self.execute_syscall(vm, library_call),
             DeprecatedSyscallSelector::LibraryCallL1Handler => {
-                self.execute_syscall(vm, library_call_l1_handler)
+                self.execute_syscall(vm, selector, library_call_l1_handler)
+            }
+            DeprecatedSyscallSelector::ReplaceClass => {
+                self.execute_syscall(vm, selector, replace_class)
             }
-            DeprecatedSyscallSelector::ReplaceClass => self.execute_syscall(vm, replace_class),
             DeprecatedSyscallSelector::SendMessageToL1 => {
-                self.execute_syscall(vm, send_message_to_l1)
+                self.execute_syscall(vm, selector, send_message_to_l1)
+            }
+            DeprecatedSyscallSelector::StorageRead => {
+                self.execute_syscall(vm, selector, storage_read)
+            }
+            DeprecatedSyscallSelector::StorageWrite => {
+                self.execute_syscall(vm, selector, storage_write)
             }
-            DeprecatedSyscallSelector::StorageRead => self.execute_syscall(vm, storage_read),
-            DeprecatedSyscallSelector::StorageWrite => self.execute_syscall(vm, storage_write),
             _ => Err(HintError::UnknownHint(
                 format!("Unsupported syscall selector {selector:?}.").into(),
             )),
@@ -294,11 +311,12 @@ impl<'a> DeprecatedSyscallHintProcessor<'a> {
     fn execute_syscall<Request, Response, ExecuteCallback>(
         &mut self,
         vm: &mut VirtualMachine,
+        selector: DeprecatedSyscallSelector,
         execute_callback: ExecuteCallback,
     ) -> HintExecutionResult
     where
-        Request: SyscallRequest,
-        Response: SyscallResponse,
+        Request: SyscallRequest + SyscallData,
+        Response: SyscallResponse + SyscallData,
         ExecuteCallback: FnOnce(
             Request,
             &mut VirtualMachine,
@@ -306,8 +324,16 @@ impl<'a> DeprecatedSyscallHintProcessor<'a> {
         ) -> DeprecatedSyscallResult<Response>,
     {
         let request = Request::read(vm, &mut self.syscall_ptr)?;
+        self.context.trace(|tracer| tracer.on_syscall_enter(selector, &request));
 
         let response = execute_callback(request, vm, self)?;
+        self.context.trace(|tracer| {
+            tracer.on_syscall_exit(&SyscallTrace {
+                selector,
+                response: SyscallTraceResponse::Success(&response),
+                gas_consumed: 0,
+            })
+        });
         response.write(vm, &mut self.syscall_ptr)?;
 
         Ok(())
@@ -368,6 +394,8 @@ impl<'a> DeprecatedSyscallHintProcessor<'a> {
         self.accessed_keys.insert(key);
         let value = self.state.get_storage_at(self.storage_address, key)?;
         self.read_values.push(value);
+        let storage_address = self.storage_address;
+        self.context.trace(|tracer| tracer.on_storage_read(storage_address, key, value));
 
         Ok(StorageReadResponse { value })
     }
@@ -379,6 +407,8 @@ impl<'a> DeprecatedSyscallHintProcessor<'a> {
     ) -> DeprecatedSyscallResult<StorageWriteResponse> {
         self.accessed_keys.insert(key);
         self.state.set_storage_at(self.storage_address, key, value)?;
+        let storage_address = self.storage_address;
+        self.context.trace(|tracer| tracer.on_storage_write(storage_address, key, value));
 
         Ok(StorageWriteResponse {})
     }
@@ -369,6 +369,7 @@ pub fn emit_event(
     _vm: &mut VirtualMachine,
     syscall_handler: &mut DeprecatedSyscallHintProcessor<'_>,
 ) -> DeprecatedSyscallResult<EmitEventResponse> {
+    let storage_address = syscall_handler.storage_address;
     let execution_context = &mut syscall_handler.context;
     exceeds_event_size_limit(
         execution_context.versioned_constants(),
@@ -377,6 +378,7 @@ pub fn emit_event(
     )?;
     let ordered_event =
         OrderedEvent { order: execution_context.n_emitted_events, event: request.content };
+    execution_context.trace(|tracer| tracer.on_event(storage_address, &ordered_event));
     syscall_handler.events.push(ordered_event);
     execution_context.n_emitted_events += 1;
 
@@ -15,8 +15,12 @@ use crate::abi::constants;
 use crate::context::{BlockContext, TransactionContext};
 use crate::execution::call_info::CallInfo;
 use crate::execution::common_hints::ExecutionMode;
+use crate::execution::contract_executor::SharedContractExecutorRegistry;
 use crate::execution::errors::{EntryPointExecutionError, PreExecutionError};
 use crate::execution::execution_utils::execute_entry_point_call;
+#[cfg(any(feature = "cheatcodes", test))]
+use crate::execution::syscalls::cheatcodes::SharedCheatcodes;
+use crate::execution::tracer::{ExecutionTracer, SharedExecutionTracer};
 use crate::state::state_api::State;
 use crate::transaction::objects::{HasRelatedFeeType, TransactionExecutionResult, TransactionInfo};
 use crate::transaction::transaction_types::TransactionType;
@@ -60,6 +64,24 @@ pub struct CallEntryPoint {
 
 impl CallEntryPoint {
     pub fn execute(
+        self,
+        state: &mut dyn State,
+        resources: &mut ExecutionResources,
+        context: &mut EntryPointExecutionContext,
+    ) -> EntryPointExecutionResult<CallInfo> {
+        let Some(tracer) = context.tracer.clone() else {
+            return self.execute_untraced(state, resources, context);
+        };
+
+        let call = self.clone();
+        tracer.lock().expect("Execution tracer is poisoned.").on_entry_point_enter(&call);
+        let result = self.execute_untraced(state, resources, context);
+        tracer.lock().expect("Execution tracer is poisoned.").on_entry_point_exit(&call, &result);
+
+        result
+    }
+
+    fn execute_untraced(
         mut self,
         state: &mut dyn State,
         resources: &mut ExecutionResources,
@@ -96,7 +118,13 @@ impl CallEntryPoint {
         self.class_hash = Some(class_hash);
         let contract_class = state.get_compiled_contract_class(class_hash)?;
 
-        execute_entry_point_call(self, contract_class, state, resources, context).map_err(|error| {
+        let execution_result = match context.contract_executors.clone() {
+            Some(contract_executors) => contract_executors
+                .executor(class_hash, &contract_class)
+                .execute(self, contract_class, state, resources, context),
+            None => execute_entry_point_call(self, contract_class, state, resources, context),
+        };
+        execution_result.map_err(|error| {
             let vm_trace = error.try_to_vm_trace();
             match error {
                 // On VM error, pack the stack trace into the propagated error.
@@ -149,6 +177,18 @@ pub struct EntryPointExecutionContext {
 
     // The execution mode affects the behavior of the hint processor.
     pub execution_mode: ExecutionMode,
+
+    /// Observes the execution, if attached; shared with all inner calls. Taken from the block
+    /// context by default.
+    pub tracer: Option<SharedExecutionTracer>,
+
+    /// Selects the engine each call is executed by, if attached; shared with all inner calls.
+    /// Otherwise, all calls are executed by the Cairo VM. Taken from the block context by default.
+    pub contract_executors: Option<SharedContractExecutorRegistry>,
+
+    /// Overrides syscall behavior for testing, if attached; shared with all inner calls.
+    #[cfg(any(feature = "cheatcodes", test))]
+    pub cheatcodes: Option<SharedCheatcodes>,
 }
 
 impl EntryPointExecutionContext {
@@ -166,6 +206,10 @@ impl EntryPointExecutionContext {
             tx_context: tx_context.clone(),
             current_recursion_depth: Default::default(),
             execution_mode: mode,
+            tracer: tx_context.block_context.tracer.clone(),
+            contract_executors: tx_context.block_context.contract_executors.clone(),
+            #[cfg(any(feature = "cheatcodes", test))]
+            cheatcodes: None,
         })
     }
 
@@ -183,6 +227,32 @@ impl EntryPointExecutionContext {
         Self::new(tx_context, ExecutionMode::Execute, limit_steps_by_resources)
     }
 
+    pub fn with_tracer(mut self, tracer: SharedExecutionTracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    pub fn with_contract_executors(
+        mut self,
+        contract_executors: SharedContractExecutorRegistry,
+    ) -> Self {
+        self.contract_executors = Some(contract_executors);
+        self
+    }
+
+    #[cfg(any(feature = "cheatcodes", test))]
+    pub fn with_cheatcodes(mut self, cheatcodes: SharedCheatcodes) -> Self {
+        self.cheatcodes = Some(cheatcodes);
+        self
+    }
+
+    /// Calls the given hook on the attached tracer, if any.
+    pub fn trace(&self, hook: impl FnOnce(&mut dyn ExecutionTracer)) {
+        if let Some(tracer) = &self.tracer {
+            hook(&mut *tracer.lock().expect("Execution tracer is poisoned."));
+        }
+    }
+
     /// Returns the maximum number of cairo steps allowed, given the max fee, gas price and the
     /// execution mode.
     /// If fee is disabled, returns the global maximum.
@@ -0,0 +1,132 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use starknet_api::block::{BlockNumber, BlockTimestamp};
+use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::transaction::EventContent;
+use thiserror::Error;
+
+use crate::blockifier::block::BlockInfo;
+use crate::execution::call_info::Retdata;
+use crate::transaction::objects::TransactionInfo;
+
+#[cfg(test)]
+#[path = "cheatcodes_test.rs"]
+pub mod test;
+
+/// Cheatcodes shared between the test, which sets them up and inspects them afterwards, and the
+/// hint processors of all (possibly nested) calls of the execution.
+pub type SharedCheatcodes = Rc<RefCell<Cheatcodes>>;
+
+#[derive(Debug, Error)]
+pub enum CheatcodeError {
+    #[error("Expected event {event:?} was not emitted by contract {address:?}.")]
+    ExpectedEventNotEmitted { address: ContractAddress, event: EventContent },
+}
+
+/// Execution info fields seen by a contract instead of the actual ones; unset fields are not
+/// overridden.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionInfoCheats {
+    pub caller_address: Option<ContractAddress>,
+    pub block_number: Option<BlockNumber>,
+    pub block_timestamp: Option<BlockTimestamp>,
+    pub sequencer_address: Option<ContractAddress>,
+    pub tx_info: Option<TransactionInfo>,
+}
+
+impl ExecutionInfoCheats {
+    pub fn cheat_block_info(&self, block_info: &BlockInfo) -> BlockInfo {
+        BlockInfo {
+            block_number: self.block_number.unwrap_or(block_info.block_number),
+            block_timestamp: self.block_timestamp.unwrap_or(block_info.block_timestamp),
+            sequencer_address: self.sequencer_address.unwrap_or(block_info.sequencer_address),
+            ..block_info.clone()
+        }
+    }
+}
+
+/// Test-only overrides of the syscall hint processor's behavior (as used by contract testing
+/// frameworks), attached via the entry point execution context.
+/// Applies to Cairo 1 contracts only.
+#[derive(Debug, Default)]
+pub struct Cheatcodes {
+    execution_info_cheats: HashMap<ContractAddress, ExecutionInfoCheats>,
+    mocked_calls: HashMap<(ContractAddress, EntryPointSelector), Retdata>,
+    expected_events: Vec<(ContractAddress, EventContent)>,
+    emitted_events: Vec<(ContractAddress, EventContent)>,
+}
+
+impl Cheatcodes {
+    /// Overrides the `get_execution_info` syscall result of calls executed in the context of the
+    /// given contract (i.e., of its storage address).
+    pub fn cheat_execution_info(&mut self, address: ContractAddress, cheats: ExecutionInfoCheats) {
+        self.execution_info_cheats.insert(address, cheats);
+    }
+
+    pub fn stop_cheat_execution_info(&mut self, address: ContractAddress) {
+        self.execution_info_cheats.remove(&address);
+    }
+
+    pub fn execution_info_cheats(&self, address: ContractAddress) -> Option<&ExecutionInfoCheats> {
+        self.execution_info_cheats.get(&address)
+    }
+
+    /// Makes `call_contract` syscalls to the given entry point return the given data, without
+    /// executing it.
+    pub fn mock_call(
+        &mut self,
+        address: ContractAddress,
+        selector: EntryPointSelector,
+        retdata: Retdata,
+    ) {
+        self.mocked_calls.insert((address, selector), retdata);
+    }
+
+    pub fn stop_mock_call(&mut self, address: ContractAddress, selector: EntryPointSelector) {
+        self.mocked_calls.remove(&(address, selector));
+    }
+
+    pub fn mocked_call(
+        &self,
+        address: ContractAddress,
+        selector: EntryPointSelector,
+    ) -> Option<&Retdata> {
+        self.mocked_calls.get(&(address, selector))
+    }
+
+    pub fn expect_event(&mut self, address: ContractAddress, event: EventContent) {
+        self.expected_events.push((address, event));
+    }
+
+    pub fn record_event(&mut self, address: ContractAddress, event: EventContent) {
+        self.emitted_events.push((address, event));
+    }
+
+    /// Returns all events emitted since the cheatcodes were attached, in emission order.
+    pub fn emitted_events(&self) -> &[(ContractAddress, EventContent)] {
+        &self.emitted_events
+    }
+
+    /// Verifies that each expected event was emitted; an emitted event satisfies a single
+    /// expectation.
+    pub fn assert_expected_events(&self) -> Result<(), CheatcodeError> {
+        let mut unmatched_events: Vec<_> = self.emitted_events.iter().collect();
+        for (address, event) in &self.expected_events {
+            let Some(index) =
+                unmatched_events.iter().position(|(emitted_address, emitted_event)| {
+                    emitted_address == address && emitted_event == event
+                })
+            else {
+                return Err(CheatcodeError::ExpectedEventNotEmitted {
+                    address: *address,
+                    event: event.clone(),
+                });
+            };
+            unmatched_events.remove(index);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockNumber, BlockTimestamp};
+use starknet_api::core::{ChainId, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{
+    Calldata, EventContent, EventData, EventKey, Fee, TransactionHash, TransactionVersion,
+};
+use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::context::{BlockContext, TransactionContext};
+use crate::execution::call_info::{CallInfo, Retdata};
+use crate::execution::entry_point::{
+    CallEntryPoint, EntryPointExecutionContext, EntryPointExecutionResult,
+};
+use crate::execution::errors::EntryPointExecutionError;
+use crate::execution::syscalls::cheatcodes::{
+    CheatcodeError, Cheatcodes, ExecutionInfoCheats, SharedCheatcodes,
+};
+use crate::retdata;
+use crate::state::state_api::State;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{
+    create_calldata, trivial_external_entry_point_new, CairoVersion, BALANCE, CHAIN_ID_NAME,
+};
+use crate::transaction::objects::{
+    CommonAccountFields, DeprecatedTransactionInfo, TransactionInfo,
+};
+
+fn execute_with_cheatcodes(
+    call: CallEntryPoint,
+    state: &mut dyn State,
+    cheatcodes: &SharedCheatcodes,
+) -> EntryPointExecutionResult<CallInfo> {
+    let tx_context = TransactionContext {
+        block_context: BlockContext::create_for_testing(),
+        tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
+    };
+    let mut context = EntryPointExecutionContext::new_invoke(Arc::new(tx_context), true)
+        .unwrap()
+        .with_cheatcodes(cheatcodes.clone());
+    call.execute(state, &mut ExecutionResources::default(), &mut context)
+}
+
+#[test]
+fn test_cheat_execution_info() {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let mut state =
+        test_state(&BlockContext::create_for_testing().chain_info, BALANCE, &[(test_contract, 1)]);
+    let contract_address = test_contract.get_instance_address(0);
+
+    let (caller_address, sequencer_address) =
+        (contract_address!("0x123"), contract_address!("0x7"));
+    let (block_number, block_timestamp) = (BlockNumber(2024), BlockTimestamp(1700000000));
+    let (tx_hash, nonce, max_fee) =
+        (TransactionHash(stark_felt!(1991_u16)), Nonce(stark_felt!(3_u8)), Fee(42));
+    let tx_info = TransactionInfo::Deprecated(DeprecatedTransactionInfo {
+        common_fields: CommonAccountFields {
+            transaction_hash: tx_hash,
+            version: TransactionVersion::ONE,
+            nonce,
+            sender_address: caller_address,
+            ..Default::default()
+        },
+        max_fee,
+    });
+    let cheatcodes = Rc::new(RefCell::new(Cheatcodes::default()));
+    cheatcodes.borrow_mut().cheat_execution_info(
+        contract_address,
+        ExecutionInfoCheats {
+            caller_address: Some(caller_address),
+            block_number: Some(block_number),
+            block_timestamp: Some(block_timestamp),
+            sequencer_address: Some(sequencer_address),
+            tx_info: Some(tx_info),
+        },
+    );
+
+    let entry_point_selector = selector_from_name("test_get_execution_info");
+    let expected_block_info =
+        [stark_felt!(block_number.0), stark_felt!(block_timestamp.0), *sequencer_address.0.key()];
+    let chain_id = stark_felt!(&*ChainId(CHAIN_ID_NAME.to_string()).as_hex());
+    let expected_tx_info = [
+        TransactionVersion::ONE.0, // Transaction version.
+        *caller_address.0.key(),   // Account address.
+        stark_felt!(max_fee.0),    // Max fee.
+        StarkFelt::ZERO,           // Signature.
+        tx_hash.0,                 // Transaction hash.
+        chain_id,                  // Chain ID.
+        nonce.0,                   // Nonce.
+        StarkFelt::ZERO,           // Resource bounds.
+        StarkFelt::ZERO,           // Tip.
+        StarkFelt::ZERO,           // Paymaster data.
+        StarkFelt::ZERO,           // Nonce DA.
+        StarkFelt::ZERO,           // Fee DA.
+        StarkFelt::ZERO,           // Account data.
+    ];
+    let expected_call_info =
+        [*caller_address.0.key(), *contract_address.0.key(), entry_point_selector.0];
+    let call = CallEntryPoint {
+        entry_point_selector,
+        calldata: Calldata(
+            [&expected_block_info[..], &expected_tx_info, &expected_call_info].concat().into(),
+        ),
+        ..trivial_external_entry_point_new(test_contract)
+    };
+
+    let call_info = execute_with_cheatcodes(call.clone(), &mut state, &cheatcodes).unwrap();
+    assert!(!call_info.execution.failed);
+
+    // Without the cheats, the actual execution info is seen.
+    cheatcodes.borrow_mut().stop_cheat_execution_info(contract_address);
+    // 'BLOCK_INFO_MISMATCH'.
+    let block_info_mismatch = stark_felt!("0x424c4f434b5f494e464f5f4d49534d41544348");
+    assert_matches!(
+        execute_with_cheatcodes(call, &mut state, &cheatcodes),
+        Err(EntryPointExecutionError::ExecutionFailed { error_data })
+            if error_data == vec![block_info_mismatch]
+    );
+}
+
+#[test]
+fn test_mock_call() {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let mut state =
+        test_state(&BlockContext::create_for_testing().chain_info, BALANCE, &[(test_contract, 1)]);
+    let contract_address = test_contract.get_instance_address(0);
+
+    // The mocked entry point does not exist in the contract; it is never executed.
+    let mocked_selector = selector_from_name("mocked_entry_point");
+    let mocked_retdata = retdata![stark_felt!(7_u8), stark_felt!(8_u8)];
+    let cheatcodes = Rc::new(RefCell::new(Cheatcodes::default()));
+    cheatcodes.borrow_mut().mock_call(contract_address, mocked_selector, mocked_retdata.clone());
+
+    let call = CallEntryPoint {
+        entry_point_selector: selector_from_name("test_call_contract"),
+        calldata: create_calldata(contract_address, "mocked_entry_point", &[]),
+        ..trivial_external_entry_point_new(test_contract)
+    };
+    let call_info = execute_with_cheatcodes(call.clone(), &mut state, &cheatcodes).unwrap();
+    assert_eq!(call_info.execution.retdata, mocked_retdata);
+    assert!(call_info.inner_calls.is_empty());
+
+    cheatcodes.borrow_mut().stop_mock_call(contract_address, mocked_selector);
+    assert!(execute_with_cheatcodes(call, &mut state, &cheatcodes).is_err());
+}
+
+#[test]
+fn test_expect_events() {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let mut state =
+        test_state(&BlockContext::create_for_testing().chain_info, BALANCE, &[(test_contract, 1)]);
+    let contract_address = test_contract.get_instance_address(0);
+    let (key, data) = (stark_felt!(2019_u16), stark_felt!(2021_u16));
+    let event = EventContent { keys: vec![EventKey(key)], data: EventData(vec![data]) };
+
+    let cheatcodes = Rc::new(RefCell::new(Cheatcodes::default()));
+    cheatcodes.borrow_mut().expect_event(contract_address, event.clone());
+    let call = CallEntryPoint {
+        entry_point_selector: selector_from_name("test_emit_events"),
+        calldata: calldata![stark_felt!(1_u8), stark_felt!(1_u8), key, stark_felt!(1_u8), data],
+        ..trivial_external_entry_point_new(test_contract)
+    };
+    execute_with_cheatcodes(call, &mut state, &cheatcodes).unwrap();
+
+    assert_eq!(cheatcodes.borrow().emitted_events(), [(contract_address, event.clone())]);
+    cheatcodes.borrow().assert_expected_events().unwrap();
+
+    // Each emitted event satisfies a single expectation.
+    cheatcodes.borrow_mut().expect_event(contract_address, event.clone());
+    assert_matches!(
+        cheatcodes.borrow().assert_expected_events(),
+        Err(CheatcodeError::ExpectedEventNotEmitted { address, event: missing_event })
+            if address == contract_address && missing_event == event
+    );
+}
@@ -25,6 +25,8 @@ use starknet_api::StarknetApiError;
 use thiserror::Error;
 
 use crate::abi::sierra_types::SierraTypeError;
+#[cfg(any(feature = "cheatcodes", test))]
+use crate::execution::call_info::Retdata;
 use crate::execution::call_info::{CallInfo, OrderedEvent, OrderedL2ToL1Message};
 use crate::execution::common_hints::{ExecutionMode, HintExecutionResult};
 use crate::execution::entry_point::{CallEntryPoint, CallType, EntryPointExecutionContext};
@@ -33,6 +35,8 @@ use crate::execution::execution_utils::{
     felt_range_from_ptr, max_fee_for_execution_info, stark_felt_from_ptr, stark_felt_to_felt,
     write_maybe_relocatable, ReadOnlySegment, ReadOnlySegments,
 };
+#[cfg(any(feature = "cheatcodes", test))]
+use crate::execution::syscalls::cheatcodes::ExecutionInfoCheats;
 use crate::execution::syscalls::secp::{
     secp256k1_add, secp256k1_get_point_from_x, secp256k1_get_xy, secp256k1_mul, secp256k1_new,
     secp256r1_add, secp256r1_get_point_from_x, secp256r1_get_xy, secp256r1_mul, secp256r1_new,
@@ -44,6 +48,7 @@ use crate::execution::syscalls::{
     StorageReadResponse, StorageWriteResponse, SyscallRequest, SyscallRequestWrapper,
     SyscallResponse, SyscallResponseWrapper, SyscallResult, SyscallSelector,
 };
+use crate::execution::tracer::{SyscallData, SyscallTrace, SyscallTraceResponse};
 use crate::state::errors::StateError;
 use crate::state::state_api::State;
 use crate::transaction::objects::{CurrentTransactionInfo, TransactionInfo};
@@ -236,6 +241,25 @@ impl<'a> SyscallHintProcessor<'a> {
         self.call.entry_point_selector
     }
 
+    #[cfg(any(feature = "cheatcodes", test))]
+    pub fn execution_info_cheats(&self) -> ExecutionInfoCheats {
+        let Some(cheatcodes) = &self.context.cheatcodes else {
+            return ExecutionInfoCheats::default();
+        };
+        let cheatcodes = cheatcodes.borrow();
+        cheatcodes.execution_info_cheats(self.storage_address()).cloned().unwrap_or_default()
+    }
+
+    #[cfg(any(feature = "cheatcodes", test))]
+    pub fn mocked_call(
+        &self,
+        address: ContractAddress,
+        selector: EntryPointSelector,
+    ) -> Option<Retdata> {
+        let cheatcodes = self.context.cheatcodes.as_ref()?.borrow();
+        cheatcodes.mocked_call(address, selector).cloned()
+    }
+
     pub fn execution_mode(&self) -> ExecutionMode {
         self.context.execution_mode
     }
@@ -280,86 +304,129 @@ impl<'a> SyscallHintProcessor<'a> {
 
         let get_gas_cost = |name: &str| -> u64 { self.context.get_gas_cost(name) };
         match selector {
-            SyscallSelector::CallContract => {
-                self.execute_syscall(vm, call_contract, get_gas_cost("call_contract_gas_cost"))
-            }
+            SyscallSelector::CallContract => self.execute_syscall(
+                vm,
+                selector,
+                call_contract,
+                get_gas_cost("call_contract_gas_cost"),
+            ),
             SyscallSelector::Deploy => {
-                self.execute_syscall(vm, deploy, get_gas_cost("deploy_gas_cost"))
+                self.execute_syscall(vm, selector, deploy, get_gas_cost("deploy_gas_cost"))
             }
             SyscallSelector::EmitEvent => {
-                self.execute_syscall(vm, emit_event, get_gas_cost("emit_event_gas_cost"))
-            }
-            SyscallSelector::GetBlockHash => {
-                self.execute_syscall(vm, get_block_hash, get_gas_cost("get_block_hash_gas_cost"))
+                self.execute_syscall(vm, selector, emit_event, get_gas_cost("emit_event_gas_cost"))
             }
+            SyscallSelector::GetBlockHash => self.execute_syscall(
+                vm,
+                selector,
+                get_block_hash,
+                get_gas_cost("get_block_hash_gas_cost"),
+            ),
             SyscallSelector::GetExecutionInfo => self.execute_syscall(
                 vm,
+                selector,
                 get_execution_info,
                 get_gas_cost("get_execution_info_gas_cost"),
             ),
             SyscallSelector::Keccak => {
-                self.execute_syscall(vm, keccak, get_gas_cost("keccak_gas_cost"))
-            }
-            SyscallSelector::LibraryCall => {
-                self.execute_syscall(vm, library_call, get_gas_cost("library_call_gas_cost"))
+                self.execute_syscall(vm, selector, keccak, get_gas_cost("keccak_gas_cost"))
             }
+            SyscallSelector::LibraryCall => self.execute_syscall(
+                vm,
+                selector,
+                library_call,
+                get_gas_cost("library_call_gas_cost"),
+            ),
             SyscallSelector::LibraryCallL1Handler => self.execute_syscall(
                 vm,
+                selector,
                 library_call_l1_handler,
                 get_gas_cost("library_call_gas_cost"),
             ),
-            SyscallSelector::ReplaceClass => {
-                self.execute_syscall(vm, replace_class, get_gas_cost("replace_class_gas_cost"))
-            }
-            SyscallSelector::Secp256k1Add => {
-                self.execute_syscall(vm, secp256k1_add, get_gas_cost("secp256k1_add_gas_cost"))
-            }
+            SyscallSelector::ReplaceClass => self.execute_syscall(
+                vm,
+                selector,
+                replace_class,
+                get_gas_cost("replace_class_gas_cost"),
+            ),
+            SyscallSelector::Secp256k1Add => self.execute_syscall(
+                vm,
+                selector,
+                secp256k1_add,
+                get_gas_cost("secp256k1_add_gas_cost"),
+            ),
             SyscallSelector::Secp256k1GetPointFromX => self.execute_syscall(
                 vm,
+                selector,
                 secp256k1_get_point_from_x,
                 get_gas_cost("secp256k1_get_point_from_x_gas_cost"),
             ),
             SyscallSelector::Secp256k1GetXy => self.execute_syscall(
                 vm,
+                selector,
                 secp256k1_get_xy,
                 get_gas_cost("secp256k1_get_xy_gas_cost"),
             ),
-            SyscallSelector::Secp256k1Mul => {
-                self.execute_syscall(vm, secp256k1_mul, get_gas_cost("secp256k1_mul_gas_cost"))
-            }
-            SyscallSelector::Secp256k1New => {
-                self.execute_syscall(vm, secp256k1_new, get_gas_cost("secp256k1_new_gas_cost"))
-            }
-            SyscallSelector::Secp256r1Add => {
-                self.execute_syscall(vm, secp256r1_add, get_gas_cost("secp256r1_add_gas_cost"))
-            }
+            SyscallSelector::Secp256k1Mul => self.execute_syscall(
+                vm,
+                selector,
+                secp256k1_mul,
+                get_gas_cost("secp256k1_mul_gas_cost"),
+            ),
+            SyscallSelector::Secp256k1New => self.execute_syscall(
+                vm,
+                selector,
+                secp256k1_new,
+                get_gas_cost("secp256k1_new_gas_cost"),
+            ),
+            SyscallSelector::Secp256r1Add => self.execute_syscall(
+                vm,
+                selector,
+                secp256r1_add,
+                get_gas_cost("secp256r1_add_gas_cost"),
+            ),
             SyscallSelector::Secp256r1GetPointFromX => self.execute_syscall(
                 vm,
+                selector,
                 secp256r1_get_point_from_x,
                 get_gas_cost("secp256r1_get_point_from_x_gas_cost"),
             ),
             SyscallSelector::Secp256r1GetXy => self.execute_syscall(
                 vm,
+                selector,
                 secp256r1_get_xy,
                 get_gas_cost("secp256r1_get_xy_gas_cost"),
             ),
-            SyscallSelector::Secp256r1Mul => {
-                self.execute_syscall(vm, secp256r1_mul, get_gas_cost("secp256r1_mul_gas_cost"))
-            }
-            SyscallSelector::Secp256r1New => {
-                self.execute_syscall(vm, secp256r1_new, get_gas_cost("secp256r1_new_gas_cost"))
-            }
+            SyscallSelector::Secp256r1Mul => self.execute_syscall(
+                vm,
+                selector,
+                secp256r1_mul,
+                get_gas_cost("secp256r1_mul_gas_cost"),
+            ),
+            SyscallSelector::Secp256r1New => self.execute_syscall(
+                vm,
+                selector,
+                secp256r1_new,
+                get_gas_cost("secp256r1_new_gas_cost"),
+            ),
             SyscallSelector::SendMessageToL1 => self.execute_syscall(
                 vm,
+                selector,
                 send_message_to_l1,
                 get_gas_cost("send_message_to_l1_gas_cost"),
             ),
-            SyscallSelector::StorageRead => {
-                self.execute_syscall(vm, storage_read, get_gas_cost("storage_read_gas_cost"))
-            }
-            SyscallSelector::StorageWrite => {
-                self.execute_syscall(vm, storage_write, get_gas_cost("storage_write_gas_cost"))
-            }
+            SyscallSelector::StorageRead => self.execute_syscall(
+                vm,
+                selector,
+                storage_read,
+                get_gas_cost("storage_read_gas_cost"),
+            ),
+            SyscallSelector::StorageWrite => self.execute_syscall(
+                vm,
+                selector,
+                storage_write,
+                get_gas_cost("storage_write_gas_cost"),
+            ),
             _ => Err(HintError::UnknownHint(
                 format!("Unsupported syscall selector {selector:?}.").into(),
             )),
@@ -411,12 +478,13 @@ impl<'a> SyscallHintProcessor<'a> {
     fn execute_syscall<Request, Response, ExecuteCallback>(
         &mut self,
         vm: &mut VirtualMachine,
+        selector: SyscallSelector,
         execute_callback: ExecuteCallback,
         syscall_gas_cost: u64,
     ) -> HintExecutionResult
     where
-        Request: SyscallRequest + std::fmt::Debug,
-        Response: SyscallResponse + std::fmt::Debug,
+        Request: SyscallRequest + SyscallData,
+        Response: SyscallResponse + SyscallData,
         ExecuteCallback: FnOnce(
             Request,
             &mut VirtualMachine,
@@ -429,6 +497,7 @@ impl<'a> SyscallHintProcessor<'a> {
 
         let SyscallRequestWrapper { gas_counter, request } =
             SyscallRequestWrapper::<Request>::read(vm, &mut self.syscall_ptr)?;
+        self.context.trace(|tracer| tracer.on_syscall_enter(selector, &request));
 
         if gas_counter < required_gas {
             //  Out of gas failure.
@@ -436,6 +505,7 @@ impl<'a> SyscallHintProcessor<'a> {
                 StarkFelt::try_from(OUT_OF_GAS_ERROR).map_err(SyscallExecutionError::from)?;
             let response: SyscallResponseWrapper<Response> =
                 SyscallResponseWrapper::Failure { gas_counter, error_data: vec![out_of_gas_error] };
+            self.trace_syscall_exit(selector, &response, gas_counter);
             response.write(vm, &mut self.syscall_ptr)?;
 
             return Ok(());
@@ -454,11 +524,32 @@ impl<'a> SyscallHintProcessor<'a> {
             Err(error) => return Err(error.into()),
         };
 
+        self.trace_syscall_exit(selector, &response, gas_counter);
         response.write(vm, &mut self.syscall_ptr)?;
 
         Ok(())
     }
 
+    fn trace_syscall_exit<Response: SyscallResponse + SyscallData>(
+        &self,
+        selector: SyscallSelector,
+        response: &SyscallResponseWrapper<Response>,
+        initial_gas_counter: u64,
+    ) {
+        let (response, remaining_gas) = match response {
+            SyscallResponseWrapper::Success { gas_counter, response } => {
+                (SyscallTraceResponse::Success(response), *gas_counter)
+            }
+            SyscallResponseWrapper::Failure { gas_counter, error_data } => {
+                (SyscallTraceResponse::Failure(error_data), *gas_counter)
+            }
+        };
+        let gas_consumed = initial_gas_counter - remaining_gas;
+        self.context.trace(|tracer| {
+            tracer.on_syscall_exit(&SyscallTrace { selector, response, gas_consumed })
+        });
+    }
+
     fn read_next_syscall_selector(&mut self, vm: &mut VirtualMachine) -> SyscallResult<StarkFelt> {
         let selector = stark_felt_from_ptr(vm, &mut self.syscall_ptr)?;
 
@@ -481,10 +572,13 @@ impl<'a> SyscallHintProcessor<'a> {
         let block_info_ptr = self.allocate_block_info_segment(vm)?;
         let tx_info_ptr = self.allocate_tx_info_segment(vm)?;
 
+        let caller_address = self.caller_address();
+        #[cfg(any(feature = "cheatcodes", test))]
+        let caller_address = self.execution_info_cheats().caller_address.unwrap_or(caller_address);
         let additional_info: Vec<MaybeRelocatable> = vec![
             block_info_ptr.into(),
             tx_info_ptr.into(),
-            stark_felt_to_felt(*self.caller_address().0.key()).into(),
+            stark_felt_to_felt(*caller_address.0.key()).into(),
             stark_felt_to_felt(*self.storage_address().0.key()).into(),
             stark_felt_to_felt(self.entry_point_selector().0).into(),
         ];
@@ -499,6 +593,8 @@ impl<'a> SyscallHintProcessor<'a> {
         vm: &mut VirtualMachine,
     ) -> SyscallResult<Relocatable> {
         let block_info = &self.context.tx_context.block_context.block_info;
+        #[cfg(any(feature = "cheatcodes", test))]
+        let block_info = &self.execution_info_cheats().cheat_block_info(block_info);
         let block_timestamp = block_info.block_timestamp.0;
         let block_number = block_info.block_number.0;
         let versioned_constants = self.context.versioned_constants();
@@ -543,6 +639,8 @@ impl<'a> SyscallHintProcessor<'a> {
 
     fn allocate_tx_info_segment(&mut self, vm: &mut VirtualMachine) -> SyscallResult<Relocatable> {
         let tx_info = &self.context.tx_context.clone().tx_info;
+        #[cfg(any(feature = "cheatcodes", test))]
+        let tx_info = &self.execution_info_cheats().tx_info.unwrap_or_else(|| tx_info.clone());
         let (tx_signature_start_ptr, tx_signature_end_ptr) =
             &self.allocate_data_segment(vm, &tx_info.signature().0)?;
 
@@ -610,6 +708,8 @@ impl<'a> SyscallHintProcessor<'a> {
         self.accessed_keys.insert(key);
         let value = self.state.get_storage_at(self.storage_address(), key)?;
         self.read_values.push(value);
+        let storage_address = self.storage_address();
+        self.context.trace(|tracer| tracer.on_storage_read(storage_address, key, value));
 
         Ok(StorageReadResponse { value })
     }
@@ -621,6 +721,8 @@ impl<'a> SyscallHintProcessor<'a> {
     ) -> SyscallResult<StorageWriteResponse> {
         self.accessed_keys.insert(key);
         self.state.set_storage_at(self.storage_address(), key, value)?;
+        let storage_address = self.storage_address();
+        self.context.trace(|tracer| tracer.on_storage_write(storage_address, key, value));
 
         Ok(StorageWriteResponse {})
     }
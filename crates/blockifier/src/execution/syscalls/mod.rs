@@ -31,6 +31,8 @@ use crate::execution::syscalls::hint_processor::{INVALID_INPUT_LENGTH_ERROR, OUT
 use crate::transaction::transaction_utils::update_remaining_gas;
 use crate::versioned_constants::{EventLimits, VersionedConstants};
 
+#[cfg(any(feature = "cheatcodes", test))]
+pub mod cheatcodes;
 pub mod hint_processor;
 mod secp;
 
@@ -167,6 +169,11 @@ pub fn call_contract(
         };
         return Err(error.as_call_contract_execution_error(storage_address));
     }
+    #[cfg(any(feature = "cheatcodes", test))]
+    if let Some(retdata) = syscall_handler.mocked_call(storage_address, request.function_selector) {
+        let retdata_segment = create_retdata_segment(vm, syscall_handler, &retdata.0)?;
+        return Ok(CallContractResponse { segment: retdata_segment });
+    }
     let entry_point = CallEntryPoint {
         class_hash: None,
         code_address: Some(storage_address),
@@ -319,6 +326,7 @@ pub fn emit_event(
     syscall_handler: &mut SyscallHintProcessor<'_>,
     _remaining_gas: &mut u64,
 ) -> SyscallResult<EmitEventResponse> {
+    let storage_address = syscall_handler.storage_address();
     let execution_context = &mut syscall_handler.context;
     exceeds_event_size_limit(
         execution_context.versioned_constants(),
@@ -327,6 +335,11 @@ pub fn emit_event(
     )?;
     let ordered_event =
         OrderedEvent { order: execution_context.n_emitted_events, event: request.content };
+    execution_context.trace(|tracer| tracer.on_event(storage_address, &ordered_event));
+    #[cfg(any(feature = "cheatcodes", test))]
+    if let Some(cheatcodes) = &execution_context.cheatcodes {
+        cheatcodes.borrow_mut().record_event(storage_address, ordered_event.event.clone());
+    }
     syscall_handler.events.push(ordered_event);
     execution_context.n_emitted_events += 1;
 
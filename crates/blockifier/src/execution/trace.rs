@@ -0,0 +1,462 @@
+use std::ops::AddAssign;
+
+use cairo_vm::serde::deserialize_program::BuiltinName;
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use serde::Serialize;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, EthAddress, Nonce};
+use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::DeclareTransaction;
+
+use crate::execution::call_info::{CallInfo, OrderedEvent, OrderedL2ToL1Message};
+use crate::execution::entry_point::CallType;
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::state::state_api::{StateReader, StateResult};
+use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::objects::TransactionExecutionInfo;
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transaction_types::TransactionType;
+
+#[cfg(test)]
+#[path = "trace_test.rs"]
+pub mod test;
+
+// Conversions of execution outputs to the traces of the Starknet JSON-RPC API
+// (`starknet_traceTransaction` and friends).
+
+/// The type of an invoked entry point.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TraceEntryPointType {
+    External,
+    L1Handler,
+    Constructor,
+}
+
+impl From<EntryPointType> for TraceEntryPointType {
+    fn from(entry_point_type: EntryPointType) -> Self {
+        match entry_point_type {
+            EntryPointType::External => Self::External,
+            EntryPointType::L1Handler => Self::L1Handler,
+            EntryPointType::Constructor => Self::Constructor,
+        }
+    }
+}
+
+/// The type of a call; a delegate call is a library call (i.e., a call that executes the code of a
+/// class in the context of the caller).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TraceCallType {
+    Call,
+    LibraryCall,
+}
+
+impl From<CallType> for TraceCallType {
+    fn from(call_type: CallType) -> Self {
+        match call_type {
+            CallType::Call => Self::Call,
+            CallType::Delegate => Self::LibraryCall,
+        }
+    }
+}
+
+/// The Cairo resources used by a call (including its inner calls), or by a transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ComputationResources {
+    pub steps: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub memory_holes: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub range_check_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub pedersen_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub poseidon_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub ec_op_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub ecdsa_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub bitwise_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub keccak_builtin_applications: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub segment_arena_builtin: usize,
+}
+
+impl From<&ExecutionResources> for ComputationResources {
+    fn from(resources: &ExecutionResources) -> Self {
+        let get_builtin_count = |builtin_name: BuiltinName| {
+            resources.builtin_instance_counter.get(builtin_name.name()).copied().unwrap_or_default()
+        };
+
+        Self {
+            steps: resources.n_steps,
+            memory_holes: resources.n_memory_holes,
+            range_check_builtin_applications: get_builtin_count(BuiltinName::range_check),
+            pedersen_builtin_applications: get_builtin_count(BuiltinName::pedersen),
+            poseidon_builtin_applications: get_builtin_count(BuiltinName::poseidon),
+            ec_op_builtin_applications: get_builtin_count(BuiltinName::ec_op),
+            ecdsa_builtin_applications: get_builtin_count(BuiltinName::ecdsa),
+            bitwise_builtin_applications: get_builtin_count(BuiltinName::bitwise),
+            keccak_builtin_applications: get_builtin_count(BuiltinName::keccak),
+            segment_arena_builtin: get_builtin_count(BuiltinName::segment_arena),
+        }
+    }
+}
+
+impl AddAssign<&ComputationResources> for ComputationResources {
+    fn add_assign(&mut self, other: &ComputationResources) {
+        self.steps += other.steps;
+        self.memory_holes += other.memory_holes;
+        self.range_check_builtin_applications += other.range_check_builtin_applications;
+        self.pedersen_builtin_applications += other.pedersen_builtin_applications;
+        self.poseidon_builtin_applications += other.poseidon_builtin_applications;
+        self.ec_op_builtin_applications += other.ec_op_builtin_applications;
+        self.ecdsa_builtin_applications += other.ecdsa_builtin_applications;
+        self.bitwise_builtin_applications += other.bitwise_builtin_applications;
+        self.keccak_builtin_applications += other.keccak_builtin_applications;
+        self.segment_arena_builtin += other.segment_arena_builtin;
+    }
+}
+
+/// The L1 gas a transaction is charged for data availability.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct DataAvailabilityResources {
+    pub l1_gas: u128,
+    pub l1_data_gas: u128,
+}
+
+/// The resources used by a transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct TransactionExecutionResources {
+    #[serde(flatten)]
+    pub computation_resources: ComputationResources,
+    pub data_availability: DataAvailabilityResources,
+}
+
+impl TransactionExecutionResources {
+    /// Sums the resources of the calls made by the transaction; the execution of a reverted
+    /// transaction is not included.
+    pub fn new(execution_info: &TransactionExecutionInfo) -> Self {
+        let mut computation_resources = ComputationResources::default();
+        for call_info in execution_info.non_optional_call_infos() {
+            computation_resources += &ComputationResources::from(&call_info.resources);
+        }
+        let da_gas = &execution_info.da_gas;
+
+        Self {
+            computation_resources,
+            data_availability: DataAvailabilityResources {
+                l1_gas: da_gas.l1_gas,
+                l1_data_gas: da_gas.l1_data_gas,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct TraceEvent {
+    pub order: usize,
+    pub keys: Vec<StarkFelt>,
+    pub data: Vec<StarkFelt>,
+}
+
+impl From<&OrderedEvent> for TraceEvent {
+    fn from(ordered_event: &OrderedEvent) -> Self {
+        let event = &ordered_event.event;
+        Self {
+            order: ordered_event.order,
+            keys: event.keys.iter().map(|key| key.0).collect(),
+            data: event.data.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct TraceMessage {
+    pub order: usize,
+    pub from_address: ContractAddress,
+    pub to_address: EthAddress,
+    pub payload: Vec<StarkFelt>,
+}
+
+impl TraceMessage {
+    /// The sender of a message is the contract in whose context it was sent.
+    pub fn new(ordered_message: &OrderedL2ToL1Message, from_address: ContractAddress) -> Self {
+        let message = &ordered_message.message;
+        Self {
+            order: ordered_message.order,
+            from_address,
+            to_address: message.to_address,
+            payload: message.payload.0.clone(),
+        }
+    }
+}
+
+/// A call and its inner calls, as a tree.
+/// Events and messages are those emitted by the call itself; their order is relative to the
+/// execution phase (validation, execution or fee transfer) the call belongs to.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct FunctionInvocation {
+    pub contract_address: ContractAddress,
+    pub entry_point_selector: StarkFelt,
+    pub calldata: Vec<StarkFelt>,
+    pub caller_address: ContractAddress,
+    pub class_hash: ClassHash,
+    pub entry_point_type: TraceEntryPointType,
+    pub call_type: TraceCallType,
+    pub result: Vec<StarkFelt>,
+    pub calls: Vec<FunctionInvocation>,
+    pub events: Vec<TraceEvent>,
+    pub messages: Vec<TraceMessage>,
+    pub execution_resources: ComputationResources,
+}
+
+impl From<&CallInfo> for FunctionInvocation {
+    fn from(call_info: &CallInfo) -> Self {
+        let CallInfo { call, execution, resources, inner_calls, .. } = call_info;
+        Self {
+            contract_address: call.storage_address,
+            entry_point_selector: call.entry_point_selector.0,
+            calldata: call.calldata.0.to_vec(),
+            caller_address: call.caller_address,
+            class_hash: call.class_hash.expect("Class hash must be set after execution."),
+            entry_point_type: call.entry_point_type.into(),
+            call_type: call.call_type.into(),
+            result: execution.retdata.0.clone(),
+            calls: inner_calls.iter().map(Self::from).collect(),
+            events: execution.events.iter().map(TraceEvent::from).collect(),
+            messages: execution
+                .l2_to_l1_messages
+                .iter()
+                .map(|message| TraceMessage::new(message, call.storage_address))
+                .collect(),
+            execution_resources: resources.into(),
+        }
+    }
+}
+
+/// The outcome of the execution phase of an invoke transaction.
+// Boxing the invocation would only move the size difference to `TransactionTrace`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ExecuteInvocation {
+    FunctionInvocation(FunctionInvocation),
+    Reverted { revert_reason: String },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct StorageDiffItem {
+    pub key: StorageKey,
+    pub value: StarkFelt,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ContractStorageDiff {
+    pub address: ContractAddress,
+    pub storage_entries: Vec<StorageDiffItem>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct DeclaredClass {
+    pub class_hash: ClassHash,
+    pub compiled_class_hash: CompiledClassHash,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct DeployedContract {
+    pub address: ContractAddress,
+    pub class_hash: ClassHash,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ReplacedClass {
+    pub contract_address: ContractAddress,
+    pub class_hash: ClassHash,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct NonceUpdate {
+    pub contract_address: ContractAddress,
+    pub nonce: Nonce,
+}
+
+/// The changes made by a transaction, in the format of the JSON-RPC API.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct StateDiff {
+    pub storage_diffs: Vec<ContractStorageDiff>,
+    /// Cairo 0 classes are not part of the commitment state diff, hence are not filled by
+    /// `StateDiff::new`, but by `TransactionTrace::new`.
+    pub deprecated_declared_classes: Vec<ClassHash>,
+    pub declared_classes: Vec<DeclaredClass>,
+    pub deployed_contracts: Vec<DeployedContract>,
+    pub replaced_classes: Vec<ReplacedClass>,
+    pub nonces: Vec<NonceUpdate>,
+}
+
+impl StateDiff {
+    /// Converts the given state diff, using the state on top of which it was made to tell deployed
+    /// contracts from replaced classes.
+    pub fn new(
+        state_diff: &CommitmentStateDiff,
+        state_before: &impl StateReader,
+    ) -> StateResult<Self> {
+        let mut deployed_contracts = Vec::new();
+        let mut replaced_classes = Vec::new();
+        for (&address, &class_hash) in &state_diff.address_to_class_hash {
+            if state_before.get_class_hash_at(address)? == ClassHash::default() {
+                deployed_contracts.push(DeployedContract { address, class_hash });
+            } else {
+                replaced_classes.push(ReplacedClass { contract_address: address, class_hash });
+            }
+        }
+
+        Ok(Self {
+            storage_diffs: state_diff
+                .storage_updates
+                .iter()
+                .map(|(&address, storage_updates)| ContractStorageDiff {
+                    address,
+                    storage_entries: storage_updates
+                        .iter()
+                        .map(|(&key, &value)| StorageDiffItem { key, value })
+                        .collect(),
+                })
+                .collect(),
+            deprecated_declared_classes: Vec::new(),
+            declared_classes: state_diff
+                .class_hash_to_compiled_class_hash
+                .iter()
+                .map(|(&class_hash, &compiled_class_hash)| DeclaredClass {
+                    class_hash,
+                    compiled_class_hash,
+                })
+                .collect(),
+            deployed_contracts,
+            replaced_classes,
+            nonces: state_diff
+                .address_to_nonce
+                .iter()
+                .map(|(&contract_address, &nonce)| NonceUpdate { contract_address, nonce })
+                .collect(),
+        })
+    }
+}
+
+/// The trace of a transaction, in the format of the JSON-RPC API.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionTrace {
+    Declare {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+        execution_resources: TransactionExecutionResources,
+    },
+    DeployAccount {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        constructor_invocation: FunctionInvocation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+        execution_resources: TransactionExecutionResources,
+    },
+    Invoke {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        execute_invocation: ExecuteInvocation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+        execution_resources: TransactionExecutionResources,
+    },
+    L1Handler {
+        function_invocation: FunctionInvocation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+        execution_resources: TransactionExecutionResources,
+    },
+}
+
+impl TransactionTrace {
+    /// Returns the trace of the given successfully executed transaction (which may have been
+    /// reverted). The state diff is completed with the Cairo 0 class the transaction declared, if
+    /// any.
+    pub fn new(
+        tx: &Transaction,
+        execution_info: &TransactionExecutionInfo,
+        mut state_diff: Option<StateDiff>,
+    ) -> Self {
+        let tx_type = match tx {
+            Transaction::AccountTransaction(account_tx) => account_tx.tx_type(),
+            Transaction::L1HandlerTransaction(_) => TransactionType::L1Handler,
+        };
+        if let (
+            Transaction::AccountTransaction(AccountTransaction::Declare(declare_tx)),
+            Some(state_diff),
+        ) = (tx, &mut state_diff)
+        {
+            if matches!(declare_tx.tx(), DeclareTransaction::V0(_) | DeclareTransaction::V1(_)) {
+                state_diff.deprecated_declared_classes.push(declare_tx.class_hash());
+            }
+        }
+
+        let invocation = |call_info: &Option<CallInfo>| call_info.as_ref().map(Into::into);
+        let validate_invocation = invocation(&execution_info.validate_call_info);
+        let execute_invocation = invocation(&execution_info.execute_call_info);
+        let fee_transfer_invocation = invocation(&execution_info.fee_transfer_call_info);
+        let execution_resources = TransactionExecutionResources::new(execution_info);
+
+        match tx_type {
+            TransactionType::Declare => Self::Declare {
+                validate_invocation,
+                fee_transfer_invocation,
+                state_diff,
+                execution_resources,
+            },
+            TransactionType::DeployAccount => Self::DeployAccount {
+                validate_invocation,
+                constructor_invocation: execute_invocation
+                    .expect("Deploy account transactions must execute their constructor."),
+                fee_transfer_invocation,
+                state_diff,
+                execution_resources,
+            },
+            TransactionType::InvokeFunction => Self::Invoke {
+                validate_invocation,
+                execute_invocation: match (&execution_info.revert_error, execute_invocation) {
+                    (Some(revert_reason), _) => {
+                        ExecuteInvocation::Reverted { revert_reason: revert_reason.clone() }
+                    }
+                    (None, Some(function_invocation)) => {
+                        ExecuteInvocation::FunctionInvocation(function_invocation)
+                    }
+                    (None, None) => panic!("Non-reverted invoke transactions must execute."),
+                },
+                fee_transfer_invocation,
+                state_diff,
+                execution_resources,
+            },
+            TransactionType::L1Handler => Self::L1Handler {
+                function_invocation: execute_invocation
+                    .expect("L1 handler transactions must execute their entry point."),
+                state_diff,
+                execution_resources,
+            },
+        }
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{Fee, TransactionVersion};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::context::BlockContext;
+use crate::execution::trace::{
+    DeployedContract, ExecuteInvocation, ReplacedClass, StateDiff, TraceCallType,
+    TraceEntryPointType, TraceEvent, TransactionTrace,
+};
+use crate::state::cached_state::{CachedState, CommitmentStateDiff};
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::declare::declare_tx;
+use crate::test_utils::dict_state_reader::DictStateReader;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{create_calldata, CairoVersion, BALANCE, MAX_FEE};
+use crate::transaction::test_utils::{
+    account_invoke_tx, block_context, calculate_class_info_for_testing,
+};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::ExecutableTransaction;
+use crate::{declare_tx_args, invoke_tx_args};
+
+#[rstest]
+fn test_invoke_trace(block_context: BlockContext) {
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let mut state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+    let sender_address = account_contract.get_instance_address(0);
+    let test_contract_address = test_contract.get_instance_address(0);
+    let (key, data) = (stark_felt!(5_u8), stark_felt!(7_u8));
+    let calldata = create_calldata(
+        test_contract_address,
+        "test_emit_events",
+        &[
+            stark_felt!(1_u8), // events_number.
+            stark_felt!(1_u8), // keys length.
+            key,
+            stark_felt!(1_u8), // data length.
+            data,
+        ],
+    );
+    let tx = account_invoke_tx(invoke_tx_args! { max_fee: Fee(MAX_FEE), sender_address, calldata });
+
+    let mut tx_state = CachedState::create_transactional(&mut state);
+    let execution_info = tx.clone().execute(&mut tx_state, &block_context, true, true).unwrap();
+    let commitment_state_diff = tx_state.to_state_diff();
+    tx_state.abort();
+    let state_diff = StateDiff::new(&commitment_state_diff, &state).unwrap();
+    let trace = TransactionTrace::new(
+        &Transaction::AccountTransaction(tx),
+        &execution_info,
+        Some(state_diff),
+    );
+
+    let TransactionTrace::Invoke {
+        validate_invocation,
+        execute_invocation,
+        fee_transfer_invocation,
+        state_diff,
+        execution_resources,
+    } = &trace
+    else {
+        panic!("Expected an invoke trace, got: {trace:?}.");
+    };
+    assert!(validate_invocation.is_some());
+    assert!(fee_transfer_invocation.is_some());
+    assert!(execution_resources.computation_resources.steps > 0);
+    assert_eq!(execution_resources.data_availability.l1_gas, execution_info.da_gas.l1_gas);
+
+    // The account's `__execute__` calls the test contract, which emits the event.
+    let execute_invocation = assert_matches!(
+        execute_invocation,
+        ExecuteInvocation::FunctionInvocation(invocation) => invocation
+    );
+    assert_eq!(execute_invocation.contract_address, sender_address);
+    assert_eq!(execute_invocation.entry_point_type, TraceEntryPointType::External);
+    assert!(execute_invocation.events.is_empty());
+    let [inner_invocation] = &execute_invocation.calls[..] else {
+        panic!("Expected a single inner call, got: {:?}.", execute_invocation.calls);
+    };
+    assert_eq!(inner_invocation.contract_address, test_contract_address);
+    assert_eq!(inner_invocation.caller_address, sender_address);
+    assert_eq!(inner_invocation.class_hash, test_contract.get_class_hash());
+    assert_eq!(inner_invocation.call_type, TraceCallType::Call);
+    assert_eq!(
+        inner_invocation.events,
+        vec![TraceEvent { order: 0, keys: vec![key], data: vec![data] }]
+    );
+
+    let state_diff = state_diff.as_ref().unwrap();
+    assert!(state_diff.deployed_contracts.is_empty());
+    assert!(state_diff.nonces.iter().any(|nonce_update| {
+        nonce_update.contract_address == sender_address
+            && nonce_update.nonce == Nonce(stark_felt!(1_u8))
+    }));
+
+    let json_trace = serde_json::to_value(&trace).unwrap();
+    assert_eq!(json_trace["type"], "INVOKE");
+    assert_eq!(json_trace["execute_invocation"]["entry_point_type"], "EXTERNAL");
+    assert_eq!(json_trace["execute_invocation"]["calls"][0]["call_type"], "CALL");
+}
+
+#[test]
+fn test_state_diff_deployed_and_replaced_classes() {
+    let deployed_address = contract_address!("0x100");
+    let replaced_address = contract_address!("0x200");
+    let (old_class_hash, new_class_hash) = (class_hash!("0x10"), class_hash!("0x20"));
+    let state = DictStateReader {
+        address_to_class_hash: HashMap::from([(replaced_address, old_class_hash)]),
+        ..Default::default()
+    };
+    let commitment_state_diff = CommitmentStateDiff {
+        address_to_class_hash: indexmap! {
+            deployed_address => new_class_hash,
+            replaced_address => new_class_hash,
+        },
+        address_to_nonce: indexmap! {},
+        storage_updates: indexmap! {},
+        class_hash_to_compiled_class_hash: indexmap! {},
+    };
+
+    let state_diff = StateDiff::new(&commitment_state_diff, &state).unwrap();
+    assert_eq!(
+        state_diff.deployed_contracts,
+        vec![DeployedContract { address: deployed_address, class_hash: new_class_hash }]
+    );
+    assert_eq!(
+        state_diff.replaced_classes,
+        vec![ReplacedClass { contract_address: replaced_address, class_hash: new_class_hash }]
+    );
+}
+
+#[rstest]
+fn test_cairo0_declare_trace(block_context: BlockContext) {
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let mut state = test_state(&block_context.chain_info, BALANCE, &[(account_contract, 1)]);
+    let empty_contract = FeatureContract::Empty(CairoVersion::Cairo0);
+    let class_hash = empty_contract.get_class_hash();
+    let tx = declare_tx(
+        declare_tx_args! {
+            max_fee: Fee(MAX_FEE),
+            sender_address: account_contract.get_instance_address(0),
+            version: TransactionVersion::ONE,
+            class_hash,
+        },
+        calculate_class_info_for_testing(empty_contract.get_class()),
+    );
+
+    let mut tx_state = CachedState::create_transactional(&mut state);
+    let execution_info = tx.clone().execute(&mut tx_state, &block_context, true, true).unwrap();
+    let commitment_state_diff = tx_state.to_state_diff();
+    tx_state.abort();
+    let state_diff = StateDiff::new(&commitment_state_diff, &state).unwrap();
+    assert!(state_diff.deprecated_declared_classes.is_empty());
+    let trace = TransactionTrace::new(
+        &Transaction::AccountTransaction(tx),
+        &execution_info,
+        Some(state_diff),
+    );
+
+    // The declared Cairo 0 class is part of the trace's state diff, though not of the commitment
+    // state diff.
+    let TransactionTrace::Declare { state_diff, .. } = &trace else {
+        panic!("Expected a declare trace, got: {trace:?}.");
+    };
+    let state_diff = state_diff.as_ref().unwrap();
+    assert_eq!(state_diff.deprecated_declared_classes, vec![class_hash]);
+    assert!(state_diff.declared_classes.is_empty());
+    let json_trace = serde_json::to_value(&trace).unwrap();
+    assert_eq!(json_trace["type"], "DECLARE");
+    assert_eq!(
+        json_trace["state_diff"]["deprecated_declared_classes"],
+        serde_json::json!([class_hash])
+    );
+}
@@ -0,0 +1,87 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use starknet_api::core::ContractAddress;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::call_info::{CallInfo, OrderedEvent};
+use crate::execution::entry_point::{CallEntryPoint, EntryPointExecutionResult};
+use crate::execution::syscalls::SyscallSelector;
+
+#[cfg(test)]
+#[path = "tracer_test.rs"]
+pub mod test;
+
+/// A tracer shared between the caller, who attaches it to the block context (or to a single
+/// execution context) and inspects it afterwards, and the hint processors of all (possibly nested)
+/// calls of the execution.
+pub type SharedExecutionTracer = Arc<Mutex<dyn ExecutionTracer + Send>>;
+
+/// A syscall request or response, of the concrete type of its syscall; e.g., a
+/// `StorageReadRequest` of `syscalls` (Cairo 1) or of `deprecated_syscalls` (Cairo 0). Tracers
+/// inspect it by downcasting.
+pub trait SyscallData: Any + Debug {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Debug> SyscallData for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl dyn SyscallData {
+    /// Returns the data as the given type, if it is of that type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+/// The outcome of a syscall, as written back to the syscall segment.
+#[derive(Debug)]
+pub enum SyscallTraceResponse<'a> {
+    Success(&'a dyn SyscallData),
+    /// A Cairo 1 syscall that failed gracefully, e.g., due to insufficient gas.
+    Failure(&'a [StarkFelt]),
+}
+
+/// A syscall that was executed by one of the hint processors.
+#[derive(Debug)]
+pub struct SyscallTrace<'a> {
+    pub selector: SyscallSelector,
+    pub response: SyscallTraceResponse<'a>,
+    /// The gas charged for the syscall; always zero in Cairo 0, which is not gas-metered.
+    pub gas_consumed: u64,
+}
+
+/// Live hooks into the execution of entry points, called by the syscall hint processors as
+/// execution progresses. All hooks do nothing by default.
+///
+/// A syscall that ends in an unrecoverable error is not reported on exit; the error is seen on the
+/// exit of the entry point instead.
+pub trait ExecutionTracer: Debug {
+    /// Called before the entry point is executed; the class hash may not be resolved yet.
+    fn on_entry_point_enter(&mut self, _call: &CallEntryPoint) {}
+
+    fn on_entry_point_exit(
+        &mut self,
+        _call: &CallEntryPoint,
+        _result: &EntryPointExecutionResult<CallInfo>,
+    ) {
+    }
+
+    /// Called once the syscall request is read, before it is executed.
+    fn on_syscall_enter(&mut self, _selector: SyscallSelector, _request: &dyn SyscallData) {}
+
+    /// Called after the syscall is executed, including any calls nested in it.
+    fn on_syscall_exit(&mut self, _syscall: &SyscallTrace<'_>) {}
+
+    fn on_storage_read(&mut self, _address: ContractAddress, _key: StorageKey, _value: StarkFelt) {}
+
+    fn on_storage_write(&mut self, _address: ContractAddress, _key: StorageKey, _value: StarkFelt) {
+    }
+
+    fn on_event(&mut self, _address: ContractAddress, _event: &OrderedEvent) {}
+}
@@ -0,0 +1,276 @@
+use std::sync::{Arc, Mutex};
+
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, EventContent, EventData, EventKey};
+use starknet_api::{calldata, stark_felt};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::blockifier::transaction_executor::TransactionExecutor;
+use crate::bouncer::BouncerConfig;
+use crate::context::{BlockContext, TransactionContext};
+use crate::execution::call_info::{CallInfo, OrderedEvent};
+use crate::execution::entry_point::{
+    CallEntryPoint, EntryPointExecutionContext, EntryPointExecutionResult,
+};
+use crate::execution::syscalls::SyscallSelector;
+use crate::execution::tracer::{ExecutionTracer, SyscallData, SyscallTrace, SyscallTraceResponse};
+use crate::execution::{deprecated_syscalls, syscalls};
+use crate::invoke_tx_args;
+use crate::state::state_api::State;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{
+    create_calldata, trivial_external_entry_point_new, CairoVersion, NonceManager, BALANCE,
+};
+use crate::transaction::constants::{EXECUTE_ENTRY_POINT_NAME, VALIDATE_ENTRY_POINT_NAME};
+use crate::transaction::objects::{DeprecatedTransactionInfo, TransactionInfo};
+use crate::transaction::test_utils::account_invoke_tx;
+use crate::transaction::transaction_execution::Transaction;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum TraceRecord {
+    EntryPointEnter(EntryPointSelector),
+    EntryPointExit {
+        selector: EntryPointSelector,
+        succeeded: bool,
+    },
+    SyscallEnter(SyscallSelector),
+    SyscallExit(SyscallSelector),
+    /// The target of a called contract, as read off the syscall request.
+    CallTarget(ContractAddress, EntryPointSelector),
+    /// The value read by a storage read, as read off the syscall response.
+    ReadValue(StarkFelt),
+    StorageRead(ContractAddress, StorageKey, StarkFelt),
+    StorageWrite(ContractAddress, StorageKey, StarkFelt),
+    Event(ContractAddress, OrderedEvent),
+}
+
+#[derive(Debug, Default)]
+struct RecordingTracer {
+    records: Vec<TraceRecord>,
+    syscall_gas_consumed: Vec<u64>,
+}
+
+impl ExecutionTracer for RecordingTracer {
+    fn on_entry_point_enter(&mut self, call: &CallEntryPoint) {
+        self.records.push(TraceRecord::EntryPointEnter(call.entry_point_selector));
+    }
+
+    fn on_entry_point_exit(
+        &mut self,
+        call: &CallEntryPoint,
+        result: &EntryPointExecutionResult<CallInfo>,
+    ) {
+        self.records.push(TraceRecord::EntryPointExit {
+            selector: call.entry_point_selector,
+            succeeded: result.is_ok(),
+        });
+    }
+
+    fn on_syscall_enter(&mut self, selector: SyscallSelector, request: &dyn SyscallData) {
+        self.records.push(TraceRecord::SyscallEnter(selector));
+        let call_target = match (
+            request.downcast_ref::<syscalls::CallContractRequest>(),
+            request.downcast_ref::<deprecated_syscalls::CallContractRequest>(),
+        ) {
+            (Some(request), _) => Some((request.contract_address, request.function_selector)),
+            (_, Some(request)) => Some((request.contract_address, request.function_selector)),
+            (None, None) => None,
+        };
+        if let Some((contract_address, function_selector)) = call_target {
+            self.records.push(TraceRecord::CallTarget(contract_address, function_selector));
+        }
+    }
+
+    fn on_syscall_exit(&mut self, syscall: &SyscallTrace<'_>) {
+        if let SyscallTraceResponse::Success(response) = syscall.response {
+            let read_value = match (
+                response.downcast_ref::<syscalls::StorageReadResponse>(),
+                response.downcast_ref::<deprecated_syscalls::StorageReadResponse>(),
+            ) {
+                (Some(response), _) => Some(response.value),
+                (_, Some(response)) => Some(response.value),
+                (None, None) => None,
+            };
+            if let Some(value) = read_value {
+                self.records.push(TraceRecord::ReadValue(value));
+            }
+        }
+        self.records.push(TraceRecord::SyscallExit(syscall.selector));
+        self.syscall_gas_consumed.push(syscall.gas_consumed);
+    }
+
+    fn on_storage_read(&mut self, address: ContractAddress, key: StorageKey, value: StarkFelt) {
+        self.records.push(TraceRecord::StorageRead(address, key, value));
+    }
+
+    fn on_storage_write(&mut self, address: ContractAddress, key: StorageKey, value: StarkFelt) {
+        self.records.push(TraceRecord::StorageWrite(address, key, value));
+    }
+
+    fn on_event(&mut self, address: ContractAddress, event: &OrderedEvent) {
+        self.records.push(TraceRecord::Event(address, event.clone()));
+    }
+}
+
+fn execute_traced(call: CallEntryPoint, state: &mut dyn State) -> RecordingTracer {
+    let tracer = Arc::new(Mutex::new(RecordingTracer::default()));
+    let tx_context = TransactionContext {
+        block_context: BlockContext::create_for_testing(),
+        tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
+    };
+    let mut context = EntryPointExecutionContext::new_invoke(Arc::new(tx_context), true)
+        .unwrap()
+        .with_tracer(tracer.clone());
+    call.execute(state, &mut ExecutionResources::default(), &mut context).unwrap();
+
+    drop(context);
+    Arc::try_unwrap(tracer).unwrap().into_inner().unwrap()
+}
+
+#[rstest]
+fn test_trace_nested_call(
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let mut state =
+        test_state(&BlockContext::create_for_testing().chain_info, BALANCE, &[(test_contract, 1)]);
+    let contract_address = test_contract.get_instance_address(0);
+    let (key, value) = (stark_felt!(1234_u16), stark_felt!(18_u8));
+    let outer_selector = selector_from_name("test_call_contract");
+    let inner_selector = selector_from_name("test_storage_read_write");
+    let call = CallEntryPoint {
+        entry_point_selector: outer_selector,
+        calldata: create_calldata(contract_address, "test_storage_read_write", &[key, value]),
+        ..trivial_external_entry_point_new(test_contract)
+    };
+
+    let tracer = execute_traced(call, &mut state);
+    let storage_key = StorageKey::try_from(key).unwrap();
+    // Cairo 0 storage writes also read the value they overwrite.
+    let overwritten_value_read = match cairo_version {
+        CairoVersion::Cairo0 => {
+            vec![TraceRecord::StorageRead(contract_address, storage_key, StarkFelt::ZERO)]
+        }
+        CairoVersion::Cairo1 => vec![],
+    };
+    let expected_records = [
+        vec![
+            TraceRecord::EntryPointEnter(outer_selector),
+            TraceRecord::SyscallEnter(SyscallSelector::CallContract),
+            TraceRecord::CallTarget(contract_address, inner_selector),
+            TraceRecord::EntryPointEnter(inner_selector),
+            TraceRecord::SyscallEnter(SyscallSelector::StorageWrite),
+        ],
+        overwritten_value_read,
+        vec![
+            TraceRecord::StorageWrite(contract_address, storage_key, value),
+            TraceRecord::SyscallExit(SyscallSelector::StorageWrite),
+            TraceRecord::SyscallEnter(SyscallSelector::StorageRead),
+            TraceRecord::StorageRead(contract_address, storage_key, value),
+            TraceRecord::ReadValue(value),
+            TraceRecord::SyscallExit(SyscallSelector::StorageRead),
+            TraceRecord::EntryPointExit { selector: inner_selector, succeeded: true },
+            TraceRecord::SyscallExit(SyscallSelector::CallContract),
+            TraceRecord::EntryPointExit { selector: outer_selector, succeeded: true },
+        ],
+    ]
+    .concat();
+    assert_eq!(tracer.records, expected_records);
+
+    // Only Cairo 1 syscalls are charged gas.
+    let is_gas_metered = matches!(cairo_version, CairoVersion::Cairo1);
+    assert!(tracer.syscall_gas_consumed.iter().all(|&gas| (gas > 0) == is_gas_metered));
+}
+
+#[rstest]
+fn test_trace_events(
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let mut state =
+        test_state(&BlockContext::create_for_testing().chain_info, BALANCE, &[(test_contract, 1)]);
+    let contract_address = test_contract.get_instance_address(0);
+    let (key, data) = (stark_felt!(2_u8), stark_felt!(3_u8));
+    let n_events: u8 = 2;
+    let call = CallEntryPoint {
+        entry_point_selector: selector_from_name("test_emit_events"),
+        calldata: calldata![stark_felt!(n_events), stark_felt!(1_u8), key, stark_felt!(1_u8), data],
+        ..trivial_external_entry_point_new(test_contract)
+    };
+
+    let tracer = execute_traced(call, &mut state);
+    let events: Vec<_> = tracer
+        .records
+        .into_iter()
+        .filter(|record| matches!(record, TraceRecord::Event(..)))
+        .collect();
+    let expected_events: Vec<_> = (0..usize::from(n_events))
+        .map(|order| {
+            let event = EventContent { keys: vec![EventKey(key)], data: EventData(vec![data]) };
+            TraceRecord::Event(contract_address, OrderedEvent { order, event })
+        })
+        .collect();
+    assert_eq!(events, expected_events);
+}
+
+#[rstest]
+fn test_trace_block_txs(
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let account_contract = FeatureContract::AccountWithoutValidations(cairo_version);
+    let tracer = Arc::new(Mutex::new(RecordingTracer::default()));
+    let block_context = BlockContext::create_for_account_testing().with_tracer(tracer.clone());
+    let state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+
+    let sender_address = account_contract.get_instance_address(0);
+    let mut nonce_manager = NonceManager::default();
+    let n_txs = 2;
+    let txs: Vec<Transaction> = (0..n_txs)
+        .map(|_| {
+            Transaction::AccountTransaction(account_invoke_tx(invoke_tx_args! {
+                sender_address,
+                calldata: create_calldata(
+                    test_contract.get_instance_address(0),
+                    "return_result",
+                    &[stark_felt!(2_u8)],
+                ),
+                nonce: nonce_manager.next(sender_address),
+            }))
+        })
+        .collect();
+
+    // A traced block is executed sequentially, even if asked to be executed concurrently.
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+    let n_workers = 4;
+    for result in tx_executor.execute_txs_concurrently(txs, false, n_workers) {
+        result.unwrap();
+    }
+
+    let entered_selectors: Vec<_> = tracer
+        .lock()
+        .unwrap()
+        .records
+        .iter()
+        .filter_map(|record| match record {
+            TraceRecord::EntryPointEnter(selector) => Some(*selector),
+            _ => None,
+        })
+        .collect();
+    let tx_selectors = [
+        selector_from_name(VALIDATE_ENTRY_POINT_NAME),
+        selector_from_name(EXECUTE_ENTRY_POINT_NAME),
+        selector_from_name("return_result"),
+    ];
+    assert_eq!(entered_selectors, tx_selectors.repeat(n_txs));
+}
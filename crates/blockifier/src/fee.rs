@@ -1,4 +1,5 @@
 pub mod actual_cost;
+pub mod estimate;
 pub mod eth_gas_constants;
 pub mod fee_checks;
 pub mod fee_utils;
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use starknet_api::transaction::{Fee, Resource, ResourceBounds, ResourceBoundsMapping};
+
+use crate::blockifier::simulation::{simulate_txs, SimulationFlags};
+use crate::context::BlockContext;
+use crate::fee::fee_utils::{calculate_tx_gas_vector, get_fee_by_gas_vector};
+use crate::fee::gas_usage::estimate_minimal_gas_vector;
+use crate::state::cached_state::CachedState;
+use crate::state::state_api::StateReader;
+use crate::transaction::objects::{
+    FeeType, GasVector, ResourcesMapping, TransactionExecutionResult, TransactionFeeResult,
+};
+use crate::transaction::transaction_execution::Transaction;
+
+#[cfg(test)]
+#[path = "estimate_test.rs"]
+pub mod test;
+
+/// The fee of a transaction, priced according to the block's gas prices.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeEstimate {
+    pub gas_vector: GasVector,
+    pub gas_price: u128,
+    pub data_gas_price: u128,
+    pub overall_fee: Fee,
+    pub unit: FeeType,
+}
+
+impl FeeEstimate {
+    pub fn calculate(
+        block_context: &BlockContext,
+        resources: &ResourcesMapping,
+        fee_type: &FeeType,
+    ) -> TransactionFeeResult<Self> {
+        let gas_vector = calculate_tx_gas_vector(resources, &block_context.versioned_constants)?;
+        let gas_prices = &block_context.block_info.gas_prices;
+
+        Ok(Self {
+            gas_vector,
+            gas_price: gas_prices.get_gas_price_by_fee_type(fee_type).into(),
+            data_gas_price: gas_prices.get_data_gas_price_by_fee_type(fee_type).into(),
+            overall_fee: get_fee_by_gas_vector(&block_context.block_info, gas_vector, fee_type),
+            unit: *fee_type,
+        })
+    }
+
+    /// Returns the L1 gas amount that costs the same as the given gas vector, at the estimate's
+    /// prices; resource bounds only limit L1 gas, which covers the L1 data gas as well.
+    pub fn to_discounted_l1_gas(&self, gas_vector: &GasVector) -> u128 {
+        gas_vector.l1_gas + (gas_vector.l1_data_gas * self.data_gas_price) / self.gas_price
+    }
+
+    /// Returns resource bounds that cover the estimated fee, enlarged by the given safety margin.
+    /// The amount is never below `minimal_gas_vector`, which transactions are required to cover.
+    pub fn suggest_resource_bounds(
+        &self,
+        minimal_gas_vector: &GasVector,
+        safety_margin: &SafetyMargin,
+    ) -> ResourceBoundsMapping {
+        let l1_gas_amount = self
+            .to_discounted_l1_gas(&self.gas_vector)
+            .max(self.to_discounted_l1_gas(minimal_gas_vector));
+        let max_amount =
+            u64::try_from(add_margin(l1_gas_amount, safety_margin.gas_amount_percentage))
+                .unwrap_or(u64::MAX);
+        let max_price_per_unit = add_margin(self.gas_price, safety_margin.gas_price_percentage);
+
+        ResourceBoundsMapping(BTreeMap::from([
+            (Resource::L1Gas, ResourceBounds { max_amount, max_price_per_unit }),
+            (Resource::L2Gas, ResourceBounds { max_amount: 0, max_price_per_unit: 0 }),
+        ]))
+    }
+}
+
+/// The margins, in percentage, added on top of the estimated values when suggesting resource
+/// bounds; the gas price may rise before the transaction is included in a block, and its execution
+/// may take a different path than the estimated one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SafetyMargin {
+    pub gas_amount_percentage: u128,
+    pub gas_price_percentage: u128,
+}
+
+impl Default for SafetyMargin {
+    fn default() -> Self {
+        Self { gas_amount_percentage: 10, gas_price_percentage: 50 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FeeEstimationConfig {
+    /// Skips the `__validate__` (or equivalent) entry point of account transactions; e.g., for
+    /// transactions that are not signed yet.
+    pub skip_validate: bool,
+    pub safety_margin: SafetyMargin,
+}
+
+/// The estimated fee of a transaction, along with the resource bounds it should be sent with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionFeeEstimate {
+    pub fee_estimate: FeeEstimate,
+    pub suggested_resource_bounds: ResourceBoundsMapping,
+}
+
+/// Estimates the fee of the given transactions, executed one after the other as queries on top of
+/// the given state; the state is left untouched.
+/// Fees are not charged, so the transactions may carry arbitrary (e.g., zero) resource bounds.
+/// Stops at the first failure (which is the last returned result).
+pub fn estimate_fee<S: StateReader>(
+    state: &mut CachedState<S>,
+    block_context: &BlockContext,
+    txs: Vec<Transaction>,
+    config: &FeeEstimationConfig,
+) -> Vec<TransactionExecutionResult<TransactionFeeEstimate>> {
+    let minimal_gas_vectors: Vec<_> = txs
+        .iter()
+        .map(|tx| match tx {
+            Transaction::AccountTransaction(account_tx) => {
+                estimate_minimal_gas_vector(block_context, account_tx)
+            }
+            Transaction::L1HandlerTransaction(_) => Ok(GasVector::default()),
+        })
+        .collect();
+    let simulation_flags = SimulationFlags {
+        skip_validate: config.skip_validate,
+        skip_fee_charge: true,
+        only_query: true,
+    };
+
+    simulate_txs(state, block_context, txs, simulation_flags)
+        .into_iter()
+        .zip(minimal_gas_vectors)
+        .map(|(simulated_tx, minimal_gas_vector)| {
+            let fee_estimate = simulated_tx?.fee_estimate;
+            let suggested_resource_bounds =
+                fee_estimate.suggest_resource_bounds(&minimal_gas_vector?, &config.safety_margin);
+
+            Ok(TransactionFeeEstimate { fee_estimate, suggested_resource_bounds })
+        })
+        .collect()
+}
+
+fn add_margin(value: u128, margin_percentage: u128) -> u128 {
+    value.saturating_mul(margin_percentage.saturating_add(100)) / 100
+}
@@ -0,0 +1,77 @@
+use rstest::rstest;
+use starknet_api::core::Nonce;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::transaction::{Fee, Resource, TransactionVersion};
+
+use crate::context::BlockContext;
+use crate::fee::estimate::{estimate_fee, FeeEstimationConfig};
+use crate::invoke_tx_args;
+use crate::state::state_api::StateReader;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{create_calldata, CairoVersion, NonceManager, BALANCE};
+use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::objects::FeeType;
+use crate::transaction::test_utils::{account_invoke_tx, block_context, l1_resource_bounds};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::ExecutableTransaction;
+
+#[rstest]
+fn test_estimate_fee(
+    block_context: BlockContext,
+    #[values(true, false)] skip_validate: bool,
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let account_contract = FeatureContract::AccountWithoutValidations(cairo_version);
+    let mut state = test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(test_contract, 1), (account_contract, 1)],
+    );
+    let sender_address = account_contract.get_instance_address(0);
+    let mut nonce_manager = NonceManager::default();
+    let nonces = [nonce_manager.next(sender_address), nonce_manager.next(sender_address)];
+    let invoke_tx = |nonce, resource_bounds| {
+        account_invoke_tx(invoke_tx_args! {
+            sender_address,
+            calldata: create_calldata(
+                test_contract.get_instance_address(0),
+                "test_storage_read_write",
+                &[stark_felt!(15_u8), stark_felt!(17_u8)],
+            ),
+            version: TransactionVersion::THREE,
+            resource_bounds,
+            nonce,
+        })
+    };
+
+    // The transactions are sent without bounds, and the second one depends on the first one.
+    let txs = nonces
+        .iter()
+        .map(|&nonce| Transaction::AccountTransaction(invoke_tx(nonce, l1_resource_bounds(0, 0))))
+        .collect();
+    let config = FeeEstimationConfig { skip_validate, ..Default::default() };
+    let estimates = estimate_fee(&mut state, &block_context, txs, &config);
+
+    assert_eq!(estimates.len(), nonces.len());
+    assert_eq!(state.get_nonce_at(sender_address).unwrap(), Nonce::default());
+    for (estimate, nonce) in estimates.into_iter().zip(nonces) {
+        let estimate = estimate.unwrap();
+        let fee_estimate = &estimate.fee_estimate;
+        assert_eq!(fee_estimate.unit, FeeType::Strk);
+        assert!(fee_estimate.overall_fee > Fee(0));
+
+        // The suggested bounds suffice for the actual execution.
+        let l1_bounds = estimate.suggested_resource_bounds.0[&Resource::L1Gas];
+        assert!(l1_bounds.max_price_per_unit > fee_estimate.gas_price);
+        assert!(
+            u128::from(l1_bounds.max_amount)
+                > fee_estimate.to_discounted_l1_gas(&fee_estimate.gas_vector)
+        );
+        let tx: AccountTransaction = invoke_tx(nonce, estimate.suggested_resource_bounds);
+        let execution_info = tx.execute(&mut state, &block_context, true, true).unwrap();
+        assert!(!execution_info.is_reverted());
+    }
+}
@@ -12,6 +12,8 @@
 pub mod abi;
 pub mod blockifier;
 pub mod bouncer;
+pub mod commitment;
+pub mod concurrency;
 pub mod context;
 pub mod execution;
 pub mod fee;
@@ -20,4 +22,5 @@ pub mod state;
 pub mod test_utils;
 pub mod transaction;
 pub mod utils;
+pub mod validator;
 pub mod versioned_constants;
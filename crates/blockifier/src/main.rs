@@ -0,0 +1,320 @@
+//! A command-line tool that executes transactions on a state loaded from JSON files, and prints
+//! their execution infos and the resulting state diff as JSON; e.g., for reproducing bug reports
+//! without writing a test.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use blockifier::blockifier::block::{pre_process_block, BlockInfo, BlockNumberHashPair};
+use blockifier::blockifier::transaction_executor::TransactionExecutor;
+use blockifier::bouncer::BouncerConfig;
+use blockifier::context::ChainInfo;
+use blockifier::execution::contract_class::{
+    ClassInfo, ContractClass, ContractClassV0, ContractClassV1,
+};
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff, GlobalContractCache};
+use blockifier::state::state_snapshot::{
+    SnapshotContractClass, SnapshotStateReader, StateSnapshot, StateSnapshotFormat,
+};
+use blockifier::transaction::objects::TransactionExecutionInfo;
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::versioned_constants::VersionedConstants;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{
+    DeclareTransaction, Fee, Transaction as StarknetApiTransaction, TransactionHash,
+};
+
+// The test relies on the test utilities of the library.
+#[cfg(all(test, feature = "testing"))]
+#[path = "main_test.rs"]
+mod test;
+
+const USAGE: &str = "Usage: blockifier --state <PATH> --block-context <PATH> --txs <PATH> \
+                     [--versioned-constants <PATH>]";
+const GLOBAL_CONTRACT_CACHE_SIZE: usize = 100;
+const BINARY_STATE_EXTENSION: &str = "cbor";
+
+#[derive(Debug)]
+struct Args {
+    state: PathBuf,
+    block_context: PathBuf,
+    txs: PathBuf,
+    /// Defaults to the latest versioned constants.
+    versioned_constants: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let (mut state, mut block_context, mut txs, mut versioned_constants) =
+            (None, None, None, None);
+        while let Some(flag) = args.next() {
+            let value = match flag.as_str() {
+                "--state" => &mut state,
+                "--block-context" => &mut block_context,
+                "--txs" => &mut txs,
+                "--versioned-constants" => &mut versioned_constants,
+                _ => bail!("Unexpected argument: {flag}.\n{USAGE}"),
+            };
+            *value = Some(PathBuf::from(
+                args.next().with_context(|| format!("Missing value for {flag}.\n{USAGE}"))?,
+            ));
+        }
+
+        Ok(Self {
+            state: state.context(USAGE)?,
+            block_context: block_context.context(USAGE)?,
+            txs: txs.context(USAGE)?,
+            versioned_constants,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BlockContextFile {
+    block_info: BlockInfo,
+    chain_info: ChainInfo,
+    /// Required from block number `STORED_BLOCK_HASH_BUFFER` onwards.
+    #[serde(default)]
+    old_block_number_and_hash: Option<(u64, StarkFelt)>,
+}
+
+/// A transaction, in its Starknet API JSON format, along with the data required to execute it.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TransactionEntry {
+    tx: StarknetApiTransaction,
+    tx_hash: TransactionHash,
+    /// Required for `Declare` transactions.
+    #[serde(default)]
+    declared_class: Option<DeclaredClass>,
+    /// Required for `L1Handler` transactions.
+    #[serde(default)]
+    paid_fee_on_l1: Option<Fee>,
+}
+
+/// A declared class; a compiled Cairo 0 class for versions 0 and 1 of `Declare`, and a CASM class
+/// otherwise. The path is relative to the directory of the transactions file.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DeclaredClass {
+    path: PathBuf,
+    #[serde(default)]
+    sierra_program_length: usize,
+    #[serde(default)]
+    abi_length: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Output {
+    txs: Vec<TransactionOutput>,
+    state_diff: StateDiffOutput,
+}
+
+#[derive(Debug, Serialize)]
+struct TransactionOutput {
+    tx_hash: TransactionHash,
+    /// `None` if the transaction failed to execute; note that reverted transactions are executed.
+    execution_info: Option<TransactionExecutionInfo>,
+    error: Option<String>,
+}
+
+/// A `CommitmentStateDiff`, with deterministically ordered keys.
+#[derive(Debug, Serialize)]
+struct StateDiffOutput {
+    address_to_class_hash: BTreeMap<ContractAddress, ClassHash>,
+    address_to_nonce: BTreeMap<ContractAddress, Nonce>,
+    storage_updates: BTreeMap<ContractAddress, BTreeMap<StorageKey, StarkFelt>>,
+    class_hash_to_compiled_class_hash: BTreeMap<ClassHash, CompiledClassHash>,
+}
+
+impl From<CommitmentStateDiff> for StateDiffOutput {
+    fn from(state_diff: CommitmentStateDiff) -> Self {
+        Self {
+            address_to_class_hash: state_diff.address_to_class_hash.into_iter().collect(),
+            address_to_nonce: state_diff.address_to_nonce.into_iter().collect(),
+            storage_updates: state_diff
+                .storage_updates
+                .into_iter()
+                .map(|(address, storage_diff)| (address, storage_diff.into_iter().collect()))
+                .collect(),
+            class_hash_to_compiled_class_hash: state_diff
+                .class_hash_to_compiled_class_hash
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    let output = run(&args)?;
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+fn run(args: &Args) -> anyhow::Result<Output> {
+    let state_reader = load_state(&args.state)?;
+    let block_context_file: BlockContextFile = read_json(&args.block_context)?;
+    let versioned_constants = match &args.versioned_constants {
+        Some(path) => VersionedConstants::try_from(path.as_path())
+            .with_context(|| format!("Failed to load versioned constants from {path:?}."))?,
+        None => VersionedConstants::latest_constants().clone(),
+    };
+    let tx_entries: Vec<TransactionEntry> = read_json(&args.txs)?;
+    let tx_hashes: Vec<_> = tx_entries.iter().map(|tx_entry| tx_entry.tx_hash).collect();
+    let txs = tx_entries
+        .into_iter()
+        .map(|tx_entry| to_blockifier_tx(tx_entry, base_dir(&args.txs)))
+        .collect::<anyhow::Result<_>>()?;
+
+    let mut state =
+        CachedState::new(state_reader, GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE));
+    let block_context = pre_process_block(
+        &mut state,
+        block_context_file
+            .old_block_number_and_hash
+            .map(|(block_number, block_hash)| BlockNumberHashPair::new(block_number, block_hash)),
+        block_context_file.block_info,
+        block_context_file.chain_info,
+        versioned_constants,
+    )?;
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+    let charge_fee = true;
+    let tx_outputs = tx_executor
+        .execute_txs(txs, charge_fee)
+        .into_iter()
+        .zip(tx_hashes)
+        .map(|(tx_execution_result, tx_hash)| match tx_execution_result {
+            Ok((execution_info, _)) => {
+                TransactionOutput { tx_hash, execution_info: Some(execution_info), error: None }
+            }
+            Err(error) => {
+                TransactionOutput { tx_hash, execution_info: None, error: Some(error.to_string()) }
+            }
+        })
+        .collect();
+
+    let is_pending_block = false;
+    let (state_diff, _) = tx_executor.finalize(is_pending_block)?;
+
+    Ok(Output { txs: tx_outputs, state_diff: state_diff.into() })
+}
+
+/// Loads the initial state; a state snapshot, binary-encoded if the file extension is `cbor`, and
+/// in JSON otherwise.
+fn load_state(path: &Path) -> anyhow::Result<SnapshotStateReader> {
+    let snapshot = match path.extension() {
+        Some(extension) if extension == BINARY_STATE_EXTENSION => {
+            StateSnapshot::load(path, StateSnapshotFormat::Binary)
+                .with_context(|| format!("Failed to load the state from {path:?}."))?
+        }
+        _ => load_json_state(path)?,
+    };
+
+    Ok(snapshot.try_into()?)
+}
+
+/// Loads a JSON state snapshot, which may also reference classes by path, relative to the
+/// directory of the state file:
+/// ```json
+/// {
+///     ...,
+///     "cairo0_classes": {"<class hash>": "<path of a compiled Cairo 0 class>"},
+///     "casm_classes": {"<class hash>": "<path of a CASM class>"}
+/// }
+/// ```
+fn load_json_state(path: &Path) -> anyhow::Result<StateSnapshot> {
+    let mut state_file: serde_json::Map<String, serde_json::Value> = read_json(path)?;
+    let mut take_class_paths = |field: &str| -> anyhow::Result<BTreeMap<ClassHash, PathBuf>> {
+        state_file.remove(field).map_or(Ok(BTreeMap::new()), |class_paths| {
+            serde_json::from_value(class_paths)
+                .with_context(|| format!("Failed to parse the {field} of {path:?}."))
+        })
+    };
+    let cairo0_class_paths = take_class_paths("cairo0_classes")?;
+    let casm_class_paths = take_class_paths("casm_classes")?;
+    let mut snapshot: StateSnapshot = serde_json::from_value(state_file.into())
+        .with_context(|| format!("Failed to parse {path:?}."))?;
+
+    let mut classes = Vec::new();
+    for (class_hash, class_path) in cairo0_class_paths {
+        let deprecated_class = read_json(&base_dir(path).join(class_path))?;
+        classes.push((class_hash, SnapshotContractClass::Cairo0(deprecated_class)));
+    }
+    for (class_hash, class_path) in casm_class_paths {
+        let casm = read_json(&base_dir(path).join(class_path))?;
+        classes.push((class_hash, SnapshotContractClass::Cairo1 { casm, sierra: None }));
+    }
+    for (class_hash, contract_class) in classes {
+        if snapshot.classes.insert(class_hash, contract_class).is_some() {
+            bail!("Class {class_hash:?} is given more than once in {path:?}.");
+        }
+    }
+
+    Ok(snapshot)
+}
+
+fn to_blockifier_tx(tx_entry: TransactionEntry, base_dir: &Path) -> anyhow::Result<Transaction> {
+    let class_info = match (&tx_entry.tx, tx_entry.declared_class) {
+        (StarknetApiTransaction::Declare(declare_tx), Some(declared_class)) => {
+            let raw_class = read_to_string(&base_dir.join(declared_class.path))?;
+            let contract_class: ContractClass = match declare_tx {
+                DeclareTransaction::V0(_) | DeclareTransaction::V1(_) => {
+                    ContractClassV0::try_from_json_string(&raw_class)?.into()
+                }
+                DeclareTransaction::V2(_) | DeclareTransaction::V3(_) => {
+                    ContractClassV1::try_from_json_string(&raw_class)?.into()
+                }
+            };
+            Some(ClassInfo::new(
+                &contract_class,
+                declared_class.sierra_program_length,
+                declared_class.abi_length,
+            )?)
+        }
+        (StarknetApiTransaction::Declare(_), None) => {
+            bail!("Declare transaction {:?} is missing its declared class.", tx_entry.tx_hash)
+        }
+        (StarknetApiTransaction::Deploy(_), _) => {
+            bail!("Deploy transaction {:?} is unsupported.", tx_entry.tx_hash)
+        }
+        _ => None,
+    };
+    if matches!(tx_entry.tx, StarknetApiTransaction::L1Handler(_))
+        && tx_entry.paid_fee_on_l1.is_none()
+    {
+        bail!("L1 handler transaction {:?} is missing its paid fee on L1.", tx_entry.tx_hash);
+    }
+
+    let deployed_contract_address = None;
+    let only_query = false;
+    Ok(Transaction::from_api(
+        tx_entry.tx,
+        tx_entry.tx_hash,
+        class_info,
+        tx_entry.paid_fee_on_l1,
+        deployed_contract_address,
+        only_query,
+    )?)
+}
+
+fn base_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new("."))
+}
+
+fn read_to_string(path: &Path) -> anyhow::Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}."))
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    serde_json::from_str(&read_to_string(path)?)
+        .with_context(|| format!("Failed to parse {path:?}."))
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use blockifier::abi::abi_utils::get_fee_token_var_address;
+use blockifier::abi::constants::STORED_BLOCK_HASH_BUFFER;
+use blockifier::context::{BlockContext, ChainInfo};
+use blockifier::invoke_tx_args;
+use blockifier::state::state_snapshot::{
+    SnapshotContractClass, StateSnapshot, StateSnapshotFormat,
+};
+use blockifier::test_utils::contracts::FeatureContract;
+use blockifier::test_utils::invoke::invoke_tx;
+use blockifier::test_utils::{
+    create_calldata, CairoVersion, BALANCE, CURRENT_BLOCK_NUMBER, MAX_FEE,
+};
+use blockifier::transaction::objects::FeeType;
+use pretty_assertions::assert_eq;
+use serde::Serialize;
+use starknet_api::core::Nonce;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::transaction::{Fee, Transaction as StarknetApiTransaction};
+use strum::IntoEnumIterator;
+
+use crate::{load_state, run, Args, BlockContextFile, TransactionEntry};
+
+fn write_json(dir: &Path, file_name: &str, value: &impl Serialize) -> PathBuf {
+    let path = dir.join(file_name);
+    fs::write(&path, serde_json::to_string(value).unwrap()).unwrap();
+    path
+}
+
+#[test]
+fn test_args() {
+    let args = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+    let parsed_args =
+        args(&["--txs", "txs.json", "--state", "state.json", "--block-context", "b.json"]).unwrap();
+    assert_eq!(parsed_args.state, PathBuf::from("state.json"));
+    assert_eq!(parsed_args.versioned_constants, None);
+
+    assert!(args(&["--state", "state.json", "--block-context", "b.json"]).is_err());
+    assert!(args(&["--state", "state.json", "--txs"]).is_err());
+    assert!(args(&["--stat", "state.json"]).is_err());
+}
+
+#[test]
+fn test_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let chain_info = ChainInfo::create_for_testing();
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let erc20 = FeatureContract::ERC20;
+    let account_address = account.get_instance_address(0);
+
+    // Deploy the contracts and fund the account.
+    let mut state = StateSnapshot {
+        class_hashes: BTreeMap::from([
+            (account_address, account.get_class_hash()),
+            (test_contract.get_instance_address(0), test_contract.get_class_hash()),
+        ]),
+        classes: [account, test_contract, erc20]
+            .into_iter()
+            .map(|contract| {
+                let contract_class =
+                    SnapshotContractClass::Cairo0(contract.get_deprecated_contract_class());
+                (contract.get_class_hash(), contract_class)
+            })
+            .collect(),
+        ..Default::default()
+    };
+    for fee_type in FeeType::iter() {
+        let fee_token_address = chain_info.fee_token_address(&fee_type);
+        state.class_hashes.insert(fee_token_address, erc20.get_class_hash());
+        state.storage.insert(
+            fee_token_address,
+            BTreeMap::from([(get_fee_token_var_address(account_address), stark_felt!(BALANCE))]),
+        );
+    }
+
+    let block_context = BlockContext::create_for_testing();
+    let block_context_file = BlockContextFile {
+        block_info: block_context.block_info().clone(),
+        chain_info,
+        old_block_number_and_hash: Some((
+            CURRENT_BLOCK_NUMBER - STORED_BLOCK_HASH_BUFFER,
+            stark_felt!("0x1234"),
+        )),
+    };
+
+    let invoke_tx = invoke_tx(invoke_tx_args! {
+        sender_address: account_address,
+        calldata: create_calldata(
+            test_contract.get_instance_address(0),
+            "return_result",
+            &[stark_felt!(2_u8)],
+        ),
+        max_fee: Fee(MAX_FEE),
+    });
+    let tx_entries = [TransactionEntry {
+        tx: StarknetApiTransaction::Invoke(invoke_tx.tx),
+        tx_hash: invoke_tx.tx_hash,
+        declared_class: None,
+        paid_fee_on_l1: None,
+    }];
+
+    let args = Args {
+        state: write_json(dir.path(), "state.json", &state),
+        block_context: write_json(dir.path(), "block_context.json", &block_context_file),
+        txs: write_json(dir.path(), "txs.json", &tx_entries),
+        versioned_constants: None,
+    };
+    let output = run(&args).unwrap();
+
+    let [tx_output] = &output.txs[..] else { panic!("Expected a single transaction output.") };
+    assert_eq!(tx_output.error, None);
+    let execution_info = tx_output.execution_info.as_ref().unwrap();
+    assert_eq!(execution_info.revert_error, None);
+    assert!(execution_info.actual_fee.0 > 0);
+    assert_eq!(output.state_diff.address_to_nonce[&account_address], Nonce(stark_felt!(1_u8)));
+
+    // The same state, binary-encoded.
+    let binary_state_path = dir.path().join("state.cbor");
+    state.dump(&binary_state_path, StateSnapshotFormat::Binary).unwrap();
+    let binary_state_output = run(&Args { state: binary_state_path, ..args }).unwrap();
+    assert_eq!(
+        serde_json::to_value(binary_state_output.state_diff).unwrap(),
+        serde_json::to_value(output.state_diff).unwrap()
+    );
+}
+
+fn class_paths(contract: FeatureContract, path: &str) -> serde_json::Value {
+    serde_json::to_value(BTreeMap::from([(contract.get_class_hash(), path)])).unwrap()
+}
+
+#[test]
+fn test_load_state_with_class_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let cairo0_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let cairo1_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    fs::create_dir(dir.path().join("classes")).unwrap();
+    fs::copy(cairo0_contract.get_compiled_path(), dir.path().join("classes/cairo0.json")).unwrap();
+    fs::copy(cairo1_contract.get_compiled_path(), dir.path().join("classes/cairo1.json")).unwrap();
+
+    // Classes may be given both inline and by path.
+    let mut state = serde_json::to_value(StateSnapshot {
+        classes: BTreeMap::from([(
+            account.get_class_hash(),
+            SnapshotContractClass::Cairo0(account.get_deprecated_contract_class()),
+        )]),
+        ..Default::default()
+    })
+    .unwrap();
+    state["cairo0_classes"] = class_paths(cairo0_contract, "classes/cairo0.json");
+    state["casm_classes"] = class_paths(cairo1_contract, "classes/cairo1.json");
+    let state_reader = load_state(&write_json(dir.path(), "state.json", &state)).unwrap();
+
+    for contract in [account, cairo0_contract, cairo1_contract] {
+        assert_eq!(state_reader.classes[&contract.get_class_hash()], contract.get_class());
+    }
+
+    // A class given twice is rejected.
+    state["casm_classes"] = class_paths(cairo0_contract, "classes/cairo1.json");
+    assert!(load_state(&write_json(dir.path(), "state.json", &state)).is_err());
+}
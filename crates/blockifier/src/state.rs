@@ -1,3 +1,9 @@
 pub mod cached_state;
 pub mod errors;
+pub mod persistent_contract_cache;
+#[cfg(feature = "rpc")]
+pub mod rpc_state_reader;
 pub mod state_api;
+pub mod state_overrides;
+pub mod state_reader_combinators;
+pub mod state_snapshot;
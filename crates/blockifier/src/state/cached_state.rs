@@ -1,10 +1,12 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use cached::{Cached, SizedCache};
 use derive_more::IntoIterator;
 use indexmap::IndexMap;
+use serde::{Serialize, Serializer};
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::StorageKey;
@@ -12,7 +14,8 @@ use starknet_api::state::StorageKey;
 use crate::abi::abi_utils::get_fee_token_var_address;
 use crate::execution::contract_class::ContractClass;
 use crate::state::errors::StateError;
-use crate::state::state_api::{State, StateReader, StateResult};
+use crate::state::persistent_contract_cache::PersistentContractCache;
+use crate::state::state_api::{PrefetchedState, State, StateReader, StateResult};
 use crate::utils::subtract_mappings;
 
 #[cfg(test)]
@@ -36,6 +39,12 @@ pub struct CachedState<S: StateReader> {
     global_class_hash_to_class: GlobalContractCache,
     /// A map from class hash to the set of PC values that were visited in the class.
     pub visited_pcs: HashMap<ClassHash, HashSet<usize>>,
+    // The keys read and written through this state; updated during `State`'s immutable getters.
+    accesses: RefCell<StateAccesses>,
+    // Whether reads are recorded in `accesses`; unset by `read_untracked`.
+    tracks_reads: Cell<bool>,
+    // The changes done since the earliest snapshot taken by `snapshot`; undone by `revert_to`.
+    journal: UndoJournal,
 }
 
 impl<S: StateReader> CachedState<S> {
@@ -46,6 +55,9 @@ impl<S: StateReader> CachedState<S> {
             class_hash_to_class: RefCell::new(HashMap::default()),
             global_class_hash_to_class,
             visited_pcs: HashMap::default(),
+            accesses: RefCell::new(StateAccesses::default()),
+            tracks_reads: Cell::new(true),
+            journal: UndoJournal::default(),
         }
     }
 
@@ -57,6 +69,68 @@ impl<S: StateReader> CachedState<S> {
         CachedState::new(MutRefState::new(state), global_class_hash_to_class)
     }
 
+    /// Takes a snapshot of the changes done through this state (writes, declared classes, visited
+    /// PCs and the write set of its accesses), which can be restored later on by `revert_to`.
+    /// From then on, every change records the value it overwrites.
+    pub fn snapshot(&mut self) -> SnapshotId {
+        self.journal.snapshots.push(self.journal.entries.len());
+        SnapshotId(self.journal.snapshots.len() - 1)
+    }
+
+    /// Discards all changes done through this state since the given snapshot was taken, by
+    /// restoring the values they overwrote, latest first.
+    /// The snapshot remains valid, so it can be reverted to again; snapshots taken after it are
+    /// discarded.
+    /// Note: classes already moved to the global contract cache are not affected.
+    pub fn revert_to(&mut self, snapshot_id: SnapshotId) -> StateResult<()> {
+        let n_entries = *self
+            .journal
+            .snapshots
+            .get(snapshot_id.0)
+            .ok_or(StateError::UnknownSnapshot(snapshot_id))?;
+        let cache = self.cache.get_mut();
+        let class_hash_to_class = self.class_hash_to_class.get_mut();
+        for entry in self.journal.entries.drain(n_entries..).rev() {
+            match entry {
+                JournalEntry::StorageWrite(storage_entry, prior_value) => {
+                    restore_value(&mut cache.storage_writes, storage_entry, prior_value)
+                }
+                JournalEntry::NonceWrite(contract_address, prior_nonce) => {
+                    restore_value(&mut cache.nonce_writes, contract_address, prior_nonce)
+                }
+                JournalEntry::ClassHashWrite(contract_address, prior_class_hash) => {
+                    restore_value(&mut cache.class_hash_writes, contract_address, prior_class_hash)
+                }
+                JournalEntry::CompiledClassHashWrite(class_hash, prior_compiled_class_hash) => {
+                    restore_value(
+                        &mut cache.compiled_class_hash_writes,
+                        class_hash,
+                        prior_compiled_class_hash,
+                    )
+                }
+                JournalEntry::ContractClassWrite(class_hash, prior_contract_class) => {
+                    restore_value(class_hash_to_class, class_hash, prior_contract_class)
+                }
+                JournalEntry::WriteAccess(write_access) => {
+                    self.accesses.get_mut().remove_write(write_access)
+                }
+                JournalEntry::VisitedPcs(class_hash, new_pcs) => {
+                    if let Some(class_visited_pcs) = self.visited_pcs.get_mut(&class_hash) {
+                        for pc in new_pcs {
+                            class_visited_pcs.remove(&pc);
+                        }
+                        if class_visited_pcs.is_empty() {
+                            self.visited_pcs.remove(&class_hash);
+                        }
+                    }
+                }
+            }
+        }
+        self.journal.snapshots.truncate(snapshot_id.0 + 1);
+
+        Ok(())
+    }
+
     /// Returns the storage changes done through this state.
     /// For each contract instance (address) we have three attributes: (class hash, nonce, storage
     /// root); the state updates correspond to them.
@@ -74,11 +148,46 @@ impl<S: StateReader> CachedState<S> {
         })
     }
 
+    /// Returns the keys read and written through this state (including through committed
+    /// transactional states on top of it), and where the reads were served from.
+    pub fn get_state_accesses(&self) -> StateAccesses {
+        self.accesses.borrow().clone()
+    }
+
+    /// Runs the given reads without recording them in the accesses of this state; e.g., reads done
+    /// on behalf of speculative executions, or to validate them. Values loaded from the underlying
+    /// state are still counted as state reader hits.
+    pub fn read_untracked<T>(&self, read: impl FnOnce(&Self) -> T) -> T {
+        let tracked_reads = self.tracks_reads.replace(false);
+        let result = read(self);
+        self.tracks_reads.set(tracked_reads);
+        result
+    }
+
+    /// Records a read in the accesses of this state; if reads are untracked, only a value loaded
+    /// from the underlying state is counted.
+    fn record_read(&self, is_cache_hit: bool, record_key: impl FnOnce(&mut StateAccesses)) {
+        let mut accesses = self.accesses.borrow_mut();
+        if self.tracks_reads.get() {
+            record_key(&mut accesses);
+            accesses.count_read(is_cache_hit);
+        } else if !is_cache_hit {
+            accesses.n_state_reader_hits += 1;
+        }
+    }
+
+    /// Records a write in the accesses of this state; undone by `revert_to` if it is new.
+    fn record_write(&mut self, write_access: WriteAccess) {
+        if self.accesses.get_mut().insert_write(write_access) {
+            self.journal.record(|| JournalEntry::WriteAccess(write_access));
+        }
+    }
+
     /// Drains contract-class cache collected during execution and updates the global cache.
     pub fn move_classes_to_global_cache(&mut self) {
         let contract_class_updates: Vec<_> = self.class_hash_to_class.get_mut().drain().collect();
         for (key, value) in contract_class_updates {
-            self.global_class_hash_to_class().cache_set(key, value);
+            self.global_class_hash_to_class.set(key, value);
         }
     }
 
@@ -89,13 +198,34 @@ impl<S: StateReader> CachedState<S> {
         self.global_class_hash_to_class.lock()
     }
 
+    /// Returns a (shared) handle to the global contract class cache used by this state.
+    pub fn global_contract_cache(&self) -> GlobalContractCache {
+        self.global_class_hash_to_class.clone()
+    }
+
     pub fn update_cache(&mut self, cache_updates: StateCache) {
-        let mut cache = self.cache.borrow_mut();
+        let cache = self.cache.get_mut();
+        let journal = &mut self.journal;
 
-        cache.nonce_writes.extend(cache_updates.nonce_writes);
-        cache.class_hash_writes.extend(cache_updates.class_hash_writes);
-        cache.storage_writes.extend(cache_updates.storage_writes);
-        cache.compiled_class_hash_writes.extend(cache_updates.compiled_class_hash_writes);
+        for (contract_address, nonce) in cache_updates.nonce_writes {
+            let prior_nonce = cache.nonce_writes.insert(contract_address, nonce);
+            journal.record(|| JournalEntry::NonceWrite(contract_address, prior_nonce));
+        }
+        for (contract_address, class_hash) in cache_updates.class_hash_writes {
+            let prior_class_hash = cache.class_hash_writes.insert(contract_address, class_hash);
+            journal.record(|| JournalEntry::ClassHashWrite(contract_address, prior_class_hash));
+        }
+        for (storage_entry, value) in cache_updates.storage_writes {
+            let prior_value = cache.storage_writes.insert(storage_entry, value);
+            journal.record(|| JournalEntry::StorageWrite(storage_entry, prior_value));
+        }
+        for (class_hash, compiled_class_hash) in cache_updates.compiled_class_hash_writes {
+            let prior_compiled_class_hash =
+                cache.compiled_class_hash_writes.insert(class_hash, compiled_class_hash);
+            journal.record(|| {
+                JournalEntry::CompiledClassHashWrite(class_hash, prior_compiled_class_hash)
+            });
+        }
     }
 
     pub fn update_contract_class_caches(
@@ -103,7 +233,12 @@ impl<S: StateReader> CachedState<S> {
         local_contract_cache_updates: ContractClassMapping,
         global_contract_cache: GlobalContractCache,
     ) {
-        self.class_hash_to_class.get_mut().extend(local_contract_cache_updates);
+        let class_hash_to_class = self.class_hash_to_class.get_mut();
+        for (class_hash, contract_class) in local_contract_cache_updates {
+            let prior_contract_class = class_hash_to_class.insert(class_hash, contract_class);
+            self.journal
+                .record(|| JournalEntry::ContractClassWrite(class_hash, prior_contract_class));
+        }
         self.global_class_hash_to_class = global_contract_cache;
     }
 
@@ -113,6 +248,15 @@ impl<S: StateReader> CachedState<S> {
         }
     }
 
+    /// Merges the accesses of a transactional state on top of this one. Its reads from the
+    /// underlying state were served, and counted, by this state; only its cache hits are added.
+    pub fn update_state_accesses(&mut self, child_accesses: StateAccesses) {
+        for write_access in child_accesses.writes() {
+            self.record_write(write_access);
+        }
+        self.accesses.get_mut().extend(&StateAccesses { n_state_reader_hits: 0, ..child_accesses });
+    }
+
     /// Updates cache with initial cell values for write-only access.
     /// If written values match the original, the cell is unchanged and not counted as a
     /// storage-change for fee calculation.
@@ -194,10 +338,14 @@ impl<S: StateReader> StateReader for CachedState<S> {
     ) -> StateResult<StarkFelt> {
         let mut cache = self.cache.borrow_mut();
 
-        if cache.get_storage_at(contract_address, key).is_none() {
+        let is_cache_hit = cache.get_storage_at(contract_address, key).is_some();
+        if !is_cache_hit {
             let storage_value = self.state.get_storage_at(contract_address, key)?;
             cache.set_storage_initial_value(contract_address, key, storage_value);
         }
+        self.record_read(is_cache_hit, |accesses| {
+            accesses.storage_reads.insert((contract_address, key));
+        });
 
         let value = cache.get_storage_at(contract_address, key).unwrap_or_else(|| {
             panic!("Cannot retrieve '{contract_address:?}' and '{key:?}' from the cache.")
@@ -208,10 +356,14 @@ impl<S: StateReader> StateReader for CachedState<S> {
     fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
         let mut cache = self.cache.borrow_mut();
 
-        if cache.get_nonce_at(contract_address).is_none() {
+        let is_cache_hit = cache.get_nonce_at(contract_address).is_some();
+        if !is_cache_hit {
             let nonce = self.state.get_nonce_at(contract_address)?;
             cache.set_nonce_initial_value(contract_address, nonce);
         }
+        self.record_read(is_cache_hit, |accesses| {
+            accesses.nonce_reads.insert(contract_address);
+        });
 
         let nonce = cache
             .get_nonce_at(contract_address)
@@ -223,10 +375,14 @@ impl<S: StateReader> StateReader for CachedState<S> {
     fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
         let mut cache = self.cache.borrow_mut();
 
-        if cache.get_class_hash_at(contract_address).is_none() {
+        let is_cache_hit = cache.get_class_hash_at(contract_address).is_some();
+        if !is_cache_hit {
             let class_hash = self.state.get_class_hash_at(contract_address)?;
             cache.set_class_hash_initial_value(contract_address, class_hash);
         }
+        self.record_read(is_cache_hit, |accesses| {
+            accesses.class_hash_reads.insert(contract_address);
+        });
 
         let class_hash = cache
             .get_class_hash_at(contract_address)
@@ -237,10 +393,12 @@ impl<S: StateReader> StateReader for CachedState<S> {
     fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
         let class_hash_to_class = &mut *self.class_hash_to_class.borrow_mut();
 
+        // Classes found in the global contract cache are counted as cache hits.
+        let mut is_cache_hit = true;
         if let std::collections::hash_map::Entry::Vacant(vacant_entry) =
             class_hash_to_class.entry(class_hash)
         {
-            let contract_class = self.global_class_hash_to_class().cache_get(&class_hash).cloned();
+            let contract_class = self.global_class_hash_to_class.get(&class_hash);
 
             match contract_class {
                 Some(contract_class_from_global_cache) => {
@@ -250,9 +408,13 @@ impl<S: StateReader> StateReader for CachedState<S> {
                     let contract_class_from_db =
                         self.state.get_compiled_contract_class(class_hash)?;
                     vacant_entry.insert(contract_class_from_db);
+                    is_cache_hit = false;
                 }
             }
         }
+        self.record_read(is_cache_hit, |accesses| {
+            accesses.class_reads.insert(class_hash);
+        });
 
         let contract_class = class_hash_to_class
             .get(&class_hash)
@@ -265,16 +427,118 @@ impl<S: StateReader> StateReader for CachedState<S> {
     fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
         let mut cache = self.cache.borrow_mut();
 
-        if cache.get_compiled_class_hash(class_hash).is_none() {
+        let is_cache_hit = cache.get_compiled_class_hash(class_hash).is_some();
+        if !is_cache_hit {
             let compiled_class_hash = self.state.get_compiled_class_hash(class_hash)?;
             cache.set_compiled_class_hash_initial_value(class_hash, compiled_class_hash);
         }
+        self.record_read(is_cache_hit, |accesses| {
+            accesses.compiled_class_hash_reads.insert(class_hash);
+        });
 
         let compiled_class_hash = cache
             .get_compiled_class_hash(class_hash)
             .unwrap_or_else(|| panic!("Cannot retrieve '{class_hash:?}' from the cache."));
         Ok(*compiled_class_hash)
     }
+
+    /// Reads the values that are not cached yet from the underlying state as a single batch, and
+    /// caches them. Returns the values of all given keys, as the getters would.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let mut cache = self.cache.borrow_mut();
+        let mut class_hash_to_class = self.class_hash_to_class.borrow_mut();
+        for class_hash in class_hashes {
+            if !class_hash_to_class.contains_key(class_hash) {
+                if let Some(contract_class) = self.global_class_hash_to_class.get(class_hash) {
+                    class_hash_to_class.insert(*class_hash, contract_class);
+                }
+            }
+        }
+
+        let missing_storage_entries: Vec<StorageEntry> = storage_entries
+            .iter()
+            .copied()
+            .filter(|&(contract_address, key)| {
+                cache.get_storage_at(contract_address, key).is_none()
+            })
+            .collect();
+        let missing_contract_addresses: Vec<ContractAddress> = contract_addresses
+            .iter()
+            .copied()
+            .filter(|&contract_address| {
+                cache.get_nonce_at(contract_address).is_none()
+                    || cache.get_class_hash_at(contract_address).is_none()
+            })
+            .collect();
+        let missing_class_hashes: Vec<ClassHash> = class_hashes
+            .iter()
+            .copied()
+            .filter(|class_hash| !class_hash_to_class.contains_key(class_hash))
+            .collect();
+        let prefetched_state = self.state.prefetch(
+            &missing_storage_entries,
+            &missing_contract_addresses,
+            &missing_class_hashes,
+        )?;
+
+        // Cached values take precedence over the prefetched ones. Each loaded value counts as a
+        // state reader hit, as if it was read by a getter; later reads of it are cache hits.
+        let mut n_loaded_values = 0;
+        for ((contract_address, key), value) in prefetched_state.storage {
+            if cache.get_storage_at(contract_address, key).is_none() {
+                cache.set_storage_initial_value(contract_address, key, value);
+                n_loaded_values += 1;
+            }
+        }
+        for (contract_address, nonce) in prefetched_state.nonces {
+            if cache.get_nonce_at(contract_address).is_none() {
+                cache.set_nonce_initial_value(contract_address, nonce);
+                n_loaded_values += 1;
+            }
+        }
+        for (contract_address, class_hash) in prefetched_state.class_hashes {
+            if cache.get_class_hash_at(contract_address).is_none() {
+                cache.set_class_hash_initial_value(contract_address, class_hash);
+                n_loaded_values += 1;
+            }
+        }
+        for (class_hash, contract_class) in prefetched_state.contract_classes {
+            if let std::collections::hash_map::Entry::Vacant(vacant_entry) =
+                class_hash_to_class.entry(class_hash)
+            {
+                vacant_entry.insert(contract_class);
+                n_loaded_values += 1;
+            }
+        }
+        self.accesses.borrow_mut().n_state_reader_hits += n_loaded_values;
+
+        let mut cached_state = PrefetchedState::default();
+        for &(contract_address, key) in storage_entries {
+            if let Some(value) = cache.get_storage_at(contract_address, key) {
+                cached_state.storage.insert((contract_address, key), *value);
+            }
+        }
+        for &contract_address in contract_addresses {
+            if let Some(nonce) = cache.get_nonce_at(contract_address) {
+                cached_state.nonces.insert(contract_address, *nonce);
+            }
+            if let Some(class_hash) = cache.get_class_hash_at(contract_address) {
+                cached_state.class_hashes.insert(contract_address, *class_hash);
+            }
+        }
+        for class_hash in class_hashes {
+            if let Some(contract_class) = class_hash_to_class.get(class_hash) {
+                cached_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(cached_state)
+    }
 }
 
 impl<S: StateReader> State for CachedState<S> {
@@ -284,7 +548,9 @@ impl<S: StateReader> State for CachedState<S> {
         key: StorageKey,
         value: StarkFelt,
     ) -> StateResult<()> {
-        self.cache.get_mut().set_storage_value(contract_address, key, value);
+        let prior_value = self.cache.get_mut().set_storage_value(contract_address, key, value);
+        self.journal.record(|| JournalEntry::StorageWrite((contract_address, key), prior_value));
+        self.record_write(WriteAccess::Storage((contract_address, key)));
 
         Ok(())
     }
@@ -297,7 +563,9 @@ impl<S: StateReader> State for CachedState<S> {
             usize::try_from(current_nonce.0)?.try_into().expect("Failed to convert usize to u64.");
         let next_nonce_val = 1_u64 + current_nonce_as_u64;
         let next_nonce = Nonce(StarkFelt::from(next_nonce_val));
-        self.cache.get_mut().set_nonce_value(contract_address, next_nonce);
+        let prior_nonce = self.cache.get_mut().set_nonce_value(contract_address, next_nonce);
+        self.journal.record(|| JournalEntry::NonceWrite(contract_address, prior_nonce));
+        self.record_write(WriteAccess::Nonce(contract_address));
 
         Ok(())
     }
@@ -311,7 +579,10 @@ impl<S: StateReader> State for CachedState<S> {
             return Err(StateError::OutOfRangeContractAddress);
         }
 
-        self.cache.get_mut().set_class_hash_write(contract_address, class_hash);
+        let prior_class_hash =
+            self.cache.get_mut().set_class_hash_write(contract_address, class_hash);
+        self.journal.record(|| JournalEntry::ClassHashWrite(contract_address, prior_class_hash));
+        self.record_write(WriteAccess::ClassHash(contract_address));
         Ok(())
     }
 
@@ -320,7 +591,10 @@ impl<S: StateReader> State for CachedState<S> {
         class_hash: ClassHash,
         contract_class: ContractClass,
     ) -> StateResult<()> {
-        self.class_hash_to_class.get_mut().insert(class_hash, contract_class);
+        let prior_contract_class =
+            self.class_hash_to_class.get_mut().insert(class_hash, contract_class);
+        self.journal.record(|| JournalEntry::ContractClassWrite(class_hash, prior_contract_class));
+        self.record_write(WriteAccess::Class(class_hash));
         Ok(())
     }
 
@@ -329,12 +603,23 @@ impl<S: StateReader> State for CachedState<S> {
         class_hash: ClassHash,
         compiled_class_hash: CompiledClassHash,
     ) -> StateResult<()> {
-        self.cache.get_mut().set_compiled_class_hash_write(class_hash, compiled_class_hash);
+        let prior_compiled_class_hash =
+            self.cache.get_mut().set_compiled_class_hash_write(class_hash, compiled_class_hash);
+        self.journal
+            .record(|| JournalEntry::CompiledClassHashWrite(class_hash, prior_compiled_class_hash));
+        self.record_write(WriteAccess::CompiledClassHash(class_hash));
         Ok(())
     }
 
     fn add_visited_pcs(&mut self, class_hash: ClassHash, pcs: &HashSet<usize>) {
-        self.visited_pcs.entry(class_hash).or_default().extend(pcs);
+        let class_visited_pcs = self.visited_pcs.entry(class_hash).or_default();
+        if !self.journal.is_recording() {
+            class_visited_pcs.extend(pcs);
+            return;
+        }
+
+        let new_pcs = pcs.iter().copied().filter(|&pc| class_visited_pcs.insert(pc)).collect();
+        self.journal.record(|| JournalEntry::VisitedPcs(class_hash, new_pcs));
     }
 }
 
@@ -349,12 +634,61 @@ impl Default for CachedState<crate::test_utils::dict_state_reader::DictStateRead
                 GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST,
             ),
             visited_pcs: Default::default(),
+            accesses: Default::default(),
+            tracks_reads: Cell::new(true),
+            journal: Default::default(),
         }
     }
 }
 
 pub type StorageEntry = (ContractAddress, StorageKey);
 
+/// Identifies a snapshot of a `CachedState`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SnapshotId(usize);
+
+/// A change done through a `CachedState`, along with the value it overwrote, if any.
+#[derive(Debug)]
+enum JournalEntry {
+    StorageWrite(StorageEntry, Option<StarkFelt>),
+    NonceWrite(ContractAddress, Option<Nonce>),
+    ClassHashWrite(ContractAddress, Option<ClassHash>),
+    CompiledClassHashWrite(ClassHash, Option<CompiledClassHash>),
+    ContractClassWrite(ClassHash, Option<ContractClass>),
+    /// A key that was not in the write set of the accesses before.
+    WriteAccess(WriteAccess),
+    /// The PCs that were not visited before.
+    VisitedPcs(ClassHash, Vec<usize>),
+}
+
+/// Records the changes done through a `CachedState` while there are snapshots to revert to.
+#[derive(Debug, Default)]
+struct UndoJournal {
+    entries: Vec<JournalEntry>,
+    // The number of entries at the time each snapshot was taken, indexed by the snapshot IDs.
+    snapshots: Vec<usize>,
+}
+
+impl UndoJournal {
+    fn is_recording(&self) -> bool {
+        !self.snapshots.is_empty()
+    }
+
+    fn record(&mut self, entry: impl FnOnce() -> JournalEntry) {
+        if self.is_recording() {
+            self.entries.push(entry());
+        }
+    }
+}
+
+/// Sets the key back to its prior value, or removes it if it had none.
+fn restore_value<K: Eq + Hash, V>(map: &mut HashMap<K, V>, key: K, prior_value: Option<V>) {
+    match prior_value {
+        Some(value) => map.insert(key, value),
+        None => map.remove(&key),
+    };
+}
+
 #[derive(Debug, Default, IntoIterator)]
 pub struct StorageView(pub HashMap<StorageEntry, StarkFelt>);
 
@@ -379,7 +713,7 @@ impl From<StorageView> for IndexMap<ContractAddress, IndexMap<StorageKey, StarkF
 /// The tracked changes are needed for block state commitment.
 
 // Invariant: keys cannot be deleted from fields (only used internally by the cached state).
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct StateCache {
     // Reader's cached information; initial values, read before any write operation (per cell).
     nonce_initial_values: HashMap<ContractAddress, Nonce>,
@@ -427,17 +761,21 @@ impl StateCache {
         contract_address: ContractAddress,
         key: StorageKey,
         value: StarkFelt,
-    ) {
+    ) -> Option<StarkFelt> {
         let contract_storage_key = (contract_address, key);
-        self.storage_writes.insert(contract_storage_key, value);
+        self.storage_writes.insert(contract_storage_key, value)
     }
 
     fn set_nonce_initial_value(&mut self, contract_address: ContractAddress, nonce: Nonce) {
         self.nonce_initial_values.insert(contract_address, nonce);
     }
 
-    fn set_nonce_value(&mut self, contract_address: ContractAddress, nonce: Nonce) {
-        self.nonce_writes.insert(contract_address, nonce);
+    fn set_nonce_value(
+        &mut self,
+        contract_address: ContractAddress,
+        nonce: Nonce,
+    ) -> Option<Nonce> {
+        self.nonce_writes.insert(contract_address, nonce)
     }
 
     fn get_class_hash_at(&self, contract_address: ContractAddress) -> Option<&ClassHash> {
@@ -454,8 +792,12 @@ impl StateCache {
         self.class_hash_initial_values.insert(contract_address, class_hash);
     }
 
-    fn set_class_hash_write(&mut self, contract_address: ContractAddress, class_hash: ClassHash) {
-        self.class_hash_writes.insert(contract_address, class_hash);
+    fn set_class_hash_write(
+        &mut self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> Option<ClassHash> {
+        self.class_hash_writes.insert(contract_address, class_hash)
     }
 
     fn get_compiled_class_hash(&self, class_hash: ClassHash) -> Option<&CompiledClassHash> {
@@ -476,8 +818,8 @@ impl StateCache {
         &mut self,
         class_hash: ClassHash,
         compiled_class_hash: CompiledClassHash,
-    ) {
-        self.compiled_class_hash_writes.insert(class_hash, compiled_class_hash);
+    ) -> Option<CompiledClassHash> {
+        self.compiled_class_hash_writes.insert(class_hash, compiled_class_hash)
     }
 
     fn get_storage_updates(&self) -> HashMap<StorageEntry, StarkFelt> {
@@ -535,6 +877,15 @@ impl<'a, S: State + ?Sized> StateReader for MutRefState<'a, S> {
     fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
         self.0.get_compiled_class_hash(class_hash)
     }
+
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        self.0.prefetch(storage_entries, contract_addresses, class_hashes)
+    }
 }
 
 impl<'a, S: State + ?Sized> State for MutRefState<'a, S> {
@@ -596,6 +947,7 @@ impl<'a, S: StateReader> TransactionalState<'a, S> {
             class_hash_to_class,
             global_class_hash_to_class,
             visited_pcs,
+            accesses,
             ..
         } = self;
         StagedTransactionalState {
@@ -606,9 +958,18 @@ impl<'a, S: StateReader> TransactionalState<'a, S> {
             tx_visited_storage_entries,
             tx_unique_state_changes_keys,
             visited_pcs,
+            state_accesses: accesses.into_inner(),
         }
     }
 
+    /// Restores the cache of an execution staged on top of a state holding the same values as the
+    /// parent; e.g., a speculative execution whose observed values were validated against it.
+    /// Snapshots taken before are discarded.
+    pub fn restore_cache(&mut self, cache: StateCache) {
+        *self.cache.get_mut() = cache;
+        self.journal = UndoJournal::default();
+    }
+
     /// Commits changes in the child (wrapping) state to its parent.
     pub fn commit(self) {
         let state = self.state.0;
@@ -619,9 +980,10 @@ impl<'a, S: StateReader> TransactionalState<'a, S> {
             self.global_class_hash_to_class,
         );
         state.update_visited_pcs_cache(&self.visited_pcs);
+        state.update_state_accesses(self.accesses.into_inner());
     }
 
-    /// Drops `self`.
+    /// Drops `self`; its accesses are discarded along with its changes.
     pub fn abort(self) {}
 }
 
@@ -638,6 +1000,7 @@ pub struct StagedTransactionalState {
     pub tx_visited_storage_entries: HashSet<StorageEntry>,
     pub tx_unique_state_changes_keys: StateChangesKeys,
     pub visited_pcs: HashMap<ClassHash, HashSet<usize>>,
+    pub state_accesses: StateAccesses,
 }
 
 /// Holds uncommitted changes induced on Starknet contracts.
@@ -715,14 +1078,43 @@ impl StateChangesKeys {
 }
 
 /// Holds the state changes.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
 pub struct StateChanges {
+    #[serde(serialize_with = "serialize_storage_updates")]
     pub storage_updates: HashMap<StorageEntry, StarkFelt>,
     pub nonce_updates: HashMap<ContractAddress, Nonce>,
     pub class_hash_updates: HashMap<ContractAddress, ClassHash>,
     pub compiled_class_hash_updates: HashMap<ClassHash, CompiledClassHash>,
 }
 
+/// Serializes the storage updates by contract address, as the keys of a serialized map cannot be
+/// tuples.
+fn serialize_storage_updates<S: Serializer>(
+    storage_updates: &HashMap<StorageEntry, StarkFelt>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut storage_updates_by_contract: HashMap<ContractAddress, HashMap<StorageKey, StarkFelt>> =
+        HashMap::new();
+    for (&(contract_address, key), &value) in storage_updates {
+        storage_updates_by_contract.entry(contract_address).or_default().insert(key, value);
+    }
+
+    storage_updates_by_contract.serialize(serializer)
+}
+
+impl From<StateChanges> for CommitmentStateDiff {
+    fn from(state_changes: StateChanges) -> Self {
+        Self {
+            address_to_class_hash: IndexMap::from_iter(state_changes.class_hash_updates),
+            address_to_nonce: IndexMap::from_iter(state_changes.nonce_updates),
+            storage_updates: IndexMap::from(StorageView(state_changes.storage_updates)),
+            class_hash_to_compiled_class_hash: IndexMap::from_iter(
+                state_changes.compiled_class_hash_updates,
+            ),
+        }
+    }
+}
+
 impl StateChanges {
     /// Merges the given state changes into a single one. Note that the order of the state changes
     /// is important. The state changes are merged in the order they appear in the given vector.
@@ -796,6 +1188,121 @@ impl StateChanges {
     }
 }
 
+/// The keys read and written through a `CachedState`, and the number of reads served by its
+/// caches versus by the underlying state reader. Used, e.g., for conflict analysis between
+/// transactions and for warming the caches of a replica.
+///
+/// Note: unlike `StateChanges`, writes of the initial value are included.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateAccesses {
+    // Read set.
+    pub storage_reads: HashSet<StorageEntry>,
+    pub nonce_reads: HashSet<ContractAddress>,
+    pub class_hash_reads: HashSet<ContractAddress>,
+    pub compiled_class_hash_reads: HashSet<ClassHash>,
+    pub class_reads: HashSet<ClassHash>,
+
+    // Write set.
+    pub storage_writes: HashSet<StorageEntry>,
+    pub nonce_writes: HashSet<ContractAddress>,
+    pub class_hash_writes: HashSet<ContractAddress>,
+    pub compiled_class_hash_writes: HashSet<ClassHash>,
+    pub class_writes: HashSet<ClassHash>,
+
+    /// Reads served by the cache of the state, or by the global contract cache.
+    pub n_cache_hits: usize,
+    /// Reads served by the underlying state reader.
+    pub n_state_reader_hits: usize,
+}
+
+/// A key in the write set of `StateAccesses`.
+#[derive(Clone, Copy, Debug)]
+enum WriteAccess {
+    Storage(StorageEntry),
+    Nonce(ContractAddress),
+    ClassHash(ContractAddress),
+    CompiledClassHash(ClassHash),
+    Class(ClassHash),
+}
+
+impl StateAccesses {
+    /// Adds the key to the write set; returns whether it was not there already.
+    fn insert_write(&mut self, write_access: WriteAccess) -> bool {
+        match write_access {
+            WriteAccess::Storage(storage_entry) => self.storage_writes.insert(storage_entry),
+            WriteAccess::Nonce(contract_address) => self.nonce_writes.insert(contract_address),
+            WriteAccess::ClassHash(contract_address) => {
+                self.class_hash_writes.insert(contract_address)
+            }
+            WriteAccess::CompiledClassHash(class_hash) => {
+                self.compiled_class_hash_writes.insert(class_hash)
+            }
+            WriteAccess::Class(class_hash) => self.class_writes.insert(class_hash),
+        }
+    }
+
+    fn remove_write(&mut self, write_access: WriteAccess) {
+        match write_access {
+            WriteAccess::Storage(storage_entry) => self.storage_writes.remove(&storage_entry),
+            WriteAccess::Nonce(contract_address) => self.nonce_writes.remove(&contract_address),
+            WriteAccess::ClassHash(contract_address) => {
+                self.class_hash_writes.remove(&contract_address)
+            }
+            WriteAccess::CompiledClassHash(class_hash) => {
+                self.compiled_class_hash_writes.remove(&class_hash)
+            }
+            WriteAccess::Class(class_hash) => self.class_writes.remove(&class_hash),
+        };
+    }
+
+    fn writes(&self) -> impl Iterator<Item = WriteAccess> + '_ {
+        let storage_writes = self.storage_writes.iter().copied().map(WriteAccess::Storage);
+        let nonce_writes = self.nonce_writes.iter().copied().map(WriteAccess::Nonce);
+        let class_hash_writes = self.class_hash_writes.iter().copied().map(WriteAccess::ClassHash);
+        let compiled_class_hash_writes =
+            self.compiled_class_hash_writes.iter().copied().map(WriteAccess::CompiledClassHash);
+        let class_writes = self.class_writes.iter().copied().map(WriteAccess::Class);
+        storage_writes
+            .chain(nonce_writes)
+            .chain(class_hash_writes)
+            .chain(compiled_class_hash_writes)
+            .chain(class_writes)
+    }
+
+    fn count_read(&mut self, is_cache_hit: bool) {
+        if is_cache_hit {
+            self.n_cache_hits += 1;
+        } else {
+            self.n_state_reader_hits += 1;
+        }
+    }
+
+    pub fn extend(&mut self, other: &Self) {
+        self.storage_reads.extend(&other.storage_reads);
+        self.nonce_reads.extend(&other.nonce_reads);
+        self.class_hash_reads.extend(&other.class_hash_reads);
+        self.compiled_class_hash_reads.extend(&other.compiled_class_hash_reads);
+        self.class_reads.extend(&other.class_reads);
+        self.storage_writes.extend(&other.storage_writes);
+        self.nonce_writes.extend(&other.nonce_writes);
+        self.class_hash_writes.extend(&other.class_hash_writes);
+        self.compiled_class_hash_writes.extend(&other.compiled_class_hash_writes);
+        self.class_writes.extend(&other.class_writes);
+        self.n_cache_hits += other.n_cache_hits;
+        self.n_state_reader_hits += other.n_state_reader_hits;
+    }
+
+    /// Returns the number of distinct storage keys read or written, per contract.
+    pub fn n_storage_keys_by_contract(&self) -> HashMap<ContractAddress, usize> {
+        let mut n_storage_keys_by_contract = HashMap::new();
+        for (contract_address, _) in self.storage_reads.union(&self.storage_writes) {
+            *n_storage_keys_by_contract.entry(*contract_address).or_default() += 1;
+        }
+
+        n_storage_keys_by_contract
+    }
+}
+
 /// Holds the number of state changes.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct StateChangesCount {
@@ -811,22 +1318,74 @@ type LockedContractClassCache<'a> = MutexGuard<'a, ContractClassLRUCache>;
 #[derive(Debug, Clone)]
 // Thread-safe LRU cache for contract classes, optimized for inter-language sharing when
 // `blockifier` compiles as a shared library.
-pub struct GlobalContractCache(pub Arc<Mutex<ContractClassLRUCache>>);
+pub struct GlobalContractCache {
+    cache: Arc<Mutex<ContractClassLRUCache>>,
+    // An optional second tier, which outlives the process.
+    persistent_cache: Option<Arc<PersistentContractCache>>,
+}
 
 pub const GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST: usize = 100;
 
 impl GlobalContractCache {
-    /// Locks the cache for atomic access. Although conceptually shared, writing to this cache is
-    /// only possible for one writer at a time.
-    pub fn lock(&self) -> LockedContractClassCache<'_> {
-        self.0.lock().expect("Global contract cache is poisoned.")
+    /// Locks the in-memory cache for atomic access. Although conceptually shared, writing to this
+    /// cache is only possible for one writer at a time.
+    /// Private, since writing to the in-memory cache directly would bypass the persistent one.
+    fn lock(&self) -> LockedContractClassCache<'_> {
+        self.cache.lock().expect("Global contract cache is poisoned.")
     }
 
+    /// Clears both tiers; the persistent one is cleared for all processes sharing it.
     pub fn clear(&mut self) {
+        if let Some(persistent_cache) = &self.persistent_cache {
+            if let Err(error) = persistent_cache.clear() {
+                log::warn!("Failed to clear the persistent contract cache: {error}");
+            }
+        }
         self.lock().cache_clear();
     }
 
+    /// The number of classes in memory.
+    pub fn size(&self) -> usize {
+        self.lock().cache_size()
+    }
+
     pub fn new(cache_size: usize) -> Self {
-        Self(Arc::new(Mutex::new(ContractClassLRUCache::with_size(cache_size))))
+        Self {
+            cache: Arc::new(Mutex::new(ContractClassLRUCache::with_size(cache_size))),
+            persistent_cache: None,
+        }
+    }
+
+    /// Backs the in-memory cache by the given persistent one: classes missing from memory are
+    /// looked up on disk, and classes added to the cache are persisted.
+    pub fn with_persistent_cache(mut self, persistent_cache: PersistentContractCache) -> Self {
+        self.persistent_cache = Some(Arc::new(persistent_cache));
+        self
+    }
+
+    pub fn get(&self, class_hash: &ClassHash) -> Option<ContractClass> {
+        if let Some(contract_class) = self.lock().cache_get(class_hash) {
+            return Some(contract_class.clone());
+        }
+
+        // Failing to read from the persistent cache is equivalent to a miss.
+        let contract_class = match self.persistent_cache.as_ref()?.get(*class_hash) {
+            Ok(contract_class) => contract_class?,
+            Err(error) => {
+                log::warn!("Failed to read class {class_hash} from the persistent cache: {error}");
+                return None;
+            }
+        };
+        self.lock().cache_set(*class_hash, contract_class.clone());
+        Some(contract_class)
+    }
+
+    pub fn set(&self, class_hash: ClassHash, contract_class: ContractClass) {
+        if let Some(persistent_cache) = &self.persistent_cache {
+            if let Err(error) = persistent_cache.set(class_hash, &contract_class) {
+                log::warn!("Failed to write class {class_hash} to the persistent cache: {error}");
+            }
+        }
+        self.lock().cache_set(class_hash, contract_class);
     }
 }
@@ -1,11 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use assert_matches::assert_matches;
 use indexmap::indexmap;
 use pretty_assertions::assert_eq;
 use rstest::rstest;
-use starknet_api::core::PatriciaKey;
+use starknet_api::core::{Nonce, PatriciaKey};
 use starknet_api::hash::StarkHash;
+use starknet_api::state::StorageKey;
 use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
 
 use crate::context::{BlockContext, ChainInfo};
@@ -380,6 +381,30 @@ fn test_state_changes_merge(
     );
 }
 
+#[test]
+fn test_state_changes_serialization() {
+    let contract_address = contract_address!("0x100");
+    let state_changes = StateChanges {
+        storage_updates: HashMap::from([
+            ((contract_address, StorageKey(patricia_key!("0x10"))), stark_felt!("0x5")),
+            ((contract_address, StorageKey(patricia_key!("0x11"))), stark_felt!("0x6")),
+        ]),
+        nonce_updates: HashMap::from([(contract_address, Nonce(stark_felt!("0x1")))]),
+        ..Default::default()
+    };
+
+    // Storage updates are grouped by contract, as the keys of a serialized map must be strings.
+    assert_eq!(
+        serde_json::to_value(state_changes).unwrap(),
+        serde_json::json!({
+            "storage_updates": {"0x100": {"0x10": "0x5", "0x11": "0x6"}},
+            "nonce_updates": {"0x100": "0x1"},
+            "class_hash_updates": {},
+            "compiled_class_hash_updates": {},
+        })
+    );
+}
+
 #[test]
 fn global_contract_cache_is_used() {
     // Initialize the global cache with a single class, and initialize an empty state with this
@@ -388,8 +413,8 @@ fn global_contract_cache_is_used() {
     let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
     let class_hash = test_contract.get_class_hash();
     let contract_class = test_contract.get_class();
-    global_cache.lock().cache_set(class_hash, contract_class.clone());
-    assert_eq!(global_cache.lock().cache_size(), 1);
+    global_cache.set(class_hash, contract_class.clone());
+    assert_eq!(global_cache.size(), 1);
     let state = CachedState::new(DictStateReader::default(), global_cache.clone());
 
     // Assert local cache is initialized empty even if global cache is not empty.
@@ -398,14 +423,14 @@ fn global_contract_cache_is_used() {
     // Check state uses the global cache.
     assert_eq!(state.get_compiled_contract_class(class_hash).unwrap(), contract_class);
     assert_eq!(global_cache.lock().cache_hits().unwrap(), 1);
-    assert_eq!(global_cache.lock().cache_size(), 1);
+    assert_eq!(global_cache.size(), 1);
     // Verify local cache is also updated.
     assert_eq!(state.class_hash_to_class.borrow().get(&class_hash).unwrap(), &contract_class);
 
     // Idempotency: getting the same class again uses the local cache.
     assert_eq!(state.get_compiled_contract_class(class_hash).unwrap(), contract_class);
     assert_eq!(global_cache.lock().cache_hits().unwrap(), 1);
-    assert_eq!(global_cache.lock().cache_size(), 1);
+    assert_eq!(global_cache.size(), 1);
 }
 
 #[test]
@@ -558,3 +583,218 @@ fn test_state_changes_keys() {
         }
     )
 }
+
+#[test]
+fn test_snapshot_and_revert() {
+    let contract_address = contract_address!(CONTRACT_ADDRESS);
+    let key = StorageKey(patricia_key!("0x10"));
+    let class_hash = class_hash!("0x20");
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x30"));
+    let mut state: CachedState<DictStateReader> = CachedState::default();
+
+    state.set_storage_at(contract_address, key, stark_felt!("0x1")).unwrap();
+    let snapshot_id = state.snapshot();
+
+    // Change every kind of tracked data after the snapshot.
+    state.set_storage_at(contract_address, key, stark_felt!("0x2")).unwrap();
+    state.increment_nonce(contract_address).unwrap();
+    state.set_class_hash_at(contract_address, class_hash).unwrap();
+    state.set_compiled_class_hash(class_hash, compiled_class_hash).unwrap();
+    let contract_class = FeatureContract::TestContract(CairoVersion::Cairo0).get_class();
+    state.set_contract_class(class_hash, contract_class).unwrap();
+    state.add_visited_pcs(class_hash, &HashSet::from([0, 1]));
+    let later_snapshot_id = state.snapshot();
+
+    state.revert_to(snapshot_id).unwrap();
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x1"));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce::default());
+    assert_eq!(state.get_class_hash_at(contract_address).unwrap(), ClassHash::default());
+    assert_eq!(state.get_compiled_class_hash(class_hash).unwrap(), CompiledClassHash::default());
+    assert_matches!(
+        state.get_compiled_contract_class(class_hash),
+        Err(StateError::UndeclaredClassHash(undeclared)) if undeclared == class_hash
+    );
+    assert!(state.visited_pcs.is_empty());
+    // Writes are removed from the write set, unless done before the snapshot.
+    let accesses = state.get_state_accesses();
+    assert_eq!(accesses.storage_writes, HashSet::from([(contract_address, key)]));
+    assert!(accesses.nonce_writes.is_empty());
+    assert!(accesses.class_hash_writes.is_empty());
+    assert!(accesses.compiled_class_hash_writes.is_empty());
+    assert!(accesses.class_writes.is_empty());
+
+    // Snapshots taken after the restored one are discarded.
+    assert_matches!(
+        state.revert_to(later_snapshot_id),
+        Err(StateError::UnknownSnapshot(unknown)) if unknown == later_snapshot_id
+    );
+
+    // The restored snapshot remains valid.
+    state.set_storage_at(contract_address, key, stark_felt!("0x3")).unwrap();
+    state.revert_to(snapshot_id).unwrap();
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x1"));
+}
+
+#[test]
+fn test_revert_nested_snapshots() {
+    let contract_address = contract_address!(CONTRACT_ADDRESS);
+    let key = StorageKey(patricia_key!("0x10"));
+    let class_hash = class_hash!("0x20");
+    let mut state: CachedState<DictStateReader> = CachedState::default();
+
+    let outer_snapshot_id = state.snapshot();
+    state.set_storage_at(contract_address, key, stark_felt!("0x1")).unwrap();
+    state.add_visited_pcs(class_hash, &HashSet::from([0]));
+    let inner_snapshot_id = state.snapshot();
+
+    // Changes committed by a transactional state on top are reverted as well.
+    let mut transactional_state = CachedState::create_transactional(&mut state);
+    transactional_state.set_storage_at(contract_address, key, stark_felt!("0x2")).unwrap();
+    transactional_state.increment_nonce(contract_address).unwrap();
+    transactional_state.add_visited_pcs(class_hash, &HashSet::from([0, 1]));
+    transactional_state.commit();
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x2"));
+
+    state.revert_to(inner_snapshot_id).unwrap();
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x1"));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce::default());
+    assert_eq!(state.visited_pcs, HashMap::from([(class_hash, HashSet::from([0]))]));
+    assert_eq!(state.get_state_accesses().storage_writes, HashSet::from([(contract_address, key)]));
+    assert!(state.get_state_accesses().nonce_writes.is_empty());
+
+    state.revert_to(outer_snapshot_id).unwrap();
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), StarkFelt::ZERO);
+    assert!(state.visited_pcs.is_empty());
+    assert!(state.get_state_accesses().storage_writes.is_empty());
+}
+
+#[test]
+fn test_prefetch() {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let written_key = StorageKey(patricia_key!("0x11"));
+    let class_hash = class_hash!("0x20");
+    let undeclared_class_hash = class_hash!("0x21");
+    let contract_class = FeatureContract::TestContract(CairoVersion::Cairo0).get_class();
+    let mut state = CachedState::from(DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, key), stark_felt!("0x1")),
+            ((contract_address, written_key), stark_felt!("0x1")),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!("0x2")))]),
+        address_to_class_hash: HashMap::from([(contract_address, class_hash)]),
+        class_hash_to_class: HashMap::from([(class_hash, contract_class.clone())]),
+        ..Default::default()
+    });
+    state.set_storage_at(contract_address, written_key, stark_felt!("0x3")).unwrap();
+
+    // Cached values take precedence over the underlying ones, and undeclared classes are omitted.
+    let prefetched_state = state
+        .prefetch(
+            &[(contract_address, key), (contract_address, written_key)],
+            &[contract_address],
+            &[class_hash, undeclared_class_hash],
+        )
+        .unwrap();
+    assert_eq!(
+        prefetched_state,
+        PrefetchedState {
+            storage: HashMap::from([
+                ((contract_address, key), stark_felt!("0x1")),
+                ((contract_address, written_key), stark_felt!("0x3")),
+            ]),
+            nonces: HashMap::from([(contract_address, Nonce(stark_felt!("0x2")))]),
+            class_hashes: HashMap::from([(contract_address, class_hash)]),
+            contract_classes: HashMap::from([(class_hash, contract_class)]),
+        }
+    );
+
+    // The values are cached as initial values.
+    let cache = state.cache.borrow();
+    assert_eq!(
+        cache.storage_initial_values.get(&(contract_address, key)),
+        Some(&stark_felt!("0x1"))
+    );
+    assert_eq!(cache.storage_initial_values.get(&(contract_address, written_key)), None);
+    assert_eq!(cache.nonce_initial_values.get(&contract_address), Some(&Nonce(stark_felt!("0x2"))));
+    assert_eq!(cache.class_hash_initial_values.get(&contract_address), Some(&class_hash));
+    assert!(state.class_hash_to_class.borrow().contains_key(&class_hash));
+    drop(cache);
+
+    // Each loaded value counts as a state reader hit, and later reads of it as cache hits.
+    let accesses = state.get_state_accesses();
+    assert_eq!(accesses.n_state_reader_hits, 4);
+    assert_eq!(accesses.n_cache_hits, 0);
+    assert!(accesses.storage_reads.is_empty());
+    state.get_storage_at(contract_address, key).unwrap();
+    state.get_compiled_contract_class(class_hash).unwrap();
+    let accesses = state.get_state_accesses();
+    assert_eq!(accesses.n_state_reader_hits, 4);
+    assert_eq!(accesses.n_cache_hits, 2);
+}
+
+#[test]
+fn test_state_accesses() {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let written_key = StorageKey(patricia_key!("0x11"));
+    let class_hash = class_hash!("0x20");
+    let contract_class = FeatureContract::TestContract(CairoVersion::Cairo0).get_class();
+    let mut state = CachedState::from(DictStateReader {
+        address_to_class_hash: HashMap::from([(contract_address, class_hash)]),
+        class_hash_to_class: HashMap::from([(class_hash, contract_class)]),
+        ..Default::default()
+    });
+
+    // The first read of a value is served by the state reader, and the next ones by the cache.
+    state.get_storage_at(contract_address, key).unwrap();
+    state.get_storage_at(contract_address, key).unwrap();
+    state.get_class_hash_at(contract_address).unwrap();
+
+    let mut transactional_state = CachedState::create_transactional(&mut state);
+    transactional_state.get_storage_at(contract_address, key).unwrap();
+    transactional_state.get_storage_at(contract_address, key).unwrap();
+    transactional_state.get_compiled_contract_class(class_hash).unwrap();
+    transactional_state.set_storage_at(contract_address, written_key, stark_felt!("0x1")).unwrap();
+    transactional_state.increment_nonce(contract_address).unwrap();
+    let tx_accesses = transactional_state.get_state_accesses();
+    assert_eq!(
+        tx_accesses,
+        StateAccesses {
+            storage_reads: HashSet::from([(contract_address, key)]),
+            nonce_reads: HashSet::from([contract_address]),
+            class_reads: HashSet::from([class_hash]),
+            storage_writes: HashSet::from([(contract_address, written_key)]),
+            nonce_writes: HashSet::from([contract_address]),
+            n_cache_hits: 1,
+            n_state_reader_hits: 3,
+            ..Default::default()
+        }
+    );
+    assert_eq!(tx_accesses.n_storage_keys_by_contract(), HashMap::from([(contract_address, 2)]));
+
+    // Upon commit, the accesses are merged; the reads the transactional state delegated to the
+    // parent state were already counted by it.
+    transactional_state.commit();
+    let block_accesses = state.get_state_accesses();
+    assert_eq!(
+        block_accesses,
+        StateAccesses {
+            class_hash_reads: HashSet::from([contract_address]),
+            n_cache_hits: 3,
+            n_state_reader_hits: 4,
+            ..tx_accesses
+        }
+    );
+
+    // Untracked reads are not recorded; only the values they load count as state reader hits.
+    let untracked_key = StorageKey(patricia_key!("0x12"));
+    state.read_untracked(|state| {
+        state.get_storage_at(contract_address, key).unwrap();
+        state.get_storage_at(contract_address, untracked_key).unwrap();
+    });
+    assert_eq!(
+        state.get_state_accesses(),
+        StateAccesses { n_state_reader_hits: 5, ..block_accesses }
+    );
+}
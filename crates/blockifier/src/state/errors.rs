@@ -4,6 +4,7 @@ use starknet_api::StarknetApiError;
 use thiserror::Error;
 
 use crate::abi::constants;
+use crate::state::cached_state::SnapshotId;
 
 #[derive(Debug, Error)]
 pub enum StateError {
@@ -20,6 +21,8 @@ pub enum StateError {
     UnavailableContractAddress(ContractAddress),
     #[error("Class with hash {0} is not declared.")]
     UndeclaredClassHash(ClassHash),
+    #[error("Unknown state snapshot: {0:?}.")]
+    UnknownSnapshot(SnapshotId),
     #[error(transparent)]
     StarknetApiError(#[from] StarknetApiError),
     /// Represents all unexpected errors that may occur while reading from state.
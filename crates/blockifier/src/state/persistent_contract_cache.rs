@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use cairo_lang_casm::hints::Hint;
+use cairo_lang_starknet_classes::NestedIntList;
+use cairo_vm::serde::deserialize_program::HintParams;
+use cairo_vm::types::errors::program_errors::ProgramError;
+use cairo_vm::types::program::Program;
+use cairo_vm::types::relocatable::MaybeRelocatable;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::ClassHash;
+use starknet_api::deprecated_contract_class::{EntryPoint, EntryPointType};
+use starknet_api::hash::StarkFelt;
+use thiserror::Error;
+
+use crate::execution::contract_class::{
+    ContractClass, ContractClassV0, ContractClassV0Inner, ContractClassV1, EntryPointV1,
+};
+use crate::execution::execution_utils::{felt_to_stark_felt, stark_felt_to_felt};
+
+#[cfg(test)]
+#[path = "persistent_contract_cache_test.rs"]
+pub mod test;
+
+/// The version of the on-disk format; must be bumped whenever the persisted form changes.
+pub const PERSISTENT_CONTRACT_CACHE_FORMAT_VERSION: u32 = 2;
+const VERSION_DIR_PREFIX: &str = "contract_classes_v";
+const ENTRY_EXTENSION: &str = "cbor";
+
+#[derive(Debug, Error)]
+pub enum PersistentContractCacheError {
+    #[error("Failed to decode persisted contract class: {0}")]
+    Decode(#[from] ciborium::de::Error<io::Error>),
+    #[error("Failed to encode contract class: {0}")]
+    Encode(#[from] ciborium::ser::Error<io::Error>),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Cairo 1 bytecode must consist of field elements.")]
+    NonFeltBytecode,
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+}
+
+pub type PersistentContractCacheResult<T> = Result<T, PersistentContractCacheError>;
+
+/// The persisted form of a contract class.
+#[derive(Debug, Deserialize, Serialize)]
+enum PersistedContractClass {
+    V0(PersistedContractClassV0),
+    V1(PersistedContractClassV1),
+}
+
+impl TryFrom<&ContractClass> for PersistedContractClass {
+    type Error = PersistentContractCacheError;
+
+    fn try_from(class: &ContractClass) -> Result<Self, Self::Error> {
+        Ok(match class {
+            ContractClass::V0(class) => Self::V0(class.try_into()?),
+            ContractClass::V1(class) => Self::V1(class.try_into()?),
+        })
+    }
+}
+
+impl TryFrom<PersistedContractClass> for ContractClass {
+    type Error = ProgramError;
+
+    fn try_from(class: PersistedContractClass) -> Result<Self, Self::Error> {
+        Ok(match class {
+            PersistedContractClass::V0(class) => ContractClassV0::try_from(class)?.into(),
+            PersistedContractClass::V1(class) => ContractClassV1::try_from(class)?.into(),
+        })
+    }
+}
+
+/// The persisted form of a Cairo 0 contract class: its VM program, serialized by the VM, rather
+/// than the compiled class, whose hex-encoded data and hints are costly to convert.
+#[derive(Debug, Deserialize, Serialize)]
+struct PersistedContractClassV0 {
+    program: String,
+    entry_points_by_type: HashMap<EntryPointType, Vec<EntryPoint>>,
+}
+
+impl TryFrom<&ContractClassV0> for PersistedContractClassV0 {
+    type Error = PersistentContractCacheError;
+
+    fn try_from(class: &ContractClassV0) -> Result<Self, Self::Error> {
+        let program = String::from_utf8(class.program.serialize()?)
+            .expect("A serialized program is JSON, hence UTF-8.");
+        Ok(Self { program, entry_points_by_type: class.entry_points_by_type.clone() })
+    }
+}
+
+impl TryFrom<PersistedContractClassV0> for ContractClassV0 {
+    type Error = ProgramError;
+
+    fn try_from(class: PersistedContractClassV0) -> Result<Self, Self::Error> {
+        Ok(Self(Arc::new(ContractClassV0Inner {
+            program: Program::deserialize(class.program.as_bytes(), None)?,
+            entry_points_by_type: class.entry_points_by_type,
+        })))
+    }
+}
+
+/// The persisted form of a Cairo 1 contract class: the converted parts from which its program is
+/// built, so that loading it neither parses CASM JSON nor converts hints.
+#[derive(Debug, Deserialize, Serialize)]
+struct PersistedContractClassV1 {
+    bytecode: Vec<StarkFelt>,
+    hint_params_by_pc: HashMap<usize, Vec<HintParams>>,
+    hints: HashMap<String, Hint>,
+    entry_points_by_type: HashMap<EntryPointType, Vec<EntryPointV1>>,
+    bytecode_segment_lengths: NestedIntList,
+}
+
+/// The hints of a program serialized by the VM, which are otherwise inaccessible.
+#[derive(Deserialize)]
+struct SerializedProgramHints {
+    hints: HashMap<usize, Vec<HintParams>>,
+}
+
+impl TryFrom<&ContractClassV1> for PersistedContractClassV1 {
+    type Error = PersistentContractCacheError;
+
+    fn try_from(class: &ContractClassV1) -> Result<Self, Self::Error> {
+        let bytecode = class
+            .program
+            .iter_data()
+            .map(|value| match value {
+                MaybeRelocatable::Int(felt) => Ok(felt_to_stark_felt(felt)),
+                MaybeRelocatable::RelocatableValue(_) => {
+                    Err(PersistentContractCacheError::NonFeltBytecode)
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        let SerializedProgramHints { hints: hint_params_by_pc } =
+            serde_json::from_slice(&class.program.serialize()?).map_err(ProgramError::from)?;
+
+        Ok(Self {
+            bytecode,
+            hint_params_by_pc,
+            hints: class.hints.clone(),
+            entry_points_by_type: class.entry_points_by_type.clone(),
+            bytecode_segment_lengths: class.bytecode_segment_lengths().clone(),
+        })
+    }
+}
+
+impl TryFrom<PersistedContractClassV1> for ContractClassV1 {
+    type Error = ProgramError;
+
+    fn try_from(class: PersistedContractClassV1) -> Result<Self, Self::Error> {
+        ContractClassV1::from_parts(
+            class.bytecode.into_iter().map(stark_felt_to_felt).collect(),
+            class.hint_params_by_pc,
+            class.hints,
+            class.entry_points_by_type,
+            Some(class.bytecode_segment_lengths),
+        )
+    }
+}
+
+/// A size-bounded cache of contract classes in a local directory, which outlives the process;
+/// serves as the second tier of the global contract cache.
+///
+/// Classes are persisted in CBOR, in their converted form, so that loading them is cheaper than
+/// converting compiled classes.
+/// Entries are keyed by class hash, in a subdirectory named by the format version and the
+/// blockifier version. When the size bound is exceeded, the oldest entries are evicted; the sizes
+/// are indexed in memory when the cache is opened, so the bound only accounts for the entries
+/// written by other processes sharing the directory once they are read.
+#[derive(Debug)]
+pub struct PersistentContractCache {
+    dir: PathBuf,
+    max_size_in_bytes: u64,
+    index: Mutex<EntryIndex>,
+}
+
+/// The sizes of the entries, oldest first.
+#[derive(Debug, Default)]
+struct EntryIndex {
+    entry_sizes: IndexMap<PathBuf, u64>,
+    total_size: u64,
+}
+
+impl EntryIndex {
+    fn insert(&mut self, path: PathBuf, size: u64) {
+        let prior_size = self.entry_sizes.insert(path, size).unwrap_or_default();
+        self.total_size = self.total_size - prior_size + size;
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(size) = self.entry_sizes.shift_remove(path) {
+            self.total_size -= size;
+        }
+    }
+}
+
+impl PersistentContractCache {
+    /// Opens the cache under the given directory. Entries of other versions are kept, as other
+    /// processes may still use them; see `remove_stale_versions`.
+    pub fn new(base_dir: impl AsRef<Path>, max_size_in_bytes: u64) -> io::Result<Self> {
+        let dir = base_dir.as_ref().join(Self::version_dir_name());
+        fs::create_dir_all(&dir)?;
+
+        let mut entries = vec![];
+        for path in entry_paths(&dir)? {
+            // Entries may be concurrently evicted by another process.
+            match fs::metadata(&path) {
+                Ok(metadata) => entries.push((metadata.modified()?, path, metadata.len())),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        entries.sort();
+        let mut index = EntryIndex::default();
+        for (_, path, size) in entries {
+            index.insert(path, size);
+        }
+
+        Ok(Self { dir, max_size_in_bytes, index: Mutex::new(index) })
+    }
+
+    /// Removes the entries of other versions, which are never read by this one.
+    pub fn remove_stale_versions(&self) -> io::Result<()> {
+        let base_dir = self.dir.parent().expect("The cache directory is under the base directory.");
+        let version_dir_name = Self::version_dir_name();
+        for entry in fs::read_dir(base_dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if entry.file_type()?.is_dir()
+                && file_name.starts_with(VERSION_DIR_PREFIX)
+                && file_name != version_dir_name
+            {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes all entries of this version.
+    pub fn clear(&self) -> io::Result<()> {
+        let mut index = self.lock_index();
+        for path in entry_paths(&self.dir)? {
+            remove_entry(&path)?;
+        }
+        *index = EntryIndex::default();
+
+        Ok(())
+    }
+
+    /// Returns the persisted class of the given hash, if any. Entries that cannot be read are
+    /// removed.
+    pub fn get(
+        &self,
+        class_hash: ClassHash,
+    ) -> PersistentContractCacheResult<Option<ContractClass>> {
+        let path = self.entry_path(class_hash);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let size = file.metadata()?.len();
+
+        let contract_class =
+            ciborium::from_reader::<PersistedContractClass, _>(BufReader::new(file))
+                .map_err(PersistentContractCacheError::from)
+                .and_then(|class| Ok(ContractClass::try_from(class)?));
+        let mut index = self.lock_index();
+        match contract_class {
+            Ok(contract_class) => {
+                // The entry may have been written by another process.
+                if !index.entry_sizes.contains_key(&path) {
+                    index.insert(path, size);
+                }
+                Ok(Some(contract_class))
+            }
+            Err(error) => {
+                remove_entry(&path)?;
+                index.remove(&path);
+                Err(error)
+            }
+        }
+    }
+
+    /// Persists the given class, unless it is already persisted.
+    pub fn set(
+        &self,
+        class_hash: ClassHash,
+        contract_class: &ContractClass,
+    ) -> PersistentContractCacheResult<()> {
+        let path = self.entry_path(class_hash);
+        if path.exists() {
+            return Ok(());
+        }
+
+        // Write to a temporary file that is then renamed, so that an entry is never observed
+        // partially written; e.g., by another process sharing the directory.
+        let persisted_class = PersistedContractClass::try_from(contract_class)?;
+        let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        ciborium::into_writer(&persisted_class, &mut writer)?;
+        writer.flush()?;
+        let size = writer.get_ref().metadata()?.len();
+        drop(writer);
+        fs::rename(&tmp_path, &path)?;
+
+        let mut index = self.lock_index();
+        index.insert(path.clone(), size);
+        self.evict(&mut index, &path)
+    }
+
+    fn version_dir_name() -> String {
+        format!(
+            "{VERSION_DIR_PREFIX}{PERSISTENT_CONTRACT_CACHE_FORMAT_VERSION}-{}",
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+
+    fn entry_path(&self, class_hash: ClassHash) -> PathBuf {
+        self.dir.join(format!("{}.{ENTRY_EXTENSION}", class_hash.0))
+    }
+
+    fn lock_index(&self) -> MutexGuard<'_, EntryIndex> {
+        self.index.lock().expect("Persistent contract cache index is poisoned.")
+    }
+
+    /// Removes the oldest entries, other than the given one, until the size bound is respected.
+    fn evict(
+        &self,
+        index: &mut EntryIndex,
+        new_entry_path: &Path,
+    ) -> PersistentContractCacheResult<()> {
+        while index.total_size > self.max_size_in_bytes {
+            let Some(oldest_path) =
+                index.entry_sizes.keys().find(|path| *path != new_entry_path).cloned()
+            else {
+                break;
+            };
+            remove_entry(&oldest_path)?;
+            index.remove(&oldest_path);
+        }
+
+        Ok(())
+    }
+}
+
+/// The paths of the (complete) entries in the given directory.
+fn entry_paths(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() == Some(OsStr::new(ENTRY_EXTENSION)) {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+fn remove_entry(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
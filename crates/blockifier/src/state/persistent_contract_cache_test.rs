@@ -0,0 +1,100 @@
+use std::fs;
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::class_hash;
+use starknet_api::core::ClassHash;
+use starknet_api::hash::StarkHash;
+use tempfile::TempDir;
+
+use crate::state::cached_state::{GlobalContractCache, GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST};
+use crate::state::persistent_contract_cache::{
+    PersistentContractCache, PersistentContractCacheError, VERSION_DIR_PREFIX,
+};
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::CairoVersion;
+
+const MAX_SIZE_FOR_TEST: u64 = 1 << 30;
+
+#[test]
+fn test_persistent_contract_cache() {
+    let base_dir = TempDir::new().unwrap();
+    let cairo0_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let cairo1_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let cache = PersistentContractCache::new(&base_dir, MAX_SIZE_FOR_TEST).unwrap();
+    for contract in [cairo0_contract, cairo1_contract] {
+        cache.set(contract.get_class_hash(), &contract.get_class()).unwrap();
+    }
+
+    // Persisted classes of both Cairo versions are read by other instances.
+    let cache = PersistentContractCache::new(&base_dir, MAX_SIZE_FOR_TEST).unwrap();
+    for contract in [cairo0_contract, cairo1_contract] {
+        assert_eq!(cache.get(contract.get_class_hash()).unwrap(), Some(contract.get_class()));
+    }
+
+    // Unreadable entries are removed.
+    let entry_path = cache.entry_path(cairo1_contract.get_class_hash());
+    fs::write(&entry_path, b"Not CBOR.").unwrap();
+    assert_matches!(
+        cache.get(cairo1_contract.get_class_hash()),
+        Err(PersistentContractCacheError::Decode(_))
+    );
+    assert!(!entry_path.exists());
+}
+
+#[test]
+fn test_persistent_contract_cache_versions() {
+    let base_dir = TempDir::new().unwrap();
+    let stale_dir = base_dir.path().join(format!("{VERSION_DIR_PREFIX}0-0.0.0"));
+    let unrelated_dir = base_dir.path().join("unrelated");
+    fs::create_dir(&stale_dir).unwrap();
+    fs::create_dir(&unrelated_dir).unwrap();
+
+    // Other versions are only removed on request.
+    let cache = PersistentContractCache::new(&base_dir, MAX_SIZE_FOR_TEST).unwrap();
+    assert!(stale_dir.exists());
+    cache.remove_stale_versions().unwrap();
+    assert!(!stale_dir.exists());
+    assert!(unrelated_dir.exists());
+}
+
+#[test]
+fn test_persistent_contract_cache_eviction() {
+    let base_dir = TempDir::new().unwrap();
+    let contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let contract_class = contract.get_class();
+    let cache = PersistentContractCache::new(&base_dir, MAX_SIZE_FOR_TEST).unwrap();
+    cache.set(contract.get_class_hash(), &contract_class).unwrap();
+    let entry_size = fs::metadata(cache.entry_path(contract.get_class_hash())).unwrap().len();
+
+    // The bound allows a single entry; the oldest is evicted.
+    let cache = PersistentContractCache::new(&base_dir, entry_size).unwrap();
+    let other_class_hash = class_hash!("0x1234");
+    cache.set(other_class_hash, &contract_class).unwrap();
+    assert_eq!(cache.get(contract.get_class_hash()).unwrap(), None);
+    assert_eq!(cache.get(other_class_hash).unwrap(), Some(contract_class));
+}
+
+#[test]
+fn test_global_contract_cache_with_persistent_tier() {
+    let base_dir = TempDir::new().unwrap();
+    let contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let global_contract_cache = || {
+        GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST).with_persistent_cache(
+            PersistentContractCache::new(&base_dir, MAX_SIZE_FOR_TEST).unwrap(),
+        )
+    };
+    global_contract_cache().set(contract.get_class_hash(), contract.get_class());
+
+    // A fresh global cache, e.g., of a restarted process, loads the class from disk.
+    let global_contract_cache = global_contract_cache();
+    assert_eq!(global_contract_cache.size(), 0);
+    assert_eq!(global_contract_cache.get(&contract.get_class_hash()), Some(contract.get_class()));
+    assert_eq!(global_contract_cache.size(), 1);
+
+    // Clearing the global cache clears both tiers.
+    let mut global_contract_cache = global_contract_cache;
+    global_contract_cache.clear();
+    assert_eq!(global_contract_cache.size(), 0);
+    assert_eq!(global_contract_cache.get(&contract.get_class_hash()), None);
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use base64::Engine;
+use cairo_lang_starknet_classes::casm_contract_class::{
+    CasmContractClass, StarknetSierraCompilationError,
+};
+use cairo_lang_starknet_classes::contract_class::ContractClass as SierraContractClass;
+use cairo_vm::types::errors::program_errors::ProgramError;
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass, EntryPoint, EntryPointType,
+    Program as DeprecatedProgram,
+};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use thiserror::Error;
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use crate::execution::execution_utils::felt_to_stark_felt;
+use crate::state::cached_state::StorageEntry;
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "rpc_state_reader_test.rs"]
+pub mod test;
+
+// JSON-RPC error codes of the Starknet specification.
+const CONTRACT_NOT_FOUND_ERROR_CODE: i64 = 20;
+const CLASS_HASH_NOT_FOUND_ERROR_CODE: i64 = 28;
+
+#[derive(Debug, Error)]
+pub enum RpcStateReaderError {
+    #[error(transparent)]
+    CompilationError(#[from] StarknetSierraCompilationError),
+    #[error(transparent)]
+    DecodeError(#[from] base64::DecodeError),
+    #[error("The responses of a JSON-RPC batch do not match its requests.")]
+    InvalidBatchResponse,
+    #[error("JSON-RPC error {code}: {message}")]
+    JsonRpcError { code: i64, message: String },
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+    #[error(transparent)]
+    TransportError(#[from] Box<ureq::Error>),
+}
+
+pub type RpcStateReaderResult<T> = Result<T, RpcStateReaderError>;
+
+impl From<RpcStateReaderError> for StateError {
+    fn from(error: RpcStateReaderError) -> Self {
+        StateError::StateReadError(error.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    #[serde(default)]
+    id: usize,
+    result: Option<Value>,
+    error: Option<JsonRpcErrorObject>,
+}
+
+impl JsonRpcResponse {
+    fn into_result<T: DeserializeOwned>(self) -> RpcStateReaderResult<T> {
+        match self.error {
+            Some(JsonRpcErrorObject { code, message }) => {
+                Err(RpcStateReaderError::JsonRpcError { code, message })
+            }
+            None => Ok(serde_json::from_value(self.result.unwrap_or_default())?),
+        }
+    }
+}
+
+/// A key read by a [`RpcStateReader::prefetch`] batch; requested by a single JSON-RPC request.
+#[derive(Clone, Copy, Debug)]
+enum PrefetchKey {
+    Storage(StorageEntry),
+    Nonce(ContractAddress),
+    ClassHash(ContractAddress),
+    ContractClass(ClassHash),
+}
+
+/// A Cairo 0 class, as returned by `starknet_getClass`; the program is gzipped and base64-encoded.
+#[derive(Debug, Deserialize)]
+struct RpcDeprecatedContractClass {
+    program: String,
+    entry_points_by_type: HashMap<EntryPointType, Vec<EntryPoint>>,
+}
+
+/// The values read so far; the state at a pinned block never changes.
+#[derive(Debug, Default)]
+struct RpcStateCache {
+    storage: HashMap<StorageEntry, StarkFelt>,
+    nonces: HashMap<ContractAddress, Nonce>,
+    class_hashes: HashMap<ContractAddress, ClassHash>,
+    contract_classes: HashMap<ClassHash, ContractClass>,
+    compiled_class_hashes: HashMap<ClassHash, CompiledClassHash>,
+}
+
+/// Reads the state at a pinned block from a Starknet JSON-RPC endpoint; e.g., to simulate
+/// transactions on top of a fork of mainnet. Sierra classes are compiled to CASM on read, which
+/// also yields their compiled class hashes.
+/// Note: classes are compiled by the compiler version this crate is built with; for classes
+/// declared with another version, the CASM (and hence the execution resources), as well as the
+/// compiled class hash, may differ from the network's. Supply the network's compiled class hashes
+/// through `with_compiled_class_hashes` where they matter; e.g., for declare transactions.
+/// Every value is requested at most once; prefetched values are requested as a single batch.
+/// Requires the `rpc` feature.
+pub struct RpcStateReader {
+    url: String,
+    block_number: BlockNumber,
+    agent: ureq::Agent,
+    cache: Mutex<RpcStateCache>,
+}
+
+impl RpcStateReader {
+    pub fn new(url: impl Into<String>, block_number: BlockNumber) -> Self {
+        Self {
+            url: url.into(),
+            block_number,
+            agent: ureq::Agent::new(),
+            cache: Mutex::new(RpcStateCache::default()),
+        }
+    }
+
+    /// Uses the given compiled class hashes instead of the ones of the locally compiled classes;
+    /// e.g., the ones of the network, from its state diffs.
+    pub fn with_compiled_class_hashes(
+        self,
+        compiled_class_hashes: HashMap<ClassHash, CompiledClassHash>,
+    ) -> Self {
+        self.cache().compiled_class_hashes.extend(compiled_class_hashes);
+        self
+    }
+
+    fn cache(&self) -> MutexGuard<'_, RpcStateCache> {
+        self.cache.lock().expect("RPC state cache is poisoned.")
+    }
+
+    fn block_id(&self) -> Value {
+        json!({ "block_number": self.block_number.0 })
+    }
+
+    fn post<T: DeserializeOwned>(&self, body: &Value) -> RpcStateReaderResult<T> {
+        let response = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(Box::new)?;
+
+        // Read as a stream, since classes may exceed the size limit of `into_string`.
+        Ok(serde_json::from_reader(response.into_reader())?)
+    }
+
+    fn send_request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> RpcStateReaderResult<T> {
+        let request = json!({ "jsonrpc": "2.0", "id": 0, "method": method, "params": params });
+        self.post::<JsonRpcResponse>(&request)?.into_result()
+    }
+
+    /// Sends the given requests as a single JSON-RPC batch; returns the responses in the order of
+    /// the requests.
+    fn send_batch_request(
+        &self,
+        requests: Vec<(&str, Value)>,
+    ) -> RpcStateReaderResult<Vec<JsonRpcResponse>> {
+        let n_requests = requests.len();
+        let batch: Vec<Value> = requests
+            .into_iter()
+            .enumerate()
+            .map(|(id, (method, params))| {
+                json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+            })
+            .collect();
+        let mut responses: Vec<JsonRpcResponse> = self.post(&Value::Array(batch))?;
+
+        // Responses may be returned in any order.
+        responses.sort_by_key(|response| response.id);
+        if responses.len() != n_requests
+            || responses.iter().enumerate().any(|(id, response)| response.id != id)
+        {
+            return Err(RpcStateReaderError::InvalidBatchResponse);
+        }
+        Ok(responses)
+    }
+
+    fn contract_class_params(&self, class_hash: ClassHash) -> Value {
+        json!({ "block_id": self.block_id(), "class_hash": class_hash })
+    }
+
+    fn request(&self, key: PrefetchKey) -> (&'static str, Value) {
+        match key {
+            PrefetchKey::Storage((contract_address, key)) => (
+                "starknet_getStorageAt",
+                json!({
+                    "contract_address": contract_address,
+                    "key": key,
+                    "block_id": self.block_id(),
+                }),
+            ),
+            PrefetchKey::Nonce(contract_address) => (
+                "starknet_getNonce",
+                json!({ "block_id": self.block_id(), "contract_address": contract_address }),
+            ),
+            PrefetchKey::ClassHash(contract_address) => (
+                "starknet_getClassHashAt",
+                json!({ "block_id": self.block_id(), "contract_address": contract_address }),
+            ),
+            PrefetchKey::ContractClass(class_hash) => {
+                ("starknet_getClass", self.contract_class_params(class_hash))
+            }
+        }
+    }
+
+    /// Requests the value of a contract-related key, and caches it.
+    fn fetch_contract_value<T: Copy + Default + DeserializeOwned>(
+        &self,
+        key: PrefetchKey,
+        cache: impl FnOnce(&mut RpcStateCache, T),
+    ) -> StateResult<T> {
+        let (method, params) = self.request(key);
+        let value = or_default_if_contract_not_found(self.send_request(method, params))?;
+        cache(&mut self.cache(), value);
+        Ok(value)
+    }
+
+    fn fetch_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        let params = self.contract_class_params(class_hash);
+        let raw_contract_class = self.send_request("starknet_getClass", params);
+        let (contract_class, compiled_class_hash) =
+            parse_contract_class(class_hash, raw_contract_class)?;
+        self.cache().cache_contract_class(class_hash, contract_class.clone(), compiled_class_hash);
+        Ok(contract_class)
+    }
+}
+
+impl RpcStateCache {
+    fn cache_contract_class(
+        &mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+        compiled_class_hash: CompiledClassHash,
+    ) {
+        self.contract_classes.insert(class_hash, contract_class);
+        // Supplied compiled class hashes take precedence.
+        self.compiled_class_hashes.entry(class_hash).or_insert(compiled_class_hash);
+    }
+}
+
+impl StateReader for RpcStateReader {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        if let Some(value) = self.cache().storage.get(&(contract_address, key)) {
+            return Ok(*value);
+        }
+
+        let storage_entry = (contract_address, key);
+        self.fetch_contract_value(PrefetchKey::Storage(storage_entry), |cache, value| {
+            cache.storage.insert(storage_entry, value);
+        })
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        if let Some(nonce) = self.cache().nonces.get(&contract_address) {
+            return Ok(*nonce);
+        }
+
+        self.fetch_contract_value(PrefetchKey::Nonce(contract_address), |cache, nonce| {
+            cache.nonces.insert(contract_address, nonce);
+        })
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        if let Some(class_hash) = self.cache().class_hashes.get(&contract_address) {
+            return Ok(*class_hash);
+        }
+
+        self.fetch_contract_value(PrefetchKey::ClassHash(contract_address), |cache, class_hash| {
+            cache.class_hashes.insert(contract_address, class_hash);
+        })
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        if let Some(contract_class) = self.cache().contract_classes.get(&class_hash) {
+            return Ok(contract_class.clone());
+        }
+
+        self.fetch_contract_class(class_hash)
+    }
+
+    /// Not served by the JSON-RPC API; computed by compiling the class. Cairo 0 and undeclared
+    /// classes have no compiled class hash, hence the default one is returned.
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        if let Some(compiled_class_hash) = self.cache().compiled_class_hashes.get(&class_hash) {
+            return Ok(*compiled_class_hash);
+        }
+
+        match self.fetch_contract_class(class_hash) {
+            Ok(_) => Ok(self.cache().compiled_class_hashes[&class_hash]),
+            Err(StateError::UndeclaredClassHash(_)) => Ok(CompiledClassHash::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Requests the values that are not cached yet as a single JSON-RPC batch.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let missing_keys: Vec<PrefetchKey> = {
+            let cache = self.cache();
+            let storage_keys = storage_entries
+                .iter()
+                .filter(|storage_entry| !cache.storage.contains_key(storage_entry))
+                .map(|&storage_entry| PrefetchKey::Storage(storage_entry));
+            let nonce_keys = contract_addresses
+                .iter()
+                .filter(|contract_address| !cache.nonces.contains_key(contract_address))
+                .map(|&contract_address| PrefetchKey::Nonce(contract_address));
+            let class_hash_keys = contract_addresses
+                .iter()
+                .filter(|contract_address| !cache.class_hashes.contains_key(contract_address))
+                .map(|&contract_address| PrefetchKey::ClassHash(contract_address));
+            let contract_class_keys = class_hashes
+                .iter()
+                .filter(|class_hash| !cache.contract_classes.contains_key(class_hash))
+                .map(|&class_hash| PrefetchKey::ContractClass(class_hash));
+            storage_keys
+                .chain(nonce_keys)
+                .chain(class_hash_keys)
+                .chain(contract_class_keys)
+                .collect()
+        };
+
+        if !missing_keys.is_empty() {
+            let requests = missing_keys.iter().map(|&key| self.request(key)).collect();
+            let responses = self.send_batch_request(requests)?;
+            for (key, response) in missing_keys.into_iter().zip(responses) {
+                self.cache_prefetched_value(key, response)?;
+            }
+        }
+
+        let cache = self.cache();
+        let mut prefetched_state = PrefetchedState::default();
+        for storage_entry in storage_entries {
+            prefetched_state.storage.insert(*storage_entry, cache.storage[storage_entry]);
+        }
+        for contract_address in contract_addresses {
+            prefetched_state.nonces.insert(*contract_address, cache.nonces[contract_address]);
+            prefetched_state
+                .class_hashes
+                .insert(*contract_address, cache.class_hashes[contract_address]);
+        }
+        for class_hash in class_hashes {
+            // Undeclared classes are omitted.
+            if let Some(contract_class) = cache.contract_classes.get(class_hash) {
+                prefetched_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(prefetched_state)
+    }
+}
+
+impl RpcStateReader {
+    fn cache_prefetched_value(
+        &self,
+        key: PrefetchKey,
+        response: JsonRpcResponse,
+    ) -> StateResult<()> {
+        match key {
+            PrefetchKey::Storage(storage_entry) => {
+                let value = or_default_if_contract_not_found(response.into_result())?;
+                self.cache().storage.insert(storage_entry, value);
+            }
+            PrefetchKey::Nonce(contract_address) => {
+                let nonce = or_default_if_contract_not_found(response.into_result())?;
+                self.cache().nonces.insert(contract_address, nonce);
+            }
+            PrefetchKey::ClassHash(contract_address) => {
+                let class_hash = or_default_if_contract_not_found(response.into_result())?;
+                self.cache().class_hashes.insert(contract_address, class_hash);
+            }
+            PrefetchKey::ContractClass(class_hash) => {
+                match parse_contract_class(class_hash, response.into_result()) {
+                    Ok((contract_class, compiled_class_hash)) => self.cache().cache_contract_class(
+                        class_hash,
+                        contract_class,
+                        compiled_class_hash,
+                    ),
+                    Err(StateError::UndeclaredClassHash(_)) => {}
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps the error of a request about an undeployed contract to the default value.
+fn or_default_if_contract_not_found<T: Default>(
+    result: RpcStateReaderResult<T>,
+) -> RpcStateReaderResult<T> {
+    match result {
+        Err(RpcStateReaderError::JsonRpcError { code: CONTRACT_NOT_FOUND_ERROR_CODE, .. }) => {
+            Ok(T::default())
+        }
+        result => result,
+    }
+}
+
+/// Converts the result of a `starknet_getClass` request into an executable class and its compiled
+/// class hash; Cairo 0 classes have the default compiled class hash.
+fn parse_contract_class(
+    class_hash: ClassHash,
+    raw_contract_class: RpcStateReaderResult<Value>,
+) -> StateResult<(ContractClass, CompiledClassHash)> {
+    let mut raw_contract_class = match raw_contract_class {
+        Err(RpcStateReaderError::JsonRpcError {
+            code: CLASS_HASH_NOT_FOUND_ERROR_CODE, ..
+        }) => {
+            return Err(StateError::UndeclaredClassHash(class_hash));
+        }
+        result => result?,
+    };
+
+    // The ABI is not required for execution, and its RPC format differs from the compiled one.
+    if let Some(raw_contract_class) = raw_contract_class.as_object_mut() {
+        raw_contract_class.remove("abi");
+    }
+    if raw_contract_class.get("sierra_program").is_some() {
+        Ok(compile_sierra_class(raw_contract_class)?)
+    } else {
+        Ok((decode_deprecated_class(raw_contract_class)?, CompiledClassHash::default()))
+    }
+}
+
+fn compile_sierra_class(
+    raw_contract_class: Value,
+) -> RpcStateReaderResult<(ContractClass, CompiledClassHash)> {
+    let sierra_contract_class: SierraContractClass = serde_json::from_value(raw_contract_class)?;
+    // Declared classes are within the bytecode size limit of the network.
+    let add_pythonic_hints = false;
+    let max_bytecode_size = usize::MAX;
+    let casm_contract_class = CasmContractClass::from_contract_class(
+        sierra_contract_class,
+        add_pythonic_hints,
+        max_bytecode_size,
+    )?;
+
+    let compiled_class_hash =
+        CompiledClassHash(felt_to_stark_felt(&casm_contract_class.compiled_class_hash()));
+
+    Ok((ContractClassV1::try_from(casm_contract_class)?.into(), compiled_class_hash))
+}
+
+fn decode_deprecated_class(raw_contract_class: Value) -> RpcStateReaderResult<ContractClass> {
+    let RpcDeprecatedContractClass { program, entry_points_by_type } =
+        serde_json::from_value(raw_contract_class)?;
+    let compressed_program = base64::engine::general_purpose::STANDARD.decode(program)?;
+    let program: DeprecatedProgram =
+        serde_json::from_reader(GzDecoder::new(compressed_program.as_slice()))?;
+    let deprecated_contract_class =
+        DeprecatedContractClass { abi: None, program, entry_points_by_type };
+
+    Ok(ContractClassV0::try_from(deprecated_contract_class)?.into())
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{fs, thread};
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{
+    ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector, Nonce, PatriciaKey,
+};
+use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::execution::contract_class::ContractClass;
+use crate::state::errors::StateError;
+use crate::state::rpc_state_reader::RpcStateReader;
+use crate::state::state_api::StateReader;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::CairoVersion;
+
+// Hand-written responses in the format of the JSON-RPC specification, for made-up addresses and
+// class hashes; they are not recorded from a node.
+const RESPONSES_PATH: &str = "resources/rpc_responses.json";
+// A Sierra class: the minimal contract of the Cairo compiler's test data. Its compiled class hash
+// is the one of the CASM class in the same test data.
+const SIERRA_CLASS_HASH: &str = "0x30000";
+const SIERRA_COMPILED_CLASS_HASH: &str =
+    "0x82367c58fdfdfe74f67d69d9664ae6d24cf16b73a309f723a634e70b23d4ad";
+const SIERRA_EXTERNAL_SELECTOR: &str =
+    "0x1fc3f77ebc090777f567969ad9823cf6334ab888acb385ca72668ec5adbde80";
+
+/// Replaces every hex string in the given value by its canonical form, so that requests can be
+/// matched regardless of zero padding.
+fn normalize_hex_strings(value: Value) -> Value {
+    match value {
+        Value::String(string) if string.starts_with("0x") => {
+            Value::String(StarkFelt::try_from(string.as_str()).unwrap().to_string())
+        }
+        Value::Object(object) => Value::Object(
+            object.into_iter().map(|(key, value)| (key, normalize_hex_strings(value))).collect(),
+        ),
+        value => value,
+    }
+}
+
+fn read_request_body(stream: &TcpStream) -> Value {
+    let mut reader = BufReader::new(stream);
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end().to_ascii_lowercase();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(length) = line.strip_prefix("content-length:") {
+            content_length = length.trim().parse().unwrap();
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn find_response(exchanges: &[Value], request: &Value) -> Value {
+    let exchange = exchanges
+        .iter()
+        .find(|exchange| {
+            exchange["method"] == request["method"]
+                && normalize_hex_strings(exchange["params"].clone())
+                    == normalize_hex_strings(request["params"].clone())
+        })
+        .unwrap_or_else(|| panic!("No response for request: {request}."));
+    let mut response = json!({ "jsonrpc": "2.0", "id": request["id"] });
+    for field in ["result", "error"] {
+        if let Some(value) = exchange.get(field) {
+            response[field] = value.clone();
+        }
+    }
+
+    response
+}
+
+/// Serves the JSON-RPC responses over HTTP on a local port; a batch is served as a single request.
+/// Returns the URL of the server, and the number of requests it served.
+fn serve_responses() -> (String, Arc<AtomicUsize>) {
+    let responses_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(RESPONSES_PATH);
+    let exchanges: Vec<Value> =
+        serde_json::from_str(&fs::read_to_string(responses_path).unwrap()).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let n_requests = Arc::new(AtomicUsize::new(0));
+
+    let n_served_requests = n_requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let request = read_request_body(&stream);
+            n_served_requests.fetch_add(1, Ordering::SeqCst);
+
+            // The responses of a batch are returned in reverse order, as the order is unspecified.
+            let response = match request {
+                Value::Array(requests) => Value::Array(
+                    requests
+                        .iter()
+                        .rev()
+                        .map(|request| find_response(&exchanges, request))
+                        .collect(),
+                ),
+                request => find_response(&exchanges, &request),
+            };
+
+            let body = response.to_string();
+            let headers = format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close",
+                body.len()
+            );
+            write!(stream, "HTTP/1.1 200 OK\r\n{headers}\r\n\r\n{body}").unwrap();
+        }
+    });
+
+    (url, n_requests)
+}
+
+#[test]
+fn test_rpc_state_reader() {
+    let (url, n_requests) = serve_responses();
+    let state_reader = RpcStateReader::new(url, BlockNumber(100));
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let account_address = account.get_instance_address(0);
+    let undeployed_address = contract_address!("0x1111");
+    let key = StorageKey(patricia_key!("0x10"));
+
+    assert_eq!(state_reader.get_storage_at(account_address, key).unwrap(), stark_felt!("0x1234"));
+    assert_eq!(state_reader.get_nonce_at(account_address).unwrap(), Nonce(stark_felt!("0x5")));
+    assert_eq!(state_reader.get_class_hash_at(account_address).unwrap(), account.get_class_hash());
+    assert_eq!(
+        state_reader.get_compiled_contract_class(account.get_class_hash()).unwrap(),
+        account.get_class()
+    );
+    assert_eq!(
+        state_reader.get_compiled_class_hash(account.get_class_hash()).unwrap(),
+        CompiledClassHash::default()
+    );
+
+    // Sierra classes are compiled, which yields their compiled class hash.
+    let sierra_class_hash = class_hash!(SIERRA_CLASS_HASH);
+    assert_sierra_class(&state_reader.get_compiled_contract_class(sierra_class_hash).unwrap());
+    assert_eq!(
+        state_reader.get_compiled_class_hash(sierra_class_hash).unwrap(),
+        CompiledClassHash(stark_felt!(SIERRA_COMPILED_CLASS_HASH))
+    );
+
+    // Undeployed contracts have default values; undeclared classes are reported.
+    assert_eq!(state_reader.get_storage_at(undeployed_address, key).unwrap(), StarkFelt::ZERO);
+    assert_eq!(state_reader.get_nonce_at(undeployed_address).unwrap(), Nonce::default());
+    assert_eq!(state_reader.get_class_hash_at(undeployed_address).unwrap(), ClassHash::default());
+    let undeclared_class_hash = class_hash!("0x1234");
+    assert_matches!(
+        state_reader.get_compiled_contract_class(undeclared_class_hash),
+        Err(StateError::UndeclaredClassHash(class_hash)) if class_hash == undeclared_class_hash
+    );
+    assert_eq!(
+        state_reader.get_compiled_class_hash(undeclared_class_hash).unwrap(),
+        CompiledClassHash::default()
+    );
+
+    // Values are requested once.
+    let n_requests_before_cached_reads = n_requests.load(Ordering::SeqCst);
+    state_reader.get_storage_at(account_address, key).unwrap();
+    state_reader.get_compiled_contract_class(account.get_class_hash()).unwrap();
+    state_reader.get_compiled_class_hash(sierra_class_hash).unwrap();
+    assert_eq!(n_requests.load(Ordering::SeqCst), n_requests_before_cached_reads);
+}
+
+fn assert_sierra_class(contract_class: &ContractClass) {
+    let ContractClass::V1(contract_class) = contract_class else {
+        panic!("Expected a Cairo 1 class, got {contract_class:?}.");
+    };
+    let external_entry_points = &contract_class.entry_points_by_type[&EntryPointType::External];
+    assert_eq!(external_entry_points.len(), 1);
+    assert_eq!(
+        external_entry_points[0].selector,
+        EntryPointSelector(stark_felt!(SIERRA_EXTERNAL_SELECTOR))
+    );
+}
+
+#[test]
+fn test_rpc_state_reader_prefetch() {
+    let (url, n_requests) = serve_responses();
+    let state_reader = RpcStateReader::new(url, BlockNumber(100));
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let account_address = account.get_instance_address(0);
+    let undeployed_address = contract_address!("0x1111");
+    let key = StorageKey(patricia_key!("0x10"));
+    let sierra_class_hash = class_hash!(SIERRA_CLASS_HASH);
+    let undeclared_class_hash = class_hash!("0x1234");
+
+    // The values are requested as a single batch; undeclared classes are omitted.
+    let prefetched_state = state_reader
+        .prefetch(
+            &[(account_address, key), (undeployed_address, key)],
+            &[account_address, undeployed_address],
+            &[account.get_class_hash(), sierra_class_hash, undeclared_class_hash],
+        )
+        .unwrap();
+    assert_eq!(n_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        prefetched_state.storage,
+        HashMap::from([
+            ((account_address, key), stark_felt!("0x1234")),
+            ((undeployed_address, key), StarkFelt::ZERO),
+        ])
+    );
+    assert_eq!(
+        prefetched_state.nonces,
+        HashMap::from([
+            (account_address, Nonce(stark_felt!("0x5"))),
+            (undeployed_address, Nonce::default()),
+        ])
+    );
+    assert_eq!(
+        prefetched_state.class_hashes,
+        HashMap::from([
+            (account_address, account.get_class_hash()),
+            (undeployed_address, ClassHash::default()),
+        ])
+    );
+    assert_eq!(prefetched_state.contract_classes.len(), 2);
+    assert_eq!(prefetched_state.contract_classes[&account.get_class_hash()], account.get_class());
+    assert_sierra_class(&prefetched_state.contract_classes[&sierra_class_hash]);
+
+    // The prefetched values are cached, including the compiled class hashes.
+    assert_eq!(
+        state_reader.get_compiled_class_hash(sierra_class_hash).unwrap(),
+        CompiledClassHash(stark_felt!(SIERRA_COMPILED_CLASS_HASH))
+    );
+    assert_eq!(state_reader.get_nonce_at(undeployed_address).unwrap(), Nonce::default());
+    assert_eq!(
+        state_reader.prefetch(&[(account_address, key)], &[account_address], &[]).unwrap().storage,
+        HashMap::from([((account_address, key), stark_felt!("0x1234"))])
+    );
+    assert_eq!(n_requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_rpc_state_reader_supplied_compiled_class_hashes() {
+    let (url, _) = serve_responses();
+    let sierra_class_hash = class_hash!(SIERRA_CLASS_HASH);
+    let network_compiled_class_hash = CompiledClassHash(stark_felt!("0x1234"));
+    let state_reader = RpcStateReader::new(url, BlockNumber(100)).with_compiled_class_hashes(
+        HashMap::from([(sierra_class_hash, network_compiled_class_hash)]),
+    );
+
+    // The supplied compiled class hash is kept once the class is compiled.
+    assert_sierra_class(&state_reader.get_compiled_contract_class(sierra_class_hash).unwrap());
+    assert_eq!(
+        state_reader.get_compiled_class_hash(sierra_class_hash).unwrap(),
+        network_compiled_class_hash
+    );
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
@@ -7,6 +7,7 @@ use starknet_api::state::StorageKey;
 use crate::abi::abi_utils::get_fee_token_var_address;
 use crate::abi::sierra_types::next_storage_key;
 use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::StorageEntry;
 use crate::state::errors::StateError;
 
 pub type StateResult<T> = Result<T, StateError>;
@@ -17,6 +18,15 @@ pub enum DataAvailabilityMode {
     L2 = 1,
 }
 
+/// The values read by a [`StateReader::prefetch`] batch.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrefetchedState {
+    pub storage: HashMap<StorageEntry, StarkFelt>,
+    pub nonces: HashMap<ContractAddress, Nonce>,
+    pub class_hashes: HashMap<ContractAddress, ClassHash>,
+    pub contract_classes: HashMap<ClassHash, ContractClass>,
+}
+
 /// A read-only API for accessing Starknet global state.
 ///
 /// The `self` argument is mutable for flexibility during reads (for example, caching reads),
@@ -62,6 +72,55 @@ pub trait StateReader {
 
         Ok((low, high))
     }
+
+    /// Reads the given storage entries, the nonces and class hashes of the given contracts, and
+    /// the given classes as a batch; e.g., to warm a cache before executing a transaction.
+    /// Readers may return only part of the values (undeclared classes are always omitted); by
+    /// default, none are returned.
+    fn prefetch(
+        &self,
+        _storage_entries: &[StorageEntry],
+        _contract_addresses: &[ContractAddress],
+        _class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        Ok(PrefetchedState::default())
+    }
+}
+
+/// Reading through a shared reference; e.g., for executions that must leave the state untouched.
+impl<S: StateReader + ?Sized> StateReader for &S {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        (**self).get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        (**self).get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        (**self).get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        (**self).get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        (**self).get_compiled_class_hash(class_hash)
+    }
+
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        (**self).prefetch(storage_entries, contract_addresses, class_hashes)
+    }
 }
 
 /// A class defining the API for writing to Starknet global state.
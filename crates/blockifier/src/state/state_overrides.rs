@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::abi::abi_utils::get_fee_token_var_address;
+use crate::abi::sierra_types::next_storage_key;
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::{ContractClassMapping, StorageEntry};
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "state_overrides_test.rs"]
+pub mod test;
+
+/// Overrides of a single contract instance's state.
+#[derive(Clone, Debug, Default)]
+pub struct ContractOverrides {
+    pub nonce: Option<Nonce>,
+    pub class_hash: Option<ClassHash>,
+    pub storage: HashMap<StorageKey, StarkFelt>,
+}
+
+/// Values to read instead of the ones in the underlying state; e.g., for "what if" simulations.
+#[derive(Clone, Debug, Default)]
+pub struct StateOverrides {
+    pub contracts: HashMap<ContractAddress, ContractOverrides>,
+    /// Classes to read in addition to (or instead of) the declared ones.
+    pub contract_classes: ContractClassMapping,
+    /// Compiled class hashes to read instead of the declared ones; e.g., of injected classes.
+    pub compiled_class_hashes: HashMap<ClassHash, CompiledClassHash>,
+}
+
+impl StateOverrides {
+    pub fn with_storage(
+        mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: StarkFelt,
+    ) -> Self {
+        self.contracts.entry(contract_address).or_default().storage.insert(key, value);
+        self
+    }
+
+    pub fn with_nonce(mut self, contract_address: ContractAddress, nonce: Nonce) -> Self {
+        self.contracts.entry(contract_address).or_default().nonce = Some(nonce);
+        self
+    }
+
+    pub fn with_class_hash(
+        mut self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> Self {
+        self.contracts.entry(contract_address).or_default().class_hash = Some(class_hash);
+        self
+    }
+
+    pub fn with_contract_class(
+        mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+    ) -> Self {
+        self.contract_classes.insert(class_hash, contract_class);
+        self
+    }
+
+    pub fn with_compiled_class_hash(
+        mut self,
+        class_hash: ClassHash,
+        compiled_class_hash: CompiledClassHash,
+    ) -> Self {
+        self.compiled_class_hashes.insert(class_hash, compiled_class_hash);
+        self
+    }
+
+    /// Overrides the balance of the given account, in the given fee token.
+    pub fn with_fee_token_balance(
+        mut self,
+        contract_address: ContractAddress,
+        fee_token_address: ContractAddress,
+        balance: u128,
+    ) -> StateResult<Self> {
+        let low_key = get_fee_token_var_address(contract_address);
+        let high_key = next_storage_key(&low_key)?;
+        let fee_token_storage = &mut self.contracts.entry(fee_token_address).or_default().storage;
+        fee_token_storage.insert(low_key, StarkFelt::from(balance));
+        fee_token_storage.insert(high_key, StarkFelt::ZERO);
+
+        Ok(self)
+    }
+}
+
+/// Layers the given overrides over a state reader; reads of non-overridden values are proxied to
+/// the underlying reader.
+///
+/// Note: a `CachedState` reads contract classes from its global contract cache before reading
+/// from its underlying state; injected classes should not shadow classes that may already be
+/// cached.
+#[derive(Debug)]
+pub struct OverrideStateReader<S: StateReader> {
+    pub state: S,
+    pub overrides: StateOverrides,
+}
+
+impl<S: StateReader> OverrideStateReader<S> {
+    pub fn new(state: S, overrides: StateOverrides) -> Self {
+        Self { state, overrides }
+    }
+
+    fn contract_overrides(&self, contract_address: ContractAddress) -> Option<&ContractOverrides> {
+        self.overrides.contracts.get(&contract_address)
+    }
+}
+
+impl<S: StateReader> StateReader for OverrideStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        match self
+            .contract_overrides(contract_address)
+            .and_then(|contract_overrides| contract_overrides.storage.get(&key))
+        {
+            Some(value) => Ok(*value),
+            None => self.state.get_storage_at(contract_address, key),
+        }
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        match self.contract_overrides(contract_address).and_then(|overrides| overrides.nonce) {
+            Some(nonce) => Ok(nonce),
+            None => self.state.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        match self.contract_overrides(contract_address).and_then(|overrides| overrides.class_hash) {
+            Some(class_hash) => Ok(class_hash),
+            None => self.state.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        match self.overrides.contract_classes.get(&class_hash) {
+            Some(contract_class) => Ok(contract_class.clone()),
+            None => self.state.get_compiled_contract_class(class_hash),
+        }
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        match self.overrides.compiled_class_hashes.get(&class_hash) {
+            Some(compiled_class_hash) => Ok(*compiled_class_hash),
+            None => self.state.get_compiled_class_hash(class_hash),
+        }
+    }
+
+    /// Prefetches the non-overridden values from the underlying reader; the overridden ones are
+    /// returned as is.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let storage_override = |&(contract_address, key): &StorageEntry| {
+            self.contract_overrides(contract_address)
+                .and_then(|contract_overrides| contract_overrides.storage.get(&key).copied())
+        };
+        let underlying_storage_entries: Vec<StorageEntry> = storage_entries
+            .iter()
+            .copied()
+            .filter(|storage_entry| storage_override(storage_entry).is_none())
+            .collect();
+        let underlying_contract_addresses: Vec<ContractAddress> = contract_addresses
+            .iter()
+            .copied()
+            .filter(|&contract_address| {
+                !self.contract_overrides(contract_address).is_some_and(|contract_overrides| {
+                    contract_overrides.nonce.is_some() && contract_overrides.class_hash.is_some()
+                })
+            })
+            .collect();
+        let underlying_class_hashes: Vec<ClassHash> = class_hashes
+            .iter()
+            .copied()
+            .filter(|class_hash| !self.overrides.contract_classes.contains_key(class_hash))
+            .collect();
+        let mut prefetched_state = self.state.prefetch(
+            &underlying_storage_entries,
+            &underlying_contract_addresses,
+            &underlying_class_hashes,
+        )?;
+
+        for storage_entry in storage_entries {
+            if let Some(value) = storage_override(storage_entry) {
+                prefetched_state.storage.insert(*storage_entry, value);
+            }
+        }
+        for &contract_address in contract_addresses {
+            let Some(contract_overrides) = self.contract_overrides(contract_address) else {
+                continue;
+            };
+            if let Some(nonce) = contract_overrides.nonce {
+                prefetched_state.nonces.insert(contract_address, nonce);
+            }
+            if let Some(class_hash) = contract_overrides.class_hash {
+                prefetched_state.class_hashes.insert(contract_address, class_hash);
+            }
+        }
+        for class_hash in class_hashes {
+            if let Some(contract_class) = self.overrides.contract_classes.get(class_hash) {
+                prefetched_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(prefetched_state)
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::context::BlockContext;
+use crate::invoke_tx_args;
+use crate::state::cached_state::CachedState;
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader};
+use crate::state::state_overrides::{OverrideStateReader, StateOverrides};
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::dict_state_reader::DictStateReader;
+use crate::test_utils::initial_test_state::test_state;
+use crate::test_utils::{create_calldata, CairoVersion, BALANCE};
+use crate::transaction::errors::{
+    TransactionExecutionError, TransactionFeeError, TransactionPreValidationError,
+};
+use crate::transaction::objects::HasRelatedFeeType;
+use crate::transaction::test_utils::{account_invoke_tx, block_context, max_fee};
+use crate::transaction::transactions::ExecutableTransaction;
+
+#[test]
+fn test_override_state_reader() {
+    let contract_address = contract_address!("0x100");
+    let (overridden_key, key) =
+        (StorageKey(patricia_key!("0x10")), StorageKey(patricia_key!("0x11")));
+    let state = DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, overridden_key), stark_felt!("0x1")),
+            ((contract_address, key), stark_felt!("0x2")),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!("0x3")))]),
+        ..Default::default()
+    };
+    let injected_class = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let injected_class_hash = class_hash!("0x20");
+    let injected_compiled_class_hash = CompiledClassHash(stark_felt!("0x21"));
+    let overrides = StateOverrides::default()
+        .with_storage(contract_address, overridden_key, stark_felt!("0x7"))
+        .with_nonce(contract_address, Nonce(stark_felt!("0x8")))
+        .with_class_hash(contract_address, injected_class_hash)
+        .with_contract_class(injected_class_hash, injected_class.get_class())
+        .with_compiled_class_hash(injected_class_hash, injected_compiled_class_hash);
+
+    let reader = OverrideStateReader::new(&state, overrides);
+    assert_eq!(
+        reader.get_storage_at(contract_address, overridden_key).unwrap(),
+        stark_felt!("0x7")
+    );
+    assert_eq!(reader.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x2"));
+    assert_eq!(reader.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!("0x8")));
+    assert_eq!(reader.get_class_hash_at(contract_address).unwrap(), injected_class_hash);
+    assert_eq!(
+        reader.get_compiled_contract_class(injected_class_hash).unwrap(),
+        injected_class.get_class()
+    );
+    assert_eq!(
+        reader.get_compiled_class_hash(injected_class_hash).unwrap(),
+        injected_compiled_class_hash
+    );
+
+    // Overridden values are prefetched as is.
+    assert_eq!(
+        reader
+            .prefetch(
+                &[(contract_address, overridden_key), (contract_address, key)],
+                &[contract_address],
+                &[injected_class_hash],
+            )
+            .unwrap(),
+        PrefetchedState {
+            storage: HashMap::from([
+                ((contract_address, overridden_key), stark_felt!("0x7")),
+                ((contract_address, key), stark_felt!("0x2")),
+            ]),
+            nonces: HashMap::from([(contract_address, Nonce(stark_felt!("0x8")))]),
+            class_hashes: HashMap::from([(contract_address, injected_class_hash)]),
+            contract_classes: HashMap::from([(injected_class_hash, injected_class.get_class())]),
+        }
+    );
+
+    // The underlying state is untouched.
+    assert_eq!(state.get_storage_at(contract_address, overridden_key).unwrap(), stark_felt!("0x1"));
+    assert_matches!(
+        state.get_compiled_contract_class(injected_class_hash),
+        Err(StateError::UndeclaredClassHash(class_hash)) if class_hash == injected_class_hash
+    );
+}
+
+#[rstest]
+fn test_fee_token_balance_override(block_context: BlockContext) {
+    let account_contract = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    // No initial balance; the account cannot afford any transaction.
+    let state =
+        test_state(&block_context.chain_info, 0, &[(account_contract, 1), (test_contract, 1)]);
+    let sender_address = account_contract.get_instance_address(0);
+    let invoke_tx = || {
+        account_invoke_tx(invoke_tx_args! {
+            sender_address,
+            calldata: create_calldata(
+                test_contract.get_instance_address(0),
+                "return_result",
+                &[stark_felt!(2_u8)],
+            ),
+            max_fee: max_fee(),
+        })
+    };
+    let fee_token_address = block_context.chain_info.fee_token_address(&invoke_tx().fee_type());
+
+    let mut cached_state = CachedState::from(&state);
+    assert_matches!(
+        invoke_tx().execute(&mut cached_state, &block_context, true, true).unwrap_err(),
+        TransactionExecutionError::TransactionPreValidationError(
+            TransactionPreValidationError::TransactionFeeError(
+                TransactionFeeError::MaxFeeExceedsBalance { .. }
+            )
+        )
+    );
+
+    let overrides = StateOverrides::default()
+        .with_fee_token_balance(sender_address, fee_token_address, BALANCE)
+        .unwrap();
+    let mut cached_state = CachedState::from(OverrideStateReader::new(&state, overrides));
+    assert_eq!(
+        cached_state.get_fee_token_balance(sender_address, fee_token_address).unwrap(),
+        (stark_felt!(BALANCE), StarkFelt::ZERO)
+    );
+    let execution_info =
+        invoke_tx().execute(&mut cached_state, &block_context, true, true).unwrap();
+    assert!(!execution_info.is_reverted());
+
+    // The fee was charged from the overridden balance.
+    let (balance, _) =
+        cached_state.get_fee_token_balance(sender_address, fee_token_address).unwrap();
+    assert_eq!(balance, stark_felt!(BALANCE - execution_info.actual_fee.0));
+}
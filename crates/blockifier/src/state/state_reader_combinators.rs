@@ -0,0 +1,453 @@
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+
+use cached::{Cached, SizedCache};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::{CommitmentStateDiff, ContractClassMapping, StorageEntry};
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "state_reader_combinators_test.rs"]
+pub mod test;
+
+/// Reads from the top reader, and falls back to the bottom one for values the top reader does not
+/// hold; e.g., a local state over a forked one.
+///
+/// Note: like uninitialized values, default values (zero) are considered not held by the top
+/// reader, and undeclared classes are looked up in the bottom reader. To shadow values of the
+/// bottom reader with zeros, use `PendingStateReader` or `OverrideStateReader`.
+#[derive(Debug)]
+pub struct LayeredStateReader<A: StateReader, B: StateReader> {
+    pub top: A,
+    pub bottom: B,
+}
+
+impl<A: StateReader, B: StateReader> LayeredStateReader<A, B> {
+    pub fn new(top: A, bottom: B) -> Self {
+        Self { top, bottom }
+    }
+}
+
+/// Returns the value of the top reader, unless it is the default one.
+fn read_layers<T: Default + PartialEq>(
+    top_value: StateResult<T>,
+    read_bottom: impl FnOnce() -> StateResult<T>,
+) -> StateResult<T> {
+    let top_value = top_value?;
+    if top_value == T::default() {
+        read_bottom()
+    } else {
+        Ok(top_value)
+    }
+}
+
+/// Whether a value prefetched from the top reader is held by it, i.e., returned and not default.
+fn is_held<T: Default + PartialEq>(top_value: Option<&T>) -> bool {
+    top_value.is_some_and(|top_value| *top_value != T::default())
+}
+
+impl<A: StateReader, B: StateReader> StateReader for LayeredStateReader<A, B> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        read_layers(self.top.get_storage_at(contract_address, key), || {
+            self.bottom.get_storage_at(contract_address, key)
+        })
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        read_layers(self.top.get_nonce_at(contract_address), || {
+            self.bottom.get_nonce_at(contract_address)
+        })
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        read_layers(self.top.get_class_hash_at(contract_address), || {
+            self.bottom.get_class_hash_at(contract_address)
+        })
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        match self.top.get_compiled_contract_class(class_hash) {
+            Err(StateError::UndeclaredClassHash(_)) => {
+                self.bottom.get_compiled_contract_class(class_hash)
+            }
+            result => result,
+        }
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        read_layers(self.top.get_compiled_class_hash(class_hash), || {
+            self.bottom.get_compiled_class_hash(class_hash)
+        })
+    }
+
+    /// Prefetches all values from the top reader, and the ones it does not hold from the bottom
+    /// one. Values the top reader does not return are prefetched from the bottom one to warm it,
+    /// but only returned for classes, which are identical in both readers.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let top_state = self.top.prefetch(storage_entries, contract_addresses, class_hashes)?;
+        let bottom_storage_entries: Vec<StorageEntry> = storage_entries
+            .iter()
+            .copied()
+            .filter(|storage_entry| !is_held(top_state.storage.get(storage_entry)))
+            .collect();
+        let bottom_contract_addresses: Vec<ContractAddress> = contract_addresses
+            .iter()
+            .copied()
+            .filter(|contract_address| {
+                !is_held(top_state.nonces.get(contract_address))
+                    || !is_held(top_state.class_hashes.get(contract_address))
+            })
+            .collect();
+        let bottom_class_hashes: Vec<ClassHash> = class_hashes
+            .iter()
+            .copied()
+            .filter(|class_hash| !top_state.contract_classes.contains_key(class_hash))
+            .collect();
+        let bottom_state = self.bottom.prefetch(
+            &bottom_storage_entries,
+            &bottom_contract_addresses,
+            &bottom_class_hashes,
+        )?;
+
+        let mut prefetched_state = top_state;
+        for (storage_entry, value) in bottom_state.storage {
+            if let Some(top_value) = prefetched_state.storage.get_mut(&storage_entry) {
+                *top_value = value;
+            }
+        }
+        for (contract_address, nonce) in bottom_state.nonces {
+            if let Some(top_nonce) = prefetched_state.nonces.get_mut(&contract_address) {
+                if *top_nonce == Nonce::default() {
+                    *top_nonce = nonce;
+                }
+            }
+        }
+        for (contract_address, class_hash) in bottom_state.class_hashes {
+            if let Some(top_class_hash) = prefetched_state.class_hashes.get_mut(&contract_address) {
+                if *top_class_hash == ClassHash::default() {
+                    *top_class_hash = class_hash;
+                }
+            }
+        }
+        prefetched_state.contract_classes.extend(bottom_state.contract_classes);
+
+        Ok(prefetched_state)
+    }
+}
+
+/// The values read through a `CachingStateReader`, by type.
+#[derive(Debug)]
+struct ReadCache {
+    storage: SizedCache<StorageEntry, StarkFelt>,
+    nonces: SizedCache<ContractAddress, Nonce>,
+    class_hashes: SizedCache<ContractAddress, ClassHash>,
+    compiled_class_hashes: SizedCache<ClassHash, CompiledClassHash>,
+    contract_classes: SizedCache<ClassHash, ContractClass>,
+}
+
+/// A bounded, thread-safe read-through cache over a state reader; each type of value is cached
+/// up to the given number of entries, and the least recently used ones are evicted first.
+///
+/// Unlike `CachedState`, it does not track writes; it should only wrap readers of immutable
+/// state, e.g., of a past block.
+#[derive(Debug)]
+pub struct CachingStateReader<S: StateReader> {
+    pub state: S,
+    cache: Mutex<ReadCache>,
+}
+
+impl<S: StateReader> CachingStateReader<S> {
+    pub fn new(state: S, cache_size: usize) -> Self {
+        let cache = ReadCache {
+            storage: SizedCache::with_size(cache_size),
+            nonces: SizedCache::with_size(cache_size),
+            class_hashes: SizedCache::with_size(cache_size),
+            compiled_class_hashes: SizedCache::with_size(cache_size),
+            contract_classes: SizedCache::with_size(cache_size),
+        };
+        Self { state, cache: Mutex::new(cache) }
+    }
+
+    fn cache(&self) -> MutexGuard<'_, ReadCache> {
+        self.cache.lock().expect("State read cache is poisoned.")
+    }
+
+    /// Returns the cached value, or reads and caches it. The lock is not held while reading.
+    fn read_through<K: Copy + Eq + Hash, V: Clone>(
+        &self,
+        key: K,
+        cache: impl Fn(&mut ReadCache) -> &mut SizedCache<K, V>,
+        read: impl FnOnce() -> StateResult<V>,
+    ) -> StateResult<V> {
+        if let Some(value) = cache(&mut self.cache()).cache_get(&key) {
+            return Ok(value.clone());
+        }
+
+        let value = read()?;
+        cache(&mut self.cache()).cache_set(key, value.clone());
+        Ok(value)
+    }
+}
+
+impl<S: StateReader> StateReader for CachingStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.read_through(
+            (contract_address, key),
+            |cache| &mut cache.storage,
+            || self.state.get_storage_at(contract_address, key),
+        )
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.read_through(
+            contract_address,
+            |cache| &mut cache.nonces,
+            || self.state.get_nonce_at(contract_address),
+        )
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.read_through(
+            contract_address,
+            |cache| &mut cache.class_hashes,
+            || self.state.get_class_hash_at(contract_address),
+        )
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.read_through(
+            class_hash,
+            |cache| &mut cache.contract_classes,
+            || self.state.get_compiled_contract_class(class_hash),
+        )
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.read_through(
+            class_hash,
+            |cache| &mut cache.compiled_class_hashes,
+            || self.state.get_compiled_class_hash(class_hash),
+        )
+    }
+
+    /// Prefetches the values that are not cached yet, and caches the prefetched ones.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let (missing_storage_entries, missing_contract_addresses, missing_class_hashes) = {
+            let mut cache = self.cache();
+            let missing_storage_entries: Vec<StorageEntry> = storage_entries
+                .iter()
+                .copied()
+                .filter(|storage_entry| cache.storage.cache_get(storage_entry).is_none())
+                .collect();
+            let missing_contract_addresses: Vec<ContractAddress> = contract_addresses
+                .iter()
+                .copied()
+                .filter(|contract_address| {
+                    cache.nonces.cache_get(contract_address).is_none()
+                        || cache.class_hashes.cache_get(contract_address).is_none()
+                })
+                .collect();
+            let missing_class_hashes: Vec<ClassHash> = class_hashes
+                .iter()
+                .copied()
+                .filter(|class_hash| cache.contract_classes.cache_get(class_hash).is_none())
+                .collect();
+            (missing_storage_entries, missing_contract_addresses, missing_class_hashes)
+        };
+        let prefetched_state = self.state.prefetch(
+            &missing_storage_entries,
+            &missing_contract_addresses,
+            &missing_class_hashes,
+        )?;
+
+        let mut cache = self.cache();
+        for (storage_entry, value) in prefetched_state.storage {
+            cache.storage.cache_set(storage_entry, value);
+        }
+        for (contract_address, nonce) in prefetched_state.nonces {
+            cache.nonces.cache_set(contract_address, nonce);
+        }
+        for (contract_address, class_hash) in prefetched_state.class_hashes {
+            cache.class_hashes.cache_set(contract_address, class_hash);
+        }
+        for (class_hash, contract_class) in prefetched_state.contract_classes {
+            cache.contract_classes.cache_set(class_hash, contract_class);
+        }
+
+        let mut cached_state = PrefetchedState::default();
+        for storage_entry in storage_entries {
+            if let Some(value) = cache.storage.cache_get(storage_entry) {
+                cached_state.storage.insert(*storage_entry, *value);
+            }
+        }
+        for contract_address in contract_addresses {
+            if let Some(nonce) = cache.nonces.cache_get(contract_address) {
+                cached_state.nonces.insert(*contract_address, *nonce);
+            }
+            if let Some(class_hash) = cache.class_hashes.cache_get(contract_address) {
+                cached_state.class_hashes.insert(*contract_address, *class_hash);
+            }
+        }
+        for class_hash in class_hashes {
+            if let Some(contract_class) = cache.contract_classes.cache_get(class_hash) {
+                cached_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(cached_state)
+    }
+}
+
+/// Applies the state diff of a pending block over the state it was built on. Values in the diff,
+/// including zeros, shadow the ones of the underlying state.
+#[derive(Debug)]
+pub struct PendingStateReader<S: StateReader> {
+    pub state: S,
+    pub state_diff: CommitmentStateDiff,
+    /// The classes declared in the pending block.
+    pub contract_classes: ContractClassMapping,
+}
+
+impl<S: StateReader> PendingStateReader<S> {
+    pub fn new(state: S, state_diff: CommitmentStateDiff) -> Self {
+        Self { state, state_diff, contract_classes: ContractClassMapping::default() }
+    }
+
+    pub fn with_contract_class(
+        mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+    ) -> Self {
+        self.contract_classes.insert(class_hash, contract_class);
+        self
+    }
+}
+
+impl<S: StateReader> StateReader for PendingStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        match self
+            .state_diff
+            .storage_updates
+            .get(&contract_address)
+            .and_then(|storage_updates| storage_updates.get(&key))
+        {
+            Some(value) => Ok(*value),
+            None => self.state.get_storage_at(contract_address, key),
+        }
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        match self.state_diff.address_to_nonce.get(&contract_address) {
+            Some(nonce) => Ok(*nonce),
+            None => self.state.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        match self.state_diff.address_to_class_hash.get(&contract_address) {
+            Some(class_hash) => Ok(*class_hash),
+            None => self.state.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        match self.contract_classes.get(&class_hash) {
+            Some(contract_class) => Ok(contract_class.clone()),
+            None => self.state.get_compiled_contract_class(class_hash),
+        }
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        match self.state_diff.class_hash_to_compiled_class_hash.get(&class_hash) {
+            Some(compiled_class_hash) => Ok(*compiled_class_hash),
+            None => self.state.get_compiled_class_hash(class_hash),
+        }
+    }
+
+    /// Prefetches the values not in the diff from the underlying reader; the ones in the diff are
+    /// returned as is.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let pending_storage = |&(contract_address, key): &StorageEntry| {
+            self.state_diff
+                .storage_updates
+                .get(&contract_address)
+                .and_then(|storage_updates| storage_updates.get(&key).copied())
+        };
+        let underlying_storage_entries: Vec<StorageEntry> = storage_entries
+            .iter()
+            .copied()
+            .filter(|storage_entry| pending_storage(storage_entry).is_none())
+            .collect();
+        let underlying_contract_addresses: Vec<ContractAddress> = contract_addresses
+            .iter()
+            .copied()
+            .filter(|contract_address| {
+                !self.state_diff.address_to_nonce.contains_key(contract_address)
+                    || !self.state_diff.address_to_class_hash.contains_key(contract_address)
+            })
+            .collect();
+        let underlying_class_hashes: Vec<ClassHash> = class_hashes
+            .iter()
+            .copied()
+            .filter(|class_hash| !self.contract_classes.contains_key(class_hash))
+            .collect();
+        let mut prefetched_state = self.state.prefetch(
+            &underlying_storage_entries,
+            &underlying_contract_addresses,
+            &underlying_class_hashes,
+        )?;
+
+        for storage_entry in storage_entries {
+            if let Some(value) = pending_storage(storage_entry) {
+                prefetched_state.storage.insert(*storage_entry, value);
+            }
+        }
+        for contract_address in contract_addresses {
+            if let Some(nonce) = self.state_diff.address_to_nonce.get(contract_address) {
+                prefetched_state.nonces.insert(*contract_address, *nonce);
+            }
+            if let Some(class_hash) = self.state_diff.address_to_class_hash.get(contract_address) {
+                prefetched_state.class_hashes.insert(*contract_address, *class_hash);
+            }
+        }
+        for class_hash in class_hashes {
+            if let Some(contract_class) = self.contract_classes.get(class_hash) {
+                prefetched_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(prefetched_state)
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use assert_matches::assert_matches;
+use indexmap::{indexmap, IndexMap};
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader};
+use crate::state::state_reader_combinators::{
+    CachingStateReader, LayeredStateReader, PendingStateReader,
+};
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::dict_state_reader::DictStateReader;
+use crate::test_utils::prefetch_recording_reader::{PrefetchBatch, PrefetchRecordingReader};
+use crate::test_utils::CairoVersion;
+
+#[test]
+fn test_layered_state_reader() {
+    let contract_address = contract_address!("0x100");
+    let top_key = StorageKey(patricia_key!("0x10"));
+    let bottom_key = StorageKey(patricia_key!("0x11"));
+    let top_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let bottom_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let top = DictStateReader {
+        storage_view: HashMap::from([((contract_address, top_key), stark_felt!("0x1"))]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!("0x1")))]),
+        class_hash_to_class: HashMap::from([(
+            top_contract.get_class_hash(),
+            top_contract.get_class(),
+        )]),
+        ..Default::default()
+    };
+    let bottom = DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, top_key), stark_felt!("0x2")),
+            ((contract_address, bottom_key), stark_felt!("0x2")),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!("0x2")))]),
+        address_to_class_hash: HashMap::from([(
+            contract_address,
+            bottom_contract.get_class_hash(),
+        )]),
+        class_hash_to_class: HashMap::from([(
+            bottom_contract.get_class_hash(),
+            bottom_contract.get_class(),
+        )]),
+        ..Default::default()
+    };
+    let state = LayeredStateReader::new(top, PrefetchRecordingReader::new(bottom));
+
+    // Values of the top reader shadow the bottom ones; missing values are read from the bottom.
+    assert_eq!(state.get_storage_at(contract_address, top_key).unwrap(), stark_felt!("0x1"));
+    assert_eq!(state.get_storage_at(contract_address, bottom_key).unwrap(), stark_felt!("0x2"));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!("0x1")));
+    assert_eq!(
+        state.get_class_hash_at(contract_address).unwrap(),
+        bottom_contract.get_class_hash()
+    );
+    for contract in [top_contract, bottom_contract] {
+        assert_eq!(
+            state.get_compiled_contract_class(contract.get_class_hash()).unwrap(),
+            contract.get_class()
+        );
+    }
+
+    // Classes undeclared in both readers are reported.
+    let undeclared_class_hash = class_hash!("0x1234");
+    assert_matches!(
+        state.get_compiled_contract_class(undeclared_class_hash),
+        Err(StateError::UndeclaredClassHash(class_hash)) if class_hash == undeclared_class_hash
+    );
+
+    // Prefetches forward the values the top reader does not hold to the bottom one.
+    let prefetched_state = state
+        .prefetch(
+            &[(contract_address, top_key), (contract_address, bottom_key)],
+            &[contract_address],
+            &[
+                top_contract.get_class_hash(),
+                bottom_contract.get_class_hash(),
+                undeclared_class_hash,
+            ],
+        )
+        .unwrap();
+    assert_eq!(
+        state.bottom.batches.into_inner(),
+        vec![PrefetchBatch {
+            storage_entries: HashSet::from([(contract_address, bottom_key)]),
+            contract_addresses: HashSet::from([contract_address]),
+            class_hashes: HashSet::from([bottom_contract.get_class_hash(), undeclared_class_hash]),
+        }]
+    );
+    assert_eq!(
+        prefetched_state,
+        PrefetchedState {
+            storage: HashMap::from([
+                ((contract_address, top_key), stark_felt!("0x1")),
+                ((contract_address, bottom_key), stark_felt!("0x2")),
+            ]),
+            nonces: HashMap::from([(contract_address, Nonce(stark_felt!("0x1")))]),
+            class_hashes: HashMap::from([(contract_address, bottom_contract.get_class_hash())]),
+            contract_classes: HashMap::from([
+                (top_contract.get_class_hash(), top_contract.get_class()),
+                (bottom_contract.get_class_hash(), bottom_contract.get_class()),
+            ]),
+        }
+    );
+}
+
+#[test]
+fn test_caching_state_reader() {
+    let contract_address = contract_address!("0x100");
+    let key0 = StorageKey(patricia_key!("0x10"));
+    let key1 = StorageKey(patricia_key!("0x11"));
+    let contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let state = DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, key0), stark_felt!("0x1")),
+            ((contract_address, key1), stark_felt!("0x1")),
+        ]),
+        class_hash_to_class: HashMap::from([(contract.get_class_hash(), contract.get_class())]),
+        ..Default::default()
+    };
+    let mut state = CachingStateReader::new(state, 1);
+
+    // Read values are cached; changes of the underlying state are not observed.
+    assert_eq!(state.get_storage_at(contract_address, key0).unwrap(), stark_felt!("0x1"));
+    state.state.storage_view.insert((contract_address, key0), stark_felt!("0x2"));
+    assert_eq!(state.get_storage_at(contract_address, key0).unwrap(), stark_felt!("0x1"));
+
+    // Beyond the size bound, the least recently used values are evicted.
+    assert_eq!(state.get_storage_at(contract_address, key1).unwrap(), stark_felt!("0x1"));
+    assert_eq!(state.get_storage_at(contract_address, key0).unwrap(), stark_felt!("0x2"));
+
+    // Errors are not cached.
+    let class_hash = contract.get_class_hash();
+    let class = state.state.class_hash_to_class.remove(&class_hash).unwrap();
+    assert_matches!(
+        state.get_compiled_contract_class(class_hash),
+        Err(StateError::UndeclaredClassHash(_))
+    );
+    state.state.class_hash_to_class.insert(class_hash, class.clone());
+    assert_eq!(state.get_compiled_contract_class(class_hash).unwrap(), class);
+}
+
+#[test]
+fn test_pending_state_reader() {
+    let contract_address = contract_address!("0x100");
+    let pending_key = StorageKey(patricia_key!("0x10"));
+    let zeroed_key = StorageKey(patricia_key!("0x11"));
+    let unchanged_key = StorageKey(patricia_key!("0x12"));
+    let class_hash = class_hash!("0x20");
+    let pending_class_hash = class_hash!("0x21");
+    let pending_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let state = DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, pending_key), stark_felt!("0x1")),
+            ((contract_address, zeroed_key), stark_felt!("0x1")),
+            ((contract_address, unchanged_key), stark_felt!("0x1")),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!("0x1")))]),
+        address_to_class_hash: HashMap::from([(contract_address, class_hash)]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash,
+            CompiledClassHash(stark_felt!("0x30")),
+        )]),
+        ..Default::default()
+    };
+    let state_diff = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: indexmap!(contract_address => Nonce(stark_felt!("0x2"))),
+        storage_updates: indexmap!(contract_address => indexmap!(
+            pending_key => stark_felt!("0x2"),
+            zeroed_key => StarkFelt::ZERO,
+        )),
+        class_hash_to_compiled_class_hash: indexmap!(
+            pending_class_hash => CompiledClassHash(stark_felt!("0x31"))
+        ),
+    };
+    let state = PendingStateReader::new(PrefetchRecordingReader::new(state), state_diff)
+        .with_contract_class(pending_class_hash, pending_contract.get_class());
+
+    // Values in the diff, including zeros, shadow the underlying ones.
+    assert_eq!(state.get_storage_at(contract_address, pending_key).unwrap(), stark_felt!("0x2"));
+    assert_eq!(state.get_storage_at(contract_address, zeroed_key).unwrap(), StarkFelt::ZERO);
+    assert_eq!(state.get_storage_at(contract_address, unchanged_key).unwrap(), stark_felt!("0x1"));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!("0x2")));
+    assert_eq!(state.get_class_hash_at(contract_address).unwrap(), class_hash);
+    assert_eq!(
+        state.get_compiled_class_hash(class_hash).unwrap(),
+        CompiledClassHash(stark_felt!("0x30"))
+    );
+    assert_eq!(
+        state.get_compiled_class_hash(pending_class_hash).unwrap(),
+        CompiledClassHash(stark_felt!("0x31"))
+    );
+    assert_eq!(
+        state.get_compiled_contract_class(pending_class_hash).unwrap(),
+        pending_contract.get_class()
+    );
+
+    // Prefetches forward the values not in the diff to the underlying reader.
+    let prefetched_state = state
+        .prefetch(
+            &[
+                (contract_address, pending_key),
+                (contract_address, zeroed_key),
+                (contract_address, unchanged_key),
+            ],
+            &[contract_address],
+            &[pending_class_hash, class_hash],
+        )
+        .unwrap();
+    assert_eq!(
+        state.state.batches.into_inner(),
+        vec![PrefetchBatch {
+            storage_entries: HashSet::from([(contract_address, unchanged_key)]),
+            contract_addresses: HashSet::from([contract_address]),
+            class_hashes: HashSet::from([class_hash]),
+        }]
+    );
+    assert_eq!(
+        prefetched_state,
+        PrefetchedState {
+            storage: HashMap::from([
+                ((contract_address, pending_key), stark_felt!("0x2")),
+                ((contract_address, zeroed_key), StarkFelt::ZERO),
+                ((contract_address, unchanged_key), stark_felt!("0x1")),
+            ]),
+            nonces: HashMap::from([(contract_address, Nonce(stark_felt!("0x2")))]),
+            class_hashes: HashMap::from([(contract_address, class_hash)]),
+            contract_classes: HashMap::from([(pending_class_hash, pending_contract.get_class())]),
+        }
+    );
+}
@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use cairo_lang_starknet_classes::contract_class::ContractClass as SierraContractClass;
+use cairo_vm::types::errors::program_errors::ProgramError;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use thiserror::Error;
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use crate::state::cached_state::{CachedState, CommitmentStateDiff, StorageEntry};
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
+#[cfg(any(feature = "testing", test))]
+use crate::test_utils::dict_state_reader::DictStateReader;
+
+#[cfg(test)]
+#[path = "state_snapshot_test.rs"]
+pub mod test;
+
+#[derive(Debug, Error)]
+pub enum StateSnapshotError {
+    #[error("Failed to decode a binary state snapshot: {0}")]
+    BinaryDecodingError(String),
+    #[error("Failed to encode a binary state snapshot: {0}")]
+    BinaryEncodingError(String),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    #[error("Class {0:?} is missing from the state snapshot.")]
+    MissingClass(ClassHash),
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+}
+
+pub type StateSnapshotResult<T> = Result<T, StateSnapshotError>;
+
+/// The encodings of a state snapshot; both encode the same data model.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StateSnapshotFormat {
+    /// Human-readable; felts are encoded as hex strings, and classes in their usual JSON formats.
+    Json,
+    /// Compact, self-describing binary encoding (CBOR).
+    Binary,
+}
+
+/// A contract class, in the format it is compiled to (and declared with).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotContractClass {
+    Cairo0(DeprecatedContractClass),
+    /// The Sierra class is not required for execution, and may be omitted.
+    Cairo1 {
+        casm: CasmContractClass,
+        #[serde(default)]
+        sierra: Option<Box<SierraContractClass>>,
+    },
+}
+
+impl TryFrom<SnapshotContractClass> for ContractClass {
+    type Error = ProgramError;
+
+    fn try_from(contract_class: SnapshotContractClass) -> Result<Self, Self::Error> {
+        Ok(match contract_class {
+            SnapshotContractClass::Cairo0(deprecated_class) => {
+                ContractClassV0::try_from(deprecated_class)?.into()
+            }
+            SnapshotContractClass::Cairo1 { casm, .. } => ContractClassV1::try_from(casm)?.into(),
+        })
+    }
+}
+
+/// A full Starknet state; e.g., a genesis state, a test fixture, or the state a bug reproduces on.
+///
+/// JSON layout (all fields are optional, and default to empty):
+/// ```json
+/// {
+///     "storage": {"<address>": {"<key>": "<value>"}},
+///     "nonces": {"<address>": "<nonce>"},
+///     "class_hashes": {"<address>": "<class hash>"},
+///     "compiled_class_hashes": {"<class hash>": "<compiled class hash>"},
+///     "classes": {
+///         "<class hash>": {"cairo0": <compiled class>},
+///         "<class hash>": {"cairo1": {"casm": <CASM class>, "sierra": <Sierra class>}}
+///     }
+/// }
+/// ```
+/// Entries are kept sorted, so that dumping a snapshot is deterministic.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSnapshot {
+    pub storage: BTreeMap<ContractAddress, BTreeMap<StorageKey, StarkFelt>>,
+    pub nonces: BTreeMap<ContractAddress, Nonce>,
+    pub class_hashes: BTreeMap<ContractAddress, ClassHash>,
+    pub compiled_class_hashes: BTreeMap<ClassHash, CompiledClassHash>,
+    pub classes: BTreeMap<ClassHash, SnapshotContractClass>,
+}
+
+impl StateSnapshot {
+    pub fn from_reader(
+        reader: impl Read,
+        format: StateSnapshotFormat,
+    ) -> StateSnapshotResult<Self> {
+        match format {
+            StateSnapshotFormat::Json => Ok(serde_json::from_reader(reader)?),
+            StateSnapshotFormat::Binary => ciborium::from_reader(reader)
+                .map_err(|error| StateSnapshotError::BinaryDecodingError(error.to_string())),
+        }
+    }
+
+    pub fn to_writer(
+        &self,
+        writer: impl Write,
+        format: StateSnapshotFormat,
+    ) -> StateSnapshotResult<()> {
+        match format {
+            StateSnapshotFormat::Json => Ok(serde_json::to_writer_pretty(writer, self)?),
+            StateSnapshotFormat::Binary => ciborium::into_writer(self, writer)
+                .map_err(|error| StateSnapshotError::BinaryEncodingError(error.to_string())),
+        }
+    }
+
+    pub fn load(path: &Path, format: StateSnapshotFormat) -> StateSnapshotResult<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?), format)
+    }
+
+    pub fn dump(&self, path: &Path, format: StateSnapshotFormat) -> StateSnapshotResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut writer, format)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Applies the given state diff; classes declared by it should be added separately.
+    pub fn apply_state_diff(&mut self, state_diff: CommitmentStateDiff) {
+        for (address, storage_updates) in state_diff.storage_updates {
+            self.storage.entry(address).or_default().extend(storage_updates);
+        }
+        self.nonces.extend(state_diff.address_to_nonce);
+        self.class_hashes.extend(state_diff.address_to_class_hash);
+        self.compiled_class_hashes.extend(state_diff.class_hash_to_compiled_class_hash);
+    }
+
+    /// Applies the changes done through the given state, whose underlying state is the one of this
+    /// snapshot; classes declared through it should be added separately.
+    pub fn apply_cached_state<S: StateReader>(&mut self, state: &mut CachedState<S>) {
+        self.apply_state_diff(state.to_state_diff());
+    }
+}
+
+#[cfg(any(feature = "testing", test))]
+impl StateSnapshot {
+    /// Dumps the given state. Compiled classes cannot be converted back to the format they are
+    /// declared in, so the classes of the state are given in that format; each class of the state
+    /// must be given, and given classes the state does not have are added as well.
+    pub fn from_dict_state_reader(
+        state: &DictStateReader,
+        classes: BTreeMap<ClassHash, SnapshotContractClass>,
+    ) -> StateSnapshotResult<Self> {
+        if let Some(&class_hash) =
+            state.class_hash_to_class.keys().find(|class_hash| !classes.contains_key(class_hash))
+        {
+            return Err(StateSnapshotError::MissingClass(class_hash));
+        }
+
+        let mut storage: BTreeMap<ContractAddress, BTreeMap<StorageKey, StarkFelt>> =
+            BTreeMap::new();
+        for (&(address, key), &value) in &state.storage_view {
+            storage.entry(address).or_default().insert(key, value);
+        }
+
+        Ok(Self {
+            storage,
+            nonces: state.address_to_nonce.clone().into_iter().collect(),
+            class_hashes: state.address_to_class_hash.clone().into_iter().collect(),
+            compiled_class_hashes: state
+                .class_hash_to_compiled_class_hash
+                .clone()
+                .into_iter()
+                .collect(),
+            classes,
+        })
+    }
+}
+
+/// A `StateReader` of a state snapshot, with the classes compiled upfront.
+#[derive(Debug, Default)]
+pub struct SnapshotStateReader {
+    pub storage: HashMap<StorageEntry, StarkFelt>,
+    pub nonces: HashMap<ContractAddress, Nonce>,
+    pub class_hashes: HashMap<ContractAddress, ClassHash>,
+    pub compiled_class_hashes: HashMap<ClassHash, CompiledClassHash>,
+    pub classes: HashMap<ClassHash, ContractClass>,
+}
+
+impl TryFrom<StateSnapshot> for SnapshotStateReader {
+    type Error = StateSnapshotError;
+
+    fn try_from(snapshot: StateSnapshot) -> StateSnapshotResult<Self> {
+        let storage = snapshot
+            .storage
+            .into_iter()
+            .flat_map(|(address, storage)| {
+                storage.into_iter().map(move |(key, value)| ((address, key), value))
+            })
+            .collect();
+        let classes = snapshot
+            .classes
+            .into_iter()
+            .map(|(class_hash, contract_class)| Ok((class_hash, contract_class.try_into()?)))
+            .collect::<Result<_, ProgramError>>()?;
+
+        Ok(Self {
+            storage,
+            nonces: snapshot.nonces.into_iter().collect(),
+            class_hashes: snapshot.class_hashes.into_iter().collect(),
+            compiled_class_hashes: snapshot.compiled_class_hashes.into_iter().collect(),
+            classes,
+        })
+    }
+}
+
+impl StateReader for SnapshotStateReader {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        Ok(self.storage.get(&(contract_address, key)).copied().unwrap_or_default())
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        Ok(self.nonces.get(&contract_address).copied().unwrap_or_default())
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        Ok(self.class_hashes.get(&contract_address).copied().unwrap_or_default())
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.classes.get(&class_hash).cloned().ok_or(StateError::UndeclaredClassHash(class_hash))
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        Ok(self.compiled_class_hashes.get(&class_hash).copied().unwrap_or_default())
+    }
+
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let mut prefetched_state = PrefetchedState::default();
+        for &(contract_address, key) in storage_entries {
+            let value = self.get_storage_at(contract_address, key)?;
+            prefetched_state.storage.insert((contract_address, key), value);
+        }
+        for &contract_address in contract_addresses {
+            prefetched_state.nonces.insert(contract_address, self.get_nonce_at(contract_address)?);
+            prefetched_state
+                .class_hashes
+                .insert(contract_address, self.get_class_hash_at(contract_address)?);
+        }
+        for class_hash in class_hashes {
+            if let Some(contract_class) = self.classes.get(class_hash) {
+                prefetched_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(prefetched_state)
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, HashMap};
+
+use assert_matches::assert_matches;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::core::{ClassHash, CompiledClassHash, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::Calldata;
+use starknet_api::{calldata, stark_felt};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::execution::call_info::{CallExecution, Retdata};
+use crate::execution::entry_point::CallEntryPoint;
+use crate::retdata;
+use crate::state::cached_state::{CachedState, CommitmentStateDiff};
+use crate::state::state_api::{PrefetchedState, State, StateReader};
+use crate::state::state_snapshot::{
+    SnapshotContractClass, SnapshotStateReader, StateSnapshot, StateSnapshotError,
+    StateSnapshotFormat,
+};
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::dict_state_reader::DictStateReader;
+use crate::test_utils::{trivial_external_entry_point_new, CairoVersion};
+
+const CAIRO0_CONTRACT: FeatureContract = FeatureContract::TestContract(CairoVersion::Cairo0);
+const CAIRO1_CONTRACT: FeatureContract = FeatureContract::TestContract(CairoVersion::Cairo1);
+
+fn test_snapshot() -> StateSnapshot {
+    let (cairo0_address, cairo1_address) =
+        (CAIRO0_CONTRACT.get_instance_address(0), CAIRO1_CONTRACT.get_instance_address(0));
+    let casm = serde_json::from_str(&CAIRO1_CONTRACT.get_raw_class()).unwrap();
+
+    StateSnapshot {
+        storage: BTreeMap::from([(
+            cairo0_address,
+            BTreeMap::from([(StorageKey::from(7_u64), stark_felt!("0x8"))]),
+        )]),
+        nonces: BTreeMap::from([(cairo1_address, Nonce(stark_felt!("0x2")))]),
+        class_hashes: BTreeMap::from([
+            (cairo0_address, CAIRO0_CONTRACT.get_class_hash()),
+            (cairo1_address, CAIRO1_CONTRACT.get_class_hash()),
+        ]),
+        compiled_class_hashes: BTreeMap::from([(
+            CAIRO1_CONTRACT.get_class_hash(),
+            CompiledClassHash(stark_felt!("0x1234")),
+        )]),
+        classes: BTreeMap::from([
+            (
+                CAIRO0_CONTRACT.get_class_hash(),
+                SnapshotContractClass::Cairo0(CAIRO0_CONTRACT.get_deprecated_contract_class()),
+            ),
+            (
+                CAIRO1_CONTRACT.get_class_hash(),
+                SnapshotContractClass::Cairo1 { casm, sierra: None },
+            ),
+        ]),
+    }
+}
+
+#[rstest]
+fn test_snapshot_encoding(
+    #[values(StateSnapshotFormat::Json, StateSnapshotFormat::Binary)] format: StateSnapshotFormat,
+) {
+    let snapshot = test_snapshot();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot");
+    snapshot.dump(&path, format).unwrap();
+
+    let loaded_snapshot = StateSnapshot::load(&path, format).unwrap();
+    assert_eq!(
+        serde_json::to_value(loaded_snapshot).unwrap(),
+        serde_json::to_value(snapshot).unwrap()
+    );
+}
+
+#[test]
+fn test_snapshot_state_reader() {
+    let reader = SnapshotStateReader::try_from(test_snapshot()).unwrap();
+    let (cairo0_address, cairo1_address) =
+        (CAIRO0_CONTRACT.get_instance_address(0), CAIRO1_CONTRACT.get_instance_address(0));
+    assert_eq!(
+        reader.get_storage_at(cairo0_address, StorageKey::from(7_u64)).unwrap(),
+        stark_felt!("0x8")
+    );
+    assert_eq!(reader.get_nonce_at(cairo1_address).unwrap(), Nonce(stark_felt!("0x2")));
+    assert_eq!(reader.get_nonce_at(cairo0_address).unwrap(), Nonce::default());
+    assert_eq!(
+        reader.get_compiled_class_hash(CAIRO1_CONTRACT.get_class_hash()).unwrap(),
+        CompiledClassHash(stark_felt!("0x1234"))
+    );
+    assert_eq!(
+        reader.get_compiled_contract_class(CAIRO1_CONTRACT.get_class_hash()).unwrap(),
+        CAIRO1_CONTRACT.get_class()
+    );
+    assert!(reader.get_compiled_contract_class(ClassHash(stark_felt!("0x1"))).is_err());
+    assert_eq!(
+        reader
+            .prefetch(
+                &[(cairo0_address, StorageKey::from(7_u64))],
+                &[cairo1_address],
+                &[CAIRO1_CONTRACT.get_class_hash(), ClassHash(stark_felt!("0x1"))],
+            )
+            .unwrap(),
+        PrefetchedState {
+            storage: HashMap::from([(
+                (cairo0_address, StorageKey::from(7_u64)),
+                stark_felt!("0x8")
+            )]),
+            nonces: HashMap::from([(cairo1_address, Nonce(stark_felt!("0x2")))]),
+            class_hashes: HashMap::from([(cairo1_address, CAIRO1_CONTRACT.get_class_hash())]),
+            contract_classes: HashMap::from([(
+                CAIRO1_CONTRACT.get_class_hash(),
+                CAIRO1_CONTRACT.get_class()
+            )]),
+        }
+    );
+
+    // Execute on top of the snapshot.
+    let mut state = CachedState::from(reader);
+    let (key, value) = (stark_felt!(1234_u16), stark_felt!(18_u8));
+    let entry_point_call = CallEntryPoint {
+        calldata: calldata![key, value],
+        entry_point_selector: selector_from_name("test_storage_read_write"),
+        ..trivial_external_entry_point_new(CAIRO0_CONTRACT)
+    };
+    assert_eq!(
+        entry_point_call.execute_directly(&mut state).unwrap().execution,
+        CallExecution::from_retdata(retdata![value])
+    );
+}
+
+#[test]
+fn test_apply_state_diff() {
+    let mut snapshot = test_snapshot();
+    let cairo0_address = CAIRO0_CONTRACT.get_instance_address(0);
+    let (key, other_key) = (StorageKey::from(7_u64), StorageKey::from(8_u64));
+    snapshot.apply_state_diff(CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::from([(cairo0_address, Nonce(stark_felt!("0x1")))]),
+        storage_updates: IndexMap::from([(
+            cairo0_address,
+            IndexMap::from([(other_key, stark_felt!("0x9"))]),
+        )]),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    });
+
+    assert_eq!(
+        snapshot.storage[&cairo0_address],
+        BTreeMap::from([(key, stark_felt!("0x8")), (other_key, stark_felt!("0x9"))])
+    );
+    assert_eq!(snapshot.nonces[&cairo0_address], Nonce(stark_felt!("0x1")));
+}
+
+#[test]
+fn test_dump_cached_state() {
+    let cairo0_address = CAIRO0_CONTRACT.get_instance_address(0);
+    let (key, other_key) = (StorageKey::from(7_u64), StorageKey::from(8_u64));
+    let dict_state_reader = DictStateReader {
+        storage_view: HashMap::from([((cairo0_address, key), stark_felt!("0x8"))]),
+        address_to_class_hash: HashMap::from([(cairo0_address, CAIRO0_CONTRACT.get_class_hash())]),
+        class_hash_to_class: HashMap::from([(
+            CAIRO0_CONTRACT.get_class_hash(),
+            CAIRO0_CONTRACT.get_class(),
+        )]),
+        ..Default::default()
+    };
+    // The classes of the state must be given.
+    assert_matches!(
+        StateSnapshot::from_dict_state_reader(&dict_state_reader, BTreeMap::new()),
+        Err(StateSnapshotError::MissingClass(class_hash))
+        if class_hash == CAIRO0_CONTRACT.get_class_hash()
+    );
+    let classes = BTreeMap::from([(
+        CAIRO0_CONTRACT.get_class_hash(),
+        SnapshotContractClass::Cairo0(CAIRO0_CONTRACT.get_deprecated_contract_class()),
+    )]);
+    let mut snapshot = StateSnapshot::from_dict_state_reader(&dict_state_reader, classes).unwrap();
+    assert!(snapshot.classes.contains_key(&CAIRO0_CONTRACT.get_class_hash()));
+    assert_eq!(snapshot.storage[&cairo0_address], BTreeMap::from([(key, stark_felt!("0x8"))]));
+    assert_eq!(snapshot.class_hashes[&cairo0_address], CAIRO0_CONTRACT.get_class_hash());
+
+    let mut state = CachedState::from(dict_state_reader);
+    state.set_storage_at(cairo0_address, other_key, stark_felt!("0x9")).unwrap();
+    state.increment_nonce(cairo0_address).unwrap();
+    snapshot.apply_cached_state(&mut state);
+    assert_eq!(
+        snapshot.storage[&cairo0_address],
+        BTreeMap::from([(key, stark_felt!("0x8")), (other_key, stark_felt!("0x9"))])
+    );
+    assert_eq!(snapshot.nonces[&cairo0_address], Nonce(stark_felt!("0x1")));
+}
@@ -4,6 +4,7 @@ pub mod deploy_account;
 pub mod dict_state_reader;
 pub mod initial_test_state;
 pub mod invoke;
+pub mod prefetch_recording_reader;
 pub mod prices;
 pub mod struct_impls;
 
@@ -101,7 +101,7 @@ impl FeatureContract {
             }
     }
 
-    fn get_compiled_path(&self) -> String {
+    pub fn get_compiled_path(&self) -> String {
         let cairo_version = self.cairo_version();
         let contract_name = match self {
             Self::AccountWithLongValidate(_) => ACCOUNT_LONG_VALIDATE_NAME,
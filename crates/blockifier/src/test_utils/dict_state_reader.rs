@@ -7,7 +7,7 @@ use starknet_api::state::StorageKey;
 use crate::execution::contract_class::ContractClass;
 use crate::state::cached_state::StorageEntry;
 use crate::state::errors::StateError;
-use crate::state::state_api::{StateReader, StateResult};
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
 
 /// A simple implementation of `StateReader` using `HashMap`s as storage.
 #[derive(Debug, Default)]
@@ -57,4 +57,30 @@ impl StateReader for DictStateReader {
             self.class_hash_to_compiled_class_hash.get(&class_hash).copied().unwrap_or_default();
         Ok(compiled_class_hash)
     }
+
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        let mut prefetched_state = PrefetchedState::default();
+        for &(contract_address, key) in storage_entries {
+            let value = self.get_storage_at(contract_address, key)?;
+            prefetched_state.storage.insert((contract_address, key), value);
+        }
+        for &contract_address in contract_addresses {
+            prefetched_state.nonces.insert(contract_address, self.get_nonce_at(contract_address)?);
+            prefetched_state
+                .class_hashes
+                .insert(contract_address, self.get_class_hash_at(contract_address)?);
+        }
+        for class_hash in class_hashes {
+            if let Some(contract_class) = self.class_hash_to_class.get(class_hash) {
+                prefetched_state.contract_classes.insert(*class_hash, contract_class.clone());
+            }
+        }
+
+        Ok(prefetched_state)
+    }
 }
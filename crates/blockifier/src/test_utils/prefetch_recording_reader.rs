@@ -0,0 +1,83 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::StorageEntry;
+use crate::state::errors::StateError;
+use crate::state::state_api::{PrefetchedState, StateReader, StateResult};
+
+/// The keys of a single [`StateReader::prefetch`] batch.
+#[derive(Debug, Default, PartialEq)]
+pub struct PrefetchBatch {
+    pub storage_entries: HashSet<StorageEntry>,
+    pub contract_addresses: HashSet<ContractAddress>,
+    pub class_hashes: HashSet<ClassHash>,
+}
+
+/// A `StateReader` recording the prefetch batches requested from it, and proxying all reads to
+/// the underlying reader.
+#[derive(Debug, Default)]
+pub struct PrefetchRecordingReader<S: StateReader> {
+    pub state: S,
+    pub batches: RefCell<Vec<PrefetchBatch>>,
+    /// Whether prefetches fail (after being recorded); e.g., as with an unavailable backend.
+    pub fails_prefetch: bool,
+}
+
+impl<S: StateReader> PrefetchRecordingReader<S> {
+    pub fn new(state: S) -> Self {
+        Self { state, batches: RefCell::default(), fails_prefetch: false }
+    }
+
+    pub fn with_failing_prefetch(mut self) -> Self {
+        self.fails_prefetch = true;
+        self
+    }
+}
+
+impl<S: StateReader> StateReader for PrefetchRecordingReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.state.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.state.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.state.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.state.get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.state.get_compiled_class_hash(class_hash)
+    }
+
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        self.batches.borrow_mut().push(PrefetchBatch {
+            storage_entries: storage_entries.iter().copied().collect(),
+            contract_addresses: contract_addresses.iter().copied().collect(),
+            class_hashes: class_hashes.iter().copied().collect(),
+        });
+        if self.fails_prefetch {
+            return Err(StateError::StateReadError("Prefetch failed.".to_string()));
+        }
+        self.state.prefetch(storage_entries, contract_addresses, class_hashes)
+    }
+}
@@ -123,6 +123,9 @@ impl BlockContext {
             block_info: BlockInfo::create_for_testing(),
             chain_info: ChainInfo::create_for_testing(),
             versioned_constants: VersionedConstants::create_for_testing(),
+            concurrency_mode: false,
+            tracer: None,
+            contract_executors: None,
         }
     }
 
@@ -131,6 +134,9 @@ impl BlockContext {
             block_info: BlockInfo::create_for_testing(),
             chain_info: ChainInfo::create_for_testing(),
             versioned_constants: VersionedConstants::create_for_account_testing(),
+            concurrency_mode: false,
+            tracer: None,
+            contract_executors: None,
         }
     }
 
@@ -1,13 +1,15 @@
 use std::sync::Arc;
 
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use itertools::Itertools;
 use starknet_api::calldata;
-use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{Calldata, Fee, ResourceBounds, TransactionVersion};
 
-use crate::abi::abi_utils::selector_from_name;
+use crate::abi::abi_utils::{get_fee_token_var_address, selector_from_name};
+use crate::abi::sierra_types::next_storage_key;
 use crate::context::{BlockContext, TransactionContext};
 use crate::execution::call_info::{CallInfo, Retdata};
 use crate::execution::contract_class::ContractClass;
@@ -18,14 +20,15 @@ use crate::fee::fee_utils::{get_fee_by_gas_vector, verify_can_pay_committed_boun
 use crate::fee::gas_usage::{compute_discounted_gas_from_gas_vector, estimate_minimal_gas_vector};
 use crate::retdata;
 use crate::state::cached_state::{CachedState, TransactionalState};
-use crate::state::state_api::{State, StateReader};
+use crate::state::state_api::{State, StateReader, StateResult};
 use crate::transaction::constants;
 use crate::transaction::errors::{
     TransactionExecutionError, TransactionFeeError, TransactionPreValidationError,
 };
 use crate::transaction::objects::{
-    HasRelatedFeeType, ResourcesMapping, TransactionExecutionInfo, TransactionExecutionResult,
-    TransactionInfo, TransactionInfoCreator, TransactionPreValidationResult,
+    HasRelatedFeeType, NonceCheck, ResourcesMapping, TransactionExecutionInfo,
+    TransactionExecutionResult, TransactionInfo, TransactionInfoCreator,
+    TransactionPreValidationResult,
 };
 use crate::transaction::transaction_types::TransactionType;
 use crate::transaction::transaction_utils::update_remaining_gas;
@@ -47,7 +50,7 @@ mod flavors_test;
 mod post_execution_test;
 
 /// Represents a paid Starknet transaction.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum AccountTransaction {
     Declare(DeclareTransaction),
     DeployAccount(DeployAccountTransaction),
@@ -119,6 +122,49 @@ impl AccountTransaction {
         calldata.0.len()
     }
 
+    /// Warms the given state with the values the transaction is expected to read: the nonce,
+    /// class hash and class of the sender and of the called contracts, and the fee token balances
+    /// of the sender and the sequencer. The called contracts are taken from `__execute__`
+    /// calldata of the `Array<Call>` layout; other calldata layouts are not parsed.
+    pub fn prefetch_state(
+        &self,
+        state: &impl StateReader,
+        block_context: &BlockContext,
+    ) -> StateResult<()> {
+        let sender_address = self.create_tx_info().sender_address();
+        let fee_token_address = block_context.chain_info.fee_token_address(&self.fee_type());
+        let mut storage_entries = vec![];
+        for account_address in [sender_address, block_context.block_info.sequencer_address] {
+            let low_key = get_fee_token_var_address(account_address);
+            storage_entries.push((fee_token_address, low_key));
+            storage_entries.push((fee_token_address, next_storage_key(&low_key)?));
+        }
+
+        let mut contract_addresses = vec![sender_address];
+        let mut class_hashes = vec![];
+        match self {
+            Self::Declare(_) => {}
+            Self::DeployAccount(tx) => class_hashes.push(tx.class_hash()),
+            Self::Invoke(tx) => {
+                contract_addresses.extend(multicall_targets(&tx.calldata()).unwrap_or_default())
+            }
+        }
+        let prefetched_state =
+            state.prefetch(&storage_entries, &contract_addresses, &class_hashes)?;
+
+        // The classes of the contracts are only known once their class hashes are read.
+        let class_hashes: Vec<ClassHash> = prefetched_state
+            .class_hashes
+            .into_values()
+            .filter(|class_hash| *class_hash != ClassHash::default())
+            .unique()
+            .collect();
+        if !class_hashes.is_empty() {
+            state.prefetch(&[], &[], &class_hashes)?;
+        }
+        Ok(())
+    }
+
     pub fn signature_length(&self) -> usize {
         let signature = match self {
             Self::Declare(tx) => tx.signature(),
@@ -161,10 +207,10 @@ impl AccountTransaction {
         state: &mut S,
         tx_context: &TransactionContext,
         charge_fee: bool,
-        strict_nonce_check: bool,
+        nonce_check: &NonceCheck,
     ) -> TransactionPreValidationResult<()> {
         let tx_info = &tx_context.tx_info;
-        Self::handle_nonce(state, tx_info, strict_nonce_check)?;
+        Self::handle_nonce(state, tx_info, nonce_check)?;
 
         if charge_fee && tx_info.enforce_fee()? {
             self.check_fee_bounds(tx_context)?;
@@ -230,7 +276,7 @@ impl AccountTransaction {
     fn handle_nonce(
         state: &mut dyn State,
         tx_info: &TransactionInfo,
-        strict: bool,
+        nonce_check: &NonceCheck,
     ) -> TransactionPreValidationResult<()> {
         if tx_info.is_v0() {
             return Ok(());
@@ -239,10 +285,16 @@ impl AccountTransaction {
         let address = tx_info.sender_address();
         let account_nonce = state.get_nonce_at(address)?;
         let incoming_tx_nonce = tx_info.nonce();
-        let valid_nonce = if strict {
-            account_nonce == incoming_tx_nonce
-        } else {
-            account_nonce <= incoming_tx_nonce
+        let (valid_nonce, expected_nonce) = match nonce_check {
+            NonceCheck::Strict => (account_nonce == incoming_tx_nonce, account_nonce),
+            NonceCheck::Relaxed => (account_nonce <= incoming_tx_nonce, account_nonce),
+            NonceCheck::Mempool(policy) => {
+                let valid_nonce = account_nonce <= incoming_tx_nonce;
+                if valid_nonce {
+                    policy.verify(address, account_nonce, incoming_tx_nonce)?;
+                }
+                (valid_nonce, policy.expected_nonce(account_nonce))
+            }
         };
         if valid_nonce {
             return Ok(state.increment_nonce(address)?);
@@ -250,6 +302,7 @@ impl AccountTransaction {
         Err(TransactionPreValidationError::InvalidNonce {
             address,
             account_nonce,
+            expected_nonce,
             incoming_tx_nonce,
         })
     }
@@ -270,7 +323,7 @@ impl AccountTransaction {
         }
     }
 
-    fn handle_fee(
+    pub(crate) fn handle_fee(
         &self,
         state: &mut dyn State,
         tx_context: Arc<TransactionContext>,
@@ -281,6 +334,11 @@ impl AccountTransaction {
             // Fee charging is not enforced in some transaction simulations and tests.
             return Ok(None);
         }
+        if tx_context.block_context.concurrency_mode {
+            // Every transaction transfers its fee to the sequencer; the transfer is done upon
+            // commit, so that speculative executions do not conflict over the sequencer balance.
+            return Ok(None);
+        }
 
         // Charge fee.
         let fee_transfer_call_info = Self::execute_fee_transfer(state, tx_context, actual_fee)?;
@@ -580,8 +638,7 @@ impl<S: StateReader> ExecutableTransaction<S> for AccountTransaction {
         self.verify_tx_version(tx_context.tx_info.version())?;
 
         // Nonce and fee check should be done before running user code.
-        let strict_nonce_check = true;
-        self.perform_pre_validation_stage(state, &tx_context, charge_fee, strict_nonce_check)?;
+        self.perform_pre_validation_stage(state, &tx_context, charge_fee, &NonceCheck::Strict)?;
 
         // Run validation and execution.
         let mut remaining_gas = block_context.versioned_constants.tx_initial_gas();
@@ -605,6 +662,8 @@ impl<S: StateReader> ExecutableTransaction<S> for AccountTransaction {
         )?;
 
         let fee_transfer_call_info = self.handle_fee(state, tx_context, final_fee, charge_fee)?;
+        let state_changes = state.get_actual_state_changes()?;
+        let state_accesses = state.get_state_accesses();
 
         let tx_execution_info = TransactionExecutionInfo {
             validate_call_info,
@@ -615,6 +674,8 @@ impl<S: StateReader> ExecutableTransaction<S> for AccountTransaction {
             actual_resources: final_resources,
             revert_error,
             bouncer_resources,
+            state_changes,
+            state_accesses,
         };
         Ok(tx_execution_info)
     }
@@ -724,3 +785,21 @@ impl ValidatableTransaction for AccountTransaction {
         Ok(Some(validate_call_info))
     }
 }
+
+/// Returns the called contracts of `__execute__` calldata of the `Array<Call>` layout; i.e., the
+/// number of calls, followed by the address, selector, calldata length and calldata of each call.
+/// Returns `None` if the calldata is not of this layout.
+fn multicall_targets(calldata: &Calldata) -> Option<Vec<ContractAddress>> {
+    let calldata = &calldata.0;
+    let n_calls = usize::try_from(*calldata.first()?).ok()?;
+
+    let mut targets = vec![];
+    let mut call_offset: usize = 1;
+    for _ in 0..n_calls {
+        targets.push(ContractAddress::try_from(*calldata.get(call_offset)?).ok()?);
+        let call_calldata_length = usize::try_from(*calldata.get(call_offset + 2)?).ok()?;
+        call_offset = (call_offset + 3).checked_add(call_calldata_length)?;
+    }
+
+    (call_offset == calldata.len()).then_some(targets)
+}
@@ -37,12 +37,13 @@ use crate::test_utils::declare::declare_tx;
 use crate::test_utils::deploy_account::deploy_account_tx;
 use crate::test_utils::initial_test_state::{fund_account, test_state};
 use crate::test_utils::invoke::InvokeTxArgs;
+use crate::test_utils::prefetch_recording_reader::{PrefetchBatch, PrefetchRecordingReader};
 use crate::test_utils::{
     create_calldata, create_trivial_calldata, get_syscall_resources, get_tx_resources,
     u64_from_usize, CairoVersion, NonceManager, BALANCE, DEFAULT_STRK_L1_GAS_PRICE, MAX_FEE,
     MAX_L1_GAS_AMOUNT, MAX_L1_GAS_PRICE,
 };
-use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::account_transaction::{multicall_targets, AccountTransaction};
 use crate::transaction::constants::TRANSFER_ENTRY_POINT_NAME;
 use crate::transaction::errors::TransactionExecutionError;
 use crate::transaction::objects::{FeeType, HasRelatedFeeType, TransactionInfoCreator};
@@ -1189,3 +1190,74 @@ fn test_count_actual_storage_changes(
     assert_eq!(expected_storage_update_transfer, state_changes_transfer.storage_updates);
     assert_eq!(state_changes_count_3, expected_state_changes_count_3);
 }
+
+#[rstest]
+fn test_prefetch_state(block_context: BlockContext) {
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo1);
+    let cairo0_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let cairo1_contract = FeatureContract::TestContract(CairoVersion::Cairo1);
+    let state = PrefetchRecordingReader::new(test_state(
+        &block_context.chain_info,
+        BALANCE,
+        &[(account, 1), (cairo0_contract, 1), (cairo1_contract, 1)],
+    ));
+    let account_address = account.get_instance_address(0);
+    let cairo0_address = cairo0_contract.get_instance_address(0);
+    let cairo1_address = cairo1_contract.get_instance_address(0);
+    let selector = selector_from_name("return_result").0;
+
+    // A multicall of both contracts; the classes of the sender and the targets are prefetched once
+    // their class hashes are.
+    let calldata = calldata![
+        stark_felt!(2_u8),
+        *cairo0_address.0.key(),
+        selector,
+        stark_felt!(1_u8),
+        stark_felt!(2_u8),
+        *cairo1_address.0.key(),
+        selector,
+        stark_felt!(1_u8),
+        stark_felt!(2_u8)
+    ];
+    let tx = account_invoke_tx(invoke_tx_args! { sender_address: account_address, calldata });
+    tx.prefetch_state(&state, &block_context).unwrap();
+    let batches = state.batches.into_inner();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(
+        batches[0].contract_addresses,
+        HashSet::from([account_address, cairo0_address, cairo1_address])
+    );
+    assert_eq!(
+        batches[1],
+        PrefetchBatch {
+            class_hashes: HashSet::from([
+                account.get_class_hash(),
+                cairo0_contract.get_class_hash(),
+                cairo1_contract.get_class_hash(),
+            ]),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_multicall_targets() {
+    let (first_target, second_target) = (contract_address!("0x100"), contract_address!("0x200"));
+    let selector = selector_from_name("return_result").0;
+    let multicall_calldata = calldata![
+        stark_felt!(2_u8),
+        *first_target.0.key(),
+        selector,
+        stark_felt!(0_u8),
+        *second_target.0.key(),
+        selector,
+        stark_felt!(1_u8),
+        stark_felt!(2_u8)
+    ];
+    assert_eq!(multicall_targets(&multicall_calldata), Some(vec![first_target, second_target]));
+
+    // Calldata of other layouts, e.g., of a single call, is not parsed.
+    let single_call_calldata = create_calldata(first_target, "return_result", &[stark_felt!(2_u8)]);
+    assert_eq!(multicall_targets(&single_call_calldata), None);
+    assert_eq!(multicall_targets(&calldata![]), None);
+}
@@ -104,11 +104,31 @@ pub enum TransactionExecutionError {
 
 #[derive(Debug, Error)]
 pub enum TransactionPreValidationError {
+    #[error(
+        "Duplicate transaction nonce of contract at address {address:?}; a transaction with nonce \
+         {nonce:?} is already pending. Expected nonce: {expected_nonce:?}."
+    )]
+    DuplicateNonce { address: ContractAddress, nonce: Nonce, expected_nonce: Nonce },
     #[error(
         "Invalid transaction nonce of contract at address {address:?}. Account nonce: \
-         {account_nonce:?}; got: {incoming_tx_nonce:?}."
+         {account_nonce:?}; expected nonce: {expected_nonce:?}; got: {incoming_tx_nonce:?}."
+    )]
+    InvalidNonce {
+        address: ContractAddress,
+        account_nonce: Nonce,
+        expected_nonce: Nonce,
+        incoming_tx_nonce: Nonce,
+    },
+    #[error(
+        "Transaction nonce of contract at address {address:?} is too far in the future. Expected \
+         nonce: {expected_nonce:?}; got: {incoming_tx_nonce:?}; max allowed gap: {max_nonce_gap}."
     )]
-    InvalidNonce { address: ContractAddress, account_nonce: Nonce, incoming_tx_nonce: Nonce },
+    NonceTooFarInFuture {
+        address: ContractAddress,
+        expected_nonce: Nonce,
+        incoming_tx_nonce: Nonce,
+        max_nonce_gap: u64,
+    },
     #[error(transparent)]
     StateError(#[from] StateError),
     #[error(transparent)]
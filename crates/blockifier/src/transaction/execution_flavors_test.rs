@@ -185,11 +185,11 @@ fn test_simulate_validate_charge_fee_pre_validate(
         result.unwrap_err(),
         TransactionExecutionError::TransactionPreValidationError(
             TransactionPreValidationError::InvalidNonce {
-                address, account_nonce: expected_nonce, incoming_tx_nonce
+                address, account_nonce: actual_account_nonce, expected_nonce, incoming_tx_nonce
             }
         )
-        if (address, expected_nonce, incoming_tx_nonce) ==
-        (account_address, account_nonce, invalid_nonce)
+        if (address, actual_account_nonce, expected_nonce, incoming_tx_nonce) ==
+        (account_address, account_nonce, account_nonce, invalid_nonce)
     );
 
     // Second scenario: minimal fee not covered. Actual fee is precomputed.
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use cairo_felt::Felt252;
 use num_traits::Pow;
@@ -18,7 +18,7 @@ use crate::execution::execution_utils::{felt_to_stark_felt, stark_felt_to_felt};
 use crate::fee::eth_gas_constants;
 use crate::fee::fee_utils::calculate_tx_fee;
 use crate::fee::gas_usage::{get_da_gas_cost, get_messages_gas_usage};
-use crate::state::cached_state::StateChangesCount;
+use crate::state::cached_state::{StateAccesses, StateChanges, StateChangesCount};
 use crate::transaction::constants;
 use crate::transaction::errors::{
     TransactionExecutionError, TransactionFeeError, TransactionPreValidationError,
@@ -189,6 +189,71 @@ pub struct CommonAccountFields {
     pub only_query: bool,
 }
 
+/// How the nonce of a transaction is checked against the nonce of its sender.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NonceCheck {
+    /// The transaction nonce must equal the account nonce; used for execution.
+    Strict,
+    /// Any nonce at or above the account nonce is accepted.
+    Relaxed,
+    /// Takes the pending transactions of the account into account; used by mempools.
+    Mempool(MempoolNoncePolicy),
+}
+
+/// Checks a transaction nonce against the nonces of the pending transactions of its sender; e.g.,
+/// those in a mempool. The expected nonce is the first nonce from the account nonce onwards that
+/// is not pending.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MempoolNoncePolicy {
+    pub pending_nonces: BTreeSet<Nonce>,
+    /// The maximal distance of an accepted nonce from the expected nonce; unbounded if `None`.
+    pub max_nonce_gap: Option<u64>,
+}
+
+impl MempoolNoncePolicy {
+    pub fn expected_nonce(&self, account_nonce: Nonce) -> Nonce {
+        let mut expected_nonce = account_nonce;
+        while self.pending_nonces.contains(&expected_nonce) {
+            let next_nonce = stark_felt_to_felt(expected_nonce.0) + Felt252::from(1_u8);
+            expected_nonce = Nonce(felt_to_stark_felt(&next_nonce));
+        }
+
+        expected_nonce
+    }
+
+    /// Verifies a nonce that is at least the account nonce.
+    pub fn verify(
+        &self,
+        address: ContractAddress,
+        account_nonce: Nonce,
+        incoming_tx_nonce: Nonce,
+    ) -> TransactionPreValidationResult<()> {
+        let expected_nonce = self.expected_nonce(account_nonce);
+        if self.pending_nonces.contains(&incoming_tx_nonce) {
+            return Err(TransactionPreValidationError::DuplicateNonce {
+                address,
+                nonce: incoming_tx_nonce,
+                expected_nonce,
+            });
+        }
+
+        let Some(max_nonce_gap) = self.max_nonce_gap else {
+            return Ok(());
+        };
+        let max_allowed_nonce = stark_felt_to_felt(expected_nonce.0) + Felt252::from(max_nonce_gap);
+        if incoming_tx_nonce.0 > felt_to_stark_felt(&max_allowed_nonce) {
+            return Err(TransactionPreValidationError::NonceTooFarInFuture {
+                address,
+                expected_nonce,
+                incoming_tx_nonce,
+                max_nonce_gap,
+            });
+        }
+
+        Ok(())
+    }
+}
+
 /// Contains the information gathered by the execution of a transaction.
 #[derive(Debug, Default, Eq, PartialEq, Serialize)]
 pub struct TransactionExecutionInfo {
@@ -211,6 +276,12 @@ pub struct TransactionExecutionInfo {
     pub revert_error: Option<String>,
     /// If not None, contains the resources to account for in the bouncer.
     pub bouncer_resources: ResourcesMapping,
+    /// The changes made by the transaction (including the fee transfer) to the state it was
+    /// executed on.
+    pub state_changes: StateChanges,
+    /// The keys read and written by the transaction (including the fee transfer).
+    #[serde(skip)]
+    pub state_accesses: StateAccesses,
 }
 
 impl TransactionExecutionInfo {
@@ -429,7 +500,7 @@ pub trait HasRelatedFeeType {
     }
 }
 
-#[derive(Clone, Copy, Hash, EnumIter, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, EnumIter, Eq, PartialEq)]
 pub enum FeeType {
     Strk,
     Eth,
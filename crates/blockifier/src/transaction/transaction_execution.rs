@@ -21,7 +21,7 @@ use crate::transaction::transactions::{
 };
 
 // TODO: Move into transaction.rs, makes more sense to be defined there.
-#[derive(Debug, derive_more::From)]
+#[derive(Clone, Debug, derive_more::From)]
 pub enum Transaction {
     AccountTransaction(AccountTransaction),
     L1HandlerTransaction(L1HandlerTransaction),
@@ -138,6 +138,8 @@ impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
             actual_resources: actual_resources.clone(),
             revert_error: None,
             bouncer_resources: actual_resources,
+            state_changes: state.get_actual_state_changes()?,
+            state_accesses: state.get_state_accesses(),
         })
     }
 }
@@ -9,7 +9,9 @@ use num_traits::Pow;
 use once_cell::sync::Lazy;
 use pretty_assertions::assert_eq;
 use rstest::{fixture, rstest};
-use starknet_api::core::{ChainId, ClassHash, ContractAddress, EthAddress, Nonce, PatriciaKey};
+use starknet_api::core::{
+    ChainId, ClassHash, CompiledClassHash, ContractAddress, EthAddress, Nonce, PatriciaKey,
+};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::StorageKey;
@@ -39,7 +41,7 @@ use crate::fee::fee_utils::calculate_tx_fee;
 use crate::fee::gas_usage::{
     estimate_minimal_gas_vector, get_da_gas_cost, get_onchain_data_segment_length,
 };
-use crate::state::cached_state::{CachedState, StateChangesCount};
+use crate::state::cached_state::{CachedState, StateChanges, StateChangesCount, StorageEntry};
 use crate::state::errors::StateError;
 use crate::state::state_api::{State, StateReader};
 use crate::test_utils::contracts::FeatureContract;
@@ -62,7 +64,7 @@ use crate::transaction::errors::{
     TransactionExecutionError, TransactionFeeError, TransactionPreValidationError,
 };
 use crate::transaction::objects::{
-    FeeType, GasVector, HasRelatedFeeType, ResourcesMapping, StarknetResources,
+    FeeType, GasVector, HasRelatedFeeType, NonceCheck, ResourcesMapping, StarknetResources,
     TransactionExecutionInfo, TransactionInfo,
 };
 use crate::transaction::test_utils::{
@@ -324,6 +326,23 @@ fn validate_final_balances(
     }
 }
 
+/// Returns the storage updates of a transfer of `actual_fee` to the (initially empty) sequencer
+/// balance, from an account holding `initial_account_balance`.
+fn expected_fee_transfer_storage_updates(
+    fee_token_address: ContractAddress,
+    erc20_account_balance_key: StorageKey,
+    initial_account_balance: u128,
+    actual_fee: Fee,
+) -> HashMap<StorageEntry, StarkFelt> {
+    HashMap::from([
+        (
+            (fee_token_address, erc20_account_balance_key),
+            stark_felt!(initial_account_balance - actual_fee.0),
+        ),
+        ((fee_token_address, test_erc20_sequencer_balance_key()), stark_felt!(actual_fee.0)),
+    ])
+}
+
 fn add_kzg_da_resources_to_resources_mapping(
     target: &mut ResourcesMapping,
     state_changes_count: &StateChangesCount,
@@ -514,6 +533,17 @@ fn test_invoke_tx(
         actual_resources: actual_resources.clone(),
         revert_error: None,
         bouncer_resources: actual_resources,
+        state_changes: StateChanges {
+            storage_updates: expected_fee_transfer_storage_updates(
+                chain_info.fee_token_address(fee_type),
+                get_fee_token_var_address(sender_address),
+                BALANCE,
+                expected_actual_fee,
+            ),
+            nonce_updates: HashMap::from([(sender_address, Nonce(stark_felt!(1_u8)))]),
+            ..Default::default()
+        },
+        state_accesses: actual_execution_info.state_accesses.clone(),
     };
 
     add_kzg_da_resources(
@@ -529,6 +559,7 @@ fn test_invoke_tx(
     // Test nonce update.
     let nonce_from_state = state.get_nonce_at(sender_address).unwrap();
     assert_eq!(nonce_from_state, Nonce(stark_felt!(1_u8)));
+    assert_eq!(actual_execution_info.state_accesses.nonce_writes, HashSet::from([sender_address]));
 
     // Test final balances.
     validate_final_balances(
@@ -1029,15 +1060,22 @@ fn test_invalid_nonce(account_cairo_version: CairoVersion) {
         account_invoke_tx(invoke_tx_args! { nonce: invalid_nonce, ..valid_invoke_tx_args.clone() });
     let invalid_tx_context = block_context.to_tx_context(&invalid_tx);
     let pre_validation_err = invalid_tx
-        .perform_pre_validation_stage(&mut transactional_state, &invalid_tx_context, false, true)
+        .perform_pre_validation_stage(
+            &mut transactional_state,
+            &invalid_tx_context,
+            false,
+            &NonceCheck::Strict,
+        )
         .unwrap_err();
 
     // Test error.
     assert_matches!(
         pre_validation_err,
-            TransactionPreValidationError::InvalidNonce {address, account_nonce, incoming_tx_nonce}
-        if (address, account_nonce, incoming_tx_nonce) ==
-        (valid_invoke_tx_args.sender_address, Nonce::default(), invalid_nonce)
+            TransactionPreValidationError::InvalidNonce {
+                address, account_nonce, expected_nonce, incoming_tx_nonce
+            }
+        if (address, account_nonce, expected_nonce, incoming_tx_nonce) ==
+        (valid_invoke_tx_args.sender_address, Nonce::default(), Nonce::default(), invalid_nonce)
     );
 
     // Non-strict.
@@ -1049,7 +1087,12 @@ fn test_invalid_nonce(account_cairo_version: CairoVersion) {
 
     let valid_tx_context = block_context.to_tx_context(&valid_tx);
     valid_tx
-        .perform_pre_validation_stage(&mut transactional_state, &valid_tx_context, false, false)
+        .perform_pre_validation_stage(
+            &mut transactional_state,
+            &valid_tx_context,
+            false,
+            &NonceCheck::Relaxed,
+        )
         .unwrap();
 
     // Negative flow: account nonce = 1, incoming tx nonce = 0.
@@ -1058,15 +1101,26 @@ fn test_invalid_nonce(account_cairo_version: CairoVersion) {
         account_invoke_tx(invoke_tx_args! { nonce: invalid_nonce, ..valid_invoke_tx_args.clone() });
     let invalid_tx_context = block_context.to_tx_context(&invalid_tx);
     let pre_validation_err = invalid_tx
-        .perform_pre_validation_stage(&mut transactional_state, &invalid_tx_context, false, false)
+        .perform_pre_validation_stage(
+            &mut transactional_state,
+            &invalid_tx_context,
+            false,
+            &NonceCheck::Relaxed,
+        )
         .unwrap_err();
 
     // Test error.
     assert_matches!(
         pre_validation_err,
-        TransactionPreValidationError::InvalidNonce {address, account_nonce, incoming_tx_nonce}
-        if (address, account_nonce, incoming_tx_nonce) ==
-        (valid_invoke_tx_args.sender_address, Nonce(stark_felt!(1_u8)), invalid_nonce)
+        TransactionPreValidationError::InvalidNonce {
+            address, account_nonce, expected_nonce, incoming_tx_nonce
+        }
+        if (address, account_nonce, expected_nonce, incoming_tx_nonce) == (
+            valid_invoke_tx_args.sender_address,
+            Nonce(stark_felt!(1_u8)),
+            Nonce(stark_felt!(1_u8)),
+            invalid_nonce
+        )
     );
 }
 
@@ -1205,6 +1259,25 @@ fn test_declare_tx(
         revert_error: None,
         actual_resources: actual_resources.clone(),
         bouncer_resources: actual_resources,
+        state_changes: StateChanges {
+            storage_updates: expected_fee_transfer_storage_updates(
+                chain_info.fee_token_address(fee_type),
+                get_fee_token_var_address(sender_address),
+                BALANCE,
+                expected_actual_fee,
+            ),
+            nonce_updates: match tx_version {
+                TransactionVersion::ZERO => HashMap::new(),
+                _ => HashMap::from([(sender_address, Nonce(stark_felt!(1_u8)))]),
+            },
+            // Only Cairo 1 classes have a compiled class hash.
+            compiled_class_hash_updates: match tx_version {
+                TransactionVersion::ZERO | TransactionVersion::ONE => HashMap::new(),
+                _ => HashMap::from([(class_hash, CompiledClassHash::default())]),
+            },
+            ..Default::default()
+        },
+        state_accesses: actual_execution_info.state_accesses.clone(),
     };
 
     add_kzg_da_resources(
@@ -1344,6 +1417,18 @@ fn test_deploy_account_tx(
         revert_error: None,
         actual_resources: actual_resources.clone(),
         bouncer_resources: actual_resources,
+        state_changes: StateChanges {
+            storage_updates: expected_fee_transfer_storage_updates(
+                chain_info.fee_token_address(fee_type),
+                deployed_account_balance_key,
+                BALANCE,
+                expected_actual_fee,
+            ),
+            nonce_updates: HashMap::from([(deployed_account_address, Nonce(stark_felt!(1_u8)))]),
+            class_hash_updates: HashMap::from([(deployed_account_address, class_hash)]),
+            ..Default::default()
+        },
+        state_accesses: actual_execution_info.state_accesses.clone(),
     };
 
     add_kzg_da_resources(
@@ -1798,10 +1883,21 @@ fn test_l1_handler(#[values(false, true)] use_kzg_da: bool) {
         actual_resources: expected_resource_mapping.clone(),
         revert_error: None,
         bouncer_resources: expected_resource_mapping,
+        state_changes: StateChanges {
+            storage_updates: HashMap::from([((contract_address, accessed_storage_key), value)]),
+            ..Default::default()
+        },
+        state_accesses: actual_execution_info.state_accesses.clone(),
     };
 
     // Check the actual returned execution info.
     assert_eq!(actual_execution_info, expected_execution_info);
+    let state_accesses = &actual_execution_info.state_accesses;
+    assert!(state_accesses.class_hash_reads.contains(&contract_address));
+    assert_eq!(
+        state_accesses.storage_writes,
+        HashSet::from([(contract_address, accessed_storage_key)])
+    );
 
     // Check the state changes.
     assert_eq!(
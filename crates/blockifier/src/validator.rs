@@ -0,0 +1,171 @@
+use starknet_api::core::Nonce;
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::TransactionHash;
+use thiserror::Error;
+
+use crate::blockifier::transaction_executor::{TransactionExecutor, TransactionExecutorError};
+use crate::bouncer::BouncerConfig;
+use crate::context::{BlockContext, TransactionContext};
+use crate::execution::call_info::CallInfo;
+use crate::fee::actual_cost::ActualCost;
+use crate::fee::fee_checks::PostValidationReport;
+use crate::state::cached_state::CachedState;
+use crate::state::errors::StateError;
+use crate::state::state_api::StateReader;
+use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::errors::{TransactionExecutionError, TransactionPreValidationError};
+use crate::transaction::objects::{NonceCheck, TransactionInfo};
+use crate::transaction::transaction_execution::Transaction;
+
+#[cfg(test)]
+#[path = "validator_test.rs"]
+pub mod test;
+
+/// The reason a transaction was rejected, by validation stage.
+#[derive(Debug, Error)]
+pub enum StatefulValidatorError {
+    #[error(transparent)]
+    StateError(#[from] StateError),
+    #[error(transparent)]
+    TransactionExecutionError(#[from] TransactionExecutionError),
+    #[error(transparent)]
+    TransactionExecutorError(#[from] TransactionExecutorError),
+    #[error(transparent)]
+    TransactionPreValidationError(#[from] TransactionPreValidationError),
+}
+
+pub type StatefulValidatorResult<T> = Result<T, StatefulValidatorError>;
+
+/// Manages transaction validation for pre-execution flows; e.g., a gateway, or a mempool.
+/// The validation step limits are those of the versioned constants of the block context.
+pub struct StatefulValidator<S: StateReader> {
+    tx_executor: TransactionExecutor<S>,
+    max_nonce_for_validation_skip: Nonce,
+}
+
+impl<S: StateReader> StatefulValidator<S> {
+    pub fn create(
+        state: CachedState<S>,
+        block_context: BlockContext,
+        max_nonce_for_validation_skip: Nonce,
+    ) -> Self {
+        let tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+        Self { tx_executor, max_nonce_for_validation_skip }
+    }
+
+    /// Validates the given transaction against the state; `deploy_account_tx_hash` is the hash of
+    /// the sender's deploy account transaction, if it was submitted but not yet processed.
+    /// The nonce check should not be strict, to admit transactions that follow pending ones.
+    pub fn perform_validations(
+        &mut self,
+        tx: AccountTransaction,
+        deploy_account_tx_hash: Option<TransactionHash>,
+        nonce_check: &NonceCheck,
+    ) -> StatefulValidatorResult<()> {
+        // Deploy account transactions should be fully executed, since the constructor must run
+        // before `__validate_deploy__`. The execution already includes all necessary validations,
+        // so they are skipped here.
+        if let AccountTransaction::DeployAccount(_) = tx {
+            self.execute(tx)?;
+            return Ok(());
+        }
+
+        let tx_context = self.tx_executor.block_context.to_tx_context(&tx);
+        // First, we check if the transaction should be skipped due to the deploy account not being
+        // processed. It is done before the pre-validations checks because, in these checks, we
+        // change the state (more precisely, we increment the nonce).
+        let skip_validate = self.skip_validate_due_to_unprocessed_deploy_account(
+            &tx_context.tx_info,
+            deploy_account_tx_hash,
+        )?;
+        self.perform_pre_validation_stage(&tx, &tx_context, nonce_check)?;
+
+        if skip_validate {
+            return Ok(());
+        }
+
+        // `__validate__` call.
+        let versioned_constants = &tx_context.block_context.versioned_constants();
+        let (_optional_call_info, actual_cost) =
+            self.validate(&tx, versioned_constants.tx_initial_gas())?;
+
+        // Post validations.
+        // TODO(Ayelet, 09/11/2023): Check call succeeded.
+        self.perform_post_validation_stage(&tx_context, &actual_cost)?;
+
+        Ok(())
+    }
+
+    /// Applicable solely to account deployment transactions: the execution of the constructor
+    /// is required before they can be validated.
+    fn execute(&mut self, tx: AccountTransaction) -> StatefulValidatorResult<()> {
+        let limit_execution_steps_by_resource_bounds = true;
+        // TODO(Ayelet, 09/11/2023): Check call succeeded.
+        self.tx_executor.execute(
+            Transaction::AccountTransaction(tx),
+            limit_execution_steps_by_resource_bounds,
+        )?;
+
+        Ok(())
+    }
+
+    fn perform_pre_validation_stage(
+        &mut self,
+        tx: &AccountTransaction,
+        tx_context: &TransactionContext,
+        nonce_check: &NonceCheck,
+    ) -> StatefulValidatorResult<()> {
+        // Run pre-validation in charge fee mode to perform fee and balance related checks.
+        let charge_fee = true;
+        tx.perform_pre_validation_stage(
+            &mut self.tx_executor.state,
+            tx_context,
+            charge_fee,
+            nonce_check,
+        )?;
+
+        Ok(())
+    }
+
+    // Check if deploy account was submitted but not processed yet. If so, then skip
+    // `__validate__` method for subsequent transactions for a better user experience.
+    // (they will otherwise fail solely because the deploy account hasn't been processed yet).
+    fn skip_validate_due_to_unprocessed_deploy_account(
+        &mut self,
+        tx_info: &TransactionInfo,
+        deploy_account_tx_hash: Option<TransactionHash>,
+    ) -> StatefulValidatorResult<bool> {
+        let nonce = self.tx_executor.state.get_nonce_at(tx_info.sender_address())?;
+        let tx_nonce = tx_info.nonce();
+
+        let deploy_account_not_processed =
+            deploy_account_tx_hash.is_some() && nonce == Nonce(StarkFelt::ZERO);
+        let is_post_deploy_nonce = Nonce(StarkFelt::ONE) <= tx_nonce;
+        let nonce_small_enough_to_qualify_for_validation_skip =
+            tx_nonce <= self.max_nonce_for_validation_skip;
+
+        let skip_validate = deploy_account_not_processed
+            && is_post_deploy_nonce
+            && nonce_small_enough_to_qualify_for_validation_skip;
+
+        Ok(skip_validate)
+    }
+
+    fn validate(
+        &mut self,
+        tx: &AccountTransaction,
+        remaining_gas: u64,
+    ) -> StatefulValidatorResult<(Option<CallInfo>, ActualCost)> {
+        Ok(self.tx_executor.validate(tx, remaining_gas)?)
+    }
+
+    fn perform_post_validation_stage(
+        &mut self,
+        tx_context: &TransactionContext,
+        actual_cost: &ActualCost,
+    ) -> StatefulValidatorResult<()> {
+        PostValidationReport::verify(tx_context, actual_cost)?;
+
+        Ok(())
+    }
+}
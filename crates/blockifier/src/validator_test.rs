@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+
+use assert_matches::assert_matches;
+use rstest::rstest;
+use starknet_api::core::Nonce;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::stark_felt;
+use starknet_api::transaction::{Fee, TransactionHash};
+
+use crate::context::BlockContext;
+use crate::state::cached_state::CachedState;
+use crate::state::state_api::State;
+use crate::test_utils::contracts::FeatureContract;
+use crate::test_utils::deploy_account::deploy_account_tx;
+use crate::test_utils::dict_state_reader::DictStateReader;
+use crate::test_utils::initial_test_state::{fund_account, test_state};
+use crate::test_utils::{create_calldata, CairoVersion, NonceManager, BALANCE, MAX_FEE};
+use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::errors::TransactionPreValidationError;
+use crate::transaction::objects::{MempoolNoncePolicy, NonceCheck};
+use crate::transaction::test_utils::account_invoke_tx;
+use crate::validator::{StatefulValidator, StatefulValidatorError};
+use crate::{deploy_account_tx_args, invoke_tx_args};
+
+fn stateful_validator(state: CachedState<DictStateReader>) -> StatefulValidator<DictStateReader> {
+    let max_nonce_for_validation_skip = Nonce(StarkFelt::ONE);
+    StatefulValidator::create(
+        state,
+        BlockContext::create_for_testing(),
+        max_nonce_for_validation_skip,
+    )
+}
+
+#[rstest]
+fn test_validate_invoke(
+    #[values(CairoVersion::Cairo0, CairoVersion::Cairo1)] cairo_version: CairoVersion,
+) {
+    let block_context = BlockContext::create_for_testing();
+    let account = FeatureContract::AccountWithoutValidations(cairo_version);
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let contract_instances = [(account, 1), (test_contract, 1)];
+    let validator =
+        || stateful_validator(test_state(block_context.chain_info(), BALANCE, &contract_instances));
+
+    let invoke_args = invoke_tx_args! {
+        sender_address: account.get_instance_address(0),
+        calldata: create_calldata(
+            test_contract.get_instance_address(0),
+            "return_result",
+            &[stark_felt!(2_u8)],
+        ),
+        max_fee: Fee(MAX_FEE),
+    };
+    validator()
+        .perform_validations(account_invoke_tx(invoke_args.clone()), None, &NonceCheck::Relaxed)
+        .unwrap();
+
+    // Rejected in the pre-validation stage.
+    let tx = account_invoke_tx(invoke_tx_args! { max_fee: Fee(BALANCE + 1), ..invoke_args });
+    assert_matches!(
+        validator().perform_validations(tx, None, &NonceCheck::Relaxed).unwrap_err(),
+        StatefulValidatorError::TransactionPreValidationError(
+            TransactionPreValidationError::TransactionFeeError(_)
+        )
+    );
+}
+
+#[rstest]
+#[case::skipped(Some(TransactionHash(StarkHash::ONE)), Nonce(StarkFelt::ONE), true)]
+#[case::no_deploy_account_tx(None, Nonce(StarkFelt::ONE), false)]
+#[case::nonce_too_large(Some(TransactionHash(StarkHash::ONE)), Nonce(stark_felt!(2_u8)), false)]
+fn test_validate_with_unprocessed_deploy_account(
+    #[case] deploy_account_tx_hash: Option<TransactionHash>,
+    #[case] nonce: Nonce,
+    #[case] expect_success: bool,
+) {
+    let block_context = BlockContext::create_for_testing();
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let mut state = test_state(block_context.chain_info(), BALANCE, &[(account, 0)]);
+
+    // The account is funded, but not deployed.
+    let deploy_account_tx = deploy_account_tx(
+        deploy_account_tx_args! { class_hash: account.get_class_hash(), max_fee: Fee(MAX_FEE) },
+        &mut NonceManager::default(),
+    );
+    let account_address = deploy_account_tx.contract_address;
+    fund_account(block_context.chain_info(), account_address, BALANCE, &mut state);
+    let mut validator = stateful_validator(state);
+
+    let tx = account_invoke_tx(invoke_tx_args! {
+        sender_address: account_address,
+        nonce,
+        max_fee: Fee(MAX_FEE),
+    });
+    let result = validator.perform_validations(tx, deploy_account_tx_hash, &NonceCheck::Relaxed);
+    assert_eq!(result.is_ok(), expect_success, "{result:?}");
+}
+
+#[test]
+fn test_validate_deploy_account() {
+    let block_context = BlockContext::create_for_testing();
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let mut state = test_state(block_context.chain_info(), BALANCE, &[(account, 0)]);
+    let deploy_account_tx = deploy_account_tx(
+        deploy_account_tx_args! { class_hash: account.get_class_hash(), max_fee: Fee(MAX_FEE) },
+        &mut NonceManager::default(),
+    );
+    fund_account(
+        block_context.chain_info(),
+        deploy_account_tx.contract_address,
+        BALANCE,
+        &mut state,
+    );
+
+    // Deploy account transactions are executed, rather than only validated.
+    let mut validator = stateful_validator(state);
+    validator
+        .perform_validations(
+            AccountTransaction::DeployAccount(deploy_account_tx),
+            None,
+            &NonceCheck::Relaxed,
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_validate_with_mempool_nonce_check() {
+    let block_context = BlockContext::create_for_testing();
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let account_address = account.get_instance_address(0);
+
+    // The account nonce is 1, and the transactions with nonces 1 and 2 are pending.
+    let nonce_check = NonceCheck::Mempool(MempoolNoncePolicy {
+        pending_nonces: BTreeSet::from([Nonce(stark_felt!(1_u8)), Nonce(stark_felt!(2_u8))]),
+        max_nonce_gap: Some(1),
+    });
+    let expected_nonce = Nonce(stark_felt!(3_u8));
+    let validate = |nonce: u8| {
+        let mut state =
+            test_state(block_context.chain_info(), BALANCE, &[(account, 1), (test_contract, 1)]);
+        state.increment_nonce(account_address).unwrap();
+        let tx = account_invoke_tx(invoke_tx_args! {
+            sender_address: account_address,
+            calldata: create_calldata(
+                test_contract.get_instance_address(0),
+                "return_result",
+                &[stark_felt!(2_u8)],
+            ),
+            nonce: Nonce(stark_felt!(nonce)),
+            max_fee: Fee(MAX_FEE),
+        });
+        stateful_validator(state).perform_validations(tx, None, &nonce_check)
+    };
+
+    validate(3).unwrap();
+    validate(4).unwrap();
+    assert_matches!(
+        validate(0).unwrap_err(),
+        StatefulValidatorError::TransactionPreValidationError(
+            TransactionPreValidationError::InvalidNonce {
+                address, account_nonce, expected_nonce: actual_expected_nonce, incoming_tx_nonce
+            }
+        ) if (address, account_nonce, actual_expected_nonce, incoming_tx_nonce)
+            == (account_address, Nonce(stark_felt!(1_u8)), expected_nonce, Nonce::default())
+    );
+    assert_matches!(
+        validate(2).unwrap_err(),
+        StatefulValidatorError::TransactionPreValidationError(
+            TransactionPreValidationError::DuplicateNonce {
+                address, nonce, expected_nonce: actual_expected_nonce
+            }
+        ) if (address, nonce, actual_expected_nonce)
+            == (account_address, Nonce(stark_felt!(2_u8)), expected_nonce)
+    );
+    assert_matches!(
+        validate(5).unwrap_err(),
+        StatefulValidatorError::TransactionPreValidationError(
+            TransactionPreValidationError::NonceTooFarInFuture {
+                address, expected_nonce: actual_expected_nonce, incoming_tx_nonce, max_nonce_gap
+            }
+        ) if (address, actual_expected_nonce, incoming_tx_nonce, max_nonce_gap)
+            == (account_address, expected_nonce, Nonce(stark_felt!(5_u8)), 1)
+    );
+}
@@ -1,14 +1,19 @@
 use blockifier::blockifier::transaction_executor::TransactionExecutorError;
+use blockifier::commitment::errors::CommitmentError;
 use blockifier::execution::errors::ContractClassError;
 use blockifier::state::errors::StateError;
 use blockifier::transaction::errors::{
     ParseError, TransactionExecutionError, TransactionPreValidationError,
 };
 use blockifier::transaction::transaction_types::TransactionType;
+use blockifier::validator::StatefulValidatorError;
 use cairo_vm::types::errors::program_errors::ProgramError;
 use pyo3::create_exception;
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ClassHash;
+use starknet_api::transaction::TransactionHash;
 use starknet_api::StarknetApiError;
 use thiserror::Error;
 
@@ -61,10 +66,12 @@ macro_rules! native_blockifier_errors {
 }
 
 native_blockifier_errors!(
+    (CommitmentError, CommitmentError, PyCommitmentError),
     (ContractClassError, ContractClassError, PyContractClassError),
     (NativeBlockifierInputError, NativeBlockifierInputError, PyNativeBlockifierInputError),
     (ProgramError, ProgramError, PyProgramError),
     (Pyo3Error, PyErr, PyPyo3Error),
+    (ReplayError, ReplayError, PyReplayError),
     (SerdeError, serde_json::Error, PySerdeError),
     (StarknetApiError, StarknetApiError, PyStarknetApiError),
     (StateError, StateError, PyStateError),
@@ -74,6 +81,18 @@ native_blockifier_errors!(
     (TransactionPreValidationError, TransactionPreValidationError, PyTransactionPreValidationError)
 );
 
+// Rejections are raised as the Python errors of their underlying stage.
+impl From<StatefulValidatorError> for NativeBlockifierError {
+    fn from(error: StatefulValidatorError) -> Self {
+        match error {
+            StatefulValidatorError::StateError(error) => error.into(),
+            StatefulValidatorError::TransactionExecutionError(error) => error.into(),
+            StatefulValidatorError::TransactionExecutorError(error) => error.into(),
+            StatefulValidatorError::TransactionPreValidationError(error) => error.into(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum NativeBlockifierInputError {
     #[error("Max steps per tx out of range: {0}")]
@@ -82,12 +101,26 @@ pub enum NativeBlockifierInputError {
     MaxValidateStepsPerTxOutOfRange(u32),
     #[error(transparent)]
     InvalidNativeBlockifierInputError(#[from] InvalidNativeBlockifierInputError),
+    #[error("The state reader proxy returned a batch of unexpected length.")]
+    InvalidPrefetchResult,
+    #[error("Block {block_number} is appended without a block ID, yet its hash is not computed.")]
+    MissingBlockId { block_number: u64 },
+    #[error("The transactions of block {block_number} are missing; it was not finalized.")]
+    MissingTxsHashingData { block_number: u64 },
     #[error(transparent)]
     ParseError(#[from] ParseError),
     #[error(transparent)]
     ProgramError(#[from] ProgramError),
     #[error(transparent)]
     StarknetApiError(#[from] StarknetApiError),
+    #[error("Block {block_number} is appended with a block ID, yet its hash is computed.")]
+    UnexpectedBlockId { block_number: u64 },
+    #[error(
+        "Block {block_number} is appended, whereas block {finalized_block_number} was finalized."
+    )]
+    UnexpectedFinalizedBlock { block_number: u64, finalized_block_number: u64 },
+    #[error("Unknown bouncer resource: {0}.")]
+    UnknownBouncerResource(String),
     #[error("Contract class of version {version} is unsupported.")]
     UnsupportedContractClassVersion { version: usize },
     #[error("Transaction of type {tx_type:?} is unsupported in version {version}.")]
@@ -106,4 +139,14 @@ pub enum InvalidNativeBlockifierInputError {
     InvalidDataGasPriceFri(u128),
 }
 
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Class {class_hash} declared in block {block_number:?} is missing from storage.")]
+    MissingDeclaredClass { block_number: BlockNumber, class_hash: ClassHash },
+    #[error("The {data} of block {block_number:?} is missing from storage.")]
+    MissingBlockData { block_number: BlockNumber, data: &'static str },
+    #[error("Transaction {tx_hash:?} of block {block_number:?} is of an unsupported type.")]
+    UnsupportedTransaction { block_number: BlockNumber, tx_hash: TransactionHash },
+}
+
 create_exception!(native_blockifier, UndeclaredClassHashError, PyException);
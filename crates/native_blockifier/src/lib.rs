@@ -21,6 +21,8 @@ pub mod py_transaction;
 pub mod py_transaction_execution_info;
 pub mod py_utils;
 pub mod py_validator;
+pub mod replay;
+pub mod replayed_node_store;
 pub mod state_readers;
 pub mod storage;
 pub mod test_utils;
@@ -33,6 +35,7 @@ use py_transaction_execution_info::{
 };
 use py_validator::PyValidator;
 use pyo3::prelude::*;
+use replay::replay_blocks;
 use storage::StorageConfig;
 
 use crate::py_state_diff::PyStateDiff;
@@ -61,6 +64,7 @@ fn native_blockifier(py: Python<'_>, py_module: &PyModule) -> PyResult<()> {
     add_py_exceptions(py, py_module)?;
 
     py_module.add_function(wrap_pyfunction!(blockifier_version, py)?)?;
+    py_module.add_function(wrap_pyfunction!(replay_blocks, py)?)?;
 
     // TODO(Dori, 1/4/2023): If and when supported in the Python build environment, gate this code
     //   with #[cfg(test)].
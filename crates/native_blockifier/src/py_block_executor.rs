@@ -4,9 +4,11 @@ use blockifier::blockifier::block::{
     pre_process_block as pre_process_block_blockifier, BlockInfo, BlockNumberHashPair, GasPrices,
 };
 use blockifier::blockifier::transaction_executor::TransactionExecutor;
+use blockifier::bouncer::{BouncerConfig, BouncerWeights};
+use blockifier::commitment::block_hash::TransactionHashingData;
 use blockifier::context::{BlockContext, ChainInfo, FeeTokenAddresses};
 use blockifier::execution::call_info::CallInfo;
-use blockifier::state::cached_state::{CachedState, GlobalContractCache};
+use blockifier::state::cached_state::{CachedState, GlobalContractCache, StateChanges};
 use blockifier::state::state_api::State;
 use blockifier::transaction::objects::{GasVector, ResourcesMapping, TransactionExecutionInfo};
 use blockifier::transaction::transaction_execution::Transaction;
@@ -50,6 +52,7 @@ pub(crate) struct ThinTransactionExecutionInfo {
     pub da_gas: GasVector,
     pub actual_resources: ResourcesMapping,
     pub revert_error: Option<String>,
+    pub state_changes: StateChanges,
 }
 
 impl From<TransactionExecutionInfo> for ThinTransactionExecutionInfo {
@@ -62,6 +65,7 @@ impl From<TransactionExecutionInfo> for ThinTransactionExecutionInfo {
             da_gas: tx_execution_info.da_gas,
             actual_resources: tx_execution_info.actual_resources,
             revert_error: tx_execution_info.revert_error,
+            state_changes: tx_execution_info.state_changes,
         }
     }
 }
@@ -82,6 +86,9 @@ pub struct PyBlockExecutor {
     /// `Send` trait is required for `pyclass` compatibility as Python objects must be threadsafe.
     pub storage: Box<dyn Storage + Send>,
     pub global_contract_cache: GlobalContractCache,
+    /// The hashing data of the transactions of the last finalized block, by its number; appended
+    /// along with the block.
+    finalized_block_txs_hashing_data: Option<(u64, Vec<TransactionHashingData>)>,
 }
 
 #[pymethods]
@@ -108,6 +115,7 @@ impl PyBlockExecutor {
             tx_executor: None,
             storage: Box::new(storage),
             global_contract_cache: GlobalContractCache::new(global_contract_cache_size),
+            finalized_block_txs_hashing_data: None,
         }
     }
 
@@ -131,7 +139,8 @@ impl PyBlockExecutor {
             &self.versioned_constants,
         )?;
 
-        let tx_executor = TransactionExecutor::new(state, block_context);
+        let bouncer_config = self.general_config.bouncer_config.clone().try_into()?;
+        let tx_executor = TransactionExecutor::new(state, block_context, bouncer_config);
         self.tx_executor = Some(tx_executor);
 
         Ok(())
@@ -190,6 +199,12 @@ impl PyBlockExecutor {
     ) -> NativeBlockifierResult<(PyStateDiff, PyVisitedSegmentsMapping)> {
         log::debug!("Finalizing execution...");
         let (commitment_state_diff, visited_pcs) = self.tx_executor().finalize(is_pending_block)?;
+        if !is_pending_block {
+            let tx_executor = self.tx_executor();
+            let block_number = tx_executor.block_context.block_info().block_number.0;
+            let txs_hashing_data = std::mem::take(&mut tx_executor.block_txs_hashing_data);
+            self.finalized_block_txs_hashing_data = Some((block_number, txs_hashing_data));
+        }
         let visited_pcs = visited_pcs
             .into_iter()
             .map(|(class_hash, class_visited_pcs_vec)| {
@@ -213,8 +228,11 @@ impl PyBlockExecutor {
     // Storage Alignment API.
 
     /// Appends state diff and block header into Papyrus storage.
+    /// If the storage computes block hashes, the block must be the last one finalized, and is given
+    /// no block ID; its header commits to its transactions, and its ID is its hash. Otherwise, its
+    /// ID is the given one.
     // Previous block ID can either be a block hash (starting from a Papyrus snapshot), or a
-    // sequential ID (throughout sequencing).
+    // sequential ID (throughout sequencing); it is used only if the previous header is missing.
     #[pyo3(signature = (
         block_id,
         previous_block_id,
@@ -225,13 +243,25 @@ impl PyBlockExecutor {
     ))]
     pub fn append_block(
         &mut self,
-        block_id: u64,
+        block_id: Option<u64>,
         previous_block_id: Option<PyFelt>,
         py_block_info: PyBlockInfo,
         py_state_diff: PyStateDiff,
         declared_class_hash_to_class: HashMap<PyFelt, (PyFelt, String)>,
         deprecated_declared_class_hash_to_class: HashMap<PyFelt, String>,
     ) -> NativeBlockifierResult<()> {
+        let block_number = py_block_info.block_number;
+        let txs_hashing_data = match self.finalized_block_txs_hashing_data.take() {
+            Some((finalized_block_number, _)) if finalized_block_number != block_number => {
+                return Err(NativeBlockifierInputError::UnexpectedFinalizedBlock {
+                    block_number,
+                    finalized_block_number,
+                }
+                .into());
+            }
+            Some((_, txs_hashing_data)) => Some(txs_hashing_data),
+            None => None,
+        };
         self.storage.append_block(
             block_id,
             previous_block_id,
@@ -239,6 +269,7 @@ impl PyBlockExecutor {
             py_state_diff,
             declared_class_hash_to_class,
             deprecated_declared_class_hash_to_class,
+            txs_hashing_data.as_deref(),
         )
     }
 
@@ -249,7 +280,7 @@ impl PyBlockExecutor {
         self.storage.get_header_marker()
     }
 
-    /// Returns the unique identifier of the given block number in bytes.
+    /// Returns the unique identifier of the given block number in bytes; i.e., its block hash.
     #[pyo3(signature = (block_number))]
     fn get_block_id_at_target(&self, block_number: u64) -> NativeBlockifierResult<Option<PyFelt>> {
         let optional_block_id_bytes = self.storage.get_block_id(block_number)?;
@@ -299,6 +330,7 @@ impl PyBlockExecutor {
             versioned_constants: VersionedConstants::latest_constants().clone(),
             tx_executor: None,
             global_contract_cache: GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST),
+            finalized_block_txs_hashing_data: None,
         }
     }
 }
@@ -323,6 +355,7 @@ impl PyBlockExecutor {
             versioned_constants: VersionedConstants::latest_constants().clone(),
             tx_executor: None,
             global_contract_cache: GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST),
+            finalized_block_txs_hashing_data: None,
         }
     }
 }
@@ -332,6 +365,45 @@ pub struct PyGeneralConfig {
     pub starknet_os_config: PyOsConfig,
     pub invoke_tx_max_n_steps: u32,
     pub validate_max_n_steps: u32,
+    pub bouncer_config: PyBouncerConfig,
+}
+
+/// The block capacity: the maximal total weight of each resource, by its name (as in
+/// `PyBouncerInfo`); resources that are not specified are unlimited.
+#[derive(Clone, Default, FromPyObject)]
+pub struct PyBouncerConfig {
+    pub full_total_weights: HashMap<String, usize>,
+}
+
+impl TryFrom<PyBouncerConfig> for BouncerConfig {
+    type Error = NativeBlockifierError;
+
+    fn try_from(py_bouncer_config: PyBouncerConfig) -> Result<Self, Self::Error> {
+        let mut block_max_capacity = BouncerWeights::max();
+        let builtin_count = &mut block_max_capacity.builtin_count;
+        for (resource, max_weight) in py_bouncer_config.full_total_weights {
+            let capacity = match resource.as_str() {
+                "gas_weight" => &mut block_max_capacity.gas,
+                "message_segment_length" => &mut block_max_capacity.message_segment_length,
+                "n_events" => &mut block_max_capacity.n_events,
+                "n_steps" => &mut block_max_capacity.n_steps,
+                "state_diff_size" => &mut block_max_capacity.state_diff_size,
+                "bitwise_builtin" => &mut builtin_count.bitwise,
+                "ecdsa_builtin" => &mut builtin_count.ecdsa,
+                "ec_op_builtin" => &mut builtin_count.ec_op,
+                "keccak_builtin" => &mut builtin_count.keccak,
+                "pedersen_builtin" => &mut builtin_count.pedersen,
+                "poseidon_builtin" => &mut builtin_count.poseidon,
+                "range_check_builtin" => &mut builtin_count.range_check,
+                _ => {
+                    return Err(NativeBlockifierInputError::UnknownBouncerResource(resource).into())
+                }
+            };
+            *capacity = max_weight;
+        }
+
+        Ok(Self { block_max_capacity })
+    }
 }
 
 #[derive(Clone, FromPyObject)]
@@ -375,7 +447,13 @@ pub fn into_block_context_args(
     block_info: &PyBlockInfo,
 ) -> NativeBlockifierResult<(BlockInfo, ChainInfo)> {
     let chain_info: ChainInfo = general_config.starknet_os_config.clone().try_into()?;
-    let block_info = BlockInfo {
+    let block_info = into_block_info(block_info)?;
+
+    Ok((block_info, chain_info))
+}
+
+pub fn into_block_info(block_info: &PyBlockInfo) -> NativeBlockifierResult<BlockInfo> {
+    Ok(BlockInfo {
         block_number: BlockNumber(block_info.block_number),
         block_timestamp: BlockTimestamp(block_info.block_timestamp),
         sequencer_address: ContractAddress::try_from(block_info.sequencer_address.0)?,
@@ -414,9 +492,7 @@ pub fn into_block_context_args(
             )?,
         },
         use_kzg_da: block_info.use_kzg_da,
-    };
-
-    Ok((block_info, chain_info))
+    })
 }
 
 // Executes block pre-processing; see `blockifier::blockifier::block::pre_process_block`
@@ -1,15 +1,37 @@
 use std::collections::HashMap;
+use std::num::NonZeroU128;
 
+use blockifier::blockifier::block::{BlockInfo, GasPrices};
+use blockifier::bouncer::{BouncerConfig, BouncerWeights, BuiltinCount};
+use blockifier::commitment::block_hash::{
+    calculate_block_commitments, verify_block_hash, BlockHeaderWithoutHash, TransactionHashingData,
+};
+use blockifier::commitment::errors::CommitmentError;
+use blockifier::commitment::node_store::InMemoryNodeStore;
+use blockifier::commitment::state_commitment::{commit_state_diff, StateRoots};
+use blockifier::state::cached_state::CommitmentStateDiff;
 use blockifier::state::state_api::State;
 use blockifier::test_utils::contracts::FeatureContract;
 use blockifier::test_utils::CairoVersion;
-use cached::Cached;
+use blockifier::transaction::objects::GasVector;
+use indexmap::{indexmap, IndexMap};
+use papyrus_storage::header::HeaderStorageReader;
 use pretty_assertions::assert_eq;
-use starknet_api::hash::StarkFelt;
+use starknet_api::block::{BlockHash, BlockNumber, GasPrice};
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{
+    Event, EventContent, EventData, EventKey, Fee, TransactionHash, TransactionSignature,
+};
+use starknet_api::{contract_address, patricia_key, stark_felt};
 
-use crate::py_block_executor::{PyBlockExecutor, PyGeneralConfig};
-use crate::py_state_diff::PyBlockInfo;
+use crate::errors::{NativeBlockifierError, NativeBlockifierInputError};
+use crate::py_block_executor::{PyBlockExecutor, PyBouncerConfig, PyGeneralConfig};
+use crate::py_state_diff::{PyBlockInfo, PyStateDiff};
 use crate::py_utils::PyFelt;
+use crate::storage::PapyrusStorage;
 use crate::test_utils::MockStorage;
 
 #[test]
@@ -35,7 +57,7 @@ fn global_contract_cache_update() {
     // Finalizing a pending block doesn't update the global contract cache.
     let is_pending_block = true;
     block_executor.finalize(is_pending_block).unwrap();
-    assert_eq!(block_executor.global_contract_cache.lock().cache_size(), 0);
+    assert_eq!(block_executor.global_contract_cache.size(), 0);
     block_executor.teardown_block_execution();
 
     // Finalizing a non-pending block does update the global cache.
@@ -45,7 +67,7 @@ fn global_contract_cache_update() {
     block_executor.tx_executor().state.set_contract_class(class_hash, contract_class).unwrap();
     let is_pending_block = false;
     block_executor.finalize(is_pending_block).unwrap();
-    assert_eq!(block_executor.global_contract_cache.lock().cache_size(), 1);
+    assert_eq!(block_executor.global_contract_cache.size(), 1);
     block_executor.teardown_block_execution();
 }
 
@@ -67,3 +89,269 @@ fn get_block_id() {
         expected_max_class_hash_as_py_felt
     );
 }
+
+#[test]
+fn append_block_with_ids() {
+    let temp_storage_path = tempfile::tempdir().unwrap().into_path();
+    let mut block_executor =
+        PyBlockExecutor::create_for_testing(PyGeneralConfig::default(), temp_storage_path);
+    let py_state_diff = || PyStateDiff {
+        address_to_class_hash: HashMap::new(),
+        address_to_nonce: HashMap::new(),
+        storage_updates: HashMap::new(),
+        class_hash_to_compiled_class_hash: HashMap::new(),
+    };
+
+    // Headers are identified by the given block IDs.
+    for block_number in 0..2 {
+        block_executor
+            .append_block(
+                Some(block_number + 1000),
+                block_number.checked_sub(1).map(|parent_number| PyFelt::from(parent_number + 1000)),
+                PyBlockInfo { block_number, ..Default::default() },
+                py_state_diff(),
+                HashMap::new(),
+                HashMap::new(),
+            )
+            .unwrap();
+        assert_eq!(
+            block_executor.get_block_id_at_target(block_number).unwrap(),
+            Some(PyFelt::from(block_number + 1000))
+        );
+    }
+
+    let block_number = 2;
+    assert!(matches!(
+        block_executor.append_block(
+            None,
+            None,
+            PyBlockInfo { block_number, ..Default::default() },
+            py_state_diff(),
+            HashMap::new(),
+            HashMap::new(),
+        ),
+        Err(NativeBlockifierError::NativeBlockifierInputError(
+            NativeBlockifierInputError::MissingBlockId { block_number: missing_block_number }
+        )) if missing_block_number == block_number
+    ));
+}
+
+#[test]
+fn append_block_with_header() {
+    let temp_storage_path = tempfile::tempdir().unwrap().into_path();
+    let chain_id = PyGeneralConfig::default().starknet_os_config.chain_id;
+    let storage =
+        PapyrusStorage::new_for_testing(temp_storage_path, &chain_id).with_block_hashes("0.13.2");
+    let mut block_executor = PyBlockExecutor::create_for_testing_with_storage(storage);
+    let (address, key, value) = ("0x100", "0x10", "0x1");
+    let py_state_diff = || PyStateDiff {
+        address_to_class_hash: HashMap::new(),
+        address_to_nonce: HashMap::new(),
+        storage_updates: HashMap::from([(
+            PyFelt(stark_felt!(address)),
+            HashMap::from([(PyFelt(stark_felt!(key)), PyFelt(stark_felt!(value)))]),
+        )]),
+        class_hash_to_compiled_class_hash: HashMap::new(),
+    };
+    let state_diff = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::new(),
+        storage_updates: indexmap!(contract_address!(address) => indexmap!(
+            StorageKey(patricia_key!(key)) => stark_felt!(value),
+        )),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+    let state_root =
+        commit_state_diff(&InMemoryNodeStore::default(), &StateRoots::default(), &state_diff)
+            .unwrap()
+            .roots
+            .global_root();
+    let event = Event {
+        from_address: contract_address!(address),
+        content: EventContent {
+            keys: vec![EventKey(stark_felt!("0x2"))],
+            data: EventData(vec![stark_felt!("0x3")]),
+        },
+    };
+    let txs_hashing_data = vec![TransactionHashingData {
+        transaction_hash: TransactionHash(stark_felt!("0x1234")),
+        transaction_signature: Some(TransactionSignature(vec![stark_felt!("0x5")])),
+        events: vec![event.clone(), event],
+        messages_sent: vec![],
+        actual_fee: Fee(7),
+        revert_error: None,
+        gas_consumed: GasVector::default(),
+    }];
+
+    // Each header commits to the transactions and state diff of its block, and to its parent.
+    let mut parent_hash = BlockHash(StarkHash::ZERO);
+    for block_number in 0..2 {
+        let py_block_info = || PyBlockInfo { block_number, ..Default::default() };
+        block_executor.finalized_block_txs_hashing_data =
+            Some((block_number, txs_hashing_data.clone()));
+        block_executor
+            .append_block(
+                None,
+                None,
+                py_block_info(),
+                py_state_diff(),
+                HashMap::new(),
+                HashMap::new(),
+            )
+            .unwrap();
+
+        let header = block_executor
+            .storage
+            .reader()
+            .begin_ro_txn()
+            .unwrap()
+            .get_block_header(BlockNumber(block_number))
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.parent_hash, parent_hash);
+        assert_eq!(header.starknet_version.0, "0.13.2");
+        // Rewriting a storage value keeps the state root.
+        assert_eq!(header.state_root, state_root);
+        assert_eq!((header.n_transactions, header.n_events), (1, 2));
+
+        // The stored header, along with the commitments it lacks, verifies against its hash.
+        let block_commitments =
+            calculate_block_commitments(&txs_hashing_data, &state_diff, &[], false).unwrap();
+        assert_eq!(header.transaction_commitment.0, block_commitments.transaction_commitment);
+        assert_eq!(header.event_commitment.0, block_commitments.event_commitment);
+        let gas_price = |price: GasPrice| NonZeroU128::new(price.0).unwrap();
+        let header_without_hash = BlockHeaderWithoutHash {
+            block_info: BlockInfo {
+                block_number: header.block_number,
+                block_timestamp: header.timestamp,
+                sequencer_address: header.sequencer.0,
+                gas_prices: GasPrices {
+                    eth_l1_gas_price: gas_price(header.l1_gas_price.price_in_wei),
+                    strk_l1_gas_price: gas_price(header.l1_gas_price.price_in_fri),
+                    eth_l1_data_gas_price: gas_price(header.l1_data_gas_price.price_in_wei),
+                    strk_l1_data_gas_price: gas_price(header.l1_data_gas_price.price_in_fri),
+                },
+                use_kzg_da: header.l1_da_mode == L1DataAvailabilityMode::Blob,
+            },
+            state_root: header.state_root,
+            parent_hash: header.parent_hash,
+            starknet_version: header.starknet_version.0,
+        };
+        verify_block_hash(header.block_hash, &header_without_hash, &block_commitments).unwrap();
+        assert_eq!(
+            block_executor.get_block_id_at_target(block_number).unwrap(),
+            Some(PyFelt(header.block_hash.0))
+        );
+        parent_hash = header.block_hash;
+    }
+
+    // Blocks are appended along with the transactions of their finalization, and no block ID.
+    let block_number = 2;
+    let py_block_info = || PyBlockInfo { block_number, ..Default::default() };
+    assert!(matches!(
+        block_executor.append_block(
+            None,
+            None,
+            py_block_info(),
+            py_state_diff(),
+            HashMap::new(),
+            HashMap::new(),
+        ),
+        Err(NativeBlockifierError::NativeBlockifierInputError(
+            NativeBlockifierInputError::MissingTxsHashingData { block_number: 2 }
+        ))
+    ));
+    block_executor.finalized_block_txs_hashing_data = Some((1, txs_hashing_data.clone()));
+    assert!(matches!(
+        block_executor.append_block(
+            None,
+            None,
+            py_block_info(),
+            py_state_diff(),
+            HashMap::new(),
+            HashMap::new(),
+        ),
+        Err(NativeBlockifierError::NativeBlockifierInputError(
+            NativeBlockifierInputError::UnexpectedFinalizedBlock {
+                block_number: 2,
+                finalized_block_number: 1
+            }
+        ))
+    ));
+    block_executor.finalized_block_txs_hashing_data = Some((block_number, txs_hashing_data));
+    assert!(matches!(
+        block_executor.append_block(
+            Some(block_number),
+            None,
+            py_block_info(),
+            py_state_diff(),
+            HashMap::new(),
+            HashMap::new(),
+        ),
+        Err(NativeBlockifierError::NativeBlockifierInputError(
+            NativeBlockifierInputError::UnexpectedBlockId { block_number: 2 }
+        ))
+    ));
+}
+
+#[test]
+fn append_block_with_legacy_header() {
+    let temp_storage_path = tempfile::tempdir().unwrap().into_path();
+    let chain_id = PyGeneralConfig::default().starknet_os_config.chain_id;
+    let storage =
+        PapyrusStorage::new_for_testing(temp_storage_path, &chain_id).with_block_hashes("0.13.1");
+    let mut block_executor = PyBlockExecutor::create_for_testing_with_storage(storage);
+    block_executor.finalized_block_txs_hashing_data = Some((0, vec![]));
+
+    // Legacy block hashes, of versions below 0.13.2, are not supported.
+    let result = block_executor.append_block(
+        None,
+        None,
+        PyBlockInfo::default(),
+        PyStateDiff {
+            address_to_class_hash: HashMap::new(),
+            address_to_nonce: HashMap::new(),
+            storage_updates: HashMap::new(),
+            class_hash_to_compiled_class_hash: HashMap::new(),
+        },
+        HashMap::new(),
+        HashMap::new(),
+    );
+    assert!(matches!(
+        result,
+        Err(NativeBlockifierError::CommitmentError(
+            CommitmentError::UnsupportedStarknetVersion(version)
+        )) if version == "0.13.1"
+    ));
+}
+
+#[test]
+fn bouncer_config_from_py_bouncer_config() {
+    let py_bouncer_config = PyBouncerConfig {
+        full_total_weights: HashMap::from([
+            ("n_steps".to_string(), 1000),
+            ("pedersen_builtin".to_string(), 10),
+        ]),
+    };
+    let expected_block_max_capacity = BouncerWeights {
+        n_steps: 1000,
+        builtin_count: BuiltinCount { pedersen: 10, ..BuiltinCount::max() },
+        ..BouncerWeights::max()
+    };
+    assert_eq!(
+        BouncerConfig::try_from(py_bouncer_config).unwrap(),
+        BouncerConfig { block_max_capacity: expected_block_max_capacity }
+    );
+
+    // Unspecified resources are unlimited, whereas unknown ones are rejected.
+    assert_eq!(BouncerConfig::try_from(PyBouncerConfig::default()).unwrap(), BouncerConfig::max());
+    let py_bouncer_config = PyBouncerConfig {
+        full_total_weights: HashMap::from([("n_memory_holes".to_string(), 1000)]),
+    };
+    assert!(matches!(
+        BouncerConfig::try_from(py_bouncer_config),
+        Err(NativeBlockifierError::NativeBlockifierInputError(
+            NativeBlockifierInputError::UnknownBouncerResource(resource)
+        )) if resource == "n_memory_holes"
+    ));
+}
@@ -3,11 +3,13 @@ use std::collections::{HashMap, HashSet};
 use blockifier::blockifier::bouncer::BouncerInfo;
 use blockifier::execution::call_info::{CallInfo, OrderedEvent, OrderedL2ToL1Message};
 use blockifier::execution::entry_point::CallType;
+use blockifier::state::cached_state::CommitmentStateDiff;
 use blockifier::transaction::objects::TransactionExecutionInfo;
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
 use pyo3::prelude::*;
 use starknet_api::deprecated_contract_class::EntryPointType;
 
+use crate::py_state_diff::PyStateDiff;
 use crate::py_utils::{to_py_vec, PyFelt};
 
 #[pyclass]
@@ -25,6 +27,15 @@ pub struct PyTransactionExecutionInfo {
     pub actual_resources: HashMap<String, usize>,
     #[pyo3(get)]
     pub revert_error: Option<String>,
+    pub state_changes: CommitmentStateDiff,
+}
+
+#[pymethods]
+impl PyTransactionExecutionInfo {
+    #[getter]
+    fn state_changes(&self) -> PyStateDiff {
+        PyStateDiff::from(self.state_changes.clone())
+    }
 }
 
 impl From<TransactionExecutionInfo> for PyTransactionExecutionInfo {
@@ -37,6 +48,7 @@ impl From<TransactionExecutionInfo> for PyTransactionExecutionInfo {
             actual_fee: info.actual_fee.0,
             actual_resources: info.actual_resources.0,
             revert_error: info.revert_error,
+            state_changes: CommitmentStateDiff::from(info.state_changes),
         }
     }
 }
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::num::NonZeroU128;
+use std::ops::Range;
+
+use blockifier::abi::constants;
+use blockifier::blockifier::block::{pre_process_block, BlockInfo, BlockNumberHashPair, GasPrices};
+use blockifier::blockifier::transaction_executor::TransactionExecutor;
+use blockifier::bouncer::BouncerConfig;
+use blockifier::context::{BlockContext, ChainInfo};
+use blockifier::execution::contract_class::{
+    ClassInfo, ContractClass, ContractClassV0, ContractClassV1,
+};
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff, GlobalContractCache};
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::versioned_constants::VersionedConstants;
+use indexmap::IndexMap;
+use papyrus_storage::body::events::ThinTransactionOutput;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::StorageReader;
+use pyo3::prelude::*;
+use starknet_api::block::{BlockHeader, BlockNumber, GasPrice};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::transaction::{
+    DeclareTransaction, Transaction as StarknetApiTransaction, TransactionHash,
+};
+
+use crate::errors::{NativeBlockifierError, NativeBlockifierResult, ReplayError};
+use crate::py_block_executor::PyGeneralConfig;
+use crate::py_utils::versioned_constants_with_overrides;
+use crate::state_readers::papyrus_state::PapyrusReader;
+use crate::storage::{open_storage_reader, StorageConfig};
+
+#[cfg(test)]
+#[path = "replay_test.rs"]
+pub mod test;
+
+type RawPapyrusReader<'env> = papyrus_storage::StorageTxn<'env, RO>;
+
+/// A difference between the state diff stored for a block, and the one obtained by re-executing
+/// it; `None` stands for an entry missing from the respective state diff.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StateDiffMismatch {
+    ClassHash {
+        address: ContractAddress,
+        expected: Option<ClassHash>,
+        actual: Option<ClassHash>,
+    },
+    Nonce {
+        address: ContractAddress,
+        expected: Option<Nonce>,
+        actual: Option<Nonce>,
+    },
+    Storage {
+        address: ContractAddress,
+        key: StorageKey,
+        expected: Option<StarkFelt>,
+        actual: Option<StarkFelt>,
+    },
+    CompiledClassHash {
+        class_hash: ClassHash,
+        expected: Option<CompiledClassHash>,
+        actual: Option<CompiledClassHash>,
+    },
+}
+
+/// The outcome of re-executing a single historical block.
+#[derive(Debug)]
+pub struct BlockReplayReport {
+    pub block_number: BlockNumber,
+    pub n_txs: usize,
+    /// Transactions included in the block that failed to execute on replay, with their errors.
+    pub failed_txs: Vec<(TransactionHash, String)>,
+    pub mismatches: Vec<StateDiffMismatch>,
+    /// A transaction of a type the blockifier cannot execute (e.g., a legacy `Deploy`), due to
+    /// which the block was skipped; i.e., neither executed nor compared.
+    pub unsupported_tx: Option<TransactionHash>,
+}
+
+impl BlockReplayReport {
+    /// A skipped block is not consistent, as it is not verified.
+    pub fn is_consistent(&self) -> bool {
+        self.unsupported_tx.is_none() && self.failed_txs.is_empty() && self.mismatches.is_empty()
+    }
+}
+
+/// Re-executes historical blocks of a (full archive) Papyrus storage, and verifies the resulting
+/// state diffs against the stored ones.
+pub struct BlockReplayer {
+    storage_reader: StorageReader,
+    chain_info: ChainInfo,
+    versioned_constants: VersionedConstants,
+    global_contract_cache: GlobalContractCache,
+}
+
+impl BlockReplayer {
+    pub fn new(
+        storage_reader: StorageReader,
+        chain_info: ChainInfo,
+        versioned_constants: VersionedConstants,
+        global_contract_cache: GlobalContractCache,
+    ) -> Self {
+        Self { storage_reader, chain_info, versioned_constants, global_contract_cache }
+    }
+
+    /// Replays each of the given blocks on top of its stored parent state; i.e., independently of
+    /// the replay results of previous blocks.
+    pub fn replay_blocks(
+        &self,
+        block_numbers: Range<BlockNumber>,
+    ) -> NativeBlockifierResult<Vec<BlockReplayReport>> {
+        (block_numbers.start.0..block_numbers.end.0)
+            .map(|block_number| self.replay_block(BlockNumber(block_number)))
+            .collect()
+    }
+
+    pub fn replay_block(
+        &self,
+        block_number: BlockNumber,
+    ) -> NativeBlockifierResult<BlockReplayReport> {
+        log::debug!("Replaying block {block_number:?}...");
+        let txn = self.storage_reader.begin_ro_txn()?;
+
+        // A reader of the given block number reads the state at the end of its parent block.
+        let papyrus_reader = PapyrusReader::new(self.storage_reader.clone(), block_number);
+        let mut state = CachedState::new(papyrus_reader, self.global_contract_cache.clone());
+        let block_context = self.pre_process_block(&txn, &mut state, block_number)?;
+
+        let (tx_hashes, txs) = match get_block_txs(&txn, block_number) {
+            Err(NativeBlockifierError::ReplayError(ReplayError::UnsupportedTransaction {
+                tx_hash,
+                ..
+            })) => {
+                log::debug!(
+                    "Skipping block {block_number:?}; transaction {tx_hash:?} is unsupported."
+                );
+                let n_txs = txn
+                    .get_block_transaction_hashes(block_number)?
+                    .map_or(0, |tx_hashes| tx_hashes.len());
+                return Ok(BlockReplayReport {
+                    block_number,
+                    n_txs,
+                    failed_txs: vec![],
+                    mismatches: vec![],
+                    unsupported_tx: Some(tx_hash),
+                });
+            }
+            block_txs => block_txs?,
+        };
+        let n_txs = txs.len();
+        // Historical blocks are already sealed; the capacity of the live bouncer must not cut them
+        // short.
+        let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+        let charge_fee = true;
+        let tx_execution_results = tx_executor.execute_txs(txs, charge_fee);
+        let failed_txs = tx_hashes
+            .into_iter()
+            .zip(tx_execution_results)
+            .filter_map(|(tx_hash, tx_execution_result)| {
+                tx_execution_result.err().map(|error| (tx_hash, error.to_string()))
+            })
+            .collect();
+
+        let is_pending_block = false;
+        let (actual_state_diff, _) = tx_executor.finalize(is_pending_block)?;
+        let stored_state_diff = txn
+            .get_state_diff(block_number)?
+            .ok_or(ReplayError::MissingBlockData { block_number, data: "state diff" })?;
+        let mismatches =
+            compare_state_diffs(&to_commitment_state_diff(stored_state_diff), &actual_state_diff);
+        log::debug!("Replayed block {block_number:?}; found {} mismatches.", mismatches.len());
+
+        Ok(BlockReplayReport { block_number, n_txs, failed_txs, mismatches, unsupported_tx: None })
+    }
+
+    fn pre_process_block(
+        &self,
+        txn: &RawPapyrusReader<'_>,
+        state: &mut CachedState<PapyrusReader>,
+        block_number: BlockNumber,
+    ) -> NativeBlockifierResult<BlockContext> {
+        let header = get_block_header(txn, block_number)?;
+        let old_block_number_and_hash =
+            match block_number.0.checked_sub(constants::STORED_BLOCK_HASH_BUFFER) {
+                Some(old_block_number) => {
+                    let old_block_number = BlockNumber(old_block_number);
+                    let old_block_hash = get_block_header(txn, old_block_number)?.block_hash;
+                    Some(BlockNumberHashPair { number: old_block_number, hash: old_block_hash })
+                }
+                None => None,
+            };
+
+        Ok(pre_process_block(
+            state,
+            old_block_number_and_hash,
+            block_info_from_header(&header),
+            self.chain_info.clone(),
+            self.versioned_constants.clone(),
+        )?)
+    }
+}
+
+/// Returns the differences between the expected (stored) and actual (re-executed) state diffs,
+/// per contract and storage key.
+pub fn compare_state_diffs(
+    expected: &CommitmentStateDiff,
+    actual: &CommitmentStateDiff,
+) -> Vec<StateDiffMismatch> {
+    let mut mismatches = Vec::new();
+    for (address, expected, actual) in
+        compare_maps(&expected.address_to_class_hash, &actual.address_to_class_hash)
+    {
+        mismatches.push(StateDiffMismatch::ClassHash { address, expected, actual });
+    }
+    for (address, expected, actual) in
+        compare_maps(&expected.address_to_nonce, &actual.address_to_nonce)
+    {
+        mismatches.push(StateDiffMismatch::Nonce { address, expected, actual });
+    }
+
+    let no_storage_updates = IndexMap::new();
+    for address in merged_keys(&expected.storage_updates, &actual.storage_updates) {
+        let expected_storage_updates =
+            expected.storage_updates.get(&address).unwrap_or(&no_storage_updates);
+        let actual_storage_updates =
+            actual.storage_updates.get(&address).unwrap_or(&no_storage_updates);
+        for (key, expected, actual) in
+            compare_maps(expected_storage_updates, actual_storage_updates)
+        {
+            mismatches.push(StateDiffMismatch::Storage { address, key, expected, actual });
+        }
+    }
+
+    for (class_hash, expected, actual) in compare_maps(
+        &expected.class_hash_to_compiled_class_hash,
+        &actual.class_hash_to_compiled_class_hash,
+    ) {
+        mismatches.push(StateDiffMismatch::CompiledClassHash { class_hash, expected, actual });
+    }
+
+    mismatches
+}
+
+/// Returns the keys of both maps, in order of first appearance (in `expected`, then in `actual`).
+fn merged_keys<K: Copy + Eq + Hash, V>(
+    expected: &IndexMap<K, V>,
+    actual: &IndexMap<K, V>,
+) -> Vec<K> {
+    expected
+        .keys()
+        .chain(actual.keys().filter(|key| !expected.contains_key(*key)))
+        .copied()
+        .collect()
+}
+
+/// Returns the entries whose values differ between the given maps.
+fn compare_maps<K: Copy + Eq + Hash, V: Copy + Eq>(
+    expected: &IndexMap<K, V>,
+    actual: &IndexMap<K, V>,
+) -> Vec<(K, Option<V>, Option<V>)> {
+    merged_keys(expected, actual)
+        .into_iter()
+        .filter_map(|key| {
+            let (expected_value, actual_value) =
+                (expected.get(&key).copied(), actual.get(&key).copied());
+            (expected_value != actual_value).then_some((key, expected_value, actual_value))
+        })
+        .collect()
+}
+
+pub fn to_commitment_state_diff(state_diff: ThinStateDiff) -> CommitmentStateDiff {
+    let mut address_to_class_hash = state_diff.deployed_contracts;
+    address_to_class_hash.extend(state_diff.replaced_classes);
+
+    CommitmentStateDiff {
+        address_to_class_hash,
+        address_to_nonce: state_diff.nonces,
+        storage_updates: state_diff.storage_diffs,
+        class_hash_to_compiled_class_hash: state_diff.declared_classes,
+    }
+}
+
+fn get_block_header(
+    txn: &RawPapyrusReader<'_>,
+    block_number: BlockNumber,
+) -> NativeBlockifierResult<BlockHeader> {
+    Ok(txn
+        .get_block_header(block_number)?
+        .ok_or(ReplayError::MissingBlockData { block_number, data: "header" })?)
+}
+
+fn block_info_from_header(header: &BlockHeader) -> BlockInfo {
+    // Prices of blocks preceding the introduction of a fee token (or of data gas) are zero, which
+    // the blockifier does not support.
+    let gas_price = |price: GasPrice| NonZeroU128::new(price.0).unwrap_or(NonZeroU128::MIN);
+
+    BlockInfo {
+        block_number: header.block_number,
+        block_timestamp: header.timestamp,
+        sequencer_address: header.sequencer.0,
+        gas_prices: GasPrices {
+            eth_l1_gas_price: gas_price(header.l1_gas_price.price_in_wei),
+            strk_l1_gas_price: gas_price(header.l1_gas_price.price_in_fri),
+            eth_l1_data_gas_price: gas_price(header.l1_data_gas_price.price_in_wei),
+            strk_l1_data_gas_price: gas_price(header.l1_data_gas_price.price_in_fri),
+        },
+        use_kzg_da: header.l1_da_mode == L1DataAvailabilityMode::Blob,
+    }
+}
+
+/// Returns the transactions of the given block, along with their hashes, ready for execution.
+fn get_block_txs(
+    txn: &RawPapyrusReader<'_>,
+    block_number: BlockNumber,
+) -> NativeBlockifierResult<(Vec<TransactionHash>, Vec<Transaction>)> {
+    let missing_body = || ReplayError::MissingBlockData { block_number, data: "body" };
+    let txs = txn.get_block_transactions(block_number)?.ok_or_else(missing_body)?;
+    let tx_hashes = txn.get_block_transaction_hashes(block_number)?.ok_or_else(missing_body)?;
+    let tx_outputs = txn.get_block_transaction_outputs(block_number)?.ok_or_else(missing_body)?;
+
+    let mut blockifier_txs = Vec::with_capacity(txs.len());
+    for ((tx, &tx_hash), tx_output) in txs.into_iter().zip(&tx_hashes).zip(tx_outputs) {
+        let class_info = match &tx {
+            StarknetApiTransaction::Declare(declare_tx) => {
+                Some(get_declared_class_info(txn, block_number, declare_tx)?)
+            }
+            StarknetApiTransaction::Deploy(_) => {
+                Err(ReplayError::UnsupportedTransaction { block_number, tx_hash })?
+            }
+            _ => None,
+        };
+        // The fee paid on L1 is not stored; the fee charged on L2 is a lower bound of it.
+        let paid_fee_on_l1 = match tx_output {
+            ThinTransactionOutput::L1Handler(l1_handler_output) => {
+                Some(l1_handler_output.actual_fee)
+            }
+            _ => None,
+        };
+        let deployed_contract_address = None;
+        let only_query = false;
+        blockifier_txs.push(Transaction::from_api(
+            tx,
+            tx_hash,
+            class_info,
+            paid_fee_on_l1,
+            deployed_contract_address,
+            only_query,
+        )?);
+    }
+
+    Ok((tx_hashes, blockifier_txs))
+}
+
+fn get_declared_class_info(
+    txn: &RawPapyrusReader<'_>,
+    block_number: BlockNumber,
+    declare_tx: &DeclareTransaction,
+) -> NativeBlockifierResult<ClassInfo> {
+    let class_hash = declare_tx.class_hash();
+    let missing_class = || ReplayError::MissingDeclaredClass { block_number, class_hash };
+    // The class is declared in the given block; read the state at its end.
+    let state_number = StateNumber(BlockNumber(block_number.0 + 1));
+    let state_reader = txn.get_state_reader()?;
+
+    let class_info = match declare_tx {
+        DeclareTransaction::V0(_) | DeclareTransaction::V1(_) => {
+            let deprecated_class = state_reader
+                .get_deprecated_class_definition_at(state_number, &class_hash)?
+                .ok_or_else(missing_class)?;
+            let abi_length = serde_json::to_string(&deprecated_class.abi)?.len();
+            let contract_class: ContractClass = ContractClassV0::try_from(deprecated_class)?.into();
+            let sierra_program_length = 0;
+            ClassInfo::new(&contract_class, sierra_program_length, abi_length)?
+        }
+        DeclareTransaction::V2(_) | DeclareTransaction::V3(_) => {
+            let sierra_class = state_reader
+                .get_class_definition_at(state_number, &class_hash)?
+                .ok_or_else(missing_class)?;
+            let casm_class = txn.get_casm(&class_hash)?.ok_or_else(missing_class)?;
+            let contract_class: ContractClass = ContractClassV1::try_from(casm_class)?.into();
+            ClassInfo::new(
+                &contract_class,
+                sierra_class.sierra_program.len(),
+                sierra_class.abi.len(),
+            )?
+        }
+    };
+
+    Ok(class_info)
+}
+
+/// Replays the given range of blocks of a (full archive) Papyrus storage, and returns the
+/// inconsistencies found (or why a block was skipped), keyed by block number; consistent blocks
+/// are omitted.
+#[pyfunction]
+#[pyo3(signature = (general_config, validate_max_n_steps, max_recursion_depth, global_contract_cache_size, storage_config, start_block_number, end_block_number))]
+pub fn replay_blocks(
+    general_config: PyGeneralConfig,
+    validate_max_n_steps: u32,
+    max_recursion_depth: usize,
+    global_contract_cache_size: usize,
+    storage_config: StorageConfig,
+    start_block_number: u64,
+    end_block_number: u64,
+) -> NativeBlockifierResult<HashMap<u64, Vec<String>>> {
+    let replayer = BlockReplayer::new(
+        open_storage_reader(storage_config)?,
+        general_config.starknet_os_config.try_into()?,
+        versioned_constants_with_overrides(validate_max_n_steps, max_recursion_depth),
+        GlobalContractCache::new(global_contract_cache_size),
+    );
+    let reports =
+        replayer.replay_blocks(BlockNumber(start_block_number)..BlockNumber(end_block_number))?;
+
+    Ok(reports
+        .into_iter()
+        .filter(|report| !report.is_consistent())
+        .map(|report| {
+            let failed_txs = report
+                .failed_txs
+                .iter()
+                .map(|(tx_hash, error)| format!("Transaction {tx_hash:?} failed: {error}"));
+            let mismatches = report.mismatches.iter().map(|mismatch| format!("{mismatch:?}"));
+            let unsupported_tx = report.unsupported_tx.iter().map(|tx_hash| {
+                format!("Skipped; transaction {tx_hash:?} is of an unsupported type.")
+            });
+            (report.block_number.0, unsupported_tx.chain(failed_txs).chain(mismatches).collect())
+        })
+        .collect())
+}
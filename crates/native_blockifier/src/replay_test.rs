@@ -0,0 +1,262 @@
+use blockifier::blockifier::block::pre_process_block;
+use blockifier::blockifier::transaction_executor::TransactionExecutor;
+use blockifier::bouncer::BouncerConfig;
+use blockifier::context::ChainInfo;
+use blockifier::state::cached_state::{
+    CommitmentStateDiff, GlobalContractCache, GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST,
+};
+use blockifier::test_utils::contracts::FeatureContract;
+use blockifier::test_utils::initial_test_state::test_state;
+use blockifier::test_utils::{
+    create_calldata, CairoVersion, BALANCE, MAX_FEE, TEST_SEQUENCER_ADDRESS,
+};
+use blockifier::transaction::transaction_execution::Transaction as BlockifierTransaction;
+use blockifier::versioned_constants::VersionedConstants;
+use indexmap::IndexMap;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{
+    ClassHash, ContractAddress, Nonce, PatriciaKey, SequencerContractAddress,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateDiff, StorageKey};
+use starknet_api::transaction::{
+    DeployTransaction, DeployTransactionOutput, Fee, InvokeTransaction, InvokeTransactionOutput,
+    InvokeTransactionV1, Transaction, TransactionHash, TransactionOutput,
+};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::replay::{
+    block_info_from_header, compare_state_diffs, BlockReplayer, StateDiffMismatch,
+};
+
+#[test]
+fn test_compare_state_diffs() {
+    let address = contract_address!("0x100");
+    let (key, other_key) = (StorageKey(patricia_key!("0x10")), StorageKey(patricia_key!("0x11")));
+    let expected = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::from([(address, class_hash!("0x1"))]),
+        address_to_nonce: IndexMap::from([(address, Nonce(stark_felt!("0x1")))]),
+        storage_updates: IndexMap::from([(
+            address,
+            IndexMap::from([(key, stark_felt!("0x5")), (other_key, stark_felt!("0x6"))]),
+        )]),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+    let actual = CommitmentStateDiff {
+        address_to_nonce: IndexMap::from([(address, Nonce(stark_felt!("0x2")))]),
+        storage_updates: IndexMap::from([(address, IndexMap::from([(key, stark_felt!("0x5"))]))]),
+        ..expected.clone()
+    };
+
+    assert_eq!(compare_state_diffs(&expected, &expected), vec![]);
+    assert_eq!(
+        compare_state_diffs(&expected, &actual),
+        vec![
+            StateDiffMismatch::Nonce {
+                address,
+                expected: Some(Nonce(stark_felt!("0x1"))),
+                actual: Some(Nonce(stark_felt!("0x2"))),
+            },
+            StateDiffMismatch::Storage {
+                address,
+                key: other_key,
+                expected: Some(stark_felt!("0x6")),
+                actual: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_replay_block() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    // Store an empty block, whose stored state diff cannot be the result of executing it.
+    let block_number = BlockNumber(0);
+    let (address, key, value) =
+        (contract_address!("0x100"), StorageKey(patricia_key!("0x10")), stark_felt!("0x5"));
+    let state_diff = StateDiff {
+        storage_diffs: IndexMap::from([(address, IndexMap::from([(key, value)]))]),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()?
+        .append_header(block_number, &BlockHeader { block_number, ..Default::default() })?
+        .append_body(block_number, BlockBody::default())?
+        .append_state_diff(block_number, state_diff, IndexMap::new())?
+        .commit()?;
+
+    let replayer = BlockReplayer::new(
+        storage_reader,
+        ChainInfo::create_for_testing(),
+        VersionedConstants::latest_constants().clone(),
+        GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST),
+    );
+    let report = replayer.replay_block(block_number).unwrap();
+    assert_eq!(report.n_txs, 0);
+    assert!(!report.is_consistent());
+    assert_eq!(
+        report.mismatches,
+        vec![StateDiffMismatch::Storage { address, key, expected: Some(value), actual: None }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_replay_blocks_skips_unsupported_txs() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    // A block with a legacy deploy transaction, followed by an empty block.
+    let deploy_tx_hash = TransactionHash(stark_felt!("0x1234"));
+    let deploy_block_body = BlockBody {
+        transactions: vec![Transaction::Deploy(DeployTransaction::default())],
+        transaction_outputs: vec![TransactionOutput::Deploy(DeployTransactionOutput::default())],
+        transaction_hashes: vec![deploy_tx_hash],
+    };
+    let mut txn = storage_writer.begin_rw_txn()?;
+    for (block_number, block_body) in
+        [(BlockNumber(0), deploy_block_body), (BlockNumber(1), BlockBody::default())]
+    {
+        txn = txn
+            .append_header(
+                block_number,
+                &BlockHeader {
+                    block_number,
+                    block_hash: BlockHash(stark_felt!(block_number.0)),
+                    ..Default::default()
+                },
+            )?
+            .append_body(block_number, block_body)?
+            .append_state_diff(block_number, StateDiff::default(), IndexMap::new())?;
+    }
+    txn.commit()?;
+
+    let replayer = BlockReplayer::new(
+        storage_reader,
+        ChainInfo::create_for_testing(),
+        VersionedConstants::latest_constants().clone(),
+        GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST),
+    );
+    let reports = replayer.replay_blocks(BlockNumber(0)..BlockNumber(2)).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].n_txs, 1);
+    assert_eq!(reports[0].unsupported_tx, Some(deploy_tx_hash));
+    assert!(!reports[0].is_consistent());
+    assert!(reports[1].is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn test_replay_invoke_tx() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+    let chain_info = ChainInfo::create_for_testing();
+    let versioned_constants = VersionedConstants::latest_constants();
+    let account = FeatureContract::AccountWithoutValidations(CairoVersion::Cairo0);
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let mut state = test_state(&chain_info, BALANCE, &[(account, 1), (test_contract, 1)]);
+
+    // The genesis block declares and deploys the test state's contracts, and funds the account.
+    let mut storage_diffs = IndexMap::<ContractAddress, IndexMap<StorageKey, StarkFelt>>::new();
+    for (&(address, key), &value) in &state.state.storage_view {
+        storage_diffs.entry(address).or_default().insert(key, value);
+    }
+    let genesis_state_diff = StateDiff {
+        deployed_contracts: state.state.address_to_class_hash.clone().into_iter().collect(),
+        storage_diffs,
+        deprecated_declared_classes: [account, test_contract, FeatureContract::ERC20]
+            .into_iter()
+            .map(|contract| {
+                (
+                    contract.get_class_hash(),
+                    serde_json::from_str(&contract.get_raw_class()).unwrap(),
+                )
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    // The next block invokes the test contract through the account.
+    let block_number = BlockNumber(1);
+    let header = BlockHeader {
+        block_number,
+        block_hash: BlockHash(stark_felt!(block_number.0)),
+        sequencer: SequencerContractAddress(contract_address!(TEST_SEQUENCER_ADDRESS)),
+        ..Default::default()
+    };
+    let (key, value) = (stark_felt!("0x10"), stark_felt!("0x5"));
+    let tx = Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+        max_fee: Fee(MAX_FEE),
+        sender_address: account.get_instance_address(0),
+        calldata: create_calldata(
+            test_contract.get_instance_address(0),
+            "test_storage_read_write",
+            &[key, value],
+        ),
+        ..Default::default()
+    }));
+    let tx_hash = TransactionHash(stark_felt!("0x1234"));
+
+    // The stored state diff of the block is that of executing it directly on the test state.
+    let block_context = pre_process_block(
+        &mut state,
+        None,
+        block_info_from_header(&header),
+        chain_info.clone(),
+        versioned_constants.clone(),
+    )
+    .unwrap();
+    let mut tx_executor = TransactionExecutor::new(state, block_context, BouncerConfig::max());
+    let blockifier_tx =
+        BlockifierTransaction::from_api(tx.clone(), tx_hash, None, None, None, false).unwrap();
+    for result in tx_executor.execute_txs(vec![blockifier_tx], true) {
+        result.unwrap();
+    }
+    let (executed_state_diff, _) = tx_executor.finalize(false).unwrap();
+    assert_eq!(
+        executed_state_diff.storage_updates[&test_contract.get_instance_address(0)]
+            [&StorageKey::try_from(key).unwrap()],
+        value
+    );
+    let block_state_diff = StateDiff {
+        deployed_contracts: executed_state_diff.address_to_class_hash,
+        storage_diffs: executed_state_diff.storage_updates,
+        nonces: executed_state_diff.address_to_nonce,
+        ..Default::default()
+    };
+    let block_body = BlockBody {
+        transactions: vec![tx],
+        transaction_outputs: vec![TransactionOutput::Invoke(InvokeTransactionOutput::default())],
+        transaction_hashes: vec![tx_hash],
+    };
+
+    storage_writer
+        .begin_rw_txn()?
+        .append_header(BlockNumber(0), &BlockHeader::default())?
+        .append_body(BlockNumber(0), BlockBody::default())?
+        .append_state_diff(BlockNumber(0), genesis_state_diff, IndexMap::new())?
+        .append_header(block_number, &header)?
+        .append_body(block_number, block_body)?
+        .append_state_diff(block_number, block_state_diff, IndexMap::new())?
+        .commit()?;
+
+    // Replaying the block on top of the stored genesis state reproduces its state diff.
+    let replayer = BlockReplayer::new(
+        storage_reader,
+        chain_info,
+        versioned_constants.clone(),
+        GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE_FOR_TEST),
+    );
+    let report = replayer.replay_block(block_number).unwrap();
+    assert_eq!(report.n_txs, 1);
+    assert_eq!(report.failed_txs, vec![]);
+    assert_eq!(report.mismatches, vec![]);
+    assert!(report.is_consistent());
+
+    Ok(())
+}
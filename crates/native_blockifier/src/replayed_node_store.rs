@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use blockifier::commitment::errors::{CommitmentError, CommitmentResult};
+use blockifier::commitment::node_store::{InMemoryNodeStore, Node, NodeStore};
+use blockifier::commitment::state_commitment::{commit_state_diff, StateCommitment, StateRoots};
+use blockifier::state::cached_state::CommitmentStateDiff;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::GlobalRoot;
+use starknet_api::hash::StarkHash;
+
+use crate::errors::{NativeBlockifierResult, ReplayError};
+use crate::replay::to_commitment_state_diff;
+
+#[cfg(test)]
+#[path = "replayed_node_store_test.rs"]
+pub mod test;
+
+/// A record of the log of a persisted node store, which is replayed when the store is opened.
+#[derive(Debug, Deserialize, Serialize)]
+enum NodeLogRecord {
+    /// The commitment of the next block.
+    Block { roots: StateRoots, new_nodes: HashMap<StarkHash, Node> },
+    /// Discards the commitments of the given block and of its successors.
+    Revert { block_number: BlockNumber },
+}
+
+/// The tries of the state of a Papyrus storage, rebuilt by replaying its stored state diffs from
+/// genesis; Papyrus storage holds no trie nodes, and has no table for them. Nodes are never
+/// removed, hence the tries of every committed block are available.
+///
+/// A store may be persisted, as an append-only log of its commitments (JSON lines) in a file of
+/// its own, which is replayed when the store is opened; otherwise, it is kept in memory only.
+///
+/// Note: the first sync replays every stored block, which takes time and memory proportional to
+/// the whole state; the whole log is also held in memory.
+#[derive(Debug, Default)]
+pub struct ReplayedNodeStore {
+    nodes: InMemoryNodeStore,
+    /// The state roots after each committed block, by block number.
+    block_roots: Vec<StateRoots>,
+    /// The log the commitments are appended to, if persisted.
+    log: Option<File>,
+}
+
+impl ReplayedNodeStore {
+    /// Opens the store persisted in the given file, which is created if missing. A record left
+    /// partially written by an interrupted append is discarded.
+    pub fn open(path: &Path) -> NativeBlockifierResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(StorageError::from)?;
+
+        let mut store = Self::default();
+        let mut reader = BufReader::new(&file);
+        let mut line = String::new();
+        let mut log_length = 0;
+        loop {
+            line.clear();
+            let n_read_bytes = reader.read_line(&mut line).map_err(StorageError::from)?;
+            if !line.ends_with('\n') {
+                break;
+            }
+            store.apply(serde_json::from_str(&line)?)?;
+            log_length += u64::try_from(n_read_bytes).expect("Line length overflow.");
+        }
+        file.set_len(log_length).map_err(StorageError::from)?;
+        store.log = Some(file);
+
+        Ok(store)
+    }
+
+    /// Returns the number of the next block to commit.
+    pub fn next_block_number(&self) -> BlockNumber {
+        BlockNumber(u64::try_from(self.block_roots.len()).expect("Block number overflow."))
+    }
+
+    /// Returns the state roots after the given block, if committed.
+    pub fn get_roots(&self, block_number: BlockNumber) -> Option<StateRoots> {
+        self.block_roots.get(usize::try_from(block_number.0).ok()?).copied()
+    }
+
+    /// Returns the state roots after the last committed block.
+    pub fn latest_roots(&self) -> StateRoots {
+        self.block_roots.last().copied().unwrap_or_default()
+    }
+
+    /// Commits the state diff of the next block, and returns the resulting state roots.
+    pub fn commit_block(
+        &mut self,
+        state_diff: &CommitmentStateDiff,
+    ) -> NativeBlockifierResult<StateRoots> {
+        let state_commitment = commit_state_diff(&self.nodes, &self.latest_roots(), state_diff)?;
+        self.append_block(state_commitment)
+    }
+
+    /// Appends the commitment of the state diff of the next block, computed on top of the latest
+    /// roots, and returns the resulting state roots.
+    pub fn append_block(
+        &mut self,
+        state_commitment: StateCommitment,
+    ) -> NativeBlockifierResult<StateRoots> {
+        let roots = state_commitment.roots;
+        self.log_and_apply(NodeLogRecord::Block { roots, new_nodes: state_commitment.new_nodes })?;
+        Ok(roots)
+    }
+
+    /// Commits the stored state diffs of the blocks that were not committed yet. The resulting
+    /// roots are verified against the state roots of the stored headers, if any; headers appended
+    /// without computing their hashes have no state root, and are not verified.
+    pub fn sync(&mut self, storage_reader: &StorageReader) -> NativeBlockifierResult<StateRoots> {
+        let txn = storage_reader.begin_ro_txn()?;
+        let state_marker = txn.get_state_marker()?;
+        // The commitments are ahead of the storage if its revert was not logged; e.g., due to a
+        // crash in between.
+        if self.next_block_number() > state_marker {
+            self.revert_block(state_marker)?;
+        }
+
+        while self.next_block_number() < state_marker {
+            let block_number = self.next_block_number();
+            let state_diff = txn
+                .get_state_diff(block_number)?
+                .ok_or(ReplayError::MissingBlockData { block_number, data: "state diff" })?;
+            let state_commitment = commit_state_diff(
+                &self.nodes,
+                &self.latest_roots(),
+                &to_commitment_state_diff(state_diff),
+            )?;
+            if let Some(header) = txn.get_block_header(block_number)? {
+                let actual = state_commitment.roots.global_root();
+                if header.state_root != GlobalRoot::default() && actual != header.state_root {
+                    return Err(CommitmentError::StateRootMismatch {
+                        block_number,
+                        expected: header.state_root,
+                        actual,
+                    }
+                    .into());
+                }
+            }
+            self.append_block(state_commitment)?;
+        }
+
+        Ok(self.latest_roots())
+    }
+
+    /// Discards the commitments of the given block and of its successors.
+    pub fn revert_block(&mut self, block_number: BlockNumber) -> NativeBlockifierResult<()> {
+        self.log_and_apply(NodeLogRecord::Revert { block_number })
+    }
+
+    fn log_and_apply(&mut self, record: NodeLogRecord) -> NativeBlockifierResult<()> {
+        if let Some(log) = &mut self.log {
+            let mut line = serde_json::to_string(&record)?;
+            line.push('\n');
+            log.write_all(line.as_bytes()).map_err(StorageError::from)?;
+        }
+        self.apply(record)
+    }
+
+    fn apply(&mut self, record: NodeLogRecord) -> NativeBlockifierResult<()> {
+        match record {
+            NodeLogRecord::Block { roots, new_nodes } => {
+                self.nodes.set_nodes(new_nodes)?;
+                self.block_roots.push(roots);
+            }
+            NodeLogRecord::Revert { block_number } => {
+                self.block_roots.truncate(usize::try_from(block_number.0).unwrap_or(usize::MAX));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl NodeStore for ReplayedNodeStore {
+    fn get_node(&self, hash: &StarkHash) -> CommitmentResult<Option<Node>> {
+        self.nodes.get_node(hash)
+    }
+
+    fn set_nodes(&mut self, nodes: HashMap<StarkHash, Node>) -> CommitmentResult<()> {
+        self.nodes.set_nodes(nodes)
+    }
+}
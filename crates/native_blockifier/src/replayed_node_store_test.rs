@@ -0,0 +1,166 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use blockifier::commitment::errors::CommitmentError;
+use blockifier::commitment::node_store::{InMemoryNodeStore, NodeStore};
+use blockifier::commitment::state_commitment::{commit_state_diff, StateRoots};
+use indexmap::{indexmap, IndexMap};
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateDiff, StorageKey};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::errors::NativeBlockifierError;
+use crate::replay::to_commitment_state_diff;
+use crate::replayed_node_store::ReplayedNodeStore;
+
+#[test]
+fn test_sync_replayed_node_store() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+    let address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let state_diffs = [
+        StateDiff {
+            deployed_contracts: indexmap!(address => class_hash!("0x20")),
+            storage_diffs: indexmap!(address => indexmap!(key => stark_felt!("0x1"))),
+            ..Default::default()
+        },
+        StateDiff {
+            nonces: indexmap!(address => Nonce(stark_felt!("0x1"))),
+            storage_diffs: indexmap!(address => indexmap!(key => stark_felt!("0x2"))),
+            ..Default::default()
+        },
+    ];
+    for (block_number, state_diff) in state_diffs.into_iter().enumerate() {
+        storage_writer
+            .begin_rw_txn()?
+            .append_state_diff(
+                BlockNumber(block_number.try_into().unwrap()),
+                state_diff,
+                IndexMap::new(),
+            )?
+            .commit()?;
+    }
+
+    // The roots are those of committing the stored state diffs one after the other.
+    let mut expected_node_store = InMemoryNodeStore::default();
+    let mut expected_roots = Vec::new();
+    let mut roots = StateRoots::default();
+    for block_number in [BlockNumber(0), BlockNumber(1)] {
+        let txn = storage_reader.begin_ro_txn()?;
+        let state_diff = to_commitment_state_diff(txn.get_state_diff(block_number)?.unwrap());
+        let state_commitment =
+            commit_state_diff(&expected_node_store, &roots, &state_diff).unwrap();
+        expected_node_store.set_nodes(state_commitment.new_nodes).unwrap();
+        roots = state_commitment.roots;
+        expected_roots.push(roots);
+
+        let header = BlockHeader {
+            block_hash: BlockHash(StarkFelt::from(block_number.0 + 1)),
+            block_number,
+            state_root: roots.global_root(),
+            ..Default::default()
+        };
+        storage_writer.begin_rw_txn()?.append_header(block_number, &header)?.commit()?;
+    }
+
+    // Syncing commits every stored block, and verifies the roots of the stored headers.
+    let mut node_store = ReplayedNodeStore::default();
+    assert_eq!(node_store.sync(&storage_reader).unwrap(), roots);
+    assert_eq!(node_store.next_block_number(), BlockNumber(2));
+    assert_eq!(node_store.latest_roots(), roots);
+    for (block_number, block_roots) in
+        [BlockNumber(0), BlockNumber(1)].into_iter().zip(expected_roots)
+    {
+        assert_eq!(node_store.get_roots(block_number), Some(block_roots));
+    }
+
+    // Reverted blocks are committed again on the next sync.
+    node_store.revert_block(BlockNumber(1)).unwrap();
+    assert_eq!(node_store.next_block_number(), BlockNumber(1));
+    assert_eq!(node_store.sync(&storage_reader).unwrap(), roots);
+
+    // A block whose header disagrees with its state diff is not committed.
+    let block_number = BlockNumber(2);
+    let state_diff = StateDiff {
+        storage_diffs: indexmap!(address => indexmap!(key => stark_felt!("0x3"))),
+        ..Default::default()
+    };
+    let header = BlockHeader {
+        block_hash: BlockHash(StarkFelt::from(block_number.0 + 1)),
+        block_number,
+        state_root: roots.global_root(),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(block_number, state_diff, IndexMap::new())?
+        .append_header(block_number, &header)?
+        .commit()?;
+    let error = node_store.sync(&storage_reader).unwrap_err();
+    assert!(matches!(
+        error,
+        NativeBlockifierError::CommitmentError(CommitmentError::StateRootMismatch {
+            block_number: mismatched_block_number,
+            expected,
+            ..
+        }) if mismatched_block_number == block_number && expected == roots.global_root()
+    ));
+    assert_eq!(node_store.next_block_number(), block_number);
+
+    Ok(())
+}
+
+#[test]
+fn test_persisted_replayed_node_store() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+    let address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    for block_number in [BlockNumber(0), BlockNumber(1)] {
+        let state_diff = StateDiff {
+            storage_diffs: indexmap!(address => indexmap!(key => stark_felt!(block_number.0 + 1))),
+            ..Default::default()
+        };
+        // Headers appended without computing their hashes have no state root, and are not
+        // verified.
+        let header = BlockHeader {
+            block_hash: BlockHash(StarkFelt::from(block_number.0 + 1)),
+            block_number,
+            ..Default::default()
+        };
+        storage_writer
+            .begin_rw_txn()?
+            .append_state_diff(block_number, state_diff, IndexMap::new())?
+            .append_header(block_number, &header)?
+            .commit()?;
+    }
+
+    let path = tempfile::tempdir().unwrap().into_path().join("replayed_nodes.jsonl");
+    let mut node_store = ReplayedNodeStore::open(&path).unwrap();
+    let roots = node_store.sync(&storage_reader).unwrap();
+    let first_roots = node_store.get_roots(BlockNumber(0));
+    node_store.revert_block(BlockNumber(1)).unwrap();
+
+    // Commitments and reverts are restored on reopening.
+    let node_store = ReplayedNodeStore::open(&path).unwrap();
+    assert_eq!(node_store.next_block_number(), BlockNumber(1));
+    assert_eq!(node_store.get_roots(BlockNumber(0)), first_roots);
+
+    // A partially written record is discarded.
+    let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+    log.write_all(b"{\"Block\":").unwrap();
+    let mut node_store = ReplayedNodeStore::open(&path).unwrap();
+    assert_eq!(node_store.next_block_number(), BlockNumber(1));
+    assert_eq!(node_store.sync(&storage_reader).unwrap(), roots);
+
+    // The nodes of the reopened store are those of the synced one.
+    let node_store = ReplayedNodeStore::open(&path).unwrap();
+    assert_eq!(node_store.latest_roots(), roots);
+    assert!(node_store.get_node(&roots.contracts_trie_root).unwrap().is_some());
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use blockifier::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use blockifier::state::cached_state::StorageEntry;
 use blockifier::state::errors::StateError;
-use blockifier::state::state_api::{StateReader, StateResult};
+use blockifier::state::state_api::{PrefetchedState, StateReader, StateResult};
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::RO;
 use papyrus_storage::state::StateStorageReader;
@@ -31,6 +32,46 @@ impl PapyrusReader {
             .begin_ro_txn()
             .map_err(|error| StateError::StateReadError(error.to_string()))
     }
+
+    /// Returns a V1 contract if found, or a V0 contract if a V1 contract is not
+    /// found, or an `Error` otherwise.
+    fn read_compiled_contract_class(
+        &self,
+        txn: &RawPapyrusReader<'_>,
+        class_hash: ClassHash,
+    ) -> StateResult<ContractClass> {
+        let state_number = StateNumber(self.latest_block);
+        let class_declaration_block_number = txn
+            .get_state_reader()
+            .and_then(|sr| sr.get_class_definition_block_number(&class_hash))
+            .map_err(|err| StateError::StateReadError(err.to_string()))?;
+        let class_is_declared: bool = matches!(class_declaration_block_number,
+                    Some(block_number) if block_number <= state_number.0);
+
+        if class_is_declared {
+            let casm_contract_class = txn
+                .get_casm(&class_hash)
+                .map_err(|err| StateError::StateReadError(err.to_string()))?
+                .expect(
+                    "Should be able to fetch a Casm class if its definition exists, database is \
+                     inconsistent.",
+                );
+
+            return Ok(ContractClass::V1(ContractClassV1::try_from(casm_contract_class)?));
+        }
+
+        let v0_contract_class = txn
+            .get_state_reader()
+            .and_then(|sr| sr.get_deprecated_class_definition_at(state_number, &class_hash))
+            .map_err(|err| StateError::StateReadError(err.to_string()))?;
+
+        match v0_contract_class {
+            Some(starknet_api_contract_class) => {
+                Ok(ContractClassV0::try_from(starknet_api_contract_class)?.into())
+            }
+            None => Err(StateError::UndeclaredClassHash(class_hash)),
+        }
+    }
 }
 
 // Currently unused - will soon replace the same `impl` for `PapyrusStateReader`.
@@ -73,46 +114,55 @@ impl StateReader for PapyrusReader {
         }
     }
 
-    /// Returns a V1 contract if found, or a V0 contract if a V1 contract is not
-    /// found, or an `Error` otherwise.
     fn get_compiled_contract_class(&self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.read_compiled_contract_class(&self.reader()?, class_hash)
+    }
+
+    fn get_compiled_class_hash(&self, _class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        todo!()
+    }
+
+    /// Reads the whole batch in a single read transaction.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
         let state_number = StateNumber(self.latest_block);
-        let class_declaration_block_number = self
-            .reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_class_definition_block_number(&class_hash))
-            .map_err(|err| StateError::StateReadError(err.to_string()))?;
-        let class_is_declared: bool = matches!(class_declaration_block_number,
-                    Some(block_number) if block_number <= state_number.0);
+        let txn = self.reader()?;
+        let state_reader =
+            txn.get_state_reader().map_err(|err| StateError::StateReadError(err.to_string()))?;
 
-        if class_is_declared {
-            let casm_contract_class = self
-                .reader()?
-                .get_casm(&class_hash)
+        let mut prefetched_state = PrefetchedState::default();
+        for &(contract_address, key) in storage_entries {
+            let value = state_reader
+                .get_storage_at(state_number, &contract_address, &key)
+                .map_err(|err| StateError::StateReadError(err.to_string()))?;
+            prefetched_state.storage.insert((contract_address, key), value);
+        }
+        for &contract_address in contract_addresses {
+            let nonce = state_reader
+                .get_nonce_at(state_number, &contract_address)
                 .map_err(|err| StateError::StateReadError(err.to_string()))?
-                .expect(
-                    "Should be able to fetch a Casm class if its definition exists, database is \
-                     inconsistent.",
-                );
-
-            return Ok(ContractClass::V1(ContractClassV1::try_from(casm_contract_class)?));
+                .unwrap_or_default();
+            let class_hash = state_reader
+                .get_class_hash_at(state_number, &contract_address)
+                .map_err(|err| StateError::StateReadError(err.to_string()))?
+                .unwrap_or_default();
+            prefetched_state.nonces.insert(contract_address, nonce);
+            prefetched_state.class_hashes.insert(contract_address, class_hash);
         }
-
-        let v0_contract_class = self
-            .reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_deprecated_class_definition_at(state_number, &class_hash))
-            .map_err(|err| StateError::StateReadError(err.to_string()))?;
-
-        match v0_contract_class {
-            Some(starknet_api_contract_class) => {
-                Ok(ContractClassV0::try_from(starknet_api_contract_class)?.into())
+        for &class_hash in class_hashes {
+            match self.read_compiled_contract_class(&txn, class_hash) {
+                Ok(contract_class) => {
+                    prefetched_state.contract_classes.insert(class_hash, contract_class);
+                }
+                Err(StateError::UndeclaredClassHash(_)) => {}
+                Err(error) => return Err(error),
             }
-            None => Err(StateError::UndeclaredClassHash(class_hash)),
         }
-    }
 
-    fn get_compiled_class_hash(&self, _class_hash: ClassHash) -> StateResult<CompiledClassHash> {
-        todo!()
+        Ok(prefetched_state)
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use blockifier::abi::abi_utils::selector_from_name;
 use blockifier::execution::call_info::{CallExecution, Retdata};
 use blockifier::execution::entry_point::CallEntryPoint;
@@ -61,3 +63,49 @@ fn test_entry_point_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_prefetch_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let test_contract = FeatureContract::TestContract(CairoVersion::Cairo0);
+    let test_class_hash = test_contract.get_class_hash();
+    let test_address = test_contract.get_instance_address(0);
+    let key = StorageKey::from(1234_u64);
+    let value = stark_felt!(18_u8);
+    let state_diff = StateDiff {
+        deployed_contracts: IndexMap::from([(test_address, test_class_hash)]),
+        storage_diffs: IndexMap::from([(test_address, IndexMap::from([(key, value)]))]),
+        ..Default::default()
+    };
+    let deprecated_declared_classes =
+        IndexMap::from([(test_class_hash, test_contract.get_deprecated_contract_class())]);
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(BlockNumber::default(), state_diff, deprecated_declared_classes)?
+        .commit()?;
+    let papyrus_reader = PapyrusReader::new(storage_reader, BlockNumber(1));
+
+    // The batch matches the single reads; undeclared classes are omitted.
+    let undeclared_class_hash =
+        FeatureContract::TestContract(CairoVersion::Cairo1).get_class_hash();
+    let prefetched_state = papyrus_reader
+        .prefetch(
+            &[(test_address, key)],
+            &[test_address],
+            &[test_class_hash, undeclared_class_hash],
+        )
+        .unwrap();
+    assert_eq!(prefetched_state.storage[&(test_address, key)], value);
+    assert_eq!(
+        prefetched_state.nonces[&test_address],
+        papyrus_reader.get_nonce_at(test_address).unwrap()
+    );
+    assert_eq!(prefetched_state.class_hashes[&test_address], test_class_hash);
+    assert_eq!(
+        prefetched_state.contract_classes,
+        HashMap::from([(test_class_hash, test_contract.get_class())])
+    );
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use blockifier::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use blockifier::state::cached_state::StorageEntry;
 use blockifier::state::errors::StateError;
-use blockifier::state::state_api::{StateReader, StateResult};
+use blockifier::state::state_api::{PrefetchedState, StateReader, StateResult};
 use pyo3::{FromPyObject, PyAny, PyErr, PyObject, PyResult, Python};
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
@@ -93,6 +94,68 @@ impl StateReader for PyStateReader {
         .map(|felt| CompiledClassHash(felt.0))
         .map_err(|err| StateError::StateReadError(err.to_string()))
     }
+
+    /// Reads the whole batch in a single call to the proxy, if it supports batching.
+    fn prefetch(
+        &self,
+        storage_entries: &[StorageEntry],
+        contract_addresses: &[ContractAddress],
+        class_hashes: &[ClassHash],
+    ) -> StateResult<PrefetchedState> {
+        Python::with_gil(|py| -> NativeBlockifierResult<PrefetchedState> {
+            let state_reader_proxy = self.state_reader_proxy.as_ref(py);
+            if !state_reader_proxy.hasattr("prefetch")? {
+                return Ok(PrefetchedState::default());
+            }
+
+            let args = (
+                ON_CHAIN_STORAGE_DOMAIN,
+                storage_entries
+                    .iter()
+                    .map(|&(contract_address, key)| {
+                        (PyFelt::from(contract_address), PyFelt::from(key))
+                    })
+                    .collect::<Vec<_>>(),
+                contract_addresses.iter().copied().map(PyFelt::from).collect::<Vec<_>>(),
+                class_hashes.iter().copied().map(PyFelt::from).collect::<Vec<_>>(),
+            );
+            let (storage_values, nonces, contract_class_hashes, raw_compiled_classes): (
+                Vec<PyFelt>,
+                Vec<PyFelt>,
+                Vec<PyFelt>,
+                Vec<Option<PyRawCompiledClass>>,
+            ) = state_reader_proxy.call_method1("prefetch", args)?.extract()?;
+            if storage_values.len() != storage_entries.len()
+                || nonces.len() != contract_addresses.len()
+                || contract_class_hashes.len() != contract_addresses.len()
+                || raw_compiled_classes.len() != class_hashes.len()
+            {
+                return Err(NativeBlockifierInputError::InvalidPrefetchResult.into());
+            }
+
+            // Undeclared classes are returned as `None`.
+            let mut prefetched_state = PrefetchedState::default();
+            for (&storage_entry, value) in storage_entries.iter().zip(storage_values) {
+                prefetched_state.storage.insert(storage_entry, value.0);
+            }
+            for ((&contract_address, nonce), class_hash) in
+                contract_addresses.iter().zip(nonces).zip(contract_class_hashes)
+            {
+                prefetched_state.nonces.insert(contract_address, Nonce(nonce.0));
+                prefetched_state.class_hashes.insert(contract_address, ClassHash(class_hash.0));
+            }
+            for (&class_hash, raw_compiled_class) in class_hashes.iter().zip(raw_compiled_classes) {
+                if let Some(raw_compiled_class) = raw_compiled_class {
+                    prefetched_state
+                        .contract_classes
+                        .insert(class_hash, ContractClass::try_from(raw_compiled_class)?);
+                }
+            }
+
+            Ok(prefetched_state)
+        })
+        .map_err(|err| StateError::StateReadError(err.to_string()))
+    }
 }
 
 #[derive(FromPyObject)]
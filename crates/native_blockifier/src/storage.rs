@@ -2,31 +2,54 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::PathBuf;
 
+use blockifier::commitment::block_hash::{
+    calculate_block_commitments, calculate_block_hash, BlockHeaderWithoutHash,
+    TransactionHashingData,
+};
+use blockifier::commitment::state_commitment::{commit_state_diff, StateCommitment};
+use blockifier::state::cached_state::CommitmentStateDiff;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::IndexMap;
 use papyrus_storage::compiled_class::CasmStorageWriter;
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
 use pyo3::prelude::*;
-use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
-use starknet_api::core::{ChainId, ClassHash, CompiledClassHash, ContractAddress};
+use starknet_api::block::{
+    BlockHash, BlockHeader, BlockNumber, GasPrice, GasPricePerToken, StarknetVersion,
+};
+use starknet_api::core::{
+    ChainId, ClassHash, CompiledClassHash, ContractAddress, EventCommitment,
+    SequencerContractAddress, TransactionCommitment,
+};
+use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::StarkHash;
 use starknet_api::state::{ContractClass, StateDiff, StateNumber};
 
-use crate::errors::NativeBlockifierResult;
+use crate::errors::{NativeBlockifierInputError, NativeBlockifierResult};
+use crate::py_block_executor::into_block_info;
 use crate::py_state_diff::PyBlockInfo;
 use crate::py_utils::{int_to_chain_id, PyFelt};
+use crate::replayed_node_store::ReplayedNodeStore;
 use crate::PyStateDiff;
 
 const GENESIS_BLOCK_ID: u64 = u64::MAX;
 
+/// The file the replayed state tries are persisted in, next to the Papyrus database.
+const NODE_STORE_FILE_NAME: &str = "replayed_nodes.jsonl";
+
 // Invariant: Only one instance of this struct should exist.
 // Reader and writer fields must be cleared before the struct goes out of scope in Python;
 // to prevent possible memory leaks (TODO: see if this is indeed necessary).
 pub struct PapyrusStorage {
     reader: Option<papyrus_storage::StorageReader>,
     writer: Option<papyrus_storage::StorageWriter>,
+    // The Starknet version of the appended blocks, if their headers carry their computed hashes
+    // (accordingly), rather than the given block IDs.
+    starknet_version: Option<String>,
+    // The state tries, for the state roots of the appended blocks; synced lazily, only if block
+    // hashes are computed.
+    node_store: ReplayedNodeStore,
 }
 
 impl PapyrusStorage {
@@ -40,6 +63,7 @@ impl PapyrusStorage {
             max_size: config.max_size,
             growth_step: 1 << 26, // 64MB.
         };
+        let node_store_path = db_config.path().join(NODE_STORE_FILE_NAME);
         let storage_config = papyrus_storage::StorageConfig {
             db_config,
             scope: papyrus_storage::StorageScope::StateOnly, // Only stores blockifier-related data.
@@ -52,9 +76,20 @@ impl PapyrusStorage {
             },
         };
         let (reader, writer) = papyrus_storage::open_storage(storage_config)?;
+        // The state tries are only needed, and thus persisted, if block hashes are computed.
+        let node_store = if config.starknet_version.is_some() {
+            ReplayedNodeStore::open(&node_store_path)?
+        } else {
+            ReplayedNodeStore::default()
+        };
         log::debug!("Initialized Blockifier storage.");
 
-        Ok(PapyrusStorage { reader: Some(reader), writer: Some(writer) })
+        Ok(PapyrusStorage {
+            reader: Some(reader),
+            writer: Some(writer),
+            starknet_version: config.starknet_version,
+            node_store,
+        })
     }
 
     /// Manually drops the storage reader and writer.
@@ -71,10 +106,131 @@ impl PapyrusStorage {
         let storage_config = papyrus_storage::StorageConfig { db_config, ..Default::default() };
         let (reader, writer) = papyrus_storage::open_storage(storage_config).unwrap();
 
-        PapyrusStorage { reader: Some(reader), writer: Some(writer) }
+        PapyrusStorage {
+            reader: Some(reader),
+            writer: Some(writer),
+            starknet_version: None,
+            node_store: ReplayedNodeStore::default(),
+        }
+    }
+
+    /// Computes the hashes of the appended blocks, of the given Starknet version, rather than
+    /// taking their block IDs.
+    /// Note: the first appended block replays every stored state diff, to compute its state root.
+    pub fn with_block_hashes(mut self, starknet_version: &str) -> Self {
+        self.starknet_version = Some(starknet_version.to_string());
+        self
+    }
+
+    /// Returns the header of the given block, to be appended on top of the stored blocks, along
+    /// with the commitment of its state diff.
+    fn compute_block_header(
+        &mut self,
+        py_block_info: &PyBlockInfo,
+        previous_block_id: Option<PyFelt>,
+        state_diff: &StateDiff,
+        txs_hashing_data: &[TransactionHashingData],
+        starknet_version: String,
+    ) -> NativeBlockifierResult<(BlockHeader, StateCommitment)> {
+        let block_info = into_block_info(py_block_info)?;
+        let block_number = block_info.block_number;
+        let reader = self.reader.as_ref().expect("Storage should be initialized.");
+
+        let previous_roots = self.node_store.sync(reader)?;
+        let commitment_state_diff = to_commitment_state_diff(state_diff);
+        let state_commitment =
+            commit_state_diff(&self.node_store, &previous_roots, &commitment_state_diff)?;
+
+        // The previous block ID is used only if the previous header is missing; e.g., when
+        // starting from a Papyrus snapshot.
+        let parent_header = match block_number.0.checked_sub(1) {
+            Some(parent_number) => {
+                reader.begin_ro_txn()?.get_block_header(BlockNumber(parent_number))?
+            }
+            None => None,
+        };
+        let parent_hash = match (parent_header, previous_block_id) {
+            (Some(parent_header), _) => parent_header.block_hash,
+            (None, Some(previous_block_id)) => BlockHash(previous_block_id.0),
+            (None, None) => BlockHash(StarkHash::ZERO),
+        };
+
+        let deprecated_declared_classes: Vec<ClassHash> =
+            state_diff.deprecated_declared_classes.keys().copied().collect();
+        let block_commitments = calculate_block_commitments(
+            txs_hashing_data,
+            &commitment_state_diff,
+            &deprecated_declared_classes,
+            block_info.use_kzg_da,
+        )?;
+        let header_without_hash = BlockHeaderWithoutHash {
+            block_info,
+            state_root: state_commitment.roots.global_root(),
+            parent_hash,
+            starknet_version,
+        };
+        let block_hash = calculate_block_hash(&header_without_hash, &block_commitments)?;
+
+        let BlockHeaderWithoutHash { block_info, state_root, starknet_version, .. } =
+            header_without_hash;
+        let gas_prices = &block_info.gas_prices;
+        let block_header = BlockHeader {
+            block_hash,
+            parent_hash,
+            block_number,
+            l1_gas_price: GasPricePerToken {
+                price_in_wei: GasPrice(gas_prices.eth_l1_gas_price.get()),
+                price_in_fri: GasPrice(gas_prices.strk_l1_gas_price.get()),
+            },
+            l1_data_gas_price: GasPricePerToken {
+                price_in_wei: GasPrice(gas_prices.eth_l1_data_gas_price.get()),
+                price_in_fri: GasPrice(gas_prices.strk_l1_data_gas_price.get()),
+            },
+            state_root,
+            sequencer: SequencerContractAddress(block_info.sequencer_address),
+            timestamp: block_info.block_timestamp,
+            l1_da_mode: if block_info.use_kzg_da {
+                L1DataAvailabilityMode::Blob
+            } else {
+                L1DataAvailabilityMode::Calldata
+            },
+            transaction_commitment: TransactionCommitment(block_commitments.transaction_commitment),
+            event_commitment: EventCommitment(block_commitments.event_commitment),
+            n_transactions: txs_hashing_data.len(),
+            n_events: txs_hashing_data
+                .iter()
+                .map(|tx_hashing_data| tx_hashing_data.events.len())
+                .sum(),
+            starknet_version: StarknetVersion(starknet_version),
+        };
+
+        Ok((block_header, state_commitment))
     }
 }
 
+/// Opens a read-only view of a full archive storage (e.g., of a Papyrus node); unlike the
+/// Blockifier storage, it also contains block bodies.
+pub fn open_storage_reader(
+    config: StorageConfig,
+) -> NativeBlockifierResult<papyrus_storage::StorageReader> {
+    let db_config = papyrus_storage::db::DbConfig {
+        path_prefix: config.path_prefix,
+        enforce_file_exists: config.enforce_file_exists,
+        chain_id: config.chain_id,
+        min_size: 1 << 20, // 1MB.
+        max_size: config.max_size,
+        growth_step: 1 << 26, // 64MB.
+    };
+    let storage_config = papyrus_storage::StorageConfig {
+        db_config,
+        scope: papyrus_storage::StorageScope::FullArchive,
+        ..Default::default()
+    };
+    let (reader, _) = papyrus_storage::open_storage(storage_config)?;
+
+    Ok(reader)
+}
+
 impl Storage for PapyrusStorage {
     /// Returns the next block number, for which state diff was not yet appended.
     fn get_state_marker(&self) -> NativeBlockifierResult<u64> {
@@ -105,18 +261,19 @@ impl Storage for PapyrusStorage {
         let (revert_txn, _, _) = revert_txn.revert_header(block_number)?;
 
         revert_txn.commit()?;
-        Ok(())
+        self.node_store.revert_block(block_number)
     }
 
     // TODO(Gilad): Refactor.
     fn append_block(
         &mut self,
-        block_id: u64,
+        block_id: Option<u64>,
         previous_block_id: Option<PyFelt>,
         py_block_info: PyBlockInfo,
         py_state_diff: PyStateDiff,
         declared_class_hash_to_class: HashMap<PyFelt, (PyFelt, String)>,
         deprecated_declared_class_hash_to_class: HashMap<PyFelt, String>,
+        txs_hashing_data: Option<&[TransactionHashingData]>,
     ) -> NativeBlockifierResult<()> {
         log::debug!(
             "Appending state diff with {block_id:?} for block_number: {}.",
@@ -185,16 +342,50 @@ impl Storage for PapyrusStorage {
             }
         }
 
-        let mut append_txn = self.writer().begin_rw_txn()?;
-        for (class_hash, contract_class) in undeclared_casm_contracts {
-            append_txn = append_txn.append_casm(&class_hash, &contract_class)?;
-        }
-
         // Construct state diff; manually add declared classes.
         let mut state_diff = StateDiff::try_from(py_state_diff)?;
         state_diff.deprecated_declared_classes = deprecated_declared_classes;
         state_diff.declared_classes = declared_classes;
         state_diff.replaced_classes = replaced_classes;
+        let starknet_version = self.starknet_version.clone();
+        let (block_header, state_commitment) = if let Some(starknet_version) = starknet_version {
+            if block_id.is_some() {
+                return Err(NativeBlockifierInputError::UnexpectedBlockId {
+                    block_number: block_number.0,
+                }
+                .into());
+            }
+            let txs_hashing_data =
+                txs_hashing_data.ok_or(NativeBlockifierInputError::MissingTxsHashingData {
+                    block_number: block_number.0,
+                })?;
+            let (block_header, state_commitment) = self.compute_block_header(
+                &py_block_info,
+                previous_block_id,
+                &state_diff,
+                txs_hashing_data,
+                starknet_version,
+            )?;
+            (block_header, Some(state_commitment))
+        } else {
+            let block_id = block_id.ok_or(NativeBlockifierInputError::MissingBlockId {
+                block_number: block_number.0,
+            })?;
+            let previous_block_id =
+                previous_block_id.unwrap_or_else(|| PyFelt::from(GENESIS_BLOCK_ID));
+            let block_header = BlockHeader {
+                block_hash: BlockHash(StarkHash::from(block_id)),
+                parent_hash: BlockHash(previous_block_id.0),
+                block_number,
+                ..Default::default()
+            };
+            (block_header, None)
+        };
+
+        let mut append_txn = self.writer().begin_rw_txn()?;
+        for (class_hash, contract_class) in undeclared_casm_contracts {
+            append_txn = append_txn.append_casm(&class_hash, &contract_class)?;
+        }
 
         let deployed_contract_class_definitions =
             IndexMap::<ClassHash, DeprecatedContractClass>::new();
@@ -204,16 +395,12 @@ impl Storage for PapyrusStorage {
             deployed_contract_class_definitions,
         )?;
 
-        let previous_block_id = previous_block_id.unwrap_or_else(|| PyFelt::from(GENESIS_BLOCK_ID));
-        let block_header = BlockHeader {
-            block_hash: BlockHash(StarkHash::from(block_id)),
-            parent_hash: BlockHash(previous_block_id.0),
-            block_number,
-            ..Default::default()
-        };
         append_txn = append_txn.append_header(block_number, &block_header)?;
 
         append_txn.commit()?;
+        if let Some(state_commitment) = state_commitment {
+            self.node_store.append_block(state_commitment)?;
+        }
         Ok(())
     }
 
@@ -249,6 +436,22 @@ impl Storage for PapyrusStorage {
     }
 }
 
+fn to_commitment_state_diff(state_diff: &StateDiff) -> CommitmentStateDiff {
+    let mut address_to_class_hash = state_diff.deployed_contracts.clone();
+    address_to_class_hash.extend(state_diff.replaced_classes.clone());
+
+    CommitmentStateDiff {
+        address_to_class_hash,
+        address_to_nonce: state_diff.nonces.clone(),
+        storage_updates: state_diff.storage_diffs.clone(),
+        class_hash_to_compiled_class_hash: state_diff
+            .declared_classes
+            .iter()
+            .map(|(class_hash, (compiled_class_hash, _))| (*class_hash, *compiled_class_hash))
+            .collect(),
+    }
+}
+
 #[pyclass]
 #[derive(Clone)]
 pub struct StorageConfig {
@@ -256,19 +459,25 @@ pub struct StorageConfig {
     chain_id: ChainId,
     enforce_file_exists: bool,
     max_size: usize,
+    // The Starknet version of the appended blocks; if given, their hashes are computed
+    // accordingly, and must be at least 0.13.2.
+    starknet_version: Option<String>,
 }
 
 #[pymethods]
 impl StorageConfig {
     #[new]
-    #[pyo3(signature = (path_prefix, chain_id, enforce_file_exists, max_size))]
+    #[pyo3(signature = (
+        path_prefix, chain_id, enforce_file_exists, max_size, starknet_version = None
+    ))]
     pub fn new(
         path_prefix: PathBuf,
         #[pyo3(from_py_with = "int_to_chain_id")] chain_id: ChainId,
         enforce_file_exists: bool,
         max_size: usize,
+        starknet_version: Option<String>,
     ) -> Self {
-        Self { path_prefix, chain_id, enforce_file_exists, max_size }
+        Self { path_prefix, chain_id, enforce_file_exists, max_size, starknet_version }
     }
 }
 
@@ -278,14 +487,18 @@ pub trait Storage {
     fn get_block_id(&self, block_number: u64) -> NativeBlockifierResult<Option<Vec<u8>>>;
 
     fn revert_block(&mut self, block_number: u64) -> NativeBlockifierResult<()>;
+    /// Appends the given block. Its header is identified by the given block ID, or, if the storage
+    /// computes block hashes, by its hash; the header then commits to the given transactions.
+    #[allow(clippy::too_many_arguments)]
     fn append_block(
         &mut self,
-        block_id: u64,
+        block_id: Option<u64>,
         previous_block_id: Option<PyFelt>,
         py_block_info: PyBlockInfo,
         py_state_diff: PyStateDiff,
         declared_class_hash_to_class: HashMap<PyFelt, (PyFelt, String)>,
         deprecated_declared_class_hash_to_class: HashMap<PyFelt, String>,
+        txs_hashing_data: Option<&[TransactionHashingData]>,
     ) -> NativeBlockifierResult<()>;
 
     fn validate_aligned(&self, source_block_number: u64);
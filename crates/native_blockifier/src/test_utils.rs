@@ -27,7 +27,7 @@ impl Storage for MockStorage {
 
     fn append_block(
         &mut self,
-        _block_id: u64,
+        _block_id: Option<u64>,
         _previous_block_id: Option<crate::py_utils::PyFelt>,
         _py_block_info: crate::py_state_diff::PyBlockInfo,
         _py_state_diff: crate::py_state_diff::PyStateDiff,
@@ -36,6 +36,7 @@ impl Storage for MockStorage {
             (crate::py_utils::PyFelt, String),
         >,
         _deprecated_declared_class_hash_to_class: HashMap<crate::py_utils::PyFelt, String>,
+        _txs_hashing_data: Option<&[blockifier::commitment::block_hash::TransactionHashingData]>,
     ) -> NativeBlockifierResult<()> {
         todo!()
     }